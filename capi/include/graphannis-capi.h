@@ -144,6 +144,14 @@ typedef enum AnnisResultOrder {
  */
 typedef struct AnnisAnnotation AnnisAnnotation;
 
+/**
+ * Allows to cancel a running query from another thread.
+ *
+ * All clones of a token share the same state, so a clone can be given to
+ * the query while the original is used to cancel it.
+ */
+typedef struct AnnisCancellationToken AnnisCancellationToken;
+
 /**
  * Identifies an edge component of the graph.
  */
@@ -158,6 +166,11 @@ typedef struct AnnisComponent_AnnotationComponentType AnnisComponent_AnnotationC
  */
 typedef struct AnnisCorpusStorage AnnisCorpusStorage;
 
+/**
+ * Iterator over the match IDs of a query, which are found by a background thread.
+ */
+typedef struct AnnisFindIterator AnnisFindIterator;
+
 /**
  * A list of changes to apply to an graph.
  */
@@ -292,12 +305,40 @@ struct AnnisCorpusStorage *annis_cs_with_max_cache_size(const char *db_dir,
  */
 void annis_cs_free(struct AnnisCorpusStorage *ptr);
 
+/**
+ * Create a new token, which can be given to a query function (e.g.
+ * `annis_cs_count(...)`) to cancel the query from another thread.
+ *
+ * The returned token must be freed with `annis_cancellation_token_free(...)`.
+ */
+struct AnnisCancellationToken *annis_cancellation_token_new(void);
+
+/**
+ * Cancel all queries that have been started with this token. The cancelled
+ * queries fail with an error of the kind `Cancelled`. A cancelled token
+ * can't be used for new queries.
+ *
+ * - `ptr` - The cancellation token.
+ */
+void annis_cancellation_token_cancel(const struct AnnisCancellationToken *ptr);
+
+/**
+ * Frees the cancellation token. Queries that use the token are not
+ * cancelled.
+ *
+ * # Safety
+ *
+ * This functions dereferences the pointer given as argument and is therefore unsafe.
+ */
+void annis_cancellation_token_free(struct AnnisCancellationToken *ptr);
+
 /**
  * Count the number of results for a `query`.
  * - `ptr` - The corpus storage object.
  * - `corpus_names` - The name of the corpora to execute the query on.
  * - `query` - The query as string.
  * - `query_language` The query language of the query (e.g. AQL).
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
  * - `err` - Pointer to a list of errors. If any error occurred, this list will be non-empty.
  *
  * Returns the count as number.
@@ -306,6 +347,7 @@ uint64_t annis_cs_count(const struct AnnisCorpusStorage *ptr,
                         const struct AnnisVec_CString *corpus_names,
                         const char *query,
                         enum AnnisQueryLanguage query_language,
+                        const struct AnnisCancellationToken *cancellation,
                         AnnisErrorList **err);
 
 /**
@@ -315,12 +357,14 @@ uint64_t annis_cs_count(const struct AnnisCorpusStorage *ptr,
  * - `corpus_names` - The name of the corpora to execute the query on.
  * - `query` - The query as string.
  * - `query_language` The query language of the query (e.g. AQL).
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
  * - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
  */
 struct AnnisCountExtra annis_cs_count_extra(const struct AnnisCorpusStorage *ptr,
                                             const struct AnnisVec_CString *corpus_names,
                                             const char *query,
                                             enum AnnisQueryLanguage query_language,
+                                            const struct AnnisCancellationToken *cancellation,
                                             AnnisErrorList **err);
 
 /**
@@ -335,6 +379,7 @@ struct AnnisCountExtra annis_cs_count_extra(const struct AnnisCorpusStorage *ptr
  * - `offset` - Skip the `n` first results, where `n` is the offset.
  * - `limit` - Return at most `n` matches, where `n` is the limit.  Use `None` to allow unlimited result sizes.
  * - `order` - Specify the order of the matches.
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
  * - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
  *
  * Returns a vector of match IDs, where each match ID consists of the matched node annotation identifiers separated by spaces.
@@ -351,8 +396,66 @@ struct AnnisVec_CString *annis_cs_find(const struct AnnisCorpusStorage *ptr,
                                        size_t offset,
                                        const size_t *limit,
                                        enum AnnisResultOrder order,
+                                       const struct AnnisCancellationToken *cancellation,
                                        AnnisErrorList **err);
 
+/**
+ * Find all results for a `query` and return an iterator over the match ID for each result.
+ *
+ * In contrast to `annis_cs_find(...)`, the matches are not collected first and can be fetched
+ * with `annis_find_iter_next(...)` as soon as they are found.
+ *
+ * - `ptr` - The corpus storage object.
+ * - `corpus_names` - The name of the corpora to execute the query on.
+ * - `query` - The query as string.
+ * - `query_language` The query language of the query (e.g. AQL).
+ * - `offset` - Skip the `n` first results, where `n` is the offset.
+ * - `limit` - Return at most `n` matches, where `n` is the limit.  Use `None` to allow unlimited result sizes.
+ * - `order` - Specify the order of the matches.
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
+ * - `err` - Pointer to a list of errors. If the query could not be started, this list will be non-empty and `NULL` is returned.
+ *
+ * Errors of the query execution are returned by `annis_find_iter_next(...)`.
+ * The returned iterator must be freed with `annis_find_iter_free(...)`, which
+ * also cancels the query if it is still running, but not the given `cancellation` token.
+ *
+ * # Safety
+ *
+ * The query is executed in a background thread, which uses the corpus storage `ptr`.
+ * The corpus storage must not be freed before the returned iterator.
+ * This functions dereferences the `err` pointer and is therefore unsafe.
+ */
+struct AnnisFindIterator *annis_cs_find_iter(const struct AnnisCorpusStorage *ptr,
+                                             const struct AnnisVec_CString *corpus_names,
+                                             const char *query,
+                                             enum AnnisQueryLanguage query_language,
+                                             size_t offset,
+                                             const size_t *limit,
+                                             enum AnnisResultOrder order,
+                                             const struct AnnisCancellationToken *cancellation,
+                                             AnnisErrorList **err);
+
+/**
+ * Returns the next match ID for the iterator given by the `ptr` argument
+ * or `NULL` if there are no more matches.
+ *
+ * The call blocks until the next match has been found.
+ *
+ * - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
+ *
+ * The resulting char* must be freeed with annis_str_free!
+ */
+char *annis_find_iter_next(struct AnnisFindIterator *ptr, AnnisErrorList **err);
+
+/**
+ * Frees the iterator given as `ptr` argument and stops the execution of its query.
+ *
+ * # Safety
+ *
+ * This functions dereferences the `ptr` pointer and is therefore unsafe.
+ */
+void annis_find_iter_free(struct AnnisFindIterator *ptr);
+
 /**
  * Return the copy of a subgraph which includes the given list of node annotation identifiers,
  * the nodes that cover the same token as the given nodes and
@@ -411,12 +514,14 @@ AnnisAnnotationGraph *annis_cs_corpus_graph(const struct AnnisCorpusStorage *ptr
  * - `corpus_name` - The name of the corpus for which the subgraph should be generated from.
  * - `query` - The query which defines included nodes.
  * - `query_language` - The query language of the query (e.g. AQL).
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
  * - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
  */
 AnnisAnnotationGraph *annis_cs_subgraph_for_query(const struct AnnisCorpusStorage *ptr,
                                                   const char *corpus_name,
                                                   const char *query,
                                                   enum AnnisQueryLanguage query_language,
+                                                  const struct AnnisCancellationToken *cancellation,
                                                   AnnisErrorList **err);
 
 /**
@@ -427,6 +532,7 @@ AnnisAnnotationGraph *annis_cs_subgraph_for_query(const struct AnnisCorpusStorag
  * - `query` - The query which defines included nodes.
  * - `query_language` - The query language of the query (e.g. AQL).
  * - `component_type_filter` - Only include edges of that belong to a component of the given type.
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
  * - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
  */
 AnnisAnnotationGraph *annis_cs_subgraph_for_query_with_ctype(const struct AnnisCorpusStorage *ptr,
@@ -434,6 +540,7 @@ AnnisAnnotationGraph *annis_cs_subgraph_for_query_with_ctype(const struct AnnisC
                                                              const char *query,
                                                              enum AnnisQueryLanguage query_language,
                                                              enum AnnisAnnotationComponentType component_type_filter,
+                                                             const struct AnnisCancellationToken *cancellation,
                                                              AnnisErrorList **err);
 
 /**
@@ -444,6 +551,7 @@ AnnisAnnotationGraph *annis_cs_subgraph_for_query_with_ctype(const struct AnnisC
  * - `query` - The query as string.
  * - `query_language` The query language of the query (e.g. AQL).
  * - `frequency_query_definition` - A string representation of the list of frequency query definitions.
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
  * - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
  *
  * Returns a frequency table of strings.
@@ -453,8 +561,30 @@ AnnisFrequencyTable_CString *annis_cs_frequency(const struct AnnisCorpusStorage
                                                 const char *query,
                                                 enum AnnisQueryLanguage query_language,
                                                 const char *frequency_query_definition,
+                                                const struct AnnisCancellationToken *cancellation,
                                                 AnnisErrorList **err);
 
+/**
+ * Count the number of results for a `query`, grouped by the given criteria.
+ *
+ * - `ptr` - The corpus storage object.
+ * - `corpus_names` - The name of the corpora to execute the query on.
+ * - `query` - The query as string.
+ * - `query_language` The query language of the query (e.g. AQL).
+ * - `group_by` - A comma-separated list of group definitions, e.g. `doc,meta:genre,1:pos`.
+ * - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
+ * - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
+ *
+ * Returns a frequency table of strings with the count for each combination of group values.
+ */
+AnnisFrequencyTable_CString *annis_cs_count_grouped(const struct AnnisCorpusStorage *ptr,
+                                                    const struct AnnisVec_CString *corpus_names,
+                                                    const char *query,
+                                                    enum AnnisQueryLanguage query_language,
+                                                    const char *group_by,
+                                                    const struct AnnisCancellationToken *cancellation,
+                                                    AnnisErrorList **err);
+
 /**
  * List all available corpora in the corpus storage.
  *
@@ -2,17 +2,21 @@ use super::Matrix;
 use super::cerror::ErrorList;
 use super::{cast_const, cast_mut, cstr, map_cerr};
 use graphannis::corpusstorage::ExportFormat;
+use graphannis::errors::Result;
 use graphannis::{
     AnnotationGraph, CorpusStorage,
     corpusstorage::{
-        CacheStrategy, CountExtra, FrequencyDefEntry, FrequencyTable, FrequencyTableRow,
-        ImportFormat, QueryAttributeDescription, QueryLanguage, ResultOrder, SearchQuery,
+        CacheStrategy, CancellationToken, CountExtra, FrequencyDefEntry, FrequencyTable,
+        FrequencyTableRow, GroupByEntry, ImportFormat, QueryAttributeDescription, QueryLanguage,
+        ResultOrder, SearchQuery,
     },
     model::{AnnotationComponent, AnnotationComponentType},
     update::GraphUpdate,
 };
 use std::ffi::CString;
 use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::JoinHandle;
 
 /// Create a new instance with a an automatic determined size of the internal corpus cache.
 ///
@@ -83,11 +87,54 @@ pub unsafe extern "C" fn annis_cs_free(ptr: *mut CorpusStorage) {
     std::mem::drop(ptr);
 }
 
+/// Create a new token, which can be given to a query function (e.g.
+/// `annis_cs_count(...)`) to cancel the query from another thread.
+///
+/// The returned token must be freed with `annis_cancellation_token_free(...)`.
+#[unsafe(no_mangle)]
+pub extern "C" fn annis_cancellation_token_new() -> *mut CancellationToken {
+    Box::into_raw(Box::new(CancellationToken::new()))
+}
+
+/// Cancel all queries that have been started with this token. The cancelled
+/// queries fail with an error of the kind `Cancelled`. A cancelled token
+/// can't be used for new queries.
+///
+/// - `ptr` - The cancellation token.
+#[unsafe(no_mangle)]
+pub extern "C" fn annis_cancellation_token_cancel(ptr: *const CancellationToken) {
+    cast_const(ptr).cancel();
+}
+
+/// Frees the cancellation token. Queries that use the token are not
+/// cancelled.
+///
+/// # Safety
+///
+/// This functions dereferences the pointer given as argument and is therefore unsafe.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn annis_cancellation_token_free(ptr: *mut CancellationToken) {
+    if ptr.is_null() {
+        return;
+    }
+    let ptr = unsafe { Box::from_raw(ptr) };
+    std::mem::drop(ptr);
+}
+
+fn cancellation_from_ptr(ptr: *const CancellationToken) -> Option<CancellationToken> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(cast_const(ptr).clone())
+    }
+}
+
 /// Count the number of results for a `query`.
 /// - `ptr` - The corpus storage object.
 /// - `corpus_names` - The name of the corpora to execute the query on.
 /// - `query` - The query as string.
 /// - `query_language` The query language of the query (e.g. AQL).
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
 /// - `err` - Pointer to a list of errors. If any error occurred, this list will be non-empty.
 ///
 /// Returns the count as number.
@@ -97,6 +144,7 @@ pub extern "C" fn annis_cs_count(
     corpus_names: *const Vec<CString>,
     query: *const libc::c_char,
     query_language: QueryLanguage,
+    cancellation: *const CancellationToken,
     err: *mut *mut ErrorList,
 ) -> u64 {
     let cs: &CorpusStorage = cast_const(ptr);
@@ -112,6 +160,7 @@ pub extern "C" fn annis_cs_count(
         corpus_names: &corpus_names,
         query_language,
         timeout: None,
+        cancellation: cancellation_from_ptr(cancellation),
     };
 
     map_cerr(cs.count(search_query), err).unwrap_or(0)
@@ -123,6 +172,7 @@ pub extern "C" fn annis_cs_count(
 /// - `corpus_names` - The name of the corpora to execute the query on.
 /// - `query` - The query as string.
 /// - `query_language` The query language of the query (e.g. AQL).
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
 /// - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
 #[unsafe(no_mangle)]
 pub extern "C" fn annis_cs_count_extra(
@@ -130,6 +180,7 @@ pub extern "C" fn annis_cs_count_extra(
     corpus_names: *const Vec<CString>,
     query: *const libc::c_char,
     query_language: QueryLanguage,
+    cancellation: *const CancellationToken,
     err: *mut *mut ErrorList,
 ) -> CountExtra {
     let cs: &CorpusStorage = cast_const(ptr);
@@ -145,6 +196,7 @@ pub extern "C" fn annis_cs_count_extra(
         corpus_names: &corpus_names,
         query_language,
         timeout: None,
+        cancellation: cancellation_from_ptr(cancellation),
     };
     map_cerr(cs.count_extra(search_query), err).unwrap_or_default()
 }
@@ -160,6 +212,7 @@ pub extern "C" fn annis_cs_count_extra(
 /// - `offset` - Skip the `n` first results, where `n` is the offset.
 /// - `limit` - Return at most `n` matches, where `n` is the limit.  Use `None` to allow unlimited result sizes.
 /// - `order` - Specify the order of the matches.
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
 /// - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
 ///
 /// Returns a vector of match IDs, where each match ID consists of the matched node annotation identifiers separated by spaces.
@@ -177,6 +230,7 @@ pub unsafe extern "C" fn annis_cs_find(
     offset: libc::size_t,
     limit: *const libc::size_t,
     order: ResultOrder,
+    cancellation: *const CancellationToken,
     err: *mut *mut ErrorList,
 ) -> *mut Vec<CString> {
     let cs: &CorpusStorage = cast_const(ptr);
@@ -192,6 +246,7 @@ pub unsafe extern "C" fn annis_cs_find(
         corpus_names: &corpus_names,
         query_language,
         timeout: None,
+        cancellation: cancellation_from_ptr(cancellation),
     };
 
     let limit = unsafe { if limit.is_null() { None } else { Some(*limit) } };
@@ -207,6 +262,156 @@ pub unsafe extern "C" fn annis_cs_find(
         .unwrap_or_else(std::ptr::null_mut)
 }
 
+/// Number of match IDs that are buffered by a [FindIterator] before the query
+/// execution waits for them to be fetched.
+const FIND_ITER_CHANNEL_SIZE: usize = 1024;
+
+/// Iterator over the match IDs of a query, which are found by a background thread.
+pub struct FindIterator {
+    receiver: Option<Receiver<Result<String>>>,
+    producer: Option<JoinHandle<()>>,
+    cancellation: CancellationToken,
+}
+
+impl Drop for FindIterator {
+    fn drop(&mut self) {
+        // Cancel the query, so the producer does not need to wait for the
+        // next match before it notices that the receiver is gone.
+        self.cancellation.cancel();
+        self.receiver.take();
+        if let Some(producer) = self.producer.take() {
+            producer.join().ok();
+        }
+    }
+}
+
+/// Allows to use the corpus storage from the producer thread of a [FindIterator].
+struct CorpusStoragePtr(*const CorpusStorage);
+
+unsafe impl Send for CorpusStoragePtr {}
+
+impl CorpusStoragePtr {
+    fn get(&self) -> &CorpusStorage {
+        cast_const(self.0)
+    }
+}
+
+/// Find all results for a `query` and return an iterator over the match ID for each result.
+///
+/// In contrast to `annis_cs_find(...)`, the matches are not collected first and can be fetched
+/// with `annis_find_iter_next(...)` as soon as they are found.
+///
+/// - `ptr` - The corpus storage object.
+/// - `corpus_names` - The name of the corpora to execute the query on.
+/// - `query` - The query as string.
+/// - `query_language` The query language of the query (e.g. AQL).
+/// - `offset` - Skip the `n` first results, where `n` is the offset.
+/// - `limit` - Return at most `n` matches, where `n` is the limit.  Use `None` to allow unlimited result sizes.
+/// - `order` - Specify the order of the matches.
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
+/// - `err` - Pointer to a list of errors. If the query could not be started, this list will be non-empty and `NULL` is returned.
+///
+/// Errors of the query execution are returned by `annis_find_iter_next(...)`.
+/// The returned iterator must be freed with `annis_find_iter_free(...)`, which
+/// also cancels the query if it is still running, but not the given `cancellation` token.
+///
+/// # Safety
+///
+/// The query is executed in a background thread, which uses the corpus storage `ptr`.
+/// The corpus storage must not be freed before the returned iterator.
+/// This functions dereferences the `err` pointer and is therefore unsafe.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn annis_cs_find_iter(
+    ptr: *const CorpusStorage,
+    corpus_names: *const Vec<CString>,
+    query: *const libc::c_char,
+    query_language: QueryLanguage,
+    offset: libc::size_t,
+    limit: *const libc::size_t,
+    order: ResultOrder,
+    cancellation: *const CancellationToken,
+    err: *mut *mut ErrorList,
+) -> *mut FindIterator {
+    let cs = CorpusStoragePtr(ptr);
+
+    let query = cstr(query).to_string();
+    let corpus_names: Vec<String> = cast_const(corpus_names)
+        .iter()
+        .map(|cn| String::from(cn.to_string_lossy()))
+        .collect();
+    let limit = unsafe { if limit.is_null() { None } else { Some(*limit) } };
+
+    // Freeing the iterator must only cancel this query and not the other
+    // queries that use the given token
+    let cancellation = cancellation_from_ptr(cancellation)
+        .map(|c| c.child_token())
+        .unwrap_or_default();
+    let query_cancellation = cancellation.clone();
+    let (sender, receiver) = mpsc::sync_channel(FIND_ITER_CHANNEL_SIZE);
+    let producer = std::thread::Builder::new().spawn(move || {
+        let search_query = SearchQuery {
+            query: &query,
+            corpus_names: &corpus_names,
+            query_language,
+            timeout: None,
+            cancellation: Some(query_cancellation),
+        };
+        let result = cs.get().find_iter(search_query, offset, limit, order, |m| {
+            sender.send(Ok(m)).is_ok()
+        });
+        if let Err(e) = result {
+            sender.send(Err(e)).ok();
+        }
+    });
+
+    map_cerr(producer, err)
+        .map(|producer| {
+            Box::into_raw(Box::new(FindIterator {
+                receiver: Some(receiver),
+                producer: Some(producer),
+                cancellation,
+            }))
+        })
+        .unwrap_or_else(std::ptr::null_mut)
+}
+
+/// Returns the next match ID for the iterator given by the `ptr` argument
+/// or `NULL` if there are no more matches.
+///
+/// The call blocks until the next match has been found.
+///
+/// - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
+///
+/// The resulting char* must be freeed with annis_str_free!
+#[unsafe(no_mangle)]
+pub extern "C" fn annis_find_iter_next(
+    ptr: *mut FindIterator,
+    err: *mut *mut ErrorList,
+) -> *mut libc::c_char {
+    let it: &mut FindIterator = cast_mut(ptr);
+    if let Some(receiver) = &it.receiver
+        && let Ok(m) = receiver.recv()
+        && let Some(m) = map_cerr(m, err)
+    {
+        return CString::new(m).unwrap_or_default().into_raw();
+    }
+    std::ptr::null_mut()
+}
+
+/// Frees the iterator given as `ptr` argument and stops the execution of its query.
+///
+/// # Safety
+///
+/// This functions dereferences the `ptr` pointer and is therefore unsafe.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn annis_find_iter_free(ptr: *mut FindIterator) {
+    if ptr.is_null() {
+        return;
+    }
+    let it = unsafe { Box::from_raw(ptr) };
+    drop(it);
+}
+
 /// Return the copy of a subgraph which includes the given list of node annotation identifiers,
 /// the nodes that cover the same token as the given nodes and
 /// all nodes that cover the token which are part of the defined context.
@@ -305,6 +510,7 @@ pub extern "C" fn annis_cs_corpus_graph(
 /// - `corpus_name` - The name of the corpus for which the subgraph should be generated from.
 /// - `query` - The query which defines included nodes.
 /// - `query_language` - The query language of the query (e.g. AQL).
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
 /// - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
 #[unsafe(no_mangle)]
 pub extern "C" fn annis_cs_subgraph_for_query(
@@ -312,6 +518,7 @@ pub extern "C" fn annis_cs_subgraph_for_query(
     corpus_name: *const libc::c_char,
     query: *const libc::c_char,
     query_language: QueryLanguage,
+    cancellation: *const CancellationToken,
     err: *mut *mut ErrorList,
 ) -> *mut AnnotationGraph {
     let cs: &CorpusStorage = cast_const(ptr);
@@ -319,7 +526,13 @@ pub extern "C" fn annis_cs_subgraph_for_query(
     let query = cstr(query);
 
     map_cerr(
-        cs.subgraph_for_query(&corpus, &query, query_language, None),
+        cs.subgraph_for_query(
+            &corpus,
+            &query,
+            query_language,
+            None,
+            cancellation_from_ptr(cancellation),
+        ),
         err,
     )
     .map(|result| Box::into_raw(Box::new(result)))
@@ -333,6 +546,7 @@ pub extern "C" fn annis_cs_subgraph_for_query(
 /// - `query` - The query which defines included nodes.
 /// - `query_language` - The query language of the query (e.g. AQL).
 /// - `component_type_filter` - Only include edges of that belong to a component of the given type.
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
 /// - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
 #[unsafe(no_mangle)]
 pub extern "C" fn annis_cs_subgraph_for_query_with_ctype(
@@ -341,6 +555,7 @@ pub extern "C" fn annis_cs_subgraph_for_query_with_ctype(
     query: *const libc::c_char,
     query_language: QueryLanguage,
     component_type_filter: AnnotationComponentType,
+    cancellation: *const CancellationToken,
     err: *mut *mut ErrorList,
 ) -> *mut AnnotationGraph {
     let cs: &CorpusStorage = cast_const(ptr);
@@ -348,7 +563,13 @@ pub extern "C" fn annis_cs_subgraph_for_query_with_ctype(
     let query = cstr(query);
 
     map_cerr(
-        cs.subgraph_for_query(&corpus, &query, query_language, Some(component_type_filter)),
+        cs.subgraph_for_query(
+            &corpus,
+            &query,
+            query_language,
+            Some(component_type_filter),
+            cancellation_from_ptr(cancellation),
+        ),
         err,
     )
     .map(|result| Box::into_raw(Box::new(result)))
@@ -362,6 +583,7 @@ pub extern "C" fn annis_cs_subgraph_for_query_with_ctype(
 /// - `query` - The query as string.
 /// - `query_language` The query language of the query (e.g. AQL).
 /// - `frequency_query_definition` - A string representation of the list of frequency query definitions.
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
 /// - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
 ///
 /// Returns a frequency table of strings.
@@ -372,6 +594,7 @@ pub extern "C" fn annis_cs_frequency(
     query: *const libc::c_char,
     query_language: QueryLanguage,
     frequency_query_definition: *const libc::c_char,
+    cancellation: *const CancellationToken,
     err: *mut *mut ErrorList,
 ) -> *mut FrequencyTable<CString> {
     let cs: &CorpusStorage = cast_const(ptr);
@@ -387,6 +610,7 @@ pub extern "C" fn annis_cs_frequency(
         corpus_names: &corpus_names,
         query_language,
         timeout: None,
+        cancellation: cancellation_from_ptr(cancellation),
     };
 
     let frequency_query_definition = cstr(frequency_query_definition);
@@ -396,28 +620,80 @@ pub extern "C" fn annis_cs_frequency(
         .collect();
 
     match map_cerr(cs.frequency(search_query, table_def), err) {
-        Some(orig_ft) => {
-            let mut result: FrequencyTable<CString> = FrequencyTable::new();
-
-            for row in orig_ft.into_iter() {
-                let mut new_tuple: Vec<CString> = Vec::with_capacity(row.values.len());
-                for att in row.values.into_iter() {
-                    if let Ok(att) = CString::new(att) {
-                        new_tuple.push(att);
-                    } else {
-                        new_tuple.push(CString::default())
-                    }
-                }
+        Some(orig_ft) => Box::into_raw(Box::new(frequency_table_to_cstring(orig_ft))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Count the number of results for a `query`, grouped by the given criteria.
+///
+/// - `ptr` - The corpus storage object.
+/// - `corpus_names` - The name of the corpora to execute the query on.
+/// - `query` - The query as string.
+/// - `query_language` The query language of the query (e.g. AQL).
+/// - `group_by` - A comma-separated list of group definitions, e.g. `doc,meta:genre,1:pos`.
+/// - `cancellation` - Token that allows to cancel the query from another thread with `annis_cancellation_token_cancel(...)`. Use `NULL` if the query is not cancelled.
+/// - `err` - Pointer to a list of errors. If any error occured, this list will be non-empty.
+///
+/// Returns a frequency table of strings with the count for each combination of group values.
+#[unsafe(no_mangle)]
+pub extern "C" fn annis_cs_count_grouped(
+    ptr: *const CorpusStorage,
+    corpus_names: *const Vec<CString>,
+    query: *const libc::c_char,
+    query_language: QueryLanguage,
+    group_by: *const libc::c_char,
+    cancellation: *const CancellationToken,
+    err: *mut *mut ErrorList,
+) -> *mut FrequencyTable<CString> {
+    let cs: &CorpusStorage = cast_const(ptr);
+
+    let query = cstr(query);
+    let corpus_names: Vec<String> = cast_const(corpus_names)
+        .iter()
+        .map(|cn| String::from(cn.to_string_lossy()))
+        .collect();
 
-                result.push(FrequencyTableRow {
-                    values: new_tuple,
-                    count: row.count,
-                });
+    let search_query = SearchQuery {
+        query: &query,
+        corpus_names: &corpus_names,
+        query_language,
+        timeout: None,
+        cancellation: cancellation_from_ptr(cancellation),
+    };
+
+    let group_by = cstr(group_by);
+    let group_by: graphannis::errors::Result<Vec<GroupByEntry>> =
+        group_by.split(',').map(|g| g.parse()).collect();
+    let Some(group_by) = map_cerr(group_by, err) else {
+        return std::ptr::null_mut();
+    };
+
+    match map_cerr(cs.count_grouped(search_query, group_by), err) {
+        Some(orig_ft) => Box::into_raw(Box::new(frequency_table_to_cstring(orig_ft))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+fn frequency_table_to_cstring(orig_ft: FrequencyTable<String>) -> FrequencyTable<CString> {
+    let mut result: FrequencyTable<CString> = FrequencyTable::new();
+
+    for row in orig_ft.into_iter() {
+        let mut new_tuple: Vec<CString> = Vec::with_capacity(row.values.len());
+        for att in row.values.into_iter() {
+            if let Ok(att) = CString::new(att) {
+                new_tuple.push(att);
+            } else {
+                new_tuple.push(CString::default())
             }
-            Box::into_raw(Box::new(result))
         }
-        None => std::ptr::null_mut(),
+
+        result.push(FrequencyTableRow {
+            values: new_tuple,
+            count: row.count,
+        });
     }
+    result
 }
 
 /// List all available corpora in the corpus storage.
@@ -4,10 +4,14 @@ extern crate anyhow;
 use clap::{App, Arg};
 use compound_duration::format_dhms;
 use graphannis::CorpusStorage;
+use graphannis::corpusstorage::FindCursor;
 use graphannis::corpusstorage::FrequencyDefEntry;
+use graphannis::corpusstorage::FrequencyTable;
+use graphannis::corpusstorage::GroupByEntry;
 use graphannis::corpusstorage::LoadStatus;
 use graphannis::corpusstorage::QueryLanguage;
 use graphannis::corpusstorage::ResultOrder;
+use graphannis::corpusstorage::SNAPSHOT_SEPARATOR;
 use graphannis::corpusstorage::{CorpusInfo, SearchQuery};
 use graphannis::corpusstorage::{ExportFormat, ImportFormat};
 use log::info;
@@ -48,18 +52,26 @@ impl ConsoleHelper {
         known_commands.insert("corpus".to_string());
         known_commands.insert("set-offset".to_string());
         known_commands.insert("set-limit".to_string());
+        known_commands.insert("set-cursor".to_string());
         known_commands.insert("set-order".to_string());
+        known_commands.insert("set-sample-seed".to_string());
         known_commands.insert("set-timeout".to_string());
         known_commands.insert("preload".to_string());
         known_commands.insert("count".to_string());
+        known_commands.insert("count-grouped".to_string());
         known_commands.insert("find".to_string());
         known_commands.insert("frequency".to_string());
         known_commands.insert("plan".to_string());
+        known_commands.insert("analyze".to_string());
         known_commands.insert("re-optimize".to_string());
         known_commands.insert("set-disk-based".to_string());
         known_commands.insert("set-parallel-search".to_string());
         known_commands.insert("set-quirks-mode".to_string());
         known_commands.insert("info".to_string());
+        known_commands.insert("check-integrity".to_string());
+        known_commands.insert("snapshot".to_string());
+        known_commands.insert("list-snapshots".to_string());
+        known_commands.insert("delete-snapshot".to_string());
 
         known_commands.insert("quit".to_string());
         known_commands.insert("exit".to_string());
@@ -129,7 +141,9 @@ struct AnnisRunner {
     current_corpus: Vec<String>,
     offset: usize,
     limit: Option<usize>,
+    cursor: Option<FindCursor>,
     result_order: ResultOrder,
+    sample_seed: Option<u64>,
     data_dir: PathBuf,
     use_parallel_joins: bool,
     use_disk: bool,
@@ -148,8 +162,10 @@ impl AnnisRunner {
             query_language: QueryLanguage::AQL,
             offset: 0,
             limit: None,
+            cursor: None,
             timeout: None,
             result_order: ResultOrder::default(),
+            sample_seed: None,
         })
     }
 
@@ -214,18 +230,26 @@ impl AnnisRunner {
                 "corpus" => self.corpus(&args),
                 "set-offset" => self.set_offset(&args),
                 "set-limit" => self.set_limit(&args),
+                "set-cursor" => self.set_cursor(&args),
                 "set-order" => self.set_order(&args),
+                "set-sample-seed" => self.set_sample_seed(&args),
                 "set-timeout" => self.set_timeout(&args),
                 "preload" => self.preload(),
                 "plan" => self.plan(&args),
+                "analyze" => self.analyze(&args),
                 "re-optimize" => self.reoptimize(),
                 "count" => self.count(&args),
+                "count-grouped" => self.count_grouped(&args),
                 "find" => self.find(&args),
                 "frequency" => self.frequency(&args),
                 "set-parallel-search" => self.use_parallel(&args),
                 "set-disk-based" => self.use_disk(&args),
                 "set-quirks-mode" => self.quirks_mode(&args),
                 "info" => self.info(&args),
+                "check-integrity" => self.check_integrity(&args),
+                "snapshot" => self.snapshot(&args),
+                "list-snapshots" => self.list_snapshots(),
+                "delete-snapshot" => self.delete_snapshot(&args),
                 "quit" | "exit" => return false,
                 _ => Err(anyhow!("unknown command \"{}\"", cmd)),
             };
@@ -394,16 +418,24 @@ impl AnnisRunner {
         if args.is_empty() {
             self.current_corpus = vec![];
         } else {
-            let corpora = self
+            let storage = self
                 .storage
                 .as_ref()
-                .ok_or_else(|| anyhow!("No corpus storage location set"))?
-                .list()?;
-            let corpora: BTreeSet<_> = corpora.into_iter().map(|c| c.name).collect();
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?;
+            let corpora: BTreeSet<_> = storage.list()?.into_iter().map(|c| c.name).collect();
             let selected = args.split_ascii_whitespace();
             self.current_corpus = Vec::new();
             for s in selected {
-                if corpora.contains(s) {
+                let is_snapshot = s
+                    .rsplit_once(SNAPSHOT_SEPARATOR)
+                    .filter(|(corpus, _)| corpora.contains(*corpus))
+                    .map(|(corpus, snapshot)| {
+                        storage
+                            .list_snapshots(corpus)
+                            .is_ok_and(|snapshots| snapshots.iter().any(|n| n == snapshot))
+                    })
+                    .unwrap_or(false);
+                if corpora.contains(s) || is_snapshot {
                     self.current_corpus.push(s.to_string());
                 } else {
                     println!(
@@ -434,6 +466,15 @@ impl AnnisRunner {
         Ok(())
     }
 
+    fn set_cursor(&mut self, args: &str) -> Result<()> {
+        if args.is_empty() {
+            self.cursor = None;
+        } else {
+            self.cursor = Some(args.trim().parse()?);
+        }
+        Ok(())
+    }
+
     fn set_order(&mut self, args: &str) -> Result<()> {
         if args.is_empty() {
             self.result_order = ResultOrder::default();
@@ -456,6 +497,18 @@ impl AnnisRunner {
         Ok(())
     }
 
+    fn set_sample_seed(&mut self, args: &str) -> Result<()> {
+        if args.is_empty() {
+            self.sample_seed = None;
+            println!("Sampling disabled");
+        } else {
+            let seed = args.trim().parse::<u64>()?;
+            println!("Sample seed set to {}", seed);
+            self.sample_seed = Some(seed);
+        }
+        Ok(())
+    }
+
     fn set_timeout(&mut self, args: &str) -> Result<()> {
         if args.is_empty() {
             self.timeout = None;
@@ -488,6 +541,104 @@ impl AnnisRunner {
         Ok(())
     }
 
+    fn check_integrity(&self, args: &str) -> Result<()> {
+        if self.current_corpus.is_empty() {
+            println!("You need to select a corpus first with the \"corpus\" command");
+        } else {
+            let repair = match args.trim() {
+                "" => false,
+                "repair" => true,
+                _ => bail!("Unknown argument \"{}\", only \"repair\" is allowed", args),
+            };
+            let storage = self
+                .storage
+                .as_ref()
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?;
+            for corpus in self.current_corpus.iter() {
+                let report = storage.check_integrity(corpus, repair)?;
+                for problem in report.repaired {
+                    println!("{}: repaired: {}", corpus, problem);
+                }
+                for problem in &report.problems {
+                    let hint = if problem.is_repairable() {
+                        " (use \"check-integrity repair\" to repair)"
+                    } else {
+                        ""
+                    };
+                    println!("{}: {}{}", corpus, problem, hint);
+                }
+                if report.problems.is_empty() {
+                    println!("{}: no problems found", corpus);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self, args: &str) -> Result<()> {
+        if self.current_corpus.is_empty() {
+            println!("You need to select a corpus first with the \"corpus\" command");
+        } else {
+            let snapshot_name = args.trim();
+            if snapshot_name.is_empty() {
+                bail!("You need the name of the snapshot as an argument");
+            }
+            let storage = self
+                .storage
+                .as_ref()
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?;
+            for corpus in self.current_corpus.iter() {
+                storage.create_snapshot(corpus, snapshot_name)?;
+                println!(
+                    "Created snapshot {}{}{}",
+                    corpus, SNAPSHOT_SEPARATOR, snapshot_name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn list_snapshots(&self) -> Result<()> {
+        if self.current_corpus.is_empty() {
+            println!("You need to select a corpus first with the \"corpus\" command");
+        } else {
+            let storage = self
+                .storage
+                .as_ref()
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?;
+            for corpus in self.current_corpus.iter() {
+                for snapshot_name in storage.list_snapshots(corpus)? {
+                    println!("{}{}{}", corpus, SNAPSHOT_SEPARATOR, snapshot_name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_snapshot(&self, args: &str) -> Result<()> {
+        if self.current_corpus.is_empty() {
+            println!("You need to select a corpus first with the \"corpus\" command");
+        } else {
+            let snapshot_name = args.trim();
+            if snapshot_name.is_empty() {
+                bail!("You need the name of the snapshot as an argument");
+            }
+            let storage = self
+                .storage
+                .as_ref()
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?;
+            for corpus in self.current_corpus.iter() {
+                if storage.delete_snapshot(corpus, snapshot_name)? {
+                    info!(
+                        "Deleted snapshot {}{}{}.",
+                        corpus, SNAPSHOT_SEPARATOR, snapshot_name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn preload(&mut self) -> Result<()> {
         if self.current_corpus.is_empty() {
             println!("You need to select a corpus first with the \"corpus\" command");
@@ -527,11 +678,34 @@ impl AnnisRunner {
         Ok(())
     }
 
+    fn analyze(&self, args: &str) -> Result<()> {
+        if self.current_corpus.is_empty() {
+            println!("You need to select a corpus first with the \"corpus\" command");
+        } else {
+            let t_before = std::time::SystemTime::now();
+            let analysis = self
+                .storage
+                .as_ref()
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?
+                .analyze(self.create_query_from_args(args))?;
+            let load_time = t_before.elapsed();
+            if let Ok(t) = load_time {
+                info! {"Analyzed query in {} ms", (t.as_secs() * 1000 + t.subsec_nanos() as u64 / 1_000_000)};
+            }
+
+            for a in analysis {
+                println!("{}", a);
+            }
+        }
+        Ok(())
+    }
+
     fn create_query_from_args<'a>(&'a self, query: &'a str) -> SearchQuery<'a, String> {
         SearchQuery {
             corpus_names: &self.current_corpus,
             query_language: self.query_language,
             timeout: self.timeout,
+            cancellation: None,
             query,
         }
     }
@@ -562,17 +736,44 @@ impl AnnisRunner {
         if self.current_corpus.is_empty() {
             println!("You need to select a corpus first with the \"corpus\" command");
         } else {
-            let t_before = std::time::SystemTime::now();
-            let matches = self
+            let storage = self
                 .storage
                 .as_ref()
-                .ok_or_else(|| anyhow!("No corpus storage location set"))?
-                .find(
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?;
+            let use_cursor = self.offset == 0
+                && (self.result_order == ResultOrder::Normal
+                    || self.result_order == ResultOrder::Inverted);
+            if self.cursor.is_some() && !use_cursor {
+                bail!("A cursor can't be combined with an offset or a random or unsorted order");
+            }
+
+            let t_before = std::time::SystemTime::now();
+            let (matches, next_cursor) = if let Some(seed) = self.sample_seed {
+                let limit = self
+                    .limit
+                    .ok_or_else(|| anyhow!("You need to set a limit to draw a sample"))?;
+                let matches = storage.sample(self.create_query_from_args(args), limit, seed)?;
+                (matches, None)
+            } else if let Some(limit) = self.limit
+                && use_cursor
+            {
+                storage.find_with_cursor(
+                    self.create_query_from_args(args),
+                    self.cursor.as_ref(),
+                    limit,
+                    self.result_order,
+                )?
+            } else if self.cursor.is_some() {
+                bail!("You need to set a limit to use a cursor");
+            } else {
+                let matches = storage.find(
                     self.create_query_from_args(args),
                     self.offset,
                     self.limit,
                     self.result_order,
                 )?;
+                (matches, None)
+            };
             let load_time = t_before.elapsed();
             if let Ok(t) = load_time {
                 info! {"Executed query in {} ms", (t.as_secs() * 1000 + t.subsec_nanos() as u64 / 1_000_000)};
@@ -581,6 +782,9 @@ impl AnnisRunner {
             for m in matches {
                 println!("{}", m);
             }
+            if let Some(next_cursor) = next_cursor {
+                info!("Use \"set-cursor {}\" to get the next page", next_cursor);
+            }
         }
         Ok(())
     }
@@ -602,13 +806,10 @@ impl AnnisRunner {
                 return Ok(());
             };
 
-            let mut out = Table::new();
-            let mut header_row = Row::empty();
-            for def in table_def.iter() {
-                header_row.add_cell(Cell::from(&format!("{}#{}", def.node_ref, def.name)));
-            }
-            header_row.add_cell(Cell::from(&"count"));
-            out.add_row(header_row);
+            let header: Vec<String> = table_def
+                .iter()
+                .map(|def| format!("{}#{}", def.node_ref, def.name))
+                .collect();
 
             let t_before = std::time::SystemTime::now();
             let frequency_table = self
@@ -621,27 +822,7 @@ impl AnnisRunner {
                 info! {"Executed query in {} ms", (t.as_secs() * 1000 + t.subsec_nanos() as u64 / 1_000_000)};
             }
 
-            // map the resulting frequency table to an output
-
-            // TODO: map header
-            for row in frequency_table.into_iter() {
-                let mut out_row = Row::empty();
-                for att in row.values.iter() {
-                    if att.trim().is_empty() {
-                        // This is whitespace only, add some quotation marks to show to make it visible
-                        let mut val = "'".to_owned();
-                        val.push_str(att);
-                        val.push('\'');
-                        out_row.add_cell(Cell::from(&val));
-                    } else {
-                        out_row.add_cell(Cell::from(att));
-                    }
-                }
-                // also add the count
-                out_row.add_cell(Cell::from(&row.count));
-                out.add_row(out_row);
-            }
-            out.printstd();
+            print_frequency_table(&header, frequency_table);
 
             // TODO output error if needed
         }
@@ -649,6 +830,49 @@ impl AnnisRunner {
         Ok(())
     }
 
+    fn count_grouped(&self, args: &str) -> Result<()> {
+        if self.current_corpus.is_empty() {
+            println!("You need to select a corpus first with the \"corpus\" command");
+        } else {
+            let splitted_arg: Vec<&str> = args.splitn(2, ' ').collect();
+            let group_by: Vec<GroupByEntry> = if splitted_arg.len() == 2 {
+                splitted_arg[0]
+                    .split(',')
+                    .map(|g| g.parse())
+                    .collect::<std::result::Result<_, _>>()?
+            } else {
+                println!(
+                    "You have to give the group definition as first argument and the AQL as second argument"
+                );
+                return Ok(());
+            };
+
+            let header: Vec<String> = group_by
+                .iter()
+                .map(|g| match g {
+                    GroupByEntry::Document => "doc".to_string(),
+                    GroupByEntry::Annotation(def) => format!("{}#{}", def.node_ref, def.name),
+                    GroupByEntry::Meta { name, .. } => format!("meta#{}", name),
+                })
+                .collect();
+
+            let t_before = std::time::SystemTime::now();
+            let frequency_table = self
+                .storage
+                .as_ref()
+                .ok_or_else(|| anyhow!("No corpus storage location set"))?
+                .count_grouped(self.create_query_from_args(splitted_arg[1]), group_by)?;
+            let load_time = t_before.elapsed();
+            if let Ok(t) = load_time {
+                info! {"Executed query in {} ms", (t.as_secs() * 1000 + t.subsec_nanos() as u64 / 1_000_000)};
+            }
+
+            print_frequency_table(&header, frequency_table);
+        }
+
+        Ok(())
+    }
+
     fn use_parallel(&mut self, args: &str) -> Result<()> {
         let new_val = match args.trim().to_lowercase().as_str() {
             "on" | "true" => true,
@@ -714,6 +938,35 @@ impl AnnisRunner {
     }
 }
 
+fn print_frequency_table(header: &[String], frequency_table: FrequencyTable<String>) {
+    let mut out = Table::new();
+    let mut header_row = Row::empty();
+    for h in header {
+        header_row.add_cell(Cell::from(h));
+    }
+    header_row.add_cell(Cell::from(&"count"));
+    out.add_row(header_row);
+
+    for row in frequency_table.into_iter() {
+        let mut out_row = Row::empty();
+        for att in row.values.iter() {
+            if att.trim().is_empty() {
+                // This is whitespace only, add some quotation marks to show to make it visible
+                let mut val = "'".to_owned();
+                val.push_str(att);
+                val.push('\'');
+                out_row.add_cell(Cell::from(&val));
+            } else {
+                out_row.add_cell(Cell::from(att));
+            }
+        }
+        // also add the count
+        out_row.add_cell(Cell::from(&row.count));
+        out.add_row(out_row);
+    }
+    out.printstd();
+}
+
 fn main() {
     let matches = App::new("graphANNIS CLI")
         .version(env!("CARGO_PKG_VERSION"))
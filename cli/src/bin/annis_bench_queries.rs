@@ -42,6 +42,7 @@ pub fn create_query_input<M>(
                     corpus_names: &def.corpus,
                     query_language: QueryLanguage::AQL,
                     timeout: None,
+                    cancellation: None,
                 };
                 let count = cs.count(search_query).unwrap_or_default();
                 assert_eq!(def.count, count);
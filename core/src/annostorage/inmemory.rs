@@ -1,9 +1,14 @@
-use super::{AnnotationStorage, EdgeAnnotationStorage, Match, NodeAnnotationStorage};
+use super::{
+    AnnotationStorage, EdgeAnnotationStorage, Match, MostCommonValuesCollector,
+    NodeAnnotationStorage, guess_count_from_statistics, guess_numeric_count_from_histogram,
+    merge_most_common_values, numeric_sort_key, numeric_sort_key_range, parse_numeric_value,
+};
 use crate::annostorage::ValueSearch;
 use crate::errors::Result;
 use crate::graph::NODE_NAME_KEY;
+use crate::try_as_boxed_iter;
 use crate::types::{AnnoKey, Annotation, Edge, NodeID};
-use crate::util::{self};
+use crate::util::{self, RegexFlags};
 use crate::{annostorage::symboltable::SymbolTable, errors::GraphAnnisCoreError};
 use core::ops::Bound::*;
 use itertools::Itertools;
@@ -12,6 +17,7 @@ use rustc_hash::FxHashSet;
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::hash::Hash;
+use std::ops::Bound;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -23,11 +29,18 @@ struct SparseAnnotation {
 
 type ValueItemMap<T> = HashMap<usize, BTreeSet<T>>;
 
+/// Maps the sort key of a numeric value to all value symbols that represent this number.
+type NumericValueMap = BTreeMap<u64, BTreeSet<usize>>;
+
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct AnnoStorageImpl<T: Ord + Hash + Default> {
     by_container: HashMap<T, Vec<SparseAnnotation>>,
     /// A map from an annotation key symbol to a map of all its values to the items having this value for the annotation key
     by_anno: HashMap<usize, ValueItemMap<T>>,
+    /// A map from an annotation key symbol to all numeric values of this annotation key.
+    /// This index is not serialized, but re-created from `by_anno` when loading the storage.
+    #[serde(skip)]
+    by_numeric_value: HashMap<usize, NumericValueMap>,
     /// Maps a distinct annotation key to the number of elements having this annotation key.
     anno_key_sizes: BTreeMap<AnnoKey, usize>,
     anno_keys: SymbolTable<AnnoKey>,
@@ -36,6 +49,11 @@ pub struct AnnoStorageImpl<T: Ord + Hash + Default> {
     /// Sampled histograms for each annotation key .
     /// Each histogram bound defines a range of values where we estimate that they have the same number of occurences.
     histogram_bounds: BTreeMap<usize, Vec<String>>,
+    /// The most common values and their number of occurrences for each annotation key symbol.
+    /// The list is saved in its own file, so storages of older versions without this list
+    /// can still be deserialized.
+    #[serde(skip)]
+    most_common_values: BTreeMap<usize, Vec<(String, usize)>>,
     largest_item: Option<T>,
     total_number_of_annos: usize,
 }
@@ -47,10 +65,12 @@ impl<T: Ord + Hash + Clone + serde::Serialize + serde::de::DeserializeOwned + De
         AnnoStorageImpl {
             by_container: HashMap::default(),
             by_anno: HashMap::default(),
+            by_numeric_value: HashMap::default(),
             anno_keys: SymbolTable::new(),
             anno_values: SymbolTable::new(),
             anno_key_sizes: BTreeMap::new(),
             histogram_bounds: BTreeMap::new(),
+            most_common_values: BTreeMap::new(),
             largest_item: None,
             total_number_of_annos: 0,
         }
@@ -59,9 +79,11 @@ impl<T: Ord + Hash + Clone + serde::Serialize + serde::de::DeserializeOwned + De
     fn clear_internal(&mut self) {
         self.by_container.clear();
         self.by_anno.clear();
+        self.by_numeric_value.clear();
         self.anno_keys.clear();
         self.anno_key_sizes.clear();
         self.histogram_bounds.clear();
+        self.most_common_values.clear();
         self.largest_item = None;
         self.anno_values.clear();
     }
@@ -84,6 +106,7 @@ impl<T: Ord + Hash + Clone + serde::Serialize + serde::de::DeserializeOwned + De
     }
 
     fn remove_element_from_by_anno(&mut self, anno: &SparseAnnotation, item: &T) {
+        let mut removed_anno_val = false;
         let remove_anno_key = if let Some(annos_for_key) = self.by_anno.get_mut(&anno.key) {
             let remove_anno_val = if let Some(items_for_anno) = annos_for_key.get_mut(&anno.val) {
                 items_for_anno.remove(item);
@@ -94,6 +117,7 @@ impl<T: Ord + Hash + Clone + serde::Serialize + serde::de::DeserializeOwned + De
             // remove the hash set of items for the original annotation if it empty
             if remove_anno_val {
                 annos_for_key.remove(&anno.val);
+                removed_anno_val = true;
                 annos_for_key.is_empty()
             } else {
                 false
@@ -101,12 +125,104 @@ impl<T: Ord + Hash + Clone + serde::Serialize + serde::de::DeserializeOwned + De
         } else {
             false
         };
+        if removed_anno_val {
+            self.remove_from_numeric_index(anno);
+        }
         if remove_anno_key {
             self.by_anno.remove(&anno.key);
             // TODO: remove from symbol table?
         }
     }
 
+    fn insert_into_numeric_index(&mut self, anno: &SparseAnnotation) {
+        if let Some(val) = self
+            .anno_values
+            .get_value_ref(anno.val)
+            .and_then(|val| parse_numeric_value(val))
+        {
+            self.by_numeric_value
+                .entry(anno.key)
+                .or_default()
+                .entry(numeric_sort_key(val))
+                .or_default()
+                .insert(anno.val);
+        }
+    }
+
+    fn remove_from_numeric_index(&mut self, anno: &SparseAnnotation) {
+        if let Some(val) = self
+            .anno_values
+            .get_value_ref(anno.val)
+            .and_then(|val| parse_numeric_value(val))
+            && let Some(numeric_values) = self.by_numeric_value.get_mut(&anno.key)
+        {
+            let sort_key = numeric_sort_key(val);
+            if let Some(value_symbols) = numeric_values.get_mut(&sort_key) {
+                value_symbols.remove(&anno.val);
+                if value_symbols.is_empty() {
+                    numeric_values.remove(&sort_key);
+                }
+            }
+            if numeric_values.is_empty() {
+                self.by_numeric_value.remove(&anno.key);
+            }
+        }
+    }
+
+    /// Re-create the numeric index from the annotation values.
+    fn rebuild_numeric_index(&mut self) {
+        self.by_numeric_value.clear();
+        let all_annos: Vec<SparseAnnotation> = self
+            .by_anno
+            .iter()
+            .flat_map(|(key, values)| {
+                values.keys().map(|val| SparseAnnotation {
+                    key: *key,
+                    val: *val,
+                })
+            })
+            .collect();
+        for anno in all_annos {
+            self.insert_into_numeric_index(&anno);
+        }
+    }
+
+    fn calculate_most_common_values(&mut self) {
+        self.most_common_values.clear();
+        for (anno_key, values_for_key) in self.by_anno.iter() {
+            let mut collector = MostCommonValuesCollector::new();
+            for (val, items) in values_for_key {
+                if let Some(val) = self.anno_values.get_value_ref(*val) {
+                    collector.add(Cow::Borrowed(val.as_str()), items.len());
+                }
+            }
+            self.most_common_values
+                .insert(*anno_key, collector.finish());
+        }
+    }
+
+    /// Load the list of most common values that has been saved with
+    /// [`save_most_common_values`](Self::save_most_common_values). If the file
+    /// does not exist, e.g. because the annotations have been saved by an older
+    /// version, the list is calculated from the annotations.
+    pub(crate) fn load_most_common_values(&mut self, path: &Path) -> Result<()> {
+        if path.is_file() {
+            let f = std::fs::File::open(path)?;
+            let mut reader = std::io::BufReader::new(f);
+            self.most_common_values = bincode::deserialize_from(&mut reader)?;
+        } else {
+            self.calculate_most_common_values();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn save_most_common_values(&self, path: &Path) -> Result<()> {
+        let f = std::fs::File::create(path)?;
+        let mut writer = std::io::BufWriter::new(f);
+        bincode::serialize_into(&mut writer, &self.most_common_values)?;
+        Ok(())
+    }
+
     fn check_and_remove_value_symbol(&mut self, value_id: usize) {
         let mut still_used = false;
         for values in self.by_anno.values() {
@@ -237,6 +353,7 @@ where
             .entry(anno.val)
             .or_default();
         item_list_for_value.insert(item.clone());
+        self.insert_into_numeric_index(&anno);
 
         if existing_anno.is_none() {
             // a new annotation entry was inserted and did not replace an existing one
@@ -291,6 +408,7 @@ where
                 // if annotation count dropped to zero remove the key
                 if new_key_count == 0 {
                     self.by_anno.remove(&key);
+                    self.by_numeric_value.remove(&key);
                     self.anno_key_sizes.remove(orig_key);
                     self.anno_keys.remove(key);
                 }
@@ -332,6 +450,7 @@ where
                     // if annotation count dropped to zero remove the key
                     if new_key_count == 0 {
                         self.by_anno.remove(&anno.key);
+                        self.by_numeric_value.remove(&anno.key);
                         self.anno_key_sizes.remove(&resolved_key);
                         self.anno_keys.remove(anno.key);
                     }
@@ -604,9 +723,10 @@ where
         namespace: Option<&str>,
         name: &str,
         pattern: &str,
+        flags: RegexFlags,
         negated: bool,
     ) -> Box<dyn Iterator<Item = Result<Match>> + 'a> {
-        let full_match_pattern = util::regex_full_match(pattern);
+        let full_match_pattern = util::regex_full_match_with_flags(pattern, flags);
         let compiled_result = regex::Regex::new(&full_match_pattern);
         if let Ok(re) = compiled_result {
             let it = self
@@ -638,6 +758,44 @@ where
         }
     }
 
+    fn numeric_anno_search<'a>(
+        &'a self,
+        namespace: Option<&str>,
+        name: &str,
+        lower: Bound<f64>,
+        upper: Bound<f64>,
+    ) -> Box<dyn Iterator<Item = Result<Match>> + 'a> {
+        let sort_key_range = if let Some(r) = numeric_sort_key_range(lower, upper) {
+            r
+        } else {
+            return Box::new(std::iter::empty());
+        };
+        let qualified_keys = match namespace {
+            Some(ns) => vec![AnnoKey {
+                ns: ns.into(),
+                name: name.into(),
+            }],
+            None => try_as_boxed_iter!(self.get_qnames(name)),
+        };
+        let it = qualified_keys
+            .into_iter()
+            .filter_map(move |key| {
+                let key_id = self.anno_keys.get_symbol(&key)?;
+                let numeric_values = self.by_numeric_value.get(&key_id)?;
+                let values_for_key = self.by_anno.get(&key_id)?;
+                Some((Arc::from(key), numeric_values, values_for_key))
+            })
+            .flat_map(move |(key, numeric_values, values_for_key)| {
+                numeric_values
+                    .range(sort_key_range.0..=sort_key_range.1)
+                    .flat_map(|(_, value_symbols)| value_symbols.iter())
+                    .filter_map(|val| values_for_key.get(val))
+                    .flat_map(|items| items.iter().cloned())
+                    .map(move |item| Ok((item, key.clone()).into()))
+            });
+        Box::new(it)
+    }
+
     fn get_all_keys_for_item(
         &self,
         item: &T,
@@ -706,48 +864,43 @@ where
             None => self.get_qnames(name)?,
         };
 
-        let mut universe_size: usize = 0;
-        let mut sum_histogram_buckets: usize = 0;
-        let mut count_matches: usize = 0;
+        let mut guessed_count = 0;
 
         // guess for each fully qualified annotation key and return the sum of all guesses
         for anno_key in qualified_keys {
-            if let Some(anno_size) = self.anno_key_sizes.get(&anno_key) {
-                universe_size += *anno_size;
-
-                if let Some(anno_key) = self.anno_keys.get_symbol(&anno_key)
-                    && let Some(histo) = self.histogram_bounds.get(&anno_key)
-                {
-                    // find the range in which the value is contained
-                    // we need to make sure the histogram is not empty -> should have at least two bounds
-                    if histo.len() >= 2 {
-                        sum_histogram_buckets += histo.len() - 1;
-
-                        for i in 0..histo.len() - 1 {
-                            let bucket_begin = &histo[i];
-                            let bucket_end = &histo[i + 1];
-                            // check if the range overlaps with the search range
-                            if bucket_begin.as_str() <= upper_val
-                                && lower_val <= bucket_end.as_str()
-                            {
-                                count_matches += 1;
-                            }
-                        }
-                    }
-                }
+            if let Some(anno_size) = self.anno_key_sizes.get(&anno_key)
+                && let Some(anno_key) = self.anno_keys.get_symbol(&anno_key)
+            {
+                let most_common_values = self
+                    .most_common_values
+                    .get(&anno_key)
+                    .map(|mcv| mcv.as_slice())
+                    .unwrap_or_default();
+                let histo = self
+                    .histogram_bounds
+                    .get(&anno_key)
+                    .map(|h| h.as_slice())
+                    .unwrap_or_default();
+                guessed_count += guess_count_from_statistics(
+                    most_common_values,
+                    histo,
+                    *anno_size,
+                    lower_val,
+                    upper_val,
+                );
             }
         }
-
-        if sum_histogram_buckets > 0 {
-            let selectivity: f64 = (count_matches as f64) / (sum_histogram_buckets as f64);
-            Ok((selectivity * (universe_size as f64)).round() as usize)
-        } else {
-            Ok(0)
-        }
+        Ok(guessed_count)
     }
 
-    fn guess_max_count_regex(&self, ns: Option<&str>, name: &str, pattern: &str) -> Result<usize> {
-        let full_match_pattern = util::regex_full_match(pattern);
+    fn guess_max_count_regex(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+        pattern: &str,
+        flags: RegexFlags,
+    ) -> Result<usize> {
+        let full_match_pattern = util::regex_full_match_with_flags(pattern, flags);
 
         // Get the total number of annotations with the namespace/name. We
         // can't get larger than this number
@@ -824,6 +977,33 @@ where
         }
     }
 
+    fn guess_max_count_numeric(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+        lower: Bound<f64>,
+        upper: Bound<f64>,
+    ) -> Result<usize> {
+        let qualified_keys = match ns {
+            Some(ns) => vec![AnnoKey {
+                name: name.into(),
+                ns: ns.into(),
+            }],
+            None => self.get_qnames(name)?,
+        };
+        let mut guessed_count = 0;
+        for anno_key in qualified_keys {
+            if let Some(anno_size) = self.anno_key_sizes.get(&anno_key)
+                && let Some(anno_key_symbol) = self.anno_keys.get_symbol(&anno_key)
+                && let Some(histo) = self.histogram_bounds.get(&anno_key_symbol)
+            {
+                guessed_count +=
+                    guess_numeric_count_from_histogram(histo, *anno_size, lower, upper);
+            }
+        }
+        Ok(guessed_count)
+    }
+
     fn guess_most_frequent_value(
         &self,
         ns: Option<&str>,
@@ -838,6 +1018,18 @@ where
             None => self.get_qnames(name)?,
         };
 
+        // use the exact counts of the most common values if available
+        let most_common_values = merge_most_common_values(
+            qualified_keys
+                .iter()
+                .filter_map(|anno_key| self.anno_keys.get_symbol(anno_key))
+                .filter_map(|anno_key| self.most_common_values.get(&anno_key))
+                .map(|mcv| mcv.as_slice()),
+        );
+        if let Some((value, _)) = most_common_values.into_iter().next() {
+            return Ok(Some(value));
+        }
+
         let mut sampled_values: HashMap<&str, usize> = HashMap::default();
 
         // guess for each fully qualified annotation key
@@ -867,6 +1059,28 @@ where
         }
     }
 
+    fn get_most_common_values(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+    ) -> Result<Vec<(Cow<'_, str>, usize)>> {
+        let qualified_keys = match ns {
+            Some(ns) => vec![AnnoKey {
+                name: name.into(),
+                ns: ns.into(),
+            }],
+            None => self.get_qnames(name)?,
+        };
+        let result = merge_most_common_values(
+            qualified_keys
+                .iter()
+                .filter_map(|anno_key| self.anno_keys.get_symbol(anno_key))
+                .filter_map(|anno_key| self.most_common_values.get(&anno_key))
+                .map(|mcv| mcv.as_slice()),
+        );
+        Ok(result)
+    }
+
     fn get_all_values(
         &self,
         key: &AnnoKey,
@@ -976,6 +1190,8 @@ where
                 }
             }
         }
+
+        self.calculate_most_common_values();
         Ok(())
     }
 
@@ -995,6 +1211,8 @@ where
 
         self.anno_keys.after_deserialization();
         self.anno_values.after_deserialization();
+        self.rebuild_numeric_index();
+        self.load_most_common_values(&location.join("nodes_most_common_values_v1.bin"))?;
 
         Ok(())
     }
@@ -1003,6 +1221,7 @@ where
         let f = std::fs::File::create(location.join("nodes_v1.bin"))?;
         let mut writer = std::io::BufWriter::new(f);
         bincode::serialize_into(&mut writer, self)?;
+        self.save_most_common_values(&location.join("nodes_most_common_values_v1.bin"))?;
 
         Ok(())
     }
@@ -1039,9 +1258,13 @@ impl NodeAnnotationStorage for AnnoStorageImpl<NodeID> {
 impl EdgeAnnotationStorage for AnnoStorageImpl<Edge> {}
 
 impl AnnoStorageImpl<Edge> {
+    /// Rebuild the indexes that are not serialized. The list of most common
+    /// values must be loaded separately with
+    /// [`load_most_common_values`](Self::load_most_common_values).
     pub fn after_deserialization(&mut self) {
         self.anno_keys.after_deserialization();
         self.anno_values.after_deserialization();
+        self.rebuild_numeric_index();
     }
 }
 
@@ -228,7 +228,13 @@ fn regex_search() {
     insert_test_strings(&mut a);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "A.*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "A.*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -237,7 +243,13 @@ fn regex_search() {
     assert_eq!(vec![1, 2, 3], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, ".A.", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            ".A.",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -246,7 +258,13 @@ fn regex_search() {
     assert_eq!(vec![1, 2, 3], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "_.*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "_.*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -255,7 +273,13 @@ fn regex_search() {
     assert_eq!(vec![0], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "(A|B).*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "(A|B).*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -264,7 +288,13 @@ fn regex_search() {
     assert_eq!(vec![1, 2, 3, 4], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "C.*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "C.*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let result = result.unwrap();
@@ -282,9 +312,261 @@ fn regex_estimation_without_prefix() {
     // Since there are very less than 250 items the histogram based statistics should be exact.
     assert_eq!(
         3,
-        a.guess_max_count_regex(Some(ANNIS_NS), NODE_NAME, ".A.")
+        a.guess_max_count_regex(Some(ANNIS_NS), NODE_NAME, ".A.", RegexFlags::default())
             .unwrap()
     );
     // Test without namespace
-    assert_eq!(3, a.guess_max_count_regex(None, NODE_NAME, ".A.").unwrap());
+    assert_eq!(
+        3,
+        a.guess_max_count_regex(None, NODE_NAME, ".A.", RegexFlags::default())
+            .unwrap()
+    );
+}
+
+#[test]
+fn regex_search_with_flags() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new();
+    insert_test_strings(&mut a);
+
+    let case_insensitive: RegexFlags = "i".parse().unwrap();
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "a.*", case_insensitive, false)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    assert_eq!(vec![1, 2, 3], result);
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(None, NODE_NAME, "(a|b).*", case_insensitive, false)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    assert_eq!(vec![1, 2, 3, 4], result);
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "aa.", case_insensitive, true)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    assert_eq!(vec![0, 4], result);
+
+    // Without the diacritic flag the letters with diacritics do not match
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "_âbc", case_insensitive, false)
+        .map_ok(|m| m.node)
+        .collect();
+    assert_eq!(0, result.unwrap().len());
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "_âbc",
+            "id".parse().unwrap(),
+            false,
+        )
+        .map_ok(|m| m.node)
+        .collect();
+    assert_eq!(vec![0], result.unwrap());
+}
+
+#[test]
+fn regex_estimation_with_flags() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new();
+    insert_test_strings(&mut a);
+    a.calculate_statistics().unwrap();
+
+    let case_insensitive: RegexFlags = "i".parse().unwrap();
+    let guessed = a
+        .guess_max_count_regex(Some(ANNIS_NS), NODE_NAME, "aa.", case_insensitive)
+        .unwrap();
+    assert!(guessed >= 3);
+    assert!(guessed <= 5);
+}
+
+fn numeric_search_result(
+    a: &AnnoStorageImpl<NodeID>,
+    lower: Bound<f64>,
+    upper: Bound<f64>,
+) -> Vec<NodeID> {
+    let result: Result<Vec<_>> = a
+        .numeric_anno_search(None, "val", lower, upper)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    result
+}
+
+#[test]
+fn numeric_search() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new();
+    let values = ["-3.5", "0", "1", "1.0", "10", "2e1", "abc", "inf", "NaN"];
+    for (node, val) in values.iter().enumerate() {
+        a.insert(
+            node as NodeID,
+            Annotation {
+                key: AnnoKey {
+                    ns: "test".into(),
+                    name: "val".into(),
+                },
+                val: (*val).into(),
+            },
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        vec![4, 5],
+        numeric_search_result(&a, Bound::Excluded(1.0), Bound::Unbounded)
+    );
+    assert_eq!(
+        vec![2, 3, 4, 5],
+        numeric_search_result(&a, Bound::Included(1.0), Bound::Unbounded)
+    );
+    assert_eq!(
+        vec![0, 1],
+        numeric_search_result(&a, Bound::Unbounded, Bound::Excluded(1.0))
+    );
+    assert_eq!(
+        vec![0, 1, 2, 3],
+        numeric_search_result(&a, Bound::Unbounded, Bound::Included(1.0))
+    );
+    assert_eq!(
+        0,
+        numeric_search_result(&a, Bound::Excluded(1.0), Bound::Excluded(1.0)).len()
+    );
+
+    // Replace and remove values and check the index is updated
+    a.insert(
+        4,
+        Annotation {
+            key: AnnoKey {
+                ns: "test".into(),
+                name: "val".into(),
+            },
+            val: "-10".into(),
+        },
+    )
+    .unwrap();
+    a.remove_annotation_for_item(
+        &5,
+        &AnnoKey {
+            ns: "test".into(),
+            name: "val".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        0,
+        numeric_search_result(&a, Bound::Excluded(1.0), Bound::Unbounded).len()
+    );
+    assert_eq!(
+        vec![0, 4],
+        numeric_search_result(&a, Bound::Unbounded, Bound::Excluded(0.0))
+    );
+
+    // The index must also be available after loading the annotation storage
+    let tmp = tempfile::tempdir().unwrap();
+    a.save_annotations_to(tmp.path()).unwrap();
+    let mut loaded: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new();
+    loaded.load_annotations_from(tmp.path()).unwrap();
+    assert_eq!(
+        vec![0, 4],
+        numeric_search_result(&loaded, Bound::Unbounded, Bound::Excluded(0.0))
+    );
+}
+
+#[test]
+fn numeric_estimation() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new();
+    for i in 0..100 {
+        a.insert(
+            i,
+            Annotation {
+                key: AnnoKey {
+                    ns: "test".into(),
+                    name: "val".into(),
+                },
+                val: i.to_string(),
+            },
+        )
+        .unwrap();
+    }
+    a.calculate_statistics().unwrap();
+
+    // Since there are less than 250 items the histogram based statistics should be exact.
+    assert_eq!(
+        10,
+        a.guess_max_count_numeric(None, "val", Bound::Excluded(89.0), Bound::Unbounded)
+            .unwrap()
+    );
+    assert_eq!(
+        50,
+        a.guess_max_count_numeric(Some("test"), "val", Bound::Unbounded, Bound::Excluded(50.0))
+            .unwrap()
+    );
+}
+
+#[test]
+fn most_common_values_estimation() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new();
+    let key = AnnoKey {
+        ns: "test".into(),
+        name: "pos".into(),
+    };
+    // A skewed distribution with one very common and many rare values
+    for i in 0..500 {
+        a.insert(
+            i,
+            Annotation {
+                key: key.clone(),
+                val: "NN".into(),
+            },
+        )
+        .unwrap();
+    }
+    for i in 500..650 {
+        a.insert(
+            i,
+            Annotation {
+                key: key.clone(),
+                val: format!("v{}", i),
+            },
+        )
+        .unwrap();
+    }
+    a.calculate_statistics().unwrap();
+
+    let most_common_values = a.get_most_common_values(None, "pos").unwrap();
+    assert_eq!(100, most_common_values.len());
+    assert_eq!((Cow::Borrowed("NN"), 500), most_common_values[0]);
+    assert_eq!(
+        Some(Cow::Borrowed("NN")),
+        a.guess_most_frequent_value(Some("test"), "pos").unwrap()
+    );
+
+    // The common value is counted exactly and a rare value can't be more
+    // common than the least common value of the list.
+    assert_eq!(500, a.guess_max_count(None, "pos", "NN", "NN").unwrap());
+    assert_eq!(1, a.guess_max_count(None, "pos", "v642", "v642").unwrap());
+
+    // The list must also be available after loading the annotation storage
+    let tmp = tempfile::tempdir().unwrap();
+    a.save_annotations_to(tmp.path()).unwrap();
+    assert!(tmp.path().join("nodes_most_common_values_v1.bin").is_file());
+    let mut loaded: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new();
+    loaded.load_annotations_from(tmp.path()).unwrap();
+    assert_eq!(
+        most_common_values,
+        loaded.get_most_common_values(Some("test"), "pos").unwrap()
+    );
+    assert_eq!(
+        500,
+        loaded.guess_max_count(None, "pos", "NN", "NN").unwrap()
+    );
 }
@@ -7,7 +7,12 @@ use smallvec::SmallVec;
 use crate::{
     errors::{GraphAnnisCoreError, Result},
     types::{AnnoKey, Annotation, Edge, NodeID},
+    util::RegexFlags,
 };
+use itertools::Itertools;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::ops::Bound;
 use std::sync::Arc;
 use std::{borrow::Cow, error::Error};
 use std::{boxed::Box, path::Path};
@@ -167,6 +172,195 @@ impl<T> ValueSearch<T> {
     }
 }
 
+/// Parse an annotation value as number for numeric value searches.
+///
+/// Only finite numbers are accepted, so values like `inf` or `NaN` (which
+/// could also be e.g. part-of-speech tags) are never treated as numbers.
+pub fn parse_numeric_value(val: &str) -> Option<f64> {
+    let parsed: f64 = val.parse().ok()?;
+    if parsed.is_finite() {
+        Some(parsed)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if the numeric value `val` is inside the range given by the
+/// `lower` and `upper` bound.
+pub fn numeric_range_contains(val: f64, lower: Bound<f64>, upper: Bound<f64>) -> bool {
+    let lower_ok = match lower {
+        Bound::Included(l) => val >= l,
+        Bound::Excluded(l) => val > l,
+        Bound::Unbounded => true,
+    };
+    let upper_ok = match upper {
+        Bound::Included(u) => val <= u,
+        Bound::Excluded(u) => val < u,
+        Bound::Unbounded => true,
+    };
+    lower_ok && upper_ok
+}
+
+/// Map a finite number to an unsigned integer with the same ordering, so it
+/// can be used as key in the sorted numeric indexes.
+pub(crate) fn numeric_sort_key(val: f64) -> u64 {
+    // Negative zero and positive zero must have the same key
+    let val = if val == 0.0 { 0.0 } else { val };
+    let bits = val.to_bits();
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | (1 << 63)
+    }
+}
+
+/// Convert the numeric range to an inclusive range of sort keys (see
+/// [`numeric_sort_key`]). Returns `None` if the range is empty.
+pub(crate) fn numeric_sort_key_range(lower: Bound<f64>, upper: Bound<f64>) -> Option<(u64, u64)> {
+    let lower = match lower {
+        Bound::Included(l) => numeric_sort_key(l),
+        Bound::Excluded(l) => numeric_sort_key(l).checked_add(1)?,
+        Bound::Unbounded => u64::MIN,
+    };
+    let upper = match upper {
+        Bound::Included(u) => numeric_sort_key(u),
+        Bound::Excluded(u) => numeric_sort_key(u).checked_sub(1)?,
+        Bound::Unbounded => u64::MAX,
+    };
+    if lower <= upper {
+        Some((lower, upper))
+    } else {
+        None
+    }
+}
+
+/// Estimate the number of annotations with a numeric value inside the given
+/// range, based on the sampled histogram bounds of a single annotation key.
+pub(crate) fn guess_numeric_count_from_histogram(
+    histo: &[String],
+    anno_size: usize,
+    lower: Bound<f64>,
+    upper: Bound<f64>,
+) -> usize {
+    if histo.is_empty() {
+        return 0;
+    }
+    let matches = histo
+        .iter()
+        .filter_map(|v| parse_numeric_value(v))
+        .filter(|v| numeric_range_contains(*v, lower, upper))
+        .count();
+    if matches == 0 {
+        // Don't assume there is no match, but use the bucket size as pessimistic guess
+        (anno_size as f64 / histo.len() as f64) as usize
+    } else {
+        let match_ratio = (matches as f64) / (histo.len() as f64);
+        ((anno_size as f64) * match_ratio).round() as usize
+    }
+}
+
+/// Maximum number of values in the list of most common values, which is
+/// maintained for each annotation key.
+pub(crate) const MAX_MOST_COMMON_VALUES: usize = 100;
+
+/// Collects the most common values of a single annotation key, from the
+/// distinct values and the number of annotations having each value.
+pub(crate) struct MostCommonValuesCollector<'a> {
+    // Min-heap, so the least common value can be removed when the heap is full.
+    // For the same count, larger values are removed first.
+    heap: BinaryHeap<Reverse<(usize, Reverse<Cow<'a, str>>)>>,
+}
+
+impl<'a> MostCommonValuesCollector<'a> {
+    pub(crate) fn new() -> MostCommonValuesCollector<'a> {
+        MostCommonValuesCollector {
+            heap: BinaryHeap::with_capacity(MAX_MOST_COMMON_VALUES + 1),
+        }
+    }
+
+    pub(crate) fn add(&mut self, value: Cow<'a, str>, count: usize) {
+        self.heap.push(Reverse((count, Reverse(value))));
+        if self.heap.len() > MAX_MOST_COMMON_VALUES {
+            self.heap.pop();
+        }
+    }
+
+    /// Returns the collected values, sorted with the most common value first.
+    pub(crate) fn finish(self) -> Vec<(String, usize)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((count, Reverse(value)))| (value.into_owned(), count))
+            .collect()
+    }
+}
+
+/// Estimate the number of annotations of a single annotation key with a value
+/// inside the given inclusive range.
+///
+/// The values in the list of most common values use their stored number of
+/// occurrences. The remaining annotations are estimated using the sampled
+/// histogram bounds.
+pub(crate) fn guess_count_from_statistics(
+    most_common_values: &[(String, usize)],
+    histo: &[String],
+    anno_size: usize,
+    lower_val: &str,
+    upper_val: &str,
+) -> usize {
+    let mut mcv_count = 0;
+    let mut mcv_total = 0;
+    for (value, count) in most_common_values {
+        mcv_total += count;
+        if lower_val <= value.as_str() && value.as_str() <= upper_val {
+            mcv_count += count;
+        }
+    }
+    let remaining = anno_size.saturating_sub(mcv_total);
+    if remaining == 0 || (lower_val == upper_val && mcv_count > 0) {
+        // All values or the single searched value are in the list
+        return mcv_count;
+    }
+
+    // we need to make sure the histogram is not empty -> should have at least two bounds
+    let mut remaining_estimate = if histo.len() >= 2 {
+        let buckets = histo.len() - 1;
+        let overlapping = (0..buckets)
+            .filter(|i| histo[*i].as_str() <= upper_val && lower_val <= histo[*i + 1].as_str())
+            .count();
+        (overlapping as f64 / buckets as f64) * (remaining as f64)
+    } else {
+        0.0
+    };
+    if lower_val == upper_val
+        && let Some((_, least_common_count)) = most_common_values.last()
+    {
+        // A value that is not in the list can't be more common than the least
+        // common value of the list, but there are other values, so it might exist
+        remaining_estimate = remaining_estimate.clamp(1.0, *least_common_count as f64);
+    }
+    mcv_count + remaining_estimate.round() as usize
+}
+
+/// Combine the most common values of several annotation keys into a single
+/// list, sorted with the most common value first.
+pub(crate) fn merge_most_common_values<'a>(
+    lists: impl IntoIterator<Item = &'a [(String, usize)]>,
+) -> Vec<(Cow<'a, str>, usize)> {
+    let mut counts: BTreeMap<&'a str, usize> = BTreeMap::new();
+    for list in lists {
+        for (value, count) in list {
+            *counts.entry(value.as_str()).or_default() += count;
+        }
+    }
+    counts
+        .into_iter()
+        .sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)))
+        .take(MAX_MOST_COMMON_VALUES)
+        .map(|(value, count)| (Cow::Borrowed(value), count))
+        .collect()
+}
+
 /// Access annotations for nodes or edges.
 pub trait AnnotationStorage<T>: Send + Sync
 where
@@ -253,6 +447,7 @@ where
     /// - `namespace`- If given, only annotations having this namespace are returned.
     /// - `name`  - Only annotations with this name are returned.
     /// - `pattern` - If given, only annotation having a value that mattches this pattern are returned.
+    /// - `flags` - Flags like case-insensitivity that are applied to the `pattern`.
     /// - `negated` - If true, find all annotations that do not match the value
     ///
     /// The result is an iterator over matches.
@@ -263,9 +458,29 @@ where
         namespace: Option<&str>,
         name: &str,
         pattern: &str,
+        flags: RegexFlags,
         negated: bool,
     ) -> Box<dyn Iterator<Item = Result<Match>> + 'a>;
 
+    /// Returns an iterator for all items where the value can be parsed as a number and is inside the given range.
+    /// Annotation values that are not numeric are ignored.
+    ///
+    /// - `namespace`- If given, only annotations having this namespace are returned.
+    /// - `name`  - Only annotations with this name are returned.
+    /// - `lower` - Lower bound of the numeric value.
+    /// - `upper` - Upper bound of the numeric value.
+    ///
+    /// The result is an iterator over matches.
+    /// A match contains the node ID and the qualifed name of the matched annotation
+    /// (e.g. there can be multiple annotations with the same name if the namespace is different).
+    fn numeric_anno_search<'a>(
+        &'a self,
+        namespace: Option<&str>,
+        name: &str,
+        lower: Bound<f64>,
+        upper: Bound<f64>,
+    ) -> Box<dyn Iterator<Item = Result<Match>> + 'a>;
+
     /// Estimate the number of results for an [annotation exact search](#tymethod.exact_anno_search) for a given an inclusive value range.
     ///
     /// - `ns` - If given, only annotations having this namespace are considered.
@@ -286,7 +501,29 @@ where
     /// - `ns` - If given, only annotations having this namespace are considered.
     /// - `name`  - Only annotations with this name are considered.
     /// - `pattern`- The regular expression pattern.
-    fn guess_max_count_regex(&self, ns: Option<&str>, name: &str, pattern: &str) -> Result<usize>;
+    /// - `flags` - Flags like case-insensitivity that are applied to the `pattern`.
+    fn guess_max_count_regex(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+        pattern: &str,
+        flags: RegexFlags,
+    ) -> Result<usize>;
+
+    /// Estimate the number of results for an [annotation numeric search](#tymethod.numeric_anno_search)
+    /// for a given range.
+    ///
+    /// - `ns` - If given, only annotations having this namespace are considered.
+    /// - `name`  - Only annotations with this name are considered.
+    /// - `lower` - Lower bound of the numeric value.
+    /// - `upper` - Upper bound of the numeric value.
+    fn guess_max_count_numeric(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+        lower: Bound<f64>,
+        upper: Bound<f64>,
+    ) -> Result<usize>;
 
     /// Estimate the most frequent value for a given annotation `name` with an optional namespace (`ns`).
     ///
@@ -297,6 +534,18 @@ where
         name: &str,
     ) -> Result<Option<Cow<'_, str>>>;
 
+    /// Return the most common values for a given annotation `name` with an
+    /// optional namespace (`ns`), together with the number of annotations
+    /// having this value. The list is sorted with the most common value first.
+    ///
+    /// The list is part of the internal statistics and might be empty if the
+    /// statistics have not been calculated yet.
+    fn get_most_common_values(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+    ) -> Result<Vec<(Cow<'_, str>, usize)>>;
+
     /// Return a list of all existing values for a given annotation `key`.
     /// If the `most_frequent_first` parameter is true, the results are sorted by their frequency.
     fn get_all_values(&self, key: &AnnoKey, most_frequent_first: bool)
@@ -321,6 +570,13 @@ where
 
     /// Save the current annotation to a `location` on the disk, but do not remember this location.
     fn save_annotations_to(&self, location: &Path) -> Result<()>;
+
+    /// Set how the annotations are compressed the next time they are saved.
+    ///
+    /// Annotation storages that are held in main memory ignore these options.
+    fn set_compression(&mut self, _options: ondisk::CompressionOptions) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// An annotation storage for nodes.
@@ -1,31 +1,78 @@
 use crate::annostorage::AnnotationStorage;
 use crate::annostorage::symboltable::SymbolTable;
-use crate::annostorage::{Match, ValueSearch};
-use crate::errors::Result;
+use crate::annostorage::{
+    Match, MostCommonValuesCollector, ValueSearch, guess_count_from_statistics,
+    guess_numeric_count_from_histogram, merge_most_common_values, numeric_sort_key,
+    numeric_sort_key_range, parse_numeric_value,
+};
+use crate::errors::{GraphAnnisCoreError, Result};
 use crate::graph::NODE_NAME_KEY;
 use crate::serializer::{FixedSizeKeySerializer, KeySerializer};
 use crate::types::{AnnoKey, Annotation, Edge, NodeID};
+use crate::util::RegexFlags;
 use crate::util::disk_collections::{DiskMap, EvictionStrategy};
 use crate::{try_as_boxed_iter, util};
 use core::ops::Bound::*;
 use itertools::Itertools;
 use rand::seq::IteratorRandom;
 use regex_syntax::Parser;
-use regex_syntax::hir::literal::Seq;
+use regex_syntax::hir::Hir;
 use serde_bytes::ByteBuf;
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::{Bound, RangeBounds};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use transient_btree_index::BtreeConfig;
 
 use super::{EdgeAnnotationStorage, NodeAnnotationStorage};
 
-pub const SUBFOLDER_NAME: &str = "nodes_diskmap_v1";
+pub const SUBFOLDER_NAME: &str = "nodes_diskmap_v2";
+/// Folder of older versions, which stored all annotation values as plain
+/// strings. These folders are still loaded, but saved in the new format.
+pub const SUBFOLDER_NAME_V1: &str = "nodes_diskmap_v1";
+
+const COMPRESSION_FILE_NAME: &str = "compression.toml";
+const DICTIONARIES_FILE_NAME: &str = "dictionaries.bin";
 
 const EVICTION_STRATEGY: EvictionStrategy = EvictionStrategy::MaximumItems(10_000);
 pub const BLOCK_CACHE_CAPACITY: usize = 32 * crate::util::disk_collections::MB;
 
+/// Get the folder of the on-disk annotation storage inside `location`. The
+/// folder of an older version is only returned if there is no folder in the
+/// current format.
+pub fn subfolder(location: &Path) -> PathBuf {
+    let result = location.join(SUBFOLDER_NAME);
+    let folder_v1 = location.join(SUBFOLDER_NAME_V1);
+    if !result.is_dir() && folder_v1.is_dir() {
+        folder_v1
+    } else {
+        result
+    }
+}
+
+/// Options how the on-disk annotation storage compresses its files when
+/// they are saved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionOptions {
+    /// Compress the blocks of the disk tables with Snappy.
+    #[serde(default)]
+    pub block_compression: bool,
+    /// Store the values of an annotation key with at most this number of
+    /// distinct values only once in a dictionary and refer to them by their
+    /// position in the dictionary. `0` disables the dictionary encoding.
+    #[serde(default)]
+    pub max_dictionary_size: usize,
+}
+
+/// The value of an annotation as stored in the `by_container` map.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum ContainerValue {
+    Plain(String),
+    /// Position of the value in the dictionary of the annotation key.
+    Dictionary(usize),
+}
+
 /// An on-disk implementation of an annotation storage.
 pub struct AnnoStorageImpl<T>
 where
@@ -36,8 +83,10 @@ where
         + serde::ser::Serialize
         + serde::de::DeserializeOwned,
 {
-    by_container: DiskMap<ByteBuf, String>,
+    by_container: ByContainerMap,
     by_anno_qname: DiskMap<ByteBuf, bool>,
+    /// Index for all annotation values that can be parsed as number.
+    by_numeric_value: DiskMap<ByteBuf, bool>,
     location: PathBuf,
     /// A handle to a temporary directory. This must be part of the struct because the temporary directory will
     /// be deleted when this handle is dropped.
@@ -51,11 +100,206 @@ where
     /// Sampled histograms for each annotation key .
     /// Each histogram bound defines a range of values where we estimate that they have the same number of occurences.
     histogram_bounds: BTreeMap<AnnoKey, Vec<String>>,
+    /// The most common values and their number of occurrences for each annotation key.
+    most_common_values: BTreeMap<AnnoKey, Vec<(String, usize)>>,
     largest_item: Option<T>,
 
+    compression: CompressionOptions,
+    /// The sorted distinct values of the dictionary encoded annotation keys,
+    /// as used by the loaded disk table of `by_container`.
+    dictionaries: BTreeMap<usize, Vec<String>>,
+
     phantom: std::marker::PhantomData<T>,
 }
 
+/// Load the persisted list of most common values from the given location.
+///
+/// Annotations saved by older versions don't have this list. In this case
+/// the list is empty until the statistics are calculated again and the
+/// estimations only use the histograms.
+fn load_most_common_values(location: &Path) -> Result<BTreeMap<AnnoKey, Vec<(String, usize)>>> {
+    let path = location.join("most_common_values.bin");
+    if path.is_file() {
+        let f = std::fs::File::open(path)?;
+        let mut reader = std::io::BufReader::new(f);
+        Ok(bincode::deserialize_from(&mut reader)?)
+    } else {
+        Ok(BTreeMap::new())
+    }
+}
+
+fn load_compression_options(location: &Path) -> Result<CompressionOptions> {
+    let path = location.join(COMPRESSION_FILE_NAME);
+    if path.is_file() {
+        let file_content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&file_content)?)
+    } else {
+        Ok(CompressionOptions::default())
+    }
+}
+
+fn load_dictionaries(location: &Path) -> Result<BTreeMap<usize, Vec<String>>> {
+    let path = location.join(DICTIONARIES_FILE_NAME);
+    if path.is_file() {
+        let f = std::fs::File::open(path)?;
+        let mut reader = std::io::BufReader::new(f);
+        Ok(bincode::deserialize_from(&mut reader)?)
+    } else {
+        Ok(BTreeMap::new())
+    }
+}
+
+type ContainerIterator<'a> = Box<dyn Iterator<Item = Result<(ByteBuf, ContainerValue)>> + 'a>;
+
+/// The `by_container` map. Disk tables of older versions, which stored all
+/// values as plain strings, are read in place and only converted to the
+/// current format when the annotations are saved.
+enum ByContainerMap {
+    Current(DiskMap<ByteBuf, ContainerValue>),
+    V1(DiskMap<ByteBuf, String>),
+}
+
+impl ByContainerMap {
+    fn load(path: &Path, is_v1: bool, c1_config: BtreeConfig) -> Result<ByContainerMap> {
+        if is_v1 {
+            let map = DiskMap::new(
+                Some(path),
+                EVICTION_STRATEGY,
+                BLOCK_CACHE_CAPACITY,
+                c1_config,
+            )?;
+            Ok(ByContainerMap::V1(map))
+        } else {
+            let map = DiskMap::new(
+                Some(path),
+                EVICTION_STRATEGY,
+                BLOCK_CACHE_CAPACITY,
+                c1_config,
+            )?;
+            Ok(ByContainerMap::Current(map))
+        }
+    }
+
+    fn new_temporary(c1_config: BtreeConfig) -> ByContainerMap {
+        ByContainerMap::Current(DiskMap::new_temporary(
+            EVICTION_STRATEGY,
+            BLOCK_CACHE_CAPACITY,
+            c1_config,
+        ))
+    }
+
+    fn insert(&mut self, key: ByteBuf, value: String) -> Result<()> {
+        match self {
+            ByContainerMap::Current(map) => map.insert(key, ContainerValue::Plain(value)),
+            ByContainerMap::V1(map) => map.insert(key, value),
+        }
+    }
+
+    fn get(&self, key: &ByteBuf) -> Result<Option<Cow<'_, ContainerValue>>> {
+        match self {
+            ByContainerMap::Current(map) => map.get(key),
+            ByContainerMap::V1(map) => Ok(map
+                .get(key)?
+                .map(|val| Cow::Owned(ContainerValue::Plain(val.into_owned())))),
+        }
+    }
+
+    fn contains_key(&self, key: &ByteBuf) -> Result<bool> {
+        match self {
+            ByContainerMap::Current(map) => map.contains_key(key),
+            ByContainerMap::V1(map) => map.contains_key(key),
+        }
+    }
+
+    fn remove(&mut self, key: &ByteBuf) -> Result<Option<ContainerValue>> {
+        match self {
+            ByContainerMap::Current(map) => map.remove(key),
+            ByContainerMap::V1(map) => Ok(map.remove(key)?.map(ContainerValue::Plain)),
+        }
+    }
+
+    fn iter(&self) -> Result<ContainerIterator<'_>> {
+        match self {
+            ByContainerMap::Current(map) => map.iter(),
+            ByContainerMap::V1(map) => Ok(Box::new(
+                map.iter()?
+                    .map_ok(|(key, val)| (key, ContainerValue::Plain(val))),
+            )),
+        }
+    }
+
+    fn range<R>(&self, range: R) -> ContainerIterator<'_>
+    where
+        R: RangeBounds<ByteBuf> + Clone,
+    {
+        match self {
+            ByContainerMap::Current(map) => map.range(range),
+            ByContainerMap::V1(map) => Box::new(
+                map.range(range)
+                    .map_ok(|(key, val)| (key, ContainerValue::Plain(val))),
+            ),
+        }
+    }
+
+    fn is_empty(&self) -> Result<bool> {
+        match self {
+            ByContainerMap::Current(map) => map.is_empty(),
+            ByContainerMap::V1(map) => map.is_empty(),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            ByContainerMap::Current(map) => map.clear(),
+            ByContainerMap::V1(map) => map.clear(),
+        }
+    }
+
+    /// Write all entries in the current format to a disk table.
+    fn write_mapped_to<F>(
+        &self,
+        location: &Path,
+        compress_blocks: bool,
+        mut map_value: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&ByteBuf, ContainerValue) -> Result<ContainerValue>,
+    {
+        match self {
+            ByContainerMap::Current(map) => {
+                map.write_mapped_to(location, compress_blocks, map_value)
+            }
+            ByContainerMap::V1(map) => {
+                map.write_mapped_to(location, compress_blocks, |key, val| {
+                    map_value(key, ContainerValue::Plain(val))
+                })
+            }
+        }
+    }
+}
+
+/// Get the actual annotation value of an entry of the `by_container` map.
+fn decode_value<'a>(
+    dictionaries: &'a BTreeMap<usize, Vec<String>>,
+    anno_key_symbol: usize,
+    value: Cow<'a, ContainerValue>,
+) -> Result<Cow<'a, str>> {
+    let index = match value {
+        Cow::Borrowed(ContainerValue::Plain(val)) => return Ok(Cow::Borrowed(val.as_str())),
+        Cow::Owned(ContainerValue::Plain(val)) => return Ok(Cow::Owned(val)),
+        Cow::Borrowed(ContainerValue::Dictionary(index)) => *index,
+        Cow::Owned(ContainerValue::Dictionary(index)) => index,
+    };
+    dictionaries
+        .get(&anno_key_symbol)
+        .and_then(|dictionary| dictionary.get(index))
+        .map(|val| Cow::Borrowed(val.as_str()))
+        .ok_or(GraphAnnisCoreError::UnknownDictionaryValue {
+            anno_key_symbol,
+            index,
+        })
+}
+
 /// Creates a key for the `by_container` tree.
 ///
 /// Structure:
@@ -93,6 +337,62 @@ fn create_by_anno_qname_key<T: FixedSizeKeySerializer>(
     result
 }
 
+/// Creates a key for the `by_numeric_value` tree.
+///
+/// The numeric value is stored as sort key (see [`numeric_sort_key`]), so that
+/// the byte-wise order of the keys is the same as the numeric order.
+///
+/// Structure:
+/// ```text
+/// [64 Bits Annotation Key Symbol][64 Bits numeric sort key][x Bits item ID]
+/// ```
+fn create_by_numeric_value_key<T: FixedSizeKeySerializer>(
+    item: T,
+    anno_key_symbol: usize,
+    sort_key: u64,
+) -> ByteBuf {
+    let mut result: ByteBuf = ByteBuf::from(anno_key_symbol.create_key().to_vec());
+    result.extend(sort_key.to_be_bytes());
+    result.extend(item.create_key());
+    result
+}
+
+/// Get the distinct value prefixes any match of the parsed regular expression
+/// must start with.
+///
+/// Prefixes are truncated to valid UTF-8 and prefixes that are already
+/// covered by a shorter prefix are omitted, so each value can only start with
+/// one of the returned prefixes. An empty prefix is returned if the regular
+/// expression has no known prefix.
+fn regex_value_prefixes(parsed_regex: &Hir) -> Vec<String> {
+    let literals = regex_syntax::hir::literal::Extractor::new().extract(parsed_regex);
+    let Some(literals) = literals.literals() else {
+        return vec![String::new()];
+    };
+    let mut prefixes: Vec<String> = literals
+        .iter()
+        .map(|lit| match std::str::from_utf8(lit.as_bytes()) {
+            Ok(prefix) => prefix.to_string(),
+            Err(e) => String::from_utf8_lossy(&lit.as_bytes()[..e.valid_up_to()]).to_string(),
+        })
+        .collect();
+    prefixes.sort();
+    prefixes.dedup();
+
+    let mut result: Vec<String> = Vec::with_capacity(prefixes.len());
+    for p in prefixes {
+        // Because the prefixes are sorted, all prefixes that start with the
+        // same shorter prefix follow it directly
+        if !result
+            .last()
+            .is_some_and(|last| p.starts_with(last.as_str()))
+        {
+            result.push(p);
+        }
+    }
+    result
+}
+
 impl<T> AnnoStorageImpl<T>
 where
     T: FixedSizeKeySerializer
@@ -108,12 +408,14 @@ where
         if let Some(path) = path {
             let path_by_container = path.join("by_container.bin");
             let path_by_anno_qname = path.join("by_anno_qname.bin");
+            let path_by_numeric_value = path.join("by_numeric_value.bin");
+            let has_numeric_index = path_by_numeric_value.is_file();
+            let is_v1 = path.file_name().is_some_and(|n| n == SUBFOLDER_NAME_V1);
 
             let mut result = AnnoStorageImpl {
-                by_container: DiskMap::new(
-                    Some(&path_by_container),
-                    EVICTION_STRATEGY,
-                    BLOCK_CACHE_CAPACITY,
+                by_container: ByContainerMap::load(
+                    &path_by_container,
+                    is_v1,
                     BtreeConfig::default().fixed_key_size(T::key_size() + 16),
                 )?,
                 by_anno_qname: DiskMap::new(
@@ -122,10 +424,19 @@ where
                     BLOCK_CACHE_CAPACITY,
                     BtreeConfig::default(),
                 )?,
+                by_numeric_value: DiskMap::new(
+                    Some(&path_by_numeric_value),
+                    EVICTION_STRATEGY,
+                    BLOCK_CACHE_CAPACITY,
+                    BtreeConfig::default(),
+                )?,
                 anno_key_symbols: SymbolTable::default(),
                 anno_key_sizes: BTreeMap::new(),
                 largest_item: None,
                 histogram_bounds: BTreeMap::new(),
+                most_common_values: BTreeMap::new(),
+                compression: load_compression_options(&path)?,
+                dictionaries: load_dictionaries(&path)?,
                 location: path.clone(),
                 temp_dir: None,
                 phantom: std::marker::PhantomData,
@@ -140,6 +451,11 @@ where
             result.histogram_bounds = bincode::deserialize_from(&mut reader)?;
             result.anno_key_symbols = bincode::deserialize_from(&mut reader)?;
             result.anno_key_symbols.after_deserialization();
+            result.most_common_values = load_most_common_values(&path)?;
+
+            if !has_numeric_index {
+                result.rebuild_numeric_index()?;
+            }
 
             Ok(result)
         } else {
@@ -147,9 +463,7 @@ where
                 .prefix("graphannis-ondisk-nodeanno-")
                 .tempdir()?;
             Ok(AnnoStorageImpl {
-                by_container: DiskMap::new_temporary(
-                    EVICTION_STRATEGY,
-                    BLOCK_CACHE_CAPACITY,
+                by_container: ByContainerMap::new_temporary(
                     BtreeConfig::default().fixed_key_size(T::key_size() + 16),
                 ),
                 by_anno_qname: DiskMap::new_temporary(
@@ -157,10 +471,18 @@ where
                     BLOCK_CACHE_CAPACITY,
                     BtreeConfig::default(),
                 ),
+                by_numeric_value: DiskMap::new_temporary(
+                    EVICTION_STRATEGY,
+                    BLOCK_CACHE_CAPACITY,
+                    BtreeConfig::default(),
+                ),
                 anno_key_symbols: SymbolTable::default(),
                 anno_key_sizes: BTreeMap::new(),
                 largest_item: None,
                 histogram_bounds: BTreeMap::new(),
+                most_common_values: BTreeMap::new(),
+                compression: CompressionOptions::default(),
+                dictionaries: BTreeMap::new(),
                 location: tmp_dir.as_ref().to_path_buf(),
                 temp_dir: Some(tmp_dir),
                 phantom: std::marker::PhantomData,
@@ -168,6 +490,41 @@ where
         }
     }
 
+    /// Re-create the numeric index from the annotation values. This is needed
+    /// when loading annotations that have been saved without this index.
+    fn rebuild_numeric_index(&mut self) -> Result<()> {
+        self.by_numeric_value.clear();
+        for entry in self.by_container.iter()? {
+            let (data, val) = entry?;
+            let anno_key_symbol = usize::parse_key(&data[T::key_size()..])?;
+            let val = decode_value(&self.dictionaries, anno_key_symbol, Cow::Owned(val))?;
+            if let Some(val) = parse_numeric_value(&val) {
+                let item = T::parse_key(&data[0..T::key_size()])?;
+                self.by_numeric_value.insert(
+                    create_by_numeric_value_key(item, anno_key_symbol, numeric_sort_key(val)),
+                    true,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_from_numeric_index(
+        &mut self,
+        item: T,
+        anno_key_symbol: usize,
+        anno_value: &str,
+    ) -> Result<()> {
+        if let Some(val) = parse_numeric_value(anno_value) {
+            self.by_numeric_value.remove(&create_by_numeric_value_key(
+                item,
+                anno_key_symbol,
+                numeric_sort_key(val),
+            ))?;
+        }
+        Ok(())
+    }
+
     fn matching_items<'a>(
         &'a self,
         namespace: Option<&str>,
@@ -321,6 +678,31 @@ where
         Ok(result)
     }
 
+    /// Collect the sorted distinct values of all annotation keys that have
+    /// few enough values to be stored in a dictionary.
+    fn create_dictionaries(&self) -> Result<BTreeMap<usize, Vec<String>>> {
+        let mut result = BTreeMap::new();
+        let max_size = self.compression.max_dictionary_size;
+        if max_size == 0 {
+            return Ok(result);
+        }
+        'keys: for anno_key in self.anno_key_sizes.keys() {
+            if let Some(anno_key_symbol) = self.anno_key_symbols.get_symbol(anno_key) {
+                let mut values = BTreeSet::new();
+                for entry in self.get_by_anno_qname_range(anno_key) {
+                    let (data, _) = entry?;
+                    let (_, _, val) = self.parse_by_anno_qname_key(data)?;
+                    values.insert(val);
+                    if values.len() > max_size {
+                        continue 'keys;
+                    }
+                }
+                result.insert(anno_key_symbol, values.into_iter().collect());
+            }
+        }
+        Ok(result)
+    }
+
     fn get_by_anno_qname_range<'a>(
         &'a self,
         anno_key: &AnnoKey,
@@ -364,11 +746,29 @@ where
             .largest_item
             .as_ref()
             .is_none_or(|largest_item| item <= *largest_item);
-        let already_existed =
-            item_smaller_than_largest && self.by_container.contains_key(&by_container_key)?;
+        let existing_value = if item_smaller_than_largest {
+            self.by_container
+                .get(&by_container_key)?
+                .map(|val| decode_value(&self.dictionaries, anno_key_symbol, val))
+                .transpose()?
+                .map(|val| val.into_owned())
+        } else {
+            None
+        };
+        let already_existed = existing_value.is_some();
         self.by_container
             .insert(by_container_key, anno.val.clone())?;
 
+        if let Some(existing_value) = existing_value {
+            self.remove_from_numeric_index(item.clone(), anno_key_symbol, &existing_value)?;
+        }
+        if let Some(val) = parse_numeric_value(&anno.val) {
+            self.by_numeric_value.insert(
+                create_by_numeric_value_key(item.clone(), anno_key_symbol, numeric_sort_key(val)),
+                true,
+            )?;
+        }
+
         // To save some space, insert an boolean value as a marker value
         // (all information is part of the key already)
         self.by_anno_qname.insert(
@@ -399,10 +799,12 @@ where
         let end = create_by_container_key(item.clone(), usize::MAX);
         for anno in self.by_container.range(start..=end) {
             let (key, val) = anno?;
+            let anno_key_symbol = usize::parse_key(&key[T::key_size()..])?;
+            let val = decode_value(&self.dictionaries, anno_key_symbol, Cow::Owned(val))?;
             let parsed_key = self.parse_by_container_key(key)?;
             let anno = Annotation {
                 key: parsed_key.1.as_ref().clone(),
-                val,
+                val: val.into(),
             };
             result.push(anno);
         }
@@ -427,10 +829,11 @@ where
                 .unwrap_or_default();
 
             if let Some(val) = self.by_container.remove(&by_container_key)? {
+                let val = decode_value(&self.dictionaries, symbol_id, Cow::Owned(val))?;
                 // remove annotation from by_anno_qname
                 let anno = Annotation {
                     key: key.as_ref().clone(),
-                    val,
+                    val: val.into(),
                 };
 
                 self.by_anno_qname.remove(&create_by_anno_qname_key(
@@ -438,6 +841,7 @@ where
                     symbol_id,
                     &anno.val,
                 ))?;
+                self.remove_from_numeric_index(item.clone(), symbol_id, &anno.val)?;
                 // decrease the annotation count for this key
                 let new_key_count: usize =
                     if let Some(num_of_keys) = self.anno_key_sizes.get_mut(&key) {
@@ -470,10 +874,11 @@ where
         if let Some(symbol_id) = self.anno_key_symbols.get_symbol(key) {
             let by_container_key = create_by_container_key(item.clone(), symbol_id);
             if let Some(val) = self.by_container.remove(&by_container_key)? {
+                let val = decode_value(&self.dictionaries, symbol_id, Cow::Owned(val))?;
                 // remove annotation from by_anno_qname
                 let anno = Annotation {
                     key: key.clone(),
-                    val,
+                    val: val.into(),
                 };
 
                 self.by_anno_qname.remove(&create_by_anno_qname_key(
@@ -481,6 +886,7 @@ where
                     symbol_id,
                     &anno.val,
                 ))?;
+                self.remove_from_numeric_index(item.clone(), symbol_id, &anno.val)?;
                 // decrease the annotation count for this key
                 let new_key_count: usize =
                     if let Some(num_of_keys) = self.anno_key_sizes.get_mut(key) {
@@ -506,10 +912,12 @@ where
     fn clear(&mut self) -> Result<()> {
         self.by_container.clear();
         self.by_anno_qname.clear();
+        self.by_numeric_value.clear();
 
         self.largest_item = None;
         self.anno_key_sizes.clear();
         self.histogram_bounds.clear();
+        self.most_common_values.clear();
 
         Ok(())
     }
@@ -547,10 +955,7 @@ where
                 .by_container
                 .get(&create_by_container_key(item.clone(), symbol_id))?;
             if let Some(val) = raw {
-                return match val {
-                    Cow::Borrowed(val) => Ok(Some(Cow::Borrowed(val.as_str()))),
-                    Cow::Owned(val) => Ok(Some(Cow::Owned(val))),
-                };
+                return Ok(Some(decode_value(&self.dictionaries, symbol_id, val)?));
             }
         }
         Ok(None)
@@ -722,9 +1127,10 @@ where
         namespace: Option<&str>,
         name: &str,
         pattern: &str,
+        flags: RegexFlags,
         negated: bool,
     ) -> Box<dyn Iterator<Item = Result<Match>> + 'a> {
-        let full_match_pattern = util::regex_full_match(pattern);
+        let full_match_pattern = util::regex_full_match_with_flags(pattern, flags);
 
         if let Ok((compiled_regex, parsed_regex)) =
             util::compile_and_parse_regex(&full_match_pattern)
@@ -749,18 +1155,17 @@ where
                     .map_ok(move |(node, anno_key, _val)| (node, anno_key).into());
                 Box::new(it)
             } else {
-                let regex_literal_sequence = regex_syntax::hir::literal::Extractor::new()
-                    .extract(&parsed_regex)
-                    .literals()
-                    .map(Seq::new);
-
-                let prefix_bytes = regex_literal_sequence
-                    .map(|seq| Vec::from(seq.longest_common_prefix().unwrap_or_default()))
-                    .unwrap_or_default();
-                let prefix = try_as_boxed_iter!(std::str::from_utf8(&prefix_bytes));
+                // Only scan the values that start with one of the possible
+                // prefixes, e.g. both "Der" and "der" for a case-insensitive
+                // search
+                let prefix_iterators: Vec<_> = regex_value_prefixes(&parsed_regex)
+                    .into_iter()
+                    .map(|prefix| self.matching_items_by_prefix(namespace, name, prefix))
+                    .collect();
 
-                let it = self
-                    .matching_items_by_prefix(namespace, name, prefix.to_string())
+                let it = prefix_iterators
+                    .into_iter()
+                    .flatten()
                     .map(move |item| match item {
                         Ok((node, anno_key)) => {
                             let value = self.get_value_for_item(&node, &anno_key)?;
@@ -787,6 +1192,52 @@ where
         }
     }
 
+    fn numeric_anno_search<'a>(
+        &'a self,
+        namespace: Option<&str>,
+        name: &str,
+        lower: Bound<f64>,
+        upper: Bound<f64>,
+    ) -> Box<dyn Iterator<Item = Result<Match>> + 'a> {
+        let (lower_sort_key, upper_sort_key) = if let Some(r) = numeric_sort_key_range(lower, upper)
+        {
+            r
+        } else {
+            return Box::new(std::iter::empty());
+        };
+        let qualified_keys = match namespace {
+            Some(ns) => vec![AnnoKey {
+                ns: ns.into(),
+                name: name.into(),
+            }],
+            None => try_as_boxed_iter!(self.get_qnames(name)),
+        };
+
+        let it = qualified_keys
+            .into_iter()
+            .filter_map(move |k| {
+                let anno_key_symbol = self.anno_key_symbols.get_symbol(&k)?;
+                Some((anno_key_symbol, Arc::from(k)))
+            })
+            .flat_map(move |(anno_key_symbol, anno_key)| {
+                let mut lower_bound = ByteBuf::from(anno_key_symbol.create_key().to_vec());
+                lower_bound.extend(lower_sort_key.to_be_bytes());
+                let mut upper_bound = ByteBuf::from(anno_key_symbol.create_key().to_vec());
+                upper_bound.extend(upper_sort_key.to_be_bytes());
+                upper_bound.extend(std::iter::repeat_n(u8::MAX, T::key_size()));
+
+                self.by_numeric_value
+                    .range(lower_bound..=upper_bound)
+                    .map(move |item| {
+                        let (data, _) = item?;
+                        // get the item ID at the end
+                        let item_id = T::parse_key(&data[data.len() - T::key_size()..])?;
+                        Ok((item_id, anno_key.clone()).into())
+                    })
+            });
+        Box::new(it)
+    }
+
     fn get_all_keys_for_item(
         &self,
         item: &T,
@@ -856,48 +1307,41 @@ where
             None => self.get_qnames(name)?,
         };
 
-        let mut universe_size: usize = 0;
-        let mut sum_histogram_buckets: usize = 0;
-        let mut count_matches: usize = 0;
+        let mut guessed_count = 0;
 
         // guess for each fully qualified annotation key and return the sum of all guesses
         for anno_key in qualified_keys {
             if let Some(anno_size) = self.anno_key_sizes.get(&anno_key) {
-                universe_size += *anno_size;
-
-                if let Some(histo) = self.histogram_bounds.get(&anno_key) {
-                    // find the range in which the value is contained
-
-                    // we need to make sure the histogram is not empty -> should have at least two bounds
-                    if histo.len() >= 2 {
-                        sum_histogram_buckets += histo.len() - 1;
-
-                        for i in 0..histo.len() - 1 {
-                            let bucket_begin = &histo[i];
-                            let bucket_end = &histo[i + 1];
-                            // check if the range overlaps with the search range
-                            if bucket_begin.as_str() <= upper_val
-                                && lower_val <= bucket_end.as_str()
-                            {
-                                count_matches += 1;
-                            }
-                        }
-                    }
-                }
+                let most_common_values = self
+                    .most_common_values
+                    .get(&anno_key)
+                    .map(|mcv| mcv.as_slice())
+                    .unwrap_or_default();
+                let histo = self
+                    .histogram_bounds
+                    .get(&anno_key)
+                    .map(|h| h.as_slice())
+                    .unwrap_or_default();
+                guessed_count += guess_count_from_statistics(
+                    most_common_values,
+                    histo,
+                    *anno_size,
+                    lower_val,
+                    upper_val,
+                );
             }
         }
-
-        if sum_histogram_buckets > 0 {
-            let selectivity: f64 = (count_matches as f64) / (sum_histogram_buckets as f64);
-            let estimation = (selectivity * (universe_size as f64)).round() as usize;
-            Ok(estimation)
-        } else {
-            Ok(0)
-        }
+        Ok(guessed_count)
     }
 
-    fn guess_max_count_regex(&self, ns: Option<&str>, name: &str, pattern: &str) -> Result<usize> {
-        let full_match_pattern = util::regex_full_match(pattern);
+    fn guess_max_count_regex(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+        pattern: &str,
+        flags: RegexFlags,
+    ) -> Result<usize> {
+        let full_match_pattern = util::regex_full_match_with_flags(pattern, flags);
 
         // Get the total number of annotations with the namespace/name. We
         // can't get larger than this number
@@ -909,17 +1353,12 @@ where
             let mut guessed_count = 0;
 
             // Add the guessed count for each prefix
-            if let Some(prefix_set) = regex_syntax::hir::literal::Extractor::new()
-                .extract(&parsed)
-                .literals()
-            {
-                for val_prefix in prefix_set {
-                    let val_prefix = std::str::from_utf8(val_prefix.as_bytes());
-                    if let Ok(lower_val) = val_prefix {
-                        let mut upper_val = String::from(lower_val);
-                        upper_val.push(std::char::MAX);
-                        guessed_count += self.guess_max_count(ns, name, lower_val, &upper_val)?;
-                    }
+            let prefixes = regex_value_prefixes(&parsed);
+            if prefixes.iter().all(|p| !p.is_empty()) {
+                for lower_val in prefixes {
+                    let mut upper_val = lower_val.clone();
+                    upper_val.push(std::char::MAX);
+                    guessed_count += self.guess_max_count(ns, name, &lower_val, &upper_val)?;
                 }
             } else {
                 // For regular expressions without a prefix the worst case would be `.*[X].*` where `[X]` are the most common characters.
@@ -971,6 +1410,32 @@ where
         }
     }
 
+    fn guess_max_count_numeric(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+        lower: Bound<f64>,
+        upper: Bound<f64>,
+    ) -> Result<usize> {
+        let qualified_keys = match ns {
+            Some(ns) => vec![AnnoKey {
+                name: name.into(),
+                ns: ns.into(),
+            }],
+            None => self.get_qnames(name)?,
+        };
+        let mut guessed_count = 0;
+        for anno_key in qualified_keys {
+            if let Some(anno_size) = self.anno_key_sizes.get(&anno_key)
+                && let Some(histo) = self.histogram_bounds.get(&anno_key)
+            {
+                guessed_count +=
+                    guess_numeric_count_from_histogram(histo, *anno_size, lower, upper);
+            }
+        }
+        Ok(guessed_count)
+    }
+
     fn guess_most_frequent_value(
         &self,
         ns: Option<&str>,
@@ -985,6 +1450,17 @@ where
             None => self.get_qnames(name)?,
         };
 
+        // use the exact counts of the most common values if available
+        let most_common_values = merge_most_common_values(
+            qualified_keys
+                .iter()
+                .filter_map(|anno_key| self.most_common_values.get(anno_key))
+                .map(|mcv| mcv.as_slice()),
+        );
+        if let Some((value, _)) = most_common_values.into_iter().next() {
+            return Ok(Some(value));
+        }
+
         let mut sampled_values: HashMap<&str, usize> = HashMap::default();
 
         // guess for each fully qualified annotation key
@@ -1012,6 +1488,27 @@ where
         }
     }
 
+    fn get_most_common_values(
+        &self,
+        ns: Option<&str>,
+        name: &str,
+    ) -> Result<Vec<(Cow<'_, str>, usize)>> {
+        let qualified_keys = match ns {
+            Some(ns) => vec![AnnoKey {
+                name: name.into(),
+                ns: ns.into(),
+            }],
+            None => self.get_qnames(name)?,
+        };
+        let result = merge_most_common_values(
+            qualified_keys
+                .iter()
+                .filter_map(|anno_key| self.most_common_values.get(anno_key))
+                .map(|mcv| mcv.as_slice()),
+        );
+        Ok(result)
+    }
+
     fn get_all_values(
         &self,
         key: &AnnoKey,
@@ -1062,9 +1559,10 @@ where
         let max_sampled_annotations = 2500;
 
         self.histogram_bounds.clear();
+        self.most_common_values.clear();
 
         // collect statistics for each annotation key separately
-        for anno_key in self.anno_key_sizes.keys() {
+        for (anno_key, anno_size) in self.anno_key_sizes.iter() {
             // sample a maximal number of annotation values
             let mut rng = rand::rng();
 
@@ -1111,26 +1609,54 @@ where
                     }
                 }
             }
+
+            // Estimate the most common values from the sorted sample, where all
+            // occurrences of a value are consecutive. If the sample does not
+            // contain all annotations, values that have only been sampled once
+            // are too rare to be included.
+            let is_complete_sample = sampled_anno_values.len() >= *anno_size;
+            let mut collector = MostCommonValuesCollector::new();
+            for occurrences in sampled_anno_values.chunk_by(|a, b| a == b) {
+                if is_complete_sample {
+                    collector.add(Cow::Borrowed(&occurrences[0]), occurrences.len());
+                } else if occurrences.len() > 1 {
+                    let estimated_count = (occurrences.len() as f64) * (*anno_size as f64)
+                        / (sampled_anno_values.len() as f64);
+                    collector.add(
+                        Cow::Borrowed(&occurrences[0]),
+                        estimated_count.round() as usize,
+                    );
+                }
+            }
+            self.most_common_values
+                .insert(anno_key.clone(), collector.finish());
         }
         Ok(())
     }
 
     fn load_annotations_from(&mut self, location: &Path) -> Result<()> {
-        let location = location.join(SUBFOLDER_NAME);
+        let location = subfolder(location);
+        let is_v1 = location.file_name().is_some_and(|n| n == SUBFOLDER_NAME_V1);
 
         if !self.location.eq(&location) {
-            self.by_container = DiskMap::new(
-                Some(&location.join("by_container.bin")),
-                EVICTION_STRATEGY,
-                BLOCK_CACHE_CAPACITY,
+            self.by_container = ByContainerMap::load(
+                &location.join("by_container.bin"),
+                is_v1,
                 BtreeConfig::default().fixed_value_size(T::key_size() + 9),
             )?;
+            self.dictionaries = load_dictionaries(&location)?;
             self.by_anno_qname = DiskMap::new(
                 Some(&location.join("by_anno_qname.bin")),
                 EVICTION_STRATEGY,
                 BLOCK_CACHE_CAPACITY,
                 BtreeConfig::default(),
             )?;
+            self.by_numeric_value = DiskMap::new(
+                Some(&location.join("by_numeric_value.bin")),
+                EVICTION_STRATEGY,
+                BLOCK_CACHE_CAPACITY,
+                BtreeConfig::default(),
+            )?;
         }
 
         // load internal helper fields
@@ -1141,18 +1667,59 @@ where
         self.histogram_bounds = bincode::deserialize_from(&mut reader)?;
         self.anno_key_symbols = bincode::deserialize_from(&mut reader)?;
         self.anno_key_symbols.after_deserialization();
+        self.most_common_values = load_most_common_values(&location)?;
+        self.compression = load_compression_options(&location)?;
+
+        if !location.join("by_numeric_value.bin").is_file() {
+            // The annotations have been saved by an older version without numeric index
+            self.rebuild_numeric_index()?;
+        }
 
         Ok(())
     }
 
     fn save_annotations_to(&self, location: &Path) -> Result<()> {
         let location = location.join(SUBFOLDER_NAME);
+        let block_compression = self.compression.block_compression;
 
         // write out the disk maps to a single sorted string table
-        self.by_container
-            .write_to(&location.join("by_container.bin"))?;
-        self.by_anno_qname
-            .write_to(&location.join("by_anno_qname.bin"))?;
+        let dictionaries = self.create_dictionaries()?;
+        self.by_container.write_mapped_to(
+            &location.join("by_container.bin"),
+            block_compression,
+            |key, value| {
+                let anno_key_symbol = usize::parse_key(&key[T::key_size()..])?;
+                let value = decode_value(&self.dictionaries, anno_key_symbol, Cow::Owned(value))?;
+                let index = dictionaries.get(&anno_key_symbol).and_then(|dictionary| {
+                    dictionary
+                        .binary_search_by(|entry| entry.as_str().cmp(&value))
+                        .ok()
+                });
+                if let Some(index) = index {
+                    Ok(ContainerValue::Dictionary(index))
+                } else {
+                    Ok(ContainerValue::Plain(value.into_owned()))
+                }
+            },
+        )?;
+        self.by_anno_qname.write_mapped_to(
+            &location.join("by_anno_qname.bin"),
+            block_compression,
+            |_, value| Ok(value),
+        )?;
+        self.by_numeric_value.write_mapped_to(
+            &location.join("by_numeric_value.bin"),
+            block_compression,
+            |_, value| Ok(value),
+        )?;
+
+        let f = std::fs::File::create(location.join(DICTIONARIES_FILE_NAME))?;
+        let mut writer = std::io::BufWriter::new(f);
+        bincode::serialize_into(&mut writer, &dictionaries)?;
+        std::fs::write(
+            location.join(COMPRESSION_FILE_NAME),
+            toml::to_string(&self.compression)?,
+        )?;
 
         // save the other custom fields
         let f = std::fs::File::create(location.join("custom.bin"))?;
@@ -1162,6 +1729,15 @@ where
         bincode::serialize_into(&mut writer, &self.histogram_bounds)?;
         bincode::serialize_into(&mut writer, &self.anno_key_symbols)?;
 
+        let f = std::fs::File::create(location.join("most_common_values.bin"))?;
+        let mut writer = std::io::BufWriter::new(f);
+        bincode::serialize_into(&mut writer, &self.most_common_values)?;
+
+        Ok(())
+    }
+
+    fn set_compression(&mut self, options: CompressionOptions) -> Result<()> {
+        self.compression = options;
         Ok(())
     }
 }
@@ -238,7 +238,13 @@ fn regex_search() {
 
     // Test with namespace
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "A.*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "A.*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -247,7 +253,13 @@ fn regex_search() {
     assert_eq!(vec![1, 2, 3], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, ".A.", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            ".A.",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -256,7 +268,13 @@ fn regex_search() {
     assert_eq!(vec![1, 2, 3], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "_.*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "_.*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -265,7 +283,13 @@ fn regex_search() {
     assert_eq!(vec![0], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "(A|B).*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "(A|B).*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -274,7 +298,13 @@ fn regex_search() {
     assert_eq!(vec![1, 2, 3, 4], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "C.*", false)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "C.*",
+            RegexFlags::default(),
+            false,
+        )
         .map_ok(|m| m.node)
         .collect();
     let result = result.unwrap();
@@ -282,7 +312,7 @@ fn regex_search() {
 
     // also test without namepsace
     let result: Result<Vec<_>> = a
-        .regex_anno_search(None, NODE_NAME, "A.*", false)
+        .regex_anno_search(None, NODE_NAME, "A.*", RegexFlags::default(), false)
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -292,7 +322,13 @@ fn regex_search() {
 
     // Test negated search
     let result: Result<Vec<_>> = a
-        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "A.*", true)
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "A.*",
+            RegexFlags::default(),
+            true,
+        )
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -300,7 +336,7 @@ fn regex_search() {
     assert_eq!(vec![0, 4], result);
 
     let result: Result<Vec<_>> = a
-        .regex_anno_search(None, NODE_NAME, "A.*", true)
+        .regex_anno_search(None, NODE_NAME, "A.*", RegexFlags::default(), true)
         .map_ok(|m| m.node)
         .collect();
     let mut result = result.unwrap();
@@ -318,9 +354,486 @@ fn regex_estimation_without_prefix() {
     // Since there are very less than 250 items the histogram based statistics should be exact.
     assert_eq!(
         3,
-        a.guess_max_count_regex(Some(ANNIS_NS), NODE_NAME, ".A.")
+        a.guess_max_count_regex(Some(ANNIS_NS), NODE_NAME, ".A.", RegexFlags::default())
             .unwrap()
     );
     // Test without namespace
-    assert_eq!(3, a.guess_max_count_regex(None, NODE_NAME, ".A.").unwrap());
+    assert_eq!(
+        3,
+        a.guess_max_count_regex(None, NODE_NAME, ".A.", RegexFlags::default())
+            .unwrap()
+    );
+}
+
+#[test]
+fn regex_search_with_flags() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    insert_test_strings(&mut a);
+
+    let case_insensitive: RegexFlags = "i".parse().unwrap();
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "a.*", case_insensitive, false)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    assert_eq!(vec![1, 2, 3], result);
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(None, NODE_NAME, "(a|b).*", case_insensitive, false)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    assert_eq!(vec![1, 2, 3, 4], result);
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "aa.", case_insensitive, true)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    assert_eq!(vec![0, 4], result);
+
+    // Without the diacritic flag the letters with diacritics do not match
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(Some(ANNIS_NS), NODE_NAME, "_âbc", case_insensitive, false)
+        .map_ok(|m| m.node)
+        .collect();
+    assert_eq!(0, result.unwrap().len());
+
+    let result: Result<Vec<_>> = a
+        .regex_anno_search(
+            Some(ANNIS_NS),
+            NODE_NAME,
+            "_âbc",
+            "id".parse().unwrap(),
+            false,
+        )
+        .map_ok(|m| m.node)
+        .collect();
+    assert_eq!(vec![0], result.unwrap());
+}
+
+#[test]
+fn regex_estimation_with_flags() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    insert_test_strings(&mut a);
+    a.calculate_statistics().unwrap();
+
+    let case_insensitive: RegexFlags = "i".parse().unwrap();
+    let guessed = a
+        .guess_max_count_regex(Some(ANNIS_NS), NODE_NAME, "aa.", case_insensitive)
+        .unwrap();
+    assert!(guessed >= 3);
+    assert!(guessed <= 5);
+}
+
+fn numeric_search_result(
+    a: &AnnoStorageImpl<NodeID>,
+    lower: Bound<f64>,
+    upper: Bound<f64>,
+) -> Vec<NodeID> {
+    let result: Result<Vec<_>> = a
+        .numeric_anno_search(None, "val", lower, upper)
+        .map_ok(|m| m.node)
+        .collect();
+    let mut result = result.unwrap();
+    result.sort();
+    result
+}
+
+#[test]
+fn numeric_search() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    let values = ["-3.5", "0", "1", "1.0", "10", "2e1", "abc", "inf", "NaN"];
+    for (node, val) in values.iter().enumerate() {
+        a.insert(
+            node as NodeID,
+            Annotation {
+                key: AnnoKey {
+                    ns: "test".into(),
+                    name: "val".into(),
+                },
+                val: (*val).into(),
+            },
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        vec![4, 5],
+        numeric_search_result(&a, Bound::Excluded(1.0), Bound::Unbounded)
+    );
+    assert_eq!(
+        vec![2, 3, 4, 5],
+        numeric_search_result(&a, Bound::Included(1.0), Bound::Unbounded)
+    );
+    assert_eq!(
+        vec![0, 1],
+        numeric_search_result(&a, Bound::Unbounded, Bound::Excluded(1.0))
+    );
+    assert_eq!(
+        vec![0, 1, 2, 3],
+        numeric_search_result(&a, Bound::Unbounded, Bound::Included(1.0))
+    );
+    assert_eq!(
+        0,
+        numeric_search_result(&a, Bound::Excluded(1.0), Bound::Excluded(1.0)).len()
+    );
+
+    // Replace and remove values and check the index is updated
+    a.insert(
+        4,
+        Annotation {
+            key: AnnoKey {
+                ns: "test".into(),
+                name: "val".into(),
+            },
+            val: "-10".into(),
+        },
+    )
+    .unwrap();
+    a.remove_annotation_for_item(
+        &5,
+        &AnnoKey {
+            ns: "test".into(),
+            name: "val".into(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        0,
+        numeric_search_result(&a, Bound::Excluded(1.0), Bound::Unbounded).len()
+    );
+    assert_eq!(
+        vec![0, 4],
+        numeric_search_result(&a, Bound::Unbounded, Bound::Excluded(0.0))
+    );
+
+    // The index must also be available after loading the annotation storage
+    let tmp = tempfile::tempdir().unwrap();
+    a.save_annotations_to(tmp.path()).unwrap();
+    let mut loaded: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    loaded.load_annotations_from(tmp.path()).unwrap();
+    assert_eq!(
+        vec![0, 4],
+        numeric_search_result(&loaded, Bound::Unbounded, Bound::Excluded(0.0))
+    );
+}
+
+#[test]
+fn numeric_estimation() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    for i in 0..100 {
+        a.insert(
+            i,
+            Annotation {
+                key: AnnoKey {
+                    ns: "test".into(),
+                    name: "val".into(),
+                },
+                val: i.to_string(),
+            },
+        )
+        .unwrap();
+    }
+    a.calculate_statistics().unwrap();
+
+    // Since there are less than 250 items the histogram based statistics should be exact.
+    assert_eq!(
+        10,
+        a.guess_max_count_numeric(None, "val", Bound::Excluded(89.0), Bound::Unbounded)
+            .unwrap()
+    );
+    assert_eq!(
+        50,
+        a.guess_max_count_numeric(Some("test"), "val", Bound::Unbounded, Bound::Excluded(50.0))
+            .unwrap()
+    );
+}
+
+#[test]
+fn most_common_values_estimation() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    let key = AnnoKey {
+        ns: "test".into(),
+        name: "pos".into(),
+    };
+    // A skewed distribution with one very common and many rare values
+    for i in 0..500 {
+        a.insert(
+            i,
+            Annotation {
+                key: key.clone(),
+                val: "NN".into(),
+            },
+        )
+        .unwrap();
+    }
+    for i in 500..650 {
+        a.insert(
+            i,
+            Annotation {
+                key: key.clone(),
+                val: format!("v{}", i),
+            },
+        )
+        .unwrap();
+    }
+    a.calculate_statistics().unwrap();
+
+    let most_common_values = a.get_most_common_values(None, "pos").unwrap();
+    assert_eq!(100, most_common_values.len());
+    assert_eq!((Cow::Borrowed("NN"), 500), most_common_values[0]);
+    assert_eq!(
+        Some(Cow::Borrowed("NN")),
+        a.guess_most_frequent_value(Some("test"), "pos").unwrap()
+    );
+
+    // The common value is counted exactly and a rare value can't be more
+    // common than the least common value of the list.
+    assert_eq!(500, a.guess_max_count(None, "pos", "NN", "NN").unwrap());
+    assert_eq!(1, a.guess_max_count(None, "pos", "v642", "v642").unwrap());
+
+    // The list must also be available after loading the annotation storage
+    let tmp = tempfile::tempdir().unwrap();
+    a.save_annotations_to(tmp.path()).unwrap();
+    let mut loaded: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    loaded.load_annotations_from(tmp.path()).unwrap();
+    assert_eq!(
+        most_common_values,
+        loaded.get_most_common_values(Some("test"), "pos").unwrap()
+    );
+    assert_eq!(
+        500,
+        loaded.guess_max_count(None, "pos", "NN", "NN").unwrap()
+    );
+}
+
+#[test]
+fn most_common_values_from_sample() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    let key = AnnoKey {
+        ns: "test".into(),
+        name: "pos".into(),
+    };
+    // More annotations than sampled, with one common value and unique values
+    for i in 0..10_000 {
+        let val = if i % 5 < 3 {
+            "NN".to_string()
+        } else {
+            format!("v{}", i)
+        };
+        a.insert(
+            i,
+            Annotation {
+                key: key.clone(),
+                val,
+            },
+        )
+        .unwrap();
+    }
+    a.calculate_statistics().unwrap();
+
+    // The count of the common value is extrapolated from the sample and the
+    // unique values are not common enough to be included
+    let most_common_values = a.get_most_common_values(None, "pos").unwrap();
+    assert_eq!(1, most_common_values.len());
+    assert_eq!("NN", most_common_values[0].0);
+    assert!((5500..=6500).contains(&most_common_values[0].1));
+}
+
+fn pos_and_lemma_annotations(a: &mut AnnoStorageImpl<NodeID>) {
+    let pos_key = AnnoKey {
+        ns: "test".into(),
+        name: "pos".into(),
+    };
+    let lemma_key = AnnoKey {
+        ns: "test".into(),
+        name: "lemma".into(),
+    };
+    for i in 0..200 {
+        let pos = ["NN", "VVFIN", "ART"][i as usize % 3];
+        a.insert(
+            i,
+            Annotation {
+                key: pos_key.clone(),
+                val: pos.into(),
+            },
+        )
+        .unwrap();
+        a.insert(
+            i,
+            Annotation {
+                key: lemma_key.clone(),
+                val: format!("lemma{}", i),
+            },
+        )
+        .unwrap();
+    }
+    a.calculate_statistics().unwrap();
+}
+
+#[test]
+fn save_compressed() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    pos_and_lemma_annotations(&mut a);
+    a.set_compression(CompressionOptions {
+        block_compression: true,
+        max_dictionary_size: 10,
+    })
+    .unwrap();
+
+    let tmp = tempfile::tempdir().unwrap();
+    a.save_annotations_to(tmp.path()).unwrap();
+
+    let mut loaded: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    loaded.load_annotations_from(tmp.path()).unwrap();
+
+    // Only the annotation key with few values is stored in a dictionary
+    let pos_symbol = loaded
+        .anno_key_symbols
+        .get_symbol(&AnnoKey {
+            ns: "test".into(),
+            name: "pos".into(),
+        })
+        .unwrap();
+    assert_eq!(1, loaded.dictionaries.len());
+    assert_eq!(
+        Some(&vec![
+            "ART".to_string(),
+            "NN".to_string(),
+            "VVFIN".to_string()
+        ]),
+        loaded.dictionaries.get(&pos_symbol)
+    );
+    assert!(loaded.compression.block_compression);
+
+    for i in 0..200 {
+        assert_eq!(
+            a.get_annotations_for_item(&i).unwrap(),
+            loaded.get_annotations_for_item(&i).unwrap()
+        );
+    }
+    assert_eq!(
+        Some(Cow::Borrowed("VVFIN")),
+        loaded
+            .get_value_for_item(
+                &4,
+                &AnnoKey {
+                    ns: "test".into(),
+                    name: "pos".into(),
+                }
+            )
+            .unwrap()
+    );
+    assert_eq!(
+        67,
+        loaded
+            .exact_anno_search(Some("test"), "pos", Some("NN").into())
+            .count()
+    );
+
+    // Changing the loaded annotations mixes plain and dictionary values
+    loaded
+        .insert(
+            0,
+            Annotation {
+                key: AnnoKey {
+                    ns: "test".into(),
+                    name: "pos".into(),
+                },
+                val: "ADJA".into(),
+            },
+        )
+        .unwrap();
+    loaded
+        .remove_annotation_for_item(
+            &1,
+            &AnnoKey {
+                ns: "test".into(),
+                name: "pos".into(),
+            },
+        )
+        .unwrap();
+    loaded.calculate_statistics().unwrap();
+
+    let tmp_changed = tempfile::tempdir().unwrap();
+    loaded.save_annotations_to(tmp_changed.path()).unwrap();
+    let mut changed: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    changed.load_annotations_from(tmp_changed.path()).unwrap();
+    assert_eq!(4, changed.dictionaries[&pos_symbol].len());
+    for i in 0..200 {
+        assert_eq!(
+            loaded.get_annotations_for_item(&i).unwrap(),
+            changed.get_annotations_for_item(&i).unwrap()
+        );
+    }
+    assert_eq!(
+        Some(Cow::Borrowed("ADJA")),
+        changed
+            .get_value_for_item(
+                &0,
+                &AnnoKey {
+                    ns: "test".into(),
+                    name: "pos".into(),
+                }
+            )
+            .unwrap()
+    );
+}
+
+#[test]
+fn migrate_v1_folder() {
+    let mut a: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    pos_and_lemma_annotations(&mut a);
+
+    // Create a folder in the old format, which stored the values as strings
+    let tmp = tempfile::tempdir().unwrap();
+    a.save_annotations_to(tmp.path()).unwrap();
+    let folder_v1 = tmp.path().join(SUBFOLDER_NAME_V1);
+    std::fs::rename(tmp.path().join(SUBFOLDER_NAME), &folder_v1).unwrap();
+    std::fs::remove_file(folder_v1.join(COMPRESSION_FILE_NAME)).unwrap();
+    std::fs::remove_file(folder_v1.join(DICTIONARIES_FILE_NAME)).unwrap();
+    let mut by_container_v1: DiskMap<ByteBuf, String> = DiskMap::default();
+    for entry in a.by_container.iter().unwrap() {
+        let (key, val) = entry.unwrap();
+        if let ContainerValue::Plain(val) = val {
+            by_container_v1.insert(key, val).unwrap();
+        }
+    }
+    by_container_v1
+        .write_to(&folder_v1.join("by_container.bin"))
+        .unwrap();
+    assert_eq!(folder_v1, subfolder(tmp.path()));
+
+    let mut loaded: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(Some(folder_v1)).unwrap();
+    loaded.load_annotations_from(tmp.path()).unwrap();
+    // The old disk table is read in place and loading does not change the folder
+    assert!(matches!(loaded.by_container, ByContainerMap::V1(_)));
+    assert!(!tmp.path().join(SUBFOLDER_NAME).exists());
+    for i in 0..200 {
+        assert_eq!(
+            a.get_annotations_for_item(&i).unwrap(),
+            loaded.get_annotations_for_item(&i).unwrap()
+        );
+    }
+
+    // Saving the annotations again uses the current format
+    let tmp_migrated = tempfile::tempdir().unwrap();
+    loaded.save_annotations_to(tmp_migrated.path()).unwrap();
+    assert_eq!(
+        tmp_migrated.path().join(SUBFOLDER_NAME),
+        subfolder(tmp_migrated.path())
+    );
+    let mut migrated: AnnoStorageImpl<NodeID> = AnnoStorageImpl::new(None).unwrap();
+    migrated.load_annotations_from(tmp_migrated.path()).unwrap();
+    for i in 0..200 {
+        assert_eq!(
+            a.get_annotations_for_item(&i).unwrap(),
+            migrated.get_annotations_for_item(&i).unwrap()
+        );
+    }
 }
@@ -21,6 +21,8 @@ pub enum GraphAnnisCoreError {
     },
     #[error("could not find implementation for graph storage with name '{0}'")]
     UnknownGraphStorageImpl(String),
+    #[error("graph storage data is corrupted: {0}")]
+    CorruptedGraphStorage(String),
     #[error("can't load component with empty path")]
     EmptyComponentPath,
     #[error("could not find annotation key ID for {0:?} when mapping to GraphML")]
@@ -67,8 +69,15 @@ pub enum GraphAnnisCoreError {
     SymbolTableOverflow,
     #[error("Annotation key with ID {0} is not in symbol table")]
     UnknownAnnoKeySymbolId(usize),
+    #[error("Value {index} is not in the dictionary of annotation key with ID {anno_key_symbol}")]
+    UnknownDictionaryValue {
+        anno_key_symbol: usize,
+        index: usize,
+    },
     #[error("The choose cache size is zero, which is not allowed.")]
     ZeroCacheSize,
+    #[error("Invalid regular expression flag '{0}'")]
+    InvalidRegexFlag(char),
     #[error(transparent)]
     TomlDeserializer(#[from] toml::de::Error),
     #[error(transparent)]
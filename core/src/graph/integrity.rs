@@ -0,0 +1,348 @@
+//! Checks for the integrity of a graph that has been persisted to disk.
+
+use super::{
+    GLOBAL_STATISTICS_FILE_NAME, Graph, NODE_TYPE_KEY, find_components_from_disk,
+    load_component_from_disk, recovery,
+};
+use crate::{
+    annostorage::{AnnotationStorage, NodeAnnotationStorage},
+    errors::Result,
+    graph::storage::{GraphStorage, load_statistics_from_location},
+    types::{ComponentType, NodeID},
+};
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Prefix of the temporary folders that are used when replacing the backup
+/// folder of a graph.
+const TEMPORARY_BACKUP_PREFIX: &str = "temporary-graphannis-backup";
+
+/// A problem found when checking the integrity of a graph on disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegrityProblem {
+    /// Saving the graph has been interrupted and the `backup` folder with the
+    /// last consistent version of the graph still exists.
+    StaleBackup,
+    /// A temporary folder of an interrupted save operation has not been
+    /// removed.
+    TemporaryFolder { path: PathBuf },
+    /// The temporary folder of an update batch that has not been written
+    /// completely to the write-ahead log.
+    IncompleteUpdateLog { path: PathBuf },
+    /// The node annotation storage can't be loaded.
+    InvalidNodeAnnotations { error: String },
+    /// The write-ahead log with the not yet persisted updates can't be read.
+    InvalidUpdateLog { error: String },
+    /// The file with the global statistics can't be read.
+    InvalidGlobalStatistics { error: String },
+    /// The statistics file of a component can't be read.
+    InvalidComponentStatistics { component: String, error: String },
+    /// The graph storage of a component can't be loaded.
+    InvalidComponent { component: String, error: String },
+    /// Edges of the component reference nodes that don't exist in the node
+    /// annotation storage.
+    MissingNodes {
+        component: String,
+        missing_node_count: usize,
+    },
+    /// The file linked by a node does not exist.
+    MissingLinkedFile { node_name: String, path: PathBuf },
+}
+
+impl IntegrityProblem {
+    /// Returns `true` if the problem can be repaired without losing any data.
+    pub fn is_repairable(&self) -> bool {
+        match self {
+            IntegrityProblem::StaleBackup
+            | IntegrityProblem::TemporaryFolder { .. }
+            | IntegrityProblem::IncompleteUpdateLog { .. }
+            | IntegrityProblem::InvalidGlobalStatistics { .. }
+            | IntegrityProblem::InvalidComponentStatistics { .. } => true,
+            IntegrityProblem::InvalidNodeAnnotations { .. }
+            | IntegrityProblem::InvalidUpdateLog { .. }
+            | IntegrityProblem::InvalidComponent { .. }
+            | IntegrityProblem::MissingNodes { .. }
+            | IntegrityProblem::MissingLinkedFile { .. } => false,
+        }
+    }
+
+    /// Returns `true` if opening the graph with [`Graph::open`] would change
+    /// the files affected by this problem, e.g. by restoring the backup or
+    /// by discarding update batches of the write-ahead log.
+    pub fn is_changed_by_open(&self) -> bool {
+        matches!(
+            self,
+            IntegrityProblem::StaleBackup
+                | IntegrityProblem::IncompleteUpdateLog { .. }
+                | IntegrityProblem::InvalidUpdateLog { .. }
+        )
+    }
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityProblem::StaleBackup => {
+                write!(f, "saving the corpus was interrupted, backup folder exists")
+            }
+            IntegrityProblem::TemporaryFolder { path } => {
+                write!(f, "temporary folder {} was not removed", path.display())
+            }
+            IntegrityProblem::IncompleteUpdateLog { path } => {
+                write!(
+                    f,
+                    "update batch {} was not written completely",
+                    path.display()
+                )
+            }
+            IntegrityProblem::InvalidNodeAnnotations { error } => {
+                write!(f, "node annotations can't be loaded: {error}")
+            }
+            IntegrityProblem::InvalidUpdateLog { error } => {
+                write!(f, "update log can't be read: {error}")
+            }
+            IntegrityProblem::InvalidGlobalStatistics { error } => {
+                write!(f, "global statistics can't be read: {error}")
+            }
+            IntegrityProblem::InvalidComponentStatistics { component, error } => {
+                write!(
+                    f,
+                    "statistics of component {component} can't be read: {error}"
+                )
+            }
+            IntegrityProblem::InvalidComponent { component, error } => {
+                write!(f, "component {component} can't be loaded: {error}")
+            }
+            IntegrityProblem::MissingNodes {
+                component,
+                missing_node_count,
+            } => write!(
+                f,
+                "component {component} has edges for {missing_node_count} non-existing node(s)"
+            ),
+            IntegrityProblem::MissingLinkedFile { node_name, path } => write!(
+                f,
+                "file {} linked by node {node_name} does not exist",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// The result of an integrity check.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Problems that have been found and that have not been repaired.
+    pub problems: Vec<IntegrityProblem>,
+    /// Problems that have been found and repaired.
+    pub repaired: Vec<IntegrityProblem>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if there are no (remaining) problems.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Add a `problem` and try to repair it with the given function if
+    /// `repair` is `true`.
+    fn add<F>(&mut self, problem: IntegrityProblem, repair: bool, repair_func: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        if repair && problem.is_repairable() {
+            repair_func()?;
+            self.repaired.push(problem);
+        } else {
+            self.problems.push(problem);
+        }
+        Ok(())
+    }
+}
+
+/// Check the integrity of the graph persisted at `location`, which is the
+/// same location used for [`Graph::open`]. The graph must not be opened by
+/// anyone else while it is checked.
+///
+/// If `repair` is `true`, problems that can be repaired without losing any
+/// data are fixed: temporary and backup folders of interrupted save
+/// operations are cleaned up and unreadable statistics are removed.
+pub fn check_integrity<CT: ComponentType>(
+    location: &Path,
+    repair: bool,
+) -> Result<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+
+    for entry in location.read_dir()? {
+        let path = entry?.path();
+        let is_temporary = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with(TEMPORARY_BACKUP_PREFIX));
+        if is_temporary && path.is_dir() {
+            report.add(
+                IntegrityProblem::TemporaryFolder { path: path.clone() },
+                repair,
+                || Ok(std::fs::remove_dir_all(&path)?),
+            )?;
+        }
+    }
+
+    // Check the same folder that would be loaded
+    let backup = location.join("backup");
+    let has_backup = backup.is_dir();
+    let dir2check = if has_backup {
+        backup
+    } else {
+        location.join("current")
+    };
+
+    let global_statistics_file = dir2check.join(GLOBAL_STATISTICS_FILE_NAME);
+    if global_statistics_file.is_file() {
+        let parsed = std::fs::read_to_string(&global_statistics_file)
+            .map_err(|e| e.to_string())
+            .and_then(|c| toml::from_str::<CT::GlobalStatistics>(&c).map_err(|e| e.to_string()));
+        if let Err(error) = parsed {
+            report.add(
+                IntegrityProblem::InvalidGlobalStatistics { error },
+                repair,
+                || Ok(std::fs::remove_file(&global_statistics_file)?),
+            )?;
+        }
+    }
+
+    for path in recovery::find_incomplete_update_logs(&dir2check)? {
+        report.add(
+            IntegrityProblem::IncompleteUpdateLog { path: path.clone() },
+            repair,
+            || Ok(std::fs::remove_dir_all(&path)?),
+        )?;
+    }
+
+    for (_, log_path) in recovery::find_update_logs(&dir2check)? {
+        if let Err(e) = recovery::read_update_log(&log_path) {
+            report.add(
+                IntegrityProblem::InvalidUpdateLog {
+                    error: e.to_string(),
+                },
+                repair,
+                || Ok(()),
+            )?;
+        }
+    }
+
+    let node_annos = match load_node_annos(&dir2check) {
+        Ok(node_annos) => Some(node_annos),
+        Err(e) => {
+            report.add(
+                IntegrityProblem::InvalidNodeAnnotations {
+                    error: e.to_string(),
+                },
+                repair,
+                || Ok(()),
+            )?;
+            None
+        }
+    };
+
+    for c in find_components_from_disk::<CT, _>(&dir2check)? {
+        let component_path = dir2check.join(super::component_to_relative_path(&c));
+        let component = c.to_string();
+
+        if let Err(e) = load_statistics_from_location(&component_path) {
+            let stats_file = component_path.join("stats.toml");
+            report.add(
+                IntegrityProblem::InvalidComponentStatistics {
+                    component: component.clone(),
+                    error: e.to_string(),
+                },
+                repair,
+                || Ok(std::fs::remove_file(&stats_file)?),
+            )?;
+            if !repair {
+                // Loading the component would fail because of the statistics
+                continue;
+            }
+        }
+
+        match load_component_from_disk(&component_path) {
+            Ok(gs) => {
+                if let Some(node_annos) = &node_annos {
+                    let missing_node_count = count_missing_nodes(gs.as_ref(), node_annos.as_ref())?;
+                    if missing_node_count > 0 {
+                        report.add(
+                            IntegrityProblem::MissingNodes {
+                                component,
+                                missing_node_count,
+                            },
+                            repair,
+                            || Ok(()),
+                        )?;
+                    }
+                }
+            }
+            Err(e) => {
+                report.add(
+                    IntegrityProblem::InvalidComponent {
+                        component,
+                        error: e.to_string(),
+                    },
+                    repair,
+                    || Ok(()),
+                )?;
+            }
+        }
+    }
+
+    if has_backup {
+        // The backup can only be restored if its content is valid
+        let can_restore = report.is_ok();
+        if repair && can_restore {
+            // Opening the graph restores the backup
+            let mut graph = Graph::<CT>::new(false)?;
+            graph.open(location)?;
+            report.repaired.push(IntegrityProblem::StaleBackup);
+        } else {
+            report.problems.push(IntegrityProblem::StaleBackup);
+        }
+    }
+
+    Ok(report)
+}
+
+fn load_node_annos(location: &Path) -> Result<Box<dyn NodeAnnotationStorage>> {
+    let ondisk_subdirectory = crate::annostorage::ondisk::subfolder(location);
+    if ondisk_subdirectory.is_dir() {
+        let node_annos =
+            crate::annostorage::ondisk::AnnoStorageImpl::new(Some(ondisk_subdirectory))?;
+        Ok(Box::new(node_annos))
+    } else {
+        let mut node_annos = crate::annostorage::inmemory::AnnoStorageImpl::new();
+        node_annos.load_annotations_from(location)?;
+        Ok(Box::new(node_annos))
+    }
+}
+
+/// Count the source and target nodes of all edges that don't have a node
+/// type annotation, which every existing node must have.
+fn count_missing_nodes(
+    gs: &dyn GraphStorage,
+    node_annos: &dyn NodeAnnotationStorage,
+) -> Result<usize> {
+    let mut missing: BTreeSet<NodeID> = BTreeSet::new();
+    for source in gs.source_nodes() {
+        let source = source?;
+        if !node_annos.has_value_for_item(&source, &NODE_TYPE_KEY)? {
+            missing.insert(source);
+        }
+        for target in gs.get_outgoing_edges(source) {
+            let target = target?;
+            if !node_annos.has_value_for_item(&target, &NODE_TYPE_KEY)? {
+                missing.insert(target);
+            }
+        }
+    }
+    Ok(missing.len())
+}
@@ -1,3 +1,5 @@
+pub mod integrity;
+pub mod recovery;
 pub mod serialization;
 pub mod storage;
 pub mod update;
@@ -13,6 +15,7 @@ use crate::{
 };
 use clru::CLruCache;
 use rayon::prelude::*;
+use recovery::{DiscardedUpdateBatch, RecoveryReport, UpdateBatch};
 use std::ops::Bound::Included;
 use std::path::{Path, PathBuf};
 use std::string::ToString;
@@ -32,6 +35,42 @@ pub const DEFAULT_EMPTY_LAYER: &str = "default_layer";
 
 const GLOBAL_STATISTICS_FILE_NAME: &str = "global_statistics.toml";
 
+/// Phases of writing a graph to disk after which a crash can be simulated in
+/// the tests.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WritePhase {
+    UpdateLogWritten,
+    BackupCreated,
+    NodeAnnotationsSaved,
+    CurrentSaved,
+}
+
+#[cfg(test)]
+thread_local! {
+    static SIMULATED_CRASH: std::cell::Cell<Option<WritePhase>> = const { std::cell::Cell::new(None) };
+}
+
+/// Environment variable with the name of the write phase after which the
+/// whole process is aborted. This is used for child processes of the tests.
+#[cfg(test)]
+const ABORT_AFTER_ENV: &str = "GRAPHANNIS_TEST_ABORT_AFTER";
+
+/// Returns an error if a crash after the given phase should be simulated.
+#[cfg(test)]
+fn simulate_crash_after(phase: WritePhase) -> Result<()> {
+    if std::env::var(ABORT_AFTER_ENV).is_ok_and(|p| p == format!("{phase:?}")) {
+        std::process::abort();
+    }
+    if SIMULATED_CRASH.get() == Some(phase) {
+        Err(GraphAnnisCoreError::Other(
+            format!("simulated crash after {phase:?}").into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 lazy_static! {
     pub static ref DEFAULT_ANNO_KEY: Arc<AnnoKey> = Arc::from(AnnoKey::default());
     pub static ref NODE_NAME_KEY: Arc<AnnoKey> = Arc::from(AnnoKey {
@@ -207,14 +246,18 @@ impl<CT: ComponentType> Graph<CT> {
     /// Opens the graph from an external location.
     /// All updates will be persisted to this location.
     ///
+    /// If saving the graph has been interrupted, the last consistent version
+    /// is restored and the updates from the write-ahead log are replayed.
+    /// The returned report describes these recovery steps.
+    ///
     /// * `location` - The path on the disk
-    pub fn open(&mut self, location: &Path) -> Result<()> {
+    pub fn open(&mut self, location: &Path) -> Result<RecoveryReport> {
         debug!("Opening corpus from {}", location.to_string_lossy());
         self.clear()?;
         self.location = Some(location.to_path_buf());
-        self.internal_open(location)?;
+        let report = self.internal_open(location)?;
 
-        Ok(())
+        Ok(report)
     }
 
     /// Overwrites all content with the graph at the external location. Updates
@@ -240,12 +283,12 @@ impl<CT: ComponentType> Graph<CT> {
     /// * `location` - The path on the disk
     /// * `preload` - If `true`, all components are loaded from disk into main memory.
     #[deprecated(note = "Please use `open` instead")]
-    pub fn load_from(&mut self, location: &Path, preload: bool) -> Result<()> {
-        self.open(location)?;
+    pub fn load_from(&mut self, location: &Path, preload: bool) -> Result<RecoveryReport> {
+        let report = self.open(location)?;
         if preload {
             self.ensure_loaded_all()?;
         }
-        Ok(())
+        Ok(report)
     }
 
     /// Internal helper function that loads the content from an external
@@ -253,7 +296,8 @@ impl<CT: ComponentType> Graph<CT> {
     ///
     /// It does not clear the current graph and does not alter the configured
     /// location to persist the graph to.
-    fn internal_open(&mut self, location: &Path) -> Result<()> {
+    fn internal_open(&mut self, location: &Path) -> Result<RecoveryReport> {
+        let mut report = RecoveryReport::default();
         let backup = location.join("backup");
 
         let mut load_from_backup = false;
@@ -273,7 +317,7 @@ impl<CT: ComponentType> Graph<CT> {
         }
 
         // Load the node annotations
-        let ondisk_subdirectory = dir2load.join(crate::annostorage::ondisk::SUBFOLDER_NAME);
+        let ondisk_subdirectory = crate::annostorage::ondisk::subfolder(&dir2load);
         if ondisk_subdirectory.exists() && ondisk_subdirectory.is_dir() {
             self.disk_based = true;
             // directly load the on disk storage from the given folder to avoid having a temporary directory
@@ -288,9 +332,16 @@ impl<CT: ComponentType> Graph<CT> {
             self.node_annos = Box::new(node_annos_tmp);
         }
 
-        let log_path = dir2load.join("update_log.bin");
-
-        let logfile_exists = log_path.exists() && log_path.is_file();
+        // Batches that have not been written completely have never been
+        // acknowledged by apply_update and can't be replayed
+        for path in recovery::find_incomplete_update_logs(&dir2load)? {
+            warn!("Discarding incomplete update batch {}", path.display());
+            std::fs::remove_dir_all(&path)?;
+            report
+                .discarded
+                .push(DiscardedUpdateBatch::Incomplete { path });
+        }
+        let update_logs = recovery::find_update_logs(&dir2load)?;
 
         self.components = find_components_from_disk(&dir2load)?
             .into_iter()
@@ -298,22 +349,54 @@ impl<CT: ComponentType> Graph<CT> {
             .collect();
 
         // If backup is active or a write log exists, always  a pre-load to get the complete corpus.
-        if logfile_exists | load_from_backup {
+        if !update_logs.is_empty() | load_from_backup {
             self.ensure_loaded_all()?;
         }
 
-        if logfile_exists {
-            // apply any outstanding log file updates
-            let log_reader = std::fs::File::open(&log_path)?;
-            let mut update = bincode::deserialize_from(log_reader)?;
-            self.apply_update_in_memory(&mut update, true, |_| {})?;
-        } else {
-            self.current_change_id = 0;
+        // apply any outstanding log file updates in the order they have been written
+        self.current_change_id = 0;
+        let mut update_logs = update_logs.into_iter();
+        while let Some((sequence, log_path)) = update_logs.next() {
+            match recovery::read_update_log(&log_path) {
+                Ok(mut update) => {
+                    let event_count = update.len()?;
+                    self.apply_update_in_memory(&mut update, true, |_| {})?;
+                    debug!("Replayed update batch {sequence} with {event_count} event(s)");
+                    report.replayed.push(UpdateBatch {
+                        sequence,
+                        event_count,
+                    });
+                }
+                Err(e) => {
+                    // Later batches might depend on the changes of this batch
+                    let unreadable = DiscardedUpdateBatch::Unreadable {
+                        sequence,
+                        error: e.to_string(),
+                        path: recovery::discard_update_log(location, &log_path)?,
+                    };
+                    warn!("Discarding {unreadable}");
+                    report.discarded.push(unreadable);
+                    for (sequence, log_path) in update_logs.by_ref() {
+                        let skipped = DiscardedUpdateBatch::Skipped {
+                            sequence,
+                            path: recovery::discard_update_log(location, &log_path)?,
+                        };
+                        warn!("Discarding {skipped}");
+                        report.discarded.push(skipped);
+                    }
+                }
+            }
         }
 
         if load_from_backup {
-            // save the current corpus under the actual location
-            self.save_to(&location.join("current"))?;
+            report.restored_backup = true;
+            // The current folder might only be partially written, replace it
+            // with the restored corpus
+            let current_location = location.join("current");
+            if current_location.exists() {
+                std::fs::remove_dir_all(&current_location)?;
+            }
+            self.internal_save(&current_location)?;
             // rename backup folder (renaming is atomic and deleting could leave an incomplete backup folder on disk)
             let tmp_dir = tempfile::Builder::new()
                 .prefix("temporary-graphannis-backup")
@@ -325,7 +408,7 @@ impl<CT: ComponentType> Graph<CT> {
             tmp_dir.close()?;
         }
 
-        Ok(())
+        Ok(report)
     }
 
     /// Save the current database to a `location` on the disk, but do not remember this location.
@@ -355,6 +438,8 @@ impl<CT: ComponentType> Graph<CT> {
         std::fs::create_dir_all(&location)?;
 
         self.node_annos.save_annotations_to(&location)?;
+        #[cfg(test)]
+        simulate_crash_after(WritePhase::NodeAnnotationsSaved)?;
 
         for (c, e) in &self.components {
             if let Some(ref data) = *e {
@@ -700,16 +785,28 @@ impl<CT: ComponentType> Graph<CT> {
                 // make sure the output path exits
                 std::fs::create_dir_all(&current_path)?;
 
-                // If successfull write log
-                let log_path = current_path.join("update_log.bin");
+                // If successfull write log as new batch after all batches that have not been saved yet
+                let sequence = recovery::find_update_logs(&current_path)?
+                    .last()
+                    .map_or(1, |(sequence, _)| sequence + 1);
+                let log_path = current_path.join(recovery::update_log_file_name(sequence));
 
                 // Create a temporary directory in the same file system as the output
-                let temporary_dir = tempfile::tempdir_in(&current_path)?;
+                let temporary_dir = tempfile::Builder::new()
+                    .prefix(recovery::TEMPORARY_UPDATE_LOG_PREFIX)
+                    .tempdir_in(&current_path)?;
                 let mut temporary_disk_file = tempfile::NamedTempFile::new_in(&temporary_dir)?;
 
                 debug!("writing WAL update log to {:?}", temporary_disk_file.path());
                 bincode::serialize_into(temporary_disk_file.as_file(), &u)?;
                 temporary_disk_file.flush()?;
+                #[cfg(test)]
+                if let Err(e) = simulate_crash_after(WritePhase::UpdateLogWritten) {
+                    // A crashed process would not remove the temporary files
+                    temporary_disk_file.keep()?;
+                    let _ = temporary_dir.keep();
+                    return Err(e);
+                }
                 debug!("moving finished WAL update log to {:?}", &log_path);
                 // Since the temporary file should be on the same file system, persisting/moving it should be an atomic operation
                 temporary_disk_file.persist(&log_path)?;
@@ -758,9 +855,13 @@ impl<CT: ComponentType> Graph<CT> {
         if !backup_location.exists() {
             std::fs::rename(&current_location, &backup_location)?;
         }
+        #[cfg(test)]
+        simulate_crash_after(WritePhase::BackupCreated)?;
 
         // Save the complete corpus without the write log to the target location
         self.internal_save(&current_location)?;
+        #[cfg(test)]
+        simulate_crash_after(WritePhase::CurrentSaved)?;
 
         // rename backup folder (renaming is atomic and deleting could leave an incomplete backup folder on disk)
         let tmp_dir = tempfile::Builder::new()
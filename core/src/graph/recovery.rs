@@ -0,0 +1,208 @@
+//! Recovery of a graph from its write-ahead log when it is opened.
+//!
+//! Each call to [`Graph::apply_update`](super::Graph::apply_update) on a graph
+//! with a location on disk writes its updates as a new batch to the
+//! write-ahead log, before the complete graph is saved in the background.
+//! When the graph is opened, all complete batches that have not been saved yet
+//! are replayed in the order they have been written.
+
+use super::update::GraphUpdate;
+use crate::errors::Result;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Prefix of the temporary folders in which a batch of the write-ahead log is
+/// written before it is moved to its final location.
+pub(crate) const TEMPORARY_UPDATE_LOG_PREFIX: &str = "temporary-graphannis-update-log";
+
+const UPDATE_LOG_PREFIX: &str = "update_log";
+const UPDATE_LOG_EXTENSION: &str = ".bin";
+
+/// Folder of a graph to which batches of the write-ahead log are moved when
+/// they can't be replayed.
+pub const DISCARDED_UPDATE_LOGS_FOLDER: &str = "discarded-update-logs";
+
+/// A batch of updates from the write-ahead log, which has been written by a
+/// single successful call to `apply_update`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateBatch {
+    /// Position of the batch in the write-ahead log, starting with 1.
+    pub sequence: u64,
+    /// Number of update events in the batch.
+    pub event_count: usize,
+}
+
+/// A batch of updates from the write-ahead log that has not been applied to
+/// the graph.
+///
+/// Incomplete batches have been removed from the disk. All other discarded
+/// batches have been moved to the [`DISCARDED_UPDATE_LOGS_FOLDER`] of the
+/// graph, so their updates are not lost and can be inspected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiscardedUpdateBatch {
+    /// Writing the batch has been interrupted, so the call to `apply_update`
+    /// that created it never returned successfully.
+    Incomplete { path: PathBuf },
+    /// The batch can't be read. It has been moved to `path`.
+    Unreadable {
+        sequence: u64,
+        error: String,
+        path: PathBuf,
+    },
+    /// The batch follows an unreadable batch and might depend on its changes.
+    /// It has been moved to `path`.
+    Skipped { sequence: u64, path: PathBuf },
+}
+
+impl fmt::Display for DiscardedUpdateBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiscardedUpdateBatch::Incomplete { path } => {
+                write!(f, "incomplete update batch {}", path.display())
+            }
+            DiscardedUpdateBatch::Unreadable {
+                sequence,
+                error,
+                path,
+            } => {
+                write!(
+                    f,
+                    "update batch {sequence} can't be read ({error}), moved to {}",
+                    path.display()
+                )
+            }
+            DiscardedUpdateBatch::Skipped { sequence, path } => {
+                write!(
+                    f,
+                    "update batch {sequence} follows an unreadable batch, moved to {}",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+/// Describes which recovery steps have been necessary when opening a graph.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    /// Saving the graph has been interrupted and it has been restored from
+    /// the last consistent version in the backup folder.
+    pub restored_backup: bool,
+    /// Batches of the write-ahead log that have been applied to the graph, in
+    /// the order they have been applied.
+    pub replayed: Vec<UpdateBatch>,
+    /// Batches of the write-ahead log that have not been applied.
+    pub discarded: Vec<DiscardedUpdateBatch>,
+}
+
+impl RecoveryReport {
+    /// Returns `true` if the graph has been saved completely and no recovery
+    /// was necessary.
+    pub fn is_empty(&self) -> bool {
+        !self.restored_backup && self.replayed.is_empty() && self.discarded.is_empty()
+    }
+}
+
+impl fmt::Display for RecoveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.restored_backup {
+            write!(f, "restored backup, ")?;
+        }
+        write!(
+            f,
+            "replayed {} and discarded {} update batch(es)",
+            self.replayed.len(),
+            self.discarded.len()
+        )
+    }
+}
+
+/// Get the file name of the batch with the given `sequence` number. The first
+/// batch uses the same name as the single update log of older versions.
+pub(crate) fn update_log_file_name(sequence: u64) -> String {
+    if sequence <= 1 {
+        format!("{UPDATE_LOG_PREFIX}{UPDATE_LOG_EXTENSION}")
+    } else {
+        format!("{UPDATE_LOG_PREFIX}_{sequence}{UPDATE_LOG_EXTENSION}")
+    }
+}
+
+fn parse_update_log_file_name(file_name: &str) -> Option<u64> {
+    let stem = file_name
+        .strip_prefix(UPDATE_LOG_PREFIX)?
+        .strip_suffix(UPDATE_LOG_EXTENSION)?;
+    if stem.is_empty() {
+        Some(1)
+    } else {
+        stem.strip_prefix('_')?.parse().ok()
+    }
+}
+
+/// Find all batches of the write-ahead log in the given folder, sorted by
+/// their sequence number.
+pub(crate) fn find_update_logs(location: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    let mut result = Vec::new();
+    if location.is_dir() {
+        for entry in location.read_dir()? {
+            let path = entry?.path();
+            let sequence = path
+                .file_name()
+                .and_then(|n| parse_update_log_file_name(&n.to_string_lossy()));
+            if let Some(sequence) = sequence
+                && path.is_file()
+            {
+                result.push((sequence, path));
+            }
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Find the temporary folders of batches that have not been written
+/// completely.
+pub(crate) fn find_incomplete_update_logs(location: &Path) -> Result<Vec<PathBuf>> {
+    let mut result = Vec::new();
+    if location.is_dir() {
+        for entry in location.read_dir()? {
+            let path = entry?.path();
+            let is_temporary = path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with(TEMPORARY_UPDATE_LOG_PREFIX));
+            if is_temporary && path.is_dir() {
+                result.push(path);
+            }
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Move a batch of the write-ahead log that can't be replayed to the folder
+/// for discarded batches of the graph at `location`. Returns the new path of
+/// the batch.
+pub(crate) fn discard_update_log(location: &Path, log_path: &Path) -> Result<PathBuf> {
+    let discarded_dir = location.join(DISCARDED_UPDATE_LOGS_FOLDER);
+    std::fs::create_dir_all(&discarded_dir)?;
+    // Batches that have been discarded earlier can have the same file name
+    let file_name = log_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut target = discarded_dir.join(&file_name);
+    let mut copy_nr = 1;
+    while target.exists() {
+        target = discarded_dir.join(format!("{copy_nr}_{file_name}"));
+        copy_nr += 1;
+    }
+    std::fs::rename(log_path, &target)?;
+    Ok(target)
+}
+
+pub(crate) fn read_update_log(path: &Path) -> Result<GraphUpdate> {
+    let log_reader = std::fs::File::open(path)?;
+    let update = bincode::deserialize_from(log_reader)?;
+    Ok(update)
+}
@@ -147,6 +147,9 @@ impl GraphStorage for AdjacencyListStorage {
         };
 
         result.annos.after_deserialization();
+        result
+            .annos
+            .load_most_common_values(&location.join("annos_most_common_values.bin"))?;
         Ok(result)
     }
 
@@ -154,6 +157,8 @@ impl GraphStorage for AdjacencyListStorage {
         serialize_gs_field(&self.edges, "edges", location)?;
         serialize_gs_field(&self.inverse_edges, "inverse_edges", location)?;
         serialize_gs_field(&self.annos, "annos", location)?;
+        self.annos
+            .save_most_common_values(&location.join("annos_most_common_values.bin"))?;
         save_statistics_to_toml(location, self.stats.as_ref())?;
         Ok(())
     }
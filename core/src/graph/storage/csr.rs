@@ -0,0 +1,422 @@
+use itertools::Itertools;
+use memmap2::Mmap;
+use normpath::PathExt;
+use rustc_hash::FxHashSet;
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{BufWriter, Write},
+    ops::Bound,
+    path::{Path, PathBuf},
+};
+use tempfile::tempfile;
+
+use crate::{
+    annostorage::{AnnotationStorage, NodeAnnotationStorage, ondisk::AnnoStorageImpl},
+    dfs::CycleSafeDFS,
+    errors::{GraphAnnisCoreError, Result},
+    try_as_boxed_iter,
+    types::{Edge, NodeID},
+};
+
+use super::{
+    EdgeContainer, GraphStatistic, GraphStorage, load_statistics_from_location,
+    save_statistics_to_toml,
+};
+
+pub(crate) const SERIALIZATION_ID: &str = "CsrV1";
+
+/// Minimal number of nodes of a component before the CSR layout is
+/// preferred over implementations that need to be deserialized.
+pub(crate) const MIN_NODES: usize = 100_000;
+/// Minimal ratio of the nodes of a component to the largest node ID. The
+/// offsets contain an entry for each node ID up to the largest one with an
+/// edge, so sparse components would waste a lot of space.
+pub(crate) const MIN_NODE_RATIO: f64 = 0.1;
+
+const ENTRY_SIZE: usize = std::mem::size_of::<NodeID>();
+
+fn read_entry(data: &[u8], index: usize) -> Result<u64> {
+    let offset = index * ENTRY_SIZE;
+    let buffer: [u8; ENTRY_SIZE] = data
+        .get(offset..(offset + ENTRY_SIZE))
+        .ok_or_else(|| {
+            GraphAnnisCoreError::CorruptedGraphStorage(format!("entry {index} is out of bounds"))
+        })?
+        .try_into()?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+fn map_file(file: &File) -> Result<Mmap> {
+    let result = unsafe { Mmap::map(file)? };
+    Ok(result)
+}
+
+/// Adjacency lists in the compressed sparse row (CSR) layout.
+///
+/// The neighbors of all nodes are stored sorted by the node ID in a single
+/// array. The offsets array contains the position of the first neighbor for
+/// each node ID, so the neighbors of node `n` are located between
+/// `offsets[n]` and `offsets[n+1]`. Both arrays consist of little endian
+/// 64 bit integers and are memory mapped from a file.
+struct CsrIndex {
+    offsets: Mmap,
+    neighbors: Mmap,
+}
+
+impl CsrIndex {
+    fn new() -> Result<CsrIndex> {
+        Ok(CsrIndex {
+            offsets: map_file(&tempfile()?)?,
+            neighbors: map_file(&tempfile()?)?,
+        })
+    }
+
+    /// Create the index from the sorted neighbors of each node. The
+    /// `adjacency` must be sorted by the node ID.
+    fn build<I>(adjacency: I) -> Result<CsrIndex>
+    where
+        I: IntoIterator<Item = Result<(NodeID, Vec<NodeID>)>>,
+    {
+        let mut offsets = BufWriter::new(tempfile()?);
+        let mut neighbors = BufWriter::new(tempfile()?);
+
+        let mut next_node: NodeID = 0;
+        let mut number_of_neighbors: u64 = 0;
+        for entry in adjacency {
+            let (node, node_neighbors) = entry?;
+            // Nodes without neighbors start and end at the same position
+            while next_node <= node {
+                offsets.write_all(&number_of_neighbors.to_le_bytes())?;
+                next_node += 1;
+            }
+            for n in node_neighbors {
+                neighbors.write_all(&n.to_le_bytes())?;
+                number_of_neighbors += 1;
+            }
+        }
+        if next_node > 0 {
+            // Add the end position of the neighbors of the last node
+            offsets.write_all(&number_of_neighbors.to_le_bytes())?;
+        }
+
+        let offsets = offsets.into_inner().map_err(|e| e.into_error())?;
+        let neighbors = neighbors.into_inner().map_err(|e| e.into_error())?;
+        Ok(CsrIndex {
+            offsets: map_file(&offsets)?,
+            neighbors: map_file(&neighbors)?,
+        })
+    }
+
+    fn load_from(location: &Path, name: &str) -> Result<CsrIndex> {
+        let offsets = File::open(location.join(format!("{name}_offsets.bin")))?;
+        let neighbors = File::open(location.join(format!("{name}_neighbors.bin")))?;
+        Ok(CsrIndex {
+            offsets: map_file(&offsets)?,
+            neighbors: map_file(&neighbors)?,
+        })
+    }
+
+    fn save_to(&self, location: &Path, name: &str) -> Result<()> {
+        std::fs::write(
+            location.join(format!("{name}_offsets.bin")),
+            &self.offsets[..],
+        )?;
+        std::fs::write(
+            location.join(format!("{name}_neighbors.bin")),
+            &self.neighbors[..],
+        )?;
+        Ok(())
+    }
+
+    /// Number of node IDs that have an entry in the offsets.
+    fn number_of_nodes(&self) -> u64 {
+        ((self.offsets.len() / ENTRY_SIZE) as u64).saturating_sub(1)
+    }
+
+    /// Get the start and end position of the neighbors of the `node`.
+    fn range(&self, node: NodeID) -> Result<Option<(usize, usize)>> {
+        if node >= self.number_of_nodes() {
+            return Ok(None);
+        }
+        let node: usize = node.try_into()?;
+        let start: usize = read_entry(&self.offsets, node)?.try_into()?;
+        let end: usize = read_entry(&self.offsets, node + 1)?.try_into()?;
+        Ok(Some((start, end)))
+    }
+
+    fn has_neighbors(&self, node: NodeID) -> Result<bool> {
+        Ok(self.range(node)?.is_some_and(|(start, end)| start < end))
+    }
+
+    fn neighbors<'a>(&'a self, node: NodeID) -> Box<dyn Iterator<Item = Result<NodeID>> + 'a> {
+        let (start, end) = match try_as_boxed_iter!(self.range(node)) {
+            Some(range) => range,
+            None => return Box::new(std::iter::empty()),
+        };
+        let data = self
+            .neighbors
+            .get((start * ENTRY_SIZE)..(end * ENTRY_SIZE))
+            .ok_or_else(|| {
+                GraphAnnisCoreError::CorruptedGraphStorage(format!(
+                    "neighbors of node {node} are out of bounds"
+                ))
+            });
+        let data = try_as_boxed_iter!(data);
+        Box::new(
+            data.chunks_exact(ENTRY_SIZE)
+                .map(|entry| Ok(u64::from_le_bytes(entry.try_into()?))),
+        )
+    }
+
+    fn nodes_with_neighbors<'a>(&'a self) -> Box<dyn Iterator<Item = Result<NodeID>> + 'a> {
+        let it = (0..self.number_of_nodes())
+            .map(move |n| Ok((n, self.has_neighbors(n)?)))
+            .filter_map_ok(|(n, has_neighbors)| if has_neighbors { Some(n) } else { None });
+        Box::new(it)
+    }
+}
+
+/// A read-only [GraphStorage] that stores the outgoing and ingoing edges in
+/// memory mapped files with a compressed sparse row (CSR) layout.
+///
+/// Loading this graph storage only maps the files into memory, so it does not
+/// need to deserialize the edges and the pages of the files are shared with
+/// other processes that use the same component.
+pub struct CsrStorage {
+    outgoing: CsrIndex,
+    ingoing: CsrIndex,
+    annos: AnnoStorageImpl<Edge>,
+    stats: Option<GraphStatistic>,
+    location: Option<PathBuf>,
+}
+
+impl CsrStorage {
+    pub fn new() -> Result<CsrStorage> {
+        Ok(CsrStorage {
+            outgoing: CsrIndex::new()?,
+            ingoing: CsrIndex::new()?,
+            annos: AnnoStorageImpl::new(None)?,
+            stats: None,
+            location: None,
+        })
+    }
+}
+
+impl EdgeContainer for CsrStorage {
+    fn get_outgoing_edges<'a>(
+        &'a self,
+        node: NodeID,
+    ) -> Box<dyn Iterator<Item = Result<NodeID>> + 'a> {
+        self.outgoing.neighbors(node)
+    }
+
+    fn has_outgoing_edges(&self, node: NodeID) -> Result<bool> {
+        self.outgoing.has_neighbors(node)
+    }
+
+    fn get_ingoing_edges<'a>(
+        &'a self,
+        node: NodeID,
+    ) -> Box<dyn Iterator<Item = Result<NodeID>> + 'a> {
+        self.ingoing.neighbors(node)
+    }
+
+    fn has_ingoing_edges(&self, node: NodeID) -> Result<bool> {
+        self.ingoing.has_neighbors(node)
+    }
+
+    fn source_nodes<'a>(&'a self) -> Box<dyn Iterator<Item = Result<NodeID>> + 'a> {
+        self.outgoing.nodes_with_neighbors()
+    }
+
+    fn get_statistics(&self) -> Option<&GraphStatistic> {
+        self.stats.as_ref()
+    }
+}
+
+impl GraphStorage for CsrStorage {
+    fn find_connected<'a>(
+        &'a self,
+        node: NodeID,
+        min_distance: usize,
+        max_distance: Bound<usize>,
+    ) -> Box<dyn Iterator<Item = Result<NodeID>> + 'a> {
+        let mut visited = FxHashSet::<NodeID>::default();
+        let max_distance = match max_distance {
+            Bound::Unbounded => usize::MAX,
+            Bound::Included(max_distance) => max_distance,
+            Bound::Excluded(max_distance) => max_distance - 1,
+        };
+        let it = CycleSafeDFS::<'a>::new(self, node, min_distance, max_distance)
+            .map_ok(|x| x.node)
+            .filter_ok(move |n| visited.insert(*n));
+        Box::new(it)
+    }
+
+    fn find_connected_inverse<'a>(
+        &'a self,
+        node: NodeID,
+        min_distance: usize,
+        max_distance: Bound<usize>,
+    ) -> Box<dyn Iterator<Item = Result<NodeID>> + 'a> {
+        let mut visited = FxHashSet::<NodeID>::default();
+        let max_distance = match max_distance {
+            Bound::Unbounded => usize::MAX,
+            Bound::Included(max_distance) => max_distance,
+            Bound::Excluded(max_distance) => max_distance - 1,
+        };
+
+        let it = CycleSafeDFS::<'a>::new_inverse(self, node, min_distance, max_distance)
+            .map_ok(|x| x.node)
+            .filter_ok(move |n| visited.insert(*n));
+        Box::new(it)
+    }
+
+    fn distance(&self, source: NodeID, target: NodeID) -> Result<Option<usize>> {
+        let mut it = CycleSafeDFS::new(self, source, usize::MIN, usize::MAX)
+            .filter_ok(|x| target == x.node)
+            .map_ok(|x| x.distance);
+
+        match it.next() {
+            Some(distance) => {
+                let distance = distance?;
+                Ok(Some(distance))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn is_connected(
+        &self,
+        source: NodeID,
+        target: NodeID,
+        min_distance: usize,
+        max_distance: Bound<usize>,
+    ) -> Result<bool> {
+        let max_distance = match max_distance {
+            Bound::Unbounded => usize::MAX,
+            Bound::Included(max_distance) => max_distance,
+            Bound::Excluded(max_distance) => max_distance - 1,
+        };
+        let mut it = CycleSafeDFS::new(self, source, min_distance, max_distance)
+            .filter_ok(|x| target == x.node);
+
+        match it.next() {
+            Some(next) => {
+                next?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn get_anno_storage(&self) -> &dyn crate::annostorage::EdgeAnnotationStorage {
+        &self.annos
+    }
+
+    fn copy(
+        &mut self,
+        _node_annos: &dyn NodeAnnotationStorage,
+        orig: &dyn GraphStorage,
+    ) -> Result<()> {
+        let mut annos = AnnoStorageImpl::new(None)?;
+
+        let mut sources: Vec<NodeID> = orig.source_nodes().try_collect()?;
+        sources.sort_unstable();
+        sources.dedup();
+
+        let mut targets = Vec::new();
+        let outgoing = CsrIndex::build(sources.into_iter().map(|source| {
+            let mut node_targets: Vec<NodeID> = orig.get_outgoing_edges(source).try_collect()?;
+            node_targets.sort_unstable();
+            node_targets.dedup();
+            for target in node_targets.iter() {
+                // Copy all annotations for this edge
+                let edge = Edge {
+                    source,
+                    target: *target,
+                };
+                for a in orig.get_anno_storage().get_annotations_for_item(&edge)? {
+                    annos.insert(edge.clone(), a)?;
+                }
+            }
+            targets.extend_from_slice(&node_targets);
+            Ok((source, node_targets))
+        }))?;
+
+        targets.sort_unstable();
+        targets.dedup();
+        let ingoing = CsrIndex::build(targets.into_iter().map(|target| {
+            let mut node_sources: Vec<NodeID> = orig.get_ingoing_edges(target).try_collect()?;
+            node_sources.sort_unstable();
+            node_sources.dedup();
+            Ok((target, node_sources))
+        }))?;
+
+        self.outgoing = outgoing;
+        self.ingoing = ingoing;
+        self.annos = annos;
+        self.stats = orig.get_statistics().cloned();
+        self.annos.calculate_statistics()?;
+        self.location = None;
+        Ok(())
+    }
+
+    fn as_edgecontainer(&self) -> &dyn EdgeContainer {
+        self
+    }
+
+    fn inverse_has_same_cost(&self) -> bool {
+        true
+    }
+
+    fn serialization_id(&self) -> String {
+        SERIALIZATION_ID.to_string()
+    }
+
+    fn load_from(location: &Path) -> Result<Self>
+    where
+        Self: std::marker::Sized,
+    {
+        let outgoing = CsrIndex::load_from(location, "outgoing")?;
+        let ingoing = CsrIndex::load_from(location, "ingoing")?;
+        let annos = AnnoStorageImpl::new(Some(crate::annostorage::ondisk::subfolder(location)))?;
+        let stats = load_statistics_from_location(location)?;
+
+        Ok(Self {
+            outgoing,
+            ingoing,
+            annos,
+            stats,
+            location: Some(location.to_path_buf()),
+        })
+    }
+
+    fn save_to(&self, location: &Path) -> Result<()> {
+        // Make sure the output location exists before trying to normalize the paths
+        std::fs::create_dir_all(location)?;
+        // Normalize all paths to check if they are the same
+        let new_location = location.normalize()?;
+        if let Some(old_location) = &self.location {
+            let old_location = old_location.normalize()?;
+            if new_location == old_location && new_location.join("outgoing_offsets.bin").is_file() {
+                // This is an immutable graph storage and overwriting the
+                // memory mapped files would change the data while reading it.
+                return Ok(());
+            }
+        }
+        self.outgoing.save_to(location, "outgoing")?;
+        self.ingoing.save_to(location, "ingoing")?;
+
+        // Save edge annotations
+        self.annos.save_annotations_to(location)?;
+
+        save_statistics_to_toml(location, self.stats.as_ref())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,180 @@
+use super::*;
+use crate::{
+    graph::storage::{WriteableGraphStorage, adjacencylist::AdjacencyListStorage, registry},
+    types::{AnnoKey, Annotation},
+};
+use pretty_assertions::assert_eq;
+
+/// Creates an example graph storage with the folllowing structure:
+///
+/// ```
+///       +-----------+
+///       v           |
+///       1     10    |
+///      / \   /      |
+///     2   3 /       |
+///      \ /          |
+///       4 ----------+
+/// ```
+fn create_cyclic_gs() -> Result<AdjacencyListStorage> {
+    let mut orig = AdjacencyListStorage::new();
+
+    orig.add_edge((1, 2).into())?;
+    orig.add_edge((1, 3).into())?;
+    orig.add_edge((10, 3).into())?;
+    orig.add_edge((2, 4).into())?;
+    orig.add_edge((3, 4).into())?;
+    orig.add_edge((4, 1).into())?;
+
+    let key = AnnoKey {
+        name: "example".into(),
+        ns: "default_ns".into(),
+    };
+    let anno = Annotation {
+        key,
+        val: "branch".into(),
+    };
+    orig.add_edge_annotation((2, 4).into(), anno)?;
+
+    Ok(orig)
+}
+
+fn create_csr_gs() -> Result<CsrStorage> {
+    let node_annos = AnnoStorageImpl::new(None)?;
+    let orig = create_cyclic_gs()?;
+    let mut result = CsrStorage::new()?;
+    result.copy(&node_annos, &orig)?;
+    Ok(result)
+}
+
+fn sorted(it: Box<dyn Iterator<Item = Result<NodeID>> + '_>) -> Vec<NodeID> {
+    let mut result: Vec<_> = it.map(|n| n.unwrap()).collect();
+    result.sort_unstable();
+    result
+}
+
+#[test]
+fn test_source_nodes() {
+    let gs = create_csr_gs().unwrap();
+    assert_eq!(vec![1, 2, 3, 4, 10], sorted(gs.source_nodes()));
+
+    let empty = CsrStorage::new().unwrap();
+    assert_eq!(0, empty.source_nodes().count());
+}
+
+#[test]
+fn test_outgoing_edges() {
+    let gs = create_csr_gs().unwrap();
+
+    assert_eq!(vec![2, 3], sorted(gs.get_outgoing_edges(1)));
+    assert_eq!(vec![4], sorted(gs.get_outgoing_edges(3)));
+    assert_eq!(vec![1], sorted(gs.get_outgoing_edges(4)));
+    assert_eq!(vec![3], sorted(gs.get_outgoing_edges(10)));
+    assert_eq!(0, gs.get_outgoing_edges(5).count());
+    assert_eq!(0, gs.get_outgoing_edges(100).count());
+
+    assert!(gs.has_outgoing_edges(10).unwrap());
+    assert!(!gs.has_outgoing_edges(5).unwrap());
+    assert!(!gs.has_outgoing_edges(100).unwrap());
+}
+
+#[test]
+fn test_ingoing_edges() {
+    let gs = create_csr_gs().unwrap();
+
+    assert_eq!(vec![4], sorted(gs.get_ingoing_edges(1)));
+    assert_eq!(vec![1, 10], sorted(gs.get_ingoing_edges(3)));
+    assert_eq!(vec![2, 3], sorted(gs.get_ingoing_edges(4)));
+    assert_eq!(0, gs.get_ingoing_edges(10).count());
+    assert_eq!(0, gs.get_ingoing_edges(100).count());
+
+    assert!(gs.has_ingoing_edges(3).unwrap());
+    assert!(!gs.has_ingoing_edges(10).unwrap());
+}
+
+#[test]
+fn test_find_connected() {
+    let gs = create_csr_gs().unwrap();
+
+    assert_eq!(
+        vec![1, 2, 3, 4],
+        sorted(gs.find_connected(10, 1, Bound::Unbounded))
+    );
+    assert_eq!(
+        vec![3, 4],
+        sorted(gs.find_connected(10, 1, Bound::Included(2)))
+    );
+    assert_eq!(
+        vec![3, 4, 10],
+        sorted(gs.find_connected(10, 0, Bound::Excluded(3)))
+    );
+    assert_eq!(
+        vec![2, 3],
+        sorted(gs.find_connected_inverse(4, 1, Bound::Included(1)))
+    );
+    assert_eq!(
+        vec![1, 2, 3, 10],
+        sorted(gs.find_connected_inverse(4, 1, Bound::Included(2)))
+    );
+}
+
+#[test]
+fn test_distance() {
+    let gs = create_csr_gs().unwrap();
+
+    assert_eq!(Some(1), gs.distance(1, 2).unwrap());
+    assert_eq!(Some(2), gs.distance(1, 4).unwrap());
+    assert_eq!(Some(3), gs.distance(10, 1).unwrap());
+    assert_eq!(None, gs.distance(2, 10).unwrap());
+
+    assert!(gs.is_connected(10, 1, 3, Bound::Included(3)).unwrap());
+    assert!(!gs.is_connected(10, 1, 1, Bound::Excluded(3)).unwrap());
+    assert!(!gs.is_connected(1, 10, 1, Bound::Unbounded).unwrap());
+}
+
+#[test]
+fn test_save_load() {
+    let save_gs = create_csr_gs().unwrap();
+
+    let tmp_location = tempfile::TempDir::new().unwrap();
+    save_gs.save_to(tmp_location.path()).unwrap();
+    std::fs::write(tmp_location.path().join("impl.cfg"), SERIALIZATION_ID).unwrap();
+
+    let new_gs = registry::deserialize(SERIALIZATION_ID, tmp_location.path()).unwrap();
+    assert_eq!(SERIALIZATION_ID, new_gs.serialization_id());
+    assert_eq!(vec![1, 2, 3, 4, 10], sorted(new_gs.source_nodes()));
+    assert_eq!(vec![1, 10], sorted(new_gs.get_ingoing_edges(3)));
+
+    let edge_anno = new_gs
+        .get_anno_storage()
+        .get_annotations_for_item(&(2, 4).into())
+        .unwrap();
+    assert_eq!(1, edge_anno.len());
+    assert_eq!("branch", edge_anno[0].val);
+    assert_eq!(
+        0,
+        new_gs
+            .get_anno_storage()
+            .get_annotations_for_item(&(3, 4).into())
+            .unwrap()
+            .len()
+    );
+
+    // Saving the graph storage to the location of its memory mapped files
+    // must not change them
+    new_gs.save_to(tmp_location.path()).unwrap();
+    let same_location_gs = CsrStorage::load_from(tmp_location.path()).unwrap();
+    assert_eq!(vec![2, 3], sorted(same_location_gs.get_outgoing_edges(1)));
+}
+
+#[test]
+fn test_save_load_empty() {
+    let save_gs = CsrStorage::new().unwrap();
+
+    let tmp_location = tempfile::TempDir::new().unwrap();
+    save_gs.save_to(tmp_location.path()).unwrap();
+
+    let new_gs = CsrStorage::load_from(tmp_location.path()).unwrap();
+    assert_eq!(0, new_gs.source_nodes().count());
+    assert_eq!(0, new_gs.get_outgoing_edges(0).count());
+}
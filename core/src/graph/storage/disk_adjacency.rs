@@ -161,9 +161,7 @@ impl GraphStorage for DiskAdjacencyListStorage {
                     .fixed_key_size(std::mem::size_of::<NodeID>() * 2)
                     .fixed_value_size(2),
             )?,
-            annos: AnnoStorageImpl::new(Some(
-                location.join(crate::annostorage::ondisk::SUBFOLDER_NAME),
-            ))?,
+            annos: AnnoStorageImpl::new(Some(crate::annostorage::ondisk::subfolder(location)))?,
             stats,
         };
         Ok(result)
@@ -370,9 +370,7 @@ impl GraphStorage for DiskPathStorage {
         )?;
 
         // Load annotation storage
-        let annos = AnnoStorageImpl::new(Some(
-            location.join(crate::annostorage::ondisk::SUBFOLDER_NAME),
-        ))?;
+        let annos = AnnoStorageImpl::new(Some(crate::annostorage::ondisk::subfolder(location)))?;
 
         let stats = load_statistics_from_location(location)?;
 
@@ -393,9 +391,11 @@ impl GraphStorage for DiskPathStorage {
         let new_location = location.normalize()?;
         if let Some(old_location) = &self.location {
             let old_location = old_location.normalize()?;
-            if new_location == old_location {
+            if new_location == old_location && new_location.join("paths.bin").is_file() {
                 // This is an immutable graph storage so there can't be any
                 // changes to write to the existing location we already use.
+                // The files are missing if the folder has been moved away,
+                // e.g. to a backup folder, and need to be written again.
                 return Ok(());
             }
         }
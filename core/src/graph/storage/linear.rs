@@ -189,6 +189,9 @@ where
         };
 
         result.annos.after_deserialization();
+        result
+            .annos
+            .load_most_common_values(&location.join("annos_most_common_values.bin"))?;
         Ok(result)
     }
 
@@ -196,6 +199,8 @@ where
         serialize_gs_field(&self.node_to_pos, "node_to_pos", location)?;
         serialize_gs_field(&self.node_chains, "node_chains", location)?;
         serialize_gs_field(&self.annos, "annos", location)?;
+        self.annos
+            .save_most_common_values(&location.join("annos_most_common_values.bin"))?;
         save_statistics_to_toml(location, self.stats.as_ref())?;
         Ok(())
     }
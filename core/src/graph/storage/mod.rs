@@ -1,4 +1,5 @@
 pub mod adjacencylist;
+pub mod csr;
 pub mod dense_adjacency;
 pub mod disk_adjacency;
 pub mod disk_path;
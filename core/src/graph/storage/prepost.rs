@@ -222,6 +222,9 @@ where
         };
 
         result.annos.after_deserialization();
+        result
+            .annos
+            .load_most_common_values(&location.join("annos_most_common_values.bin"))?;
 
         Ok(result)
     }
@@ -230,6 +233,8 @@ where
         serialize_gs_field(&self.node_to_order, "node_to_order", location)?;
         serialize_gs_field(&self.order_to_node, "order_to_node", location)?;
         serialize_gs_field(&self.annos, "annos", location)?;
+        self.annos
+            .save_most_common_values(&location.join("annos_most_common_values.bin"))?;
         save_statistics_to_toml(location, self.stats.as_ref())?;
         Ok(())
     }
@@ -1,11 +1,12 @@
 use super::adjacencylist::AdjacencyListStorage;
+use super::csr::CsrStorage;
 use super::dense_adjacency::DenseAdjacencyListStorage;
 use super::disk_adjacency::DiskAdjacencyListStorage;
 use super::disk_path::DiskPathStorage;
 use super::linear::LinearGraphStorage;
 
 use super::{GraphStatistic, GraphStorage, prepost::PrePostOrderStorage};
-use super::{disk_adjacency, disk_path};
+use super::{csr, disk_adjacency, disk_path};
 use crate::{
     errors::{GraphAnnisCoreError, Result},
     graph::Graph,
@@ -36,6 +37,7 @@ lazy_static! {
             create_info_diskpath(),
         );
         insert_info::<DenseAdjacencyListStorage>(&mut m);
+        m.insert(csr::SERIALIZATION_ID.to_owned(), create_info_csr());
 
         insert_info::<PrePostOrderStorage<u64, u64>>(&mut m);
         insert_info::<PrePostOrderStorage<u64, u32>>(&mut m);
@@ -109,6 +111,18 @@ pub fn get_optimal_impl_heuristic<CT: ComponentType>(
 }
 
 fn get_adjacencylist_impl<CT: ComponentType>(db: &Graph<CT>, stats: &GraphStatistic) -> GSInfo {
+    // Large components of disk-based graphs are memory mapped instead of
+    // loading them, if the nodes are not spread too much over the range of all
+    // node IDs. Graphs held in main memory keep using the adjacency lists,
+    // which can be changed without converting them first.
+    if db.disk_based
+        && stats.nodes >= csr::MIN_NODES
+        && let Ok(Some(largest_node_id)) = db.node_annos.get_largest_item()
+        && (stats.nodes as f64 / largest_node_id as f64) >= csr::MIN_NODE_RATIO
+    {
+        return create_info_csr();
+    }
+
     if db.disk_based {
         create_info_diskadjacency()
     } else {
@@ -207,6 +221,17 @@ fn create_info_diskpath() -> GSInfo {
     }
 }
 
+fn create_info_csr() -> GSInfo {
+    GSInfo {
+        id: csr::SERIALIZATION_ID.to_string(),
+        constructor: || Ok(Arc::from(CsrStorage::new()?)),
+        deserialize_func: |path| {
+            let result = CsrStorage::load_from(path)?;
+            Ok(Arc::from(result))
+        },
+    }
+}
+
 pub fn create_from_info(info: &GSInfo) -> Result<Arc<dyn GraphStorage>> {
     (info.constructor)()
 }
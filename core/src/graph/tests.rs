@@ -33,6 +33,68 @@ fn create_writeable_gs() {
     .unwrap();
 }
 
+fn optimize_large_component(disk_based: bool) -> Graph<DefaultComponentType> {
+    // Building the component in main memory is much faster, the flag is only
+    // set afterwards to choose the implementation as for a disk-based graph.
+    let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+    let number_of_nodes = 2 * storage::csr::MIN_NODES as NodeID;
+    for node in 0..number_of_nodes {
+        db.node_annos
+            .insert(
+                node,
+                Annotation {
+                    key: NODE_TYPE_KEY.as_ref().clone(),
+                    val: "node".into(),
+                },
+            )
+            .unwrap();
+    }
+
+    // Each target node has two ingoing edges, so this is not a tree, and the
+    // source nodes have different fan-outs, so this is not a single path
+    let component = Component::new(DefaultComponentType::Edge, "test".into(), "large".into());
+    let gs = db.get_or_create_writable(&component).unwrap();
+    for node in (0..(number_of_nodes - 2)).step_by(3) {
+        gs.add_edge((node, node + 1).into()).unwrap();
+        gs.add_edge((node, node + 2).into()).unwrap();
+        gs.add_edge((node + 1, node + 2).into()).unwrap();
+    }
+    db.disk_based = disk_based;
+    db.calculate_component_statistics(&component).unwrap();
+    db.optimize_gs_impl(&component).unwrap();
+    db
+}
+
+#[test]
+fn optimize_large_component_to_csr() {
+    let db = optimize_large_component(true);
+
+    let component = Component::new(DefaultComponentType::Edge, "test".into(), "large".into());
+    let gs = db.get_graphstorage(&component).unwrap();
+    assert_eq!(storage::csr::SERIALIZATION_ID, gs.serialization_id());
+    assert_eq!(
+        vec![3, 4],
+        gs.get_ingoing_edges(5).collect::<Result<Vec<_>>>().unwrap()
+    );
+    assert!(
+        gs.is_connected(4, 5, 1, std::ops::Bound::Included(1))
+            .unwrap()
+    );
+}
+
+#[test]
+fn optimize_large_in_memory_component_to_adjacency_list() {
+    let db = optimize_large_component(false);
+
+    let component = Component::new(DefaultComponentType::Edge, "test".into(), "large".into());
+    let gs = db.get_graphstorage(&component).unwrap();
+    assert_ne!(storage::csr::SERIALIZATION_ID, gs.serialization_id());
+    assert_eq!(
+        vec![3, 4],
+        gs.get_ingoing_edges(5).collect::<Result<Vec<_>>>().unwrap()
+    );
+}
+
 #[test]
 fn open_existing_graph_storage() {
     let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
@@ -264,3 +326,346 @@ fn update_without_updating_stats() {
         .unwrap();
     assert_eq!(0, new_guess);
 }
+
+#[test]
+fn check_integrity_of_graph() {
+    let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+    let mut updates = GraphUpdate::new();
+    for node_name in ["n1", "n2"] {
+        updates
+            .add_event(UpdateEvent::AddNode {
+                node_name: node_name.into(),
+                node_type: "node".into(),
+            })
+            .unwrap();
+    }
+    updates
+        .add_event(UpdateEvent::AddEdge {
+            source_node: "n1".into(),
+            target_node: "n2".into(),
+            layer: "test".into(),
+            component_type: DefaultComponentType::Edge.to_string(),
+            component_name: "dep".into(),
+        })
+        .unwrap();
+    db.apply_update(&mut updates, |_| {}).unwrap();
+
+    let tmp = tempfile::tempdir().unwrap();
+    db.save_to(tmp.path()).unwrap();
+    let report = integrity::check_integrity::<DefaultComponentType>(tmp.path(), false).unwrap();
+    assert!(report.is_ok());
+
+    // Simulate an interrupted save operation, which can be repaired
+    std::fs::rename(tmp.path().join("current"), tmp.path().join("backup")).unwrap();
+    let report = integrity::check_integrity::<DefaultComponentType>(tmp.path(), false).unwrap();
+    assert_eq!(
+        vec![integrity::IntegrityProblem::StaleBackup],
+        report.problems
+    );
+    let report = integrity::check_integrity::<DefaultComponentType>(tmp.path(), true).unwrap();
+    assert!(report.is_ok());
+    assert_eq!(
+        vec![integrity::IntegrityProblem::StaleBackup],
+        report.repaired
+    );
+    assert!(tmp.path().join("current").is_dir());
+    assert!(!tmp.path().join("backup").exists());
+
+    // Add an edge to a node that does not exist
+    let component = Component::new(DefaultComponentType::Edge, "test".into(), "dep".into());
+    let gs = db.get_or_create_writable(&component).unwrap();
+    gs.add_edge(Edge {
+        source: 0,
+        target: 1000,
+    })
+    .unwrap();
+    db.save_to(tmp.path()).unwrap();
+    let report = integrity::check_integrity::<DefaultComponentType>(tmp.path(), true).unwrap();
+    assert_eq!(
+        vec![integrity::IntegrityProblem::MissingNodes {
+            component: component.to_string(),
+            missing_node_count: 1
+        }],
+        report.problems
+    );
+}
+
+/// Create a graph with a single node that persists its updates to `location`.
+fn create_persisted_graph(location: &Path) -> Graph<DefaultComponentType> {
+    let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+    let mut u = GraphUpdate::new();
+    u.add_event(UpdateEvent::AddNode {
+        node_name: "root".into(),
+        node_type: "corpus".into(),
+    })
+    .unwrap();
+    db.apply_update(&mut u, |_| {}).unwrap();
+    db.persist_to(location).unwrap();
+    db
+}
+
+fn add_label(db: &mut Graph<DefaultComponentType>, anno_name: &str) -> Result<()> {
+    let mut u = GraphUpdate::new();
+    u.add_event(UpdateEvent::AddNodeLabel {
+        node_name: "root".into(),
+        anno_ns: "example".into(),
+        anno_name: anno_name.into(),
+        anno_value: "anno-value".into(),
+    })?;
+    db.apply_update(&mut u, |_| {})
+}
+
+fn has_label(db: &Graph<DefaultComponentType>, anno_name: &str) -> bool {
+    let root = db
+        .node_annos
+        .get_node_id_from_name("root")
+        .unwrap()
+        .unwrap();
+    db.node_annos
+        .get_value_for_item(
+            &root,
+            &AnnoKey {
+                ns: "example".into(),
+                name: anno_name.into(),
+            },
+        )
+        .unwrap()
+        .is_some()
+}
+
+/// Apply the update and simulate that the process is killed after the given
+/// phase of writing it to disk.
+fn add_label_and_crash(db: &mut Graph<DefaultComponentType>, anno_name: &str, phase: WritePhase) {
+    SIMULATED_CRASH.set(Some(phase));
+    let result = add_label(db, anno_name);
+    SIMULATED_CRASH.set(None);
+    assert!(result.is_err());
+}
+
+fn sync_and_crash(db: &Graph<DefaultComponentType>, phase: WritePhase) {
+    SIMULATED_CRASH.set(Some(phase));
+    let result = db.background_sync_wal_updates();
+    SIMULATED_CRASH.set(None);
+    assert!(result.is_err());
+}
+
+#[test]
+fn replay_all_update_batches() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut db = create_persisted_graph(tmp.path());
+    add_label(&mut db, "first").unwrap();
+    add_label(&mut db, "second").unwrap();
+    std::mem::drop(db);
+
+    let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+    #[allow(deprecated)]
+    let report = db.load_from(tmp.path(), true).unwrap();
+    assert!(!report.restored_backup);
+    assert_eq!(
+        vec![
+            recovery::UpdateBatch {
+                sequence: 1,
+                event_count: 1
+            },
+            recovery::UpdateBatch {
+                sequence: 2,
+                event_count: 1
+            }
+        ],
+        report.replayed
+    );
+    assert!(report.discarded.is_empty());
+    assert!(has_label(&db, "first"));
+    assert!(has_label(&db, "second"));
+
+    // After saving the graph, no recovery is necessary
+    db.background_sync_wal_updates().unwrap();
+    let report = db.open(tmp.path()).unwrap();
+    assert!(report.is_empty());
+    db.ensure_loaded_all().unwrap();
+    assert!(has_label(&db, "first"));
+    assert!(has_label(&db, "second"));
+}
+
+#[test]
+fn discard_incomplete_update_batch() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut db = create_persisted_graph(tmp.path());
+    add_label(&mut db, "first").unwrap();
+    add_label_and_crash(&mut db, "second", WritePhase::UpdateLogWritten);
+    std::mem::drop(db);
+
+    let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+    let report = db.open(tmp.path()).unwrap();
+    assert_eq!(
+        vec![recovery::UpdateBatch {
+            sequence: 1,
+            event_count: 1
+        }],
+        report.replayed
+    );
+    assert_eq!(1, report.discarded.len());
+    assert!(matches!(
+        report.discarded[0],
+        recovery::DiscardedUpdateBatch::Incomplete { .. }
+    ));
+    assert!(has_label(&db, "first"));
+    assert!(!has_label(&db, "second"));
+
+    // The incomplete batch has been removed and new batches are appended
+    add_label(&mut db, "third").unwrap();
+    let report = db.open(tmp.path()).unwrap();
+    assert_eq!(2, report.replayed.len());
+    assert!(report.discarded.is_empty());
+    assert!(has_label(&db, "first"));
+    assert!(!has_label(&db, "second"));
+    assert!(has_label(&db, "third"));
+}
+
+#[test]
+fn discard_unreadable_update_batch() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut db = create_persisted_graph(tmp.path());
+    add_label(&mut db, "first").unwrap();
+    add_label(&mut db, "second").unwrap();
+    std::mem::drop(db);
+
+    std::fs::write(tmp.path().join("current").join("update_log.bin"), "garbage").unwrap();
+
+    let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+    let report = db.open(tmp.path()).unwrap();
+    assert!(report.replayed.is_empty());
+    assert_eq!(2, report.discarded.len());
+    let discarded_dir = tmp.path().join(recovery::DISCARDED_UPDATE_LOGS_FOLDER);
+    assert!(matches!(
+        &report.discarded[0],
+        recovery::DiscardedUpdateBatch::Unreadable { sequence: 1, path, .. }
+            if path == &discarded_dir.join("update_log.bin")
+    ));
+    assert_eq!(
+        recovery::DiscardedUpdateBatch::Skipped {
+            sequence: 2,
+            path: discarded_dir.join("update_log_2.bin")
+        },
+        report.discarded[1]
+    );
+    db.ensure_loaded_all().unwrap();
+    assert!(!has_label(&db, "first"));
+    assert!(!has_label(&db, "second"));
+
+    // The skipped batch has been kept and can still be read
+    let skipped = recovery::read_update_log(&discarded_dir.join("update_log_2.bin")).unwrap();
+    assert_eq!(1, skipped.len().unwrap());
+
+    // Opening the graph again does not replay the discarded batches
+    let report = db.open(tmp.path()).unwrap();
+    assert!(report.is_empty());
+
+    // Batches discarded later don't overwrite the ones discarded before
+    add_label(&mut db, "third").unwrap();
+    std::fs::write(tmp.path().join("current").join("update_log.bin"), "garbage").unwrap();
+    let report = db.open(tmp.path()).unwrap();
+    assert_eq!(1, report.discarded.len());
+    assert!(matches!(
+        &report.discarded[0],
+        recovery::DiscardedUpdateBatch::Unreadable { sequence: 1, path, .. }
+            if path == &discarded_dir.join("1_update_log.bin")
+    ));
+    assert!(discarded_dir.join("update_log.bin").is_file());
+}
+
+#[test]
+fn recover_from_interrupted_background_sync() {
+    for phase in [
+        WritePhase::BackupCreated,
+        WritePhase::NodeAnnotationsSaved,
+        WritePhase::CurrentSaved,
+    ] {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut db = create_persisted_graph(tmp.path());
+        add_label(&mut db, "first").unwrap();
+        add_label(&mut db, "second").unwrap();
+        sync_and_crash(&db, phase);
+        std::mem::drop(db);
+
+        let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+        let report = db.open(tmp.path()).unwrap();
+        assert!(report.restored_backup, "{phase:?}");
+        assert_eq!(2, report.replayed.len(), "{phase:?}");
+        assert!(report.discarded.is_empty(), "{phase:?}");
+        assert!(has_label(&db, "first"), "{phase:?}");
+        assert!(has_label(&db, "second"), "{phase:?}");
+        assert!(!tmp.path().join("backup").exists(), "{phase:?}");
+
+        // The restored graph has been saved completely
+        let report = db.open(tmp.path()).unwrap();
+        assert!(report.is_empty(), "{phase:?}");
+        db.ensure_loaded_all().unwrap();
+        assert!(has_label(&db, "first"), "{phase:?}");
+        assert!(has_label(&db, "second"), "{phase:?}");
+    }
+}
+
+/// Environment variable with the location of the graph that is changed by
+/// [`abort_while_writing`] in a child process.
+const ABORT_LOCATION_ENV: &str = "GRAPHANNIS_TEST_ABORT_LOCATION";
+
+/// Adds a label to the graph and saves it, but the process is aborted in the
+/// configured write phase. This only does something when it is executed as
+/// child process by [`recover_from_aborted_process`].
+#[test]
+fn abort_while_writing() {
+    if let Ok(location) = std::env::var(ABORT_LOCATION_ENV) {
+        let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+        db.open(Path::new(&location)).unwrap();
+        add_label(&mut db, "second").unwrap();
+        db.background_sync_wal_updates().unwrap();
+    }
+}
+
+#[test]
+fn recover_from_aborted_process() {
+    for phase in [
+        WritePhase::UpdateLogWritten,
+        WritePhase::BackupCreated,
+        WritePhase::NodeAnnotationsSaved,
+        WritePhase::CurrentSaved,
+    ] {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut db = create_persisted_graph(tmp.path());
+        add_label(&mut db, "first").unwrap();
+        std::mem::drop(db);
+
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "graph::tests::abort_while_writing",
+                "--exact",
+                "--test-threads=1",
+            ])
+            .env(ABORT_LOCATION_ENV, tmp.path())
+            .env(ABORT_AFTER_ENV, format!("{phase:?}"))
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        // The test harness exits with code 101 if the child test panicked
+        assert!(!status.success(), "{phase:?}");
+        assert_ne!(Some(101), status.code(), "{phase:?}");
+
+        let mut db = Graph::<DefaultComponentType>::new(false).unwrap();
+        let report = db.open(tmp.path()).unwrap();
+        assert!(has_label(&db, "first"), "{phase:?}");
+        if phase == WritePhase::UpdateLogWritten {
+            // The update has never been acknowledged
+            assert!(!report.restored_backup, "{phase:?}");
+            assert_eq!(1, report.replayed.len(), "{phase:?}");
+            assert_eq!(1, report.discarded.len(), "{phase:?}");
+            assert!(!has_label(&db, "second"), "{phase:?}");
+        } else {
+            assert!(report.restored_backup, "{phase:?}");
+            assert_eq!(2, report.replayed.len(), "{phase:?}");
+            assert!(report.discarded.is_empty(), "{phase:?}");
+            assert!(has_label(&db, "second"), "{phase:?}");
+        }
+    }
+}
@@ -2,7 +2,7 @@ use bincode::config::Options;
 use itertools::Itertools;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use sstable::{SSIterator, Table, TableBuilder, TableIterator};
+use sstable::{CompressionType, SSIterator, Table, TableBuilder, TableIterator};
 use transient_btree_index::{BtreeConfig, BtreeIndex};
 
 use crate::serializer::KeyVec;
@@ -305,6 +305,26 @@ where
     }
 
     pub fn write_to(&self, location: &Path) -> Result<()> {
+        self.write_mapped_to(location, false, |_, value| Ok(value))
+    }
+
+    /// Write all entries to a disk table at the given `location` and apply
+    /// `map_value` to each value before it is written. The mapped values can
+    /// have a different type, e.g. to convert the table to a new format.
+    ///
+    /// If `compress_blocks` is `true`, the blocks of the disk table are
+    /// compressed with Snappy. Compressed blocks are detected when reading the
+    /// table, so it can be loaded like any other disk table.
+    pub fn write_mapped_to<F, W>(
+        &self,
+        location: &Path,
+        compress_blocks: bool,
+        mut map_value: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&K, V) -> Result<W>,
+        W: Serialize,
+    {
         // Make sure the parent directory exist
         if let Some(parent) = location.parent() {
             std::fs::create_dir_all(parent)?;
@@ -316,11 +336,15 @@ where
             .create(true)
             .truncate(true)
             .open(location)?;
-        let mut builder = TableBuilder::new(custom_options(self.block_cache_capacity), out_file);
+        let mut options = custom_options(self.block_cache_capacity);
+        if compress_blocks {
+            options.compression_type = CompressionType::CompressionSnappy;
+        }
+        let mut builder = TableBuilder::new(options, out_file);
         for entry in self.iter()? {
             let (key, value) = entry?;
+            let value = Some(map_value(&key, value)?);
             let key = key.create_key();
-            let value = Some(value);
             builder.add(&key, &self.serialization.serialize(&value)?)?;
         }
         builder.finish()?;
@@ -1,6 +1,10 @@
 use crate::errors::{GraphAnnisCoreError, Result};
 use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use regex_syntax::hir::{
+    Capture, Class, ClassUnicode, ClassUnicodeRange, Hir, HirKind, Repetition,
+};
 use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
 
 pub mod disk_collections;
 
@@ -50,3 +54,182 @@ pub fn compile_and_parse_regex(pattern: &str) -> Result<(regex::Regex, regex_syn
         .map_err(|e| GraphAnnisCoreError::Other(Box::new(e)))?;
     Ok((compiled_regex, parsed_regex))
 }
+
+/// Flags that change how a regular expression is matched against annotation
+/// values, e.g. `/der/i` in AQL.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct RegexFlags {
+    /// Match letters regardless of their case (`i` flag).
+    pub case_insensitive: bool,
+    /// Match letters regardless of their diacritics (`d` flag), e.g. `e` also matches `é`.
+    pub diacritic_insensitive: bool,
+}
+
+impl RegexFlags {
+    /// Returns true if no flag is set.
+    pub fn is_empty(&self) -> bool {
+        !self.case_insensitive && !self.diacritic_insensitive
+    }
+}
+
+impl std::fmt::Display for RegexFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.case_insensitive {
+            write!(f, "i")?;
+        }
+        if self.diacritic_insensitive {
+            write!(f, "d")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for RegexFlags {
+    type Err = GraphAnnisCoreError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut result = RegexFlags::default();
+        for c in s.chars() {
+            match c {
+                'i' => result.case_insensitive = true,
+                'd' => result.diacritic_insensitive = true,
+                _ => return Err(GraphAnnisCoreError::InvalidRegexFlag(c)),
+            }
+        }
+        Ok(result)
+    }
+}
+
+lazy_static! {
+    /// Maps a base letter to all letters that have the base letter plus some
+    /// diacritics as canonical decomposition.
+    static ref DIACRITIC_VARIANTS: HashMap<char, BTreeSet<char>> = {
+        let mut result: HashMap<char, BTreeSet<char>> = HashMap::new();
+        // Latin-1 Supplement, Latin Extended-A/B and Latin Extended Additional
+        let ranges = ['\u{00C0}'..='\u{024F}', '\u{1E00}'..='\u{1EFF}'];
+        for c in ranges.into_iter().flatten() {
+            let base = base_letter(c);
+            if base != c {
+                let variants = result.entry(base).or_default();
+                variants.insert(base);
+                variants.insert(c);
+            }
+        }
+        result
+    };
+}
+
+/// Get the letter without any diacritics.
+fn base_letter(c: char) -> char {
+    let mut base = None;
+    let mut only_marks = true;
+    unicode_normalization::char::decompose_canonical(c, |d| {
+        if base.is_none() {
+            base = Some(d);
+        } else if !unicode_normalization::char::is_combining_mark(d) {
+            only_marks = false;
+        }
+    });
+    match base {
+        Some(base) if only_marks => base,
+        _ => c,
+    }
+}
+
+/// Add all letters that only differ from the given letter by their diacritics to the class.
+fn add_diacritic_variants(c: char, ranges: &mut Vec<ClassUnicodeRange>) {
+    if let Some(variants) = DIACRITIC_VARIANTS.get(&base_letter(c)) {
+        for v in variants {
+            ranges.push(ClassUnicodeRange::new(*v, *v));
+        }
+    }
+}
+
+/// Replace each letter in the regular expression with a class of all its
+/// variants with diacritics.
+fn ignore_diacritics(hir: Hir) -> Hir {
+    // Only expand small classes: large classes like `\w` already contain
+    // the letters with diacritics.
+    const MAX_EXPANDED_CLASS_SIZE: u32 = 256;
+
+    match hir.into_kind() {
+        HirKind::Literal(lit) => match std::str::from_utf8(&lit.0) {
+            Ok(lit) => Hir::concat(
+                lit.chars()
+                    .map(|c| {
+                        let mut ranges = vec![ClassUnicodeRange::new(c, c)];
+                        add_diacritic_variants(c, &mut ranges);
+                        if ranges.len() > 1 {
+                            Hir::class(Class::Unicode(ClassUnicode::new(ranges)))
+                        } else {
+                            Hir::literal(c.to_string().into_bytes())
+                        }
+                    })
+                    .collect(),
+            ),
+            Err(_) => Hir::literal(lit.0),
+        },
+        HirKind::Class(Class::Unicode(cls)) => {
+            let mut ranges: Vec<ClassUnicodeRange> = cls.ranges().to_vec();
+            for r in cls.ranges() {
+                if (r.end() as u32) - (r.start() as u32) < MAX_EXPANDED_CLASS_SIZE {
+                    for c in r.start()..=r.end() {
+                        add_diacritic_variants(c, &mut ranges);
+                    }
+                }
+            }
+            Hir::class(Class::Unicode(ClassUnicode::new(ranges)))
+        }
+        HirKind::Repetition(rep) => Hir::repetition(Repetition {
+            sub: Box::new(ignore_diacritics(*rep.sub)),
+            ..rep
+        }),
+        HirKind::Capture(cap) => Hir::capture(Capture {
+            sub: Box::new(ignore_diacritics(*cap.sub)),
+            ..cap
+        }),
+        HirKind::Concat(subs) => Hir::concat(subs.into_iter().map(ignore_diacritics).collect()),
+        HirKind::Alternation(subs) => {
+            Hir::alternation(subs.into_iter().map(ignore_diacritics).collect())
+        }
+        HirKind::Class(cls) => Hir::class(cls),
+        HirKind::Look(look) => Hir::look(look),
+        HirKind::Empty => Hir::empty(),
+    }
+}
+
+/// Apply the given flags to a regular expression `pattern` and return an
+/// equivalent pattern without any flags.
+///
+/// If the pattern is invalid, it is returned unchanged, so that compiling it
+/// will report the error.
+pub fn regex_with_flags(pattern: &str, flags: RegexFlags) -> Cow<'_, str> {
+    if flags.is_empty() {
+        return Cow::Borrowed(pattern);
+    }
+    let parsed = regex_syntax::ParserBuilder::new()
+        .case_insensitive(flags.case_insensitive)
+        .build()
+        .parse(pattern);
+    match parsed {
+        Ok(parsed) => {
+            let parsed = if flags.diacritic_insensitive {
+                ignore_diacritics(parsed)
+            } else {
+                parsed
+            };
+            Cow::Owned(parsed.to_string())
+        }
+        Err(_) => Cow::Borrowed(pattern),
+    }
+}
+
+/// Combines [`regex_with_flags`] and [`regex_full_match`].
+pub fn regex_full_match_with_flags(pattern: &str, flags: RegexFlags) -> String {
+    regex_full_match(&regex_with_flags(pattern, flags))
+}
+
+#[cfg(test)]
+mod tests;
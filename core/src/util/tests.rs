@@ -0,0 +1,69 @@
+use super::*;
+
+fn is_full_match(pattern: &str, flags: RegexFlags, value: &str) -> bool {
+    let re = regex::Regex::new(&regex_full_match_with_flags(pattern, flags)).unwrap();
+    re.is_match(value)
+}
+
+#[test]
+fn parse_regex_flags() {
+    assert_eq!(RegexFlags::default(), "".parse().unwrap());
+    assert_eq!(
+        RegexFlags {
+            case_insensitive: true,
+            diacritic_insensitive: false
+        },
+        "i".parse().unwrap()
+    );
+    let both: RegexFlags = "di".parse().unwrap();
+    assert!(both.case_insensitive);
+    assert!(both.diacritic_insensitive);
+    assert_eq!("id", both.to_string());
+
+    assert!("x".parse::<RegexFlags>().is_err());
+}
+
+#[test]
+fn regex_without_flags_is_unchanged() {
+    assert_eq!("Der.*", regex_with_flags("Der.*", RegexFlags::default()));
+    assert!(!is_full_match("der", RegexFlags::default(), "Der"));
+}
+
+#[test]
+fn case_insensitive_regex() {
+    let flags = RegexFlags {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    assert!(is_full_match("der", flags, "Der"));
+    assert!(is_full_match("der", flags, "DER"));
+    assert!(is_full_match("[a-c]+", flags, "AbC"));
+    assert!(!is_full_match("der", flags, "dér"));
+    assert!(!is_full_match("der", flags, "Derr"));
+}
+
+#[test]
+fn diacritic_insensitive_regex() {
+    let flags = RegexFlags {
+        diacritic_insensitive: true,
+        ..Default::default()
+    };
+    assert!(is_full_match("cafe", flags, "café"));
+    assert!(is_full_match("café", flags, "cafe"));
+    assert!(is_full_match("[a-e]+", flags, "àéè"));
+    assert!(is_full_match("u(b|c)er", flags, "über"));
+    assert!(!is_full_match("cafe", flags, "CAFÉ"));
+
+    let both: RegexFlags = "id".parse().unwrap();
+    assert!(is_full_match("cafe", both, "CAFÉ"));
+}
+
+#[test]
+fn invalid_regex_with_flags() {
+    let flags = RegexFlags {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    assert_eq!("(abc", regex_with_flags("(abc", flags));
+    assert!(regex::Regex::new(&regex_full_match_with_flags("(abc", flags)).is_err());
+}
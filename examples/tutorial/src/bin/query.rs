@@ -9,6 +9,7 @@ fn main() {
         query: "tok=/.*s.*/",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
 
     let number_of_matches = cs.count(search_query.clone()).unwrap();
@@ -11,7 +11,9 @@ use fake::Fake;
 use fake::faker::name::raw::*;
 use fake::locales::*;
 use graphannis::CorpusStorage;
+use graphannis::corpusstorage::CompressionOptions;
 use graphannis::corpusstorage::ResultOrder;
+use graphannis::corpusstorage::{ExportFormat, ImportFormat};
 use graphannis::corpusstorage::{QueryLanguage, SearchQuery};
 use graphannis::update::{GraphUpdate, UpdateEvent};
 use std::collections::HashSet;
@@ -67,6 +69,7 @@ fn find_all_nouns_gum(bench: &mut Criterion) {
                 query: "pos=\"NN\"",
                 query_language: QueryLanguage::AQL,
                 timeout: None,
+                cancellation: None,
             };
             let f = cs.find(query, 0, None, ResultOrder::Normal);
             assert!(f.is_ok());
@@ -100,6 +103,7 @@ fn find_first_ten_nouns_gum(bench: &mut Criterion) {
                 query: "pos=\"NN\"",
                 query_language: QueryLanguage::AQL,
                 timeout: None,
+                cancellation: None,
             };
             let f = cs.find(query, 0, Some(10), ResultOrder::Normal);
             assert!(f.is_ok());
@@ -130,6 +134,7 @@ fn count_extra_coref_anno(bench: &mut Criterion) {
             query: "infstat=\"giv\" ->coref[type=\"coref\"] entity=\"person\"",
             query_language: QueryLanguage::AQL,
             timeout: None,
+            cancellation: None,
         };
         b.iter(move || {
             let r = cs.count_extra(query.clone());
@@ -161,6 +166,7 @@ fn count_extra_node(bench: &mut Criterion) {
             query: "node",
             query_language: QueryLanguage::AQL,
             timeout: None,
+            cancellation: None,
         };
 
         b.iter(move || {
@@ -196,6 +202,7 @@ fn find_first_ten_token_gum(bench: &mut Criterion) {
                 query: "tok",
                 query_language: QueryLanguage::AQL,
                 timeout: None,
+                cancellation: None,
             };
             let f = cs.find(query, 0, Some(10), ResultOrder::Normal);
             assert!(f.is_ok());
@@ -330,6 +337,112 @@ fn apply_update_ondisk(bench: &mut Criterion) {
     });
 }
 
+fn directory_size(path: &Path) -> u64 {
+    let mut result = 0;
+    for entry in std::fs::read_dir(path).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            result += directory_size(&path);
+        } else {
+            result += path.metadata().unwrap().len();
+        }
+    }
+    result
+}
+
+fn count_token_compressed(bench: &mut Criterion) {
+    if CORPUS_STORAGE.is_none() {
+        return;
+    }
+
+    let data_cs = CORPUS_STORAGE.as_ref().unwrap();
+    let corpora = data_cs.list();
+    if let Ok(corpora) = corpora {
+        let corpora: HashSet<String> = corpora.into_iter().map(|c| c.name).collect();
+        // ignore if corpus does not exist
+        if !corpora.contains("GUM") {
+            return;
+        }
+    }
+
+    // Use a copy of the GUM corpus in a separate corpus storage, so the size
+    // on disk can be measured without the other corpora
+    let export_dir = tempfile::tempdir().unwrap();
+    let graphml_file = export_dir.path().join("GUM.graphml");
+    data_cs
+        .export_to_fs(&["GUM"], &graphml_file, ExportFormat::GraphML)
+        .unwrap();
+    let db_dir = tempfile::tempdir().unwrap();
+
+    let variants = [
+        ("uncompressed", CompressionOptions::default()),
+        (
+            "block_compression",
+            CompressionOptions {
+                block_compression: true,
+                max_dictionary_size: 0,
+            },
+        ),
+        (
+            "dictionary",
+            CompressionOptions {
+                block_compression: false,
+                max_dictionary_size: 1_000,
+            },
+        ),
+        (
+            "block_compression_dictionary",
+            CompressionOptions {
+                block_compression: true,
+                max_dictionary_size: 1_000,
+            },
+        ),
+    ];
+
+    {
+        let cs = CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap();
+        for (name, _) in variants {
+            let corpus_name = format!("compression_test_corpus_{}", name);
+            cs.import_from_fs(
+                &graphml_file,
+                ImportFormat::GraphML,
+                Some(corpus_name),
+                true,
+                true,
+                |_| {},
+            )
+            .unwrap();
+        }
+        // Dropping the corpus storage waits until the updates have been saved
+    }
+
+    let cs = CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap();
+    for (name, options) in variants {
+        let corpus_name = format!("compression_test_corpus_{}", name);
+        cs.set_annotation_compression(&corpus_name, options)
+            .unwrap();
+        println!(
+            "Size of {} on disk: {} bytes",
+            corpus_name,
+            directory_size(&db_dir.path().join(&corpus_name).join("current"))
+        );
+
+        bench.bench_function(&format!("count_token_{}", name), |b| {
+            b.iter(|| {
+                cs.unload(&corpus_name).unwrap();
+                let query = SearchQuery {
+                    corpus_names: &[corpus_name.as_str()],
+                    query: "tok",
+                    query_language: QueryLanguage::AQL,
+                    timeout: None,
+                    cancellation: None,
+                };
+                cs.count(query).unwrap()
+            });
+        });
+    }
+}
+
 criterion_group!(name=default; config= Criterion::default().sample_size(50); targets =
     deserialize_gum,
     find_first_ten_token_gum,
@@ -340,4 +453,7 @@ criterion_group!(name=apply_update; config= Criterion::default().sample_size(20)
     apply_update_inmemory,
     apply_update_ondisk,
 );
-criterion_main!(default, apply_update);
+criterion_group!(name=compression; config= Criterion::default().sample_size(20); targets =
+    count_token_compressed,
+);
+criterion_main!(default, apply_update, compression);
@@ -46,6 +46,7 @@ if let Some(cs) = CORPUS_STORAGE.as_ref() {{
             corpus_names: &[\"{corpus}\"],
             query_language: QueryLanguage::AQL,
             timeout: None,
+            cancellation: None,
         }};
         cs.count(search_query).unwrap_or(0)
     }};
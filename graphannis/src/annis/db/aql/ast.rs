@@ -1,11 +1,12 @@
 use std::rc::Rc;
 
 use crate::annis::db::aql::operators::{
-    AritySpec, DominanceSpec, IdenticalCoverageSpec, IdenticalNodeSpec, InclusionSpec,
-    LeftAlignmentSpec, NearSpec, OverlapSpec, PartOfSubCorpusSpec, PointingSpec, PrecedenceSpec,
-    RightAlignmentSpec,
+    AritySpec, CommonAncestorSpec, DominanceSpec, IdenticalCoverageSpec, IdenticalNodeSpec,
+    InclusionSpec, LeftAlignmentSpec, NearSpec, OverlapSpec, PartOfSubCorpusSpec, PointingSpec,
+    PrecedenceSpec, RightAlignmentSpec, RootSpec, SiblingSpec, TokenAritySpec,
 };
 use crate::annis::db::exec::nodesearch::NodeSearchSpec;
+use graphannis_core::util::RegexFlags;
 
 #[derive(Clone, Debug, PartialOrd, Ord, Hash, PartialEq, Eq)]
 pub struct Pos {
@@ -55,6 +56,12 @@ pub enum Literal {
         spec: NodeSearchSpec,
         pos: Pos,
     },
+    /// Subquery that must not have any result for a match, e.g.
+    /// `!exists { cat="NP" & #2 >* #1 }`
+    NotExists {
+        literals: Vec<Literal>,
+        pos: Pos,
+    },
 }
 
 #[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq)]
@@ -83,7 +90,7 @@ pub struct QName(pub Option<String>, pub String);
 #[derive(Debug, Clone)]
 pub enum StringMatchType {
     Exact,
-    Regex,
+    Regex(RegexFlags),
 }
 
 #[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq)]
@@ -105,12 +112,16 @@ pub enum BinaryOpSpec {
     LeftAlignment(LeftAlignmentSpec),
     RightAlignment(RightAlignmentSpec),
     IdenticalNode(IdenticalNodeSpec),
+    Sibling(SiblingSpec),
+    CommonAncestor(CommonAncestorSpec),
     ValueComparison(ComparisonOperator),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum UnaryOpSpec {
     Arity(AritySpec),
+    Root(RootSpec),
+    TokenArity(TokenAritySpec),
 }
 
 pub use crate::annis::db::aql::operators::RangeSpec;
@@ -5,16 +5,20 @@ use super::Config;
 use super::disjunction::Disjunction;
 use crate::AnnotationGraph;
 use crate::annis::db::aql::model::AnnotationComponentType;
+use crate::annis::db::aql::operators::{IdenticalNodeSpec, PartOfSubCorpusSpec, RangeSpec};
+use crate::annis::db::exec::analyze::StatisticsCollector;
 use crate::annis::db::exec::filter::Filter;
+use crate::annis::db::exec::hashjoin::HashJoin;
 use crate::annis::db::exec::indexjoin::IndexJoin;
+use crate::annis::db::exec::mergejoin::MergeJoin;
 use crate::annis::db::exec::nestedloop::NestedLoop;
 use crate::annis::db::exec::nodesearch::{NodeSearch, NodeSearchSpec};
 use crate::annis::db::exec::parallel;
 use crate::annis::db::exec::{CostEstimate, ExecutionNode, ExecutionNodeDesc, NodeSearchDesc};
 use crate::annis::errors::*;
 use crate::annis::operator::{
-    BinaryOperator, BinaryOperatorBase, BinaryOperatorIndex, BinaryOperatorSpec, UnaryOperator,
-    UnaryOperatorSpec,
+    BinaryOperator, BinaryOperatorBase, BinaryOperatorIndex, BinaryOperatorSpec, EquiJoinKind,
+    EstimationType, UnaryOperator, UnaryOperatorSpec,
 };
 use crate::annis::util::TimeoutCheck;
 use crate::{
@@ -22,13 +26,16 @@ use crate::{
     errors::Result,
 };
 use graphannis_core::annostorage::EdgeAnnotationStorage;
+use graphannis_core::graph::{ANNIS_NS, NODE_NAME, NODE_NAME_KEY};
+use graphannis_core::types::NodeID;
 use graphannis_core::{annostorage::MatchGroup, graph::storage::GraphStatistic, types::Component};
+use itertools::Itertools;
 use rand::distr::Uniform;
 use rand::prelude::*;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BinaryOperatorArguments {
     /// *Global* position int the query of the LHS.
     /// This references not the position in the conjunction, but the position
@@ -41,18 +48,27 @@ pub struct BinaryOperatorArguments {
     pub global_reflexivity: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct BinaryOperatorSpecEntry {
     op: Arc<dyn BinaryOperatorSpec>,
     args: BinaryOperatorArguments,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct UnaryOperatorSpecEntry {
     op: Arc<dyn UnaryOperatorSpec>,
     idx: usize,
 }
 
+/// A subquery that must not have any result for a match of the conjunction.
+#[derive(Debug, Clone)]
+struct NotExistsSpecEntry {
+    subquery: Conjunction,
+    /// Variables of the subquery that refer to nodes of the outer conjunction,
+    /// together with the position of the node in the outer conjunction.
+    correlated: Vec<(String, usize)>,
+}
+
 pub struct BinaryOperatorEntry<'a> {
     pub op: BinaryOperator<'a>,
     pub args: BinaryOperatorArguments,
@@ -74,7 +90,7 @@ pub struct NodeSearchSpecEntry {
 /// variable) and a position in the conjunction itself (the internal node
 /// number). Conjunctions can be part of a [`Disjunction`], which have their own
 /// different node numbers but can share the variable names.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Conjunction {
     /// The node search information for all the variables in the query. Indexed
     /// by the position of the variable in the conjunction (first node at index
@@ -82,6 +98,7 @@ pub struct Conjunction {
     nodes: Vec<NodeSearchSpecEntry>,
     binary_operators: Vec<BinaryOperatorSpecEntry>,
     unary_operators: Vec<UnaryOperatorSpecEntry>,
+    not_exists: Vec<NotExistsSpecEntry>,
     /// Maps the variable names to the position of the variable in the conjunction.
     variables: HashMap<String, usize>,
     /// Stores the location of a variable in the query string.
@@ -136,6 +153,19 @@ fn update_components_for_nodes(
     }
 }
 
+/// Wraps the execution node so it collects execution statistics if the query
+/// is analyzed.
+fn with_statistics<'b>(
+    config: &Config,
+    exec: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'b>,
+) -> Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'b> {
+    if config.analyze {
+        Box::new(StatisticsCollector::new(exec))
+    } else {
+        exec
+    }
+}
+
 fn create_index_join<'b>(
     db: &'b AnnotationGraph,
     config: &Config,
@@ -170,6 +200,32 @@ fn create_index_join<'b>(
     }
 }
 
+/// Returns `true` if the estimated number of processed tuples of a hash join,
+/// which processes each tuple of both sides once, is smaller than the one of
+/// an index join.
+fn hash_join_is_cheaper(
+    op: &BinaryOperator,
+    exec_left: &dyn ExecutionNode<Item = Result<MatchGroup>>,
+    exec_right: &dyn ExecutionNode<Item = Result<MatchGroup>>,
+) -> Result<bool> {
+    if let (Some(cost_lhs), Some(cost_rhs)) = (
+        exec_left.get_desc().and_then(|d| d.cost.as_ref()),
+        exec_right.get_desc().and_then(|d| d.cost.as_ref()),
+    ) {
+        let out_lhs = cost_lhs.output as f64;
+        let out_rhs = cost_rhs.output as f64;
+        // the index join can use the smaller side as LHS if the operator has an inverse
+        let index_join_cost = match op.estimation_type()? {
+            EstimationType::Selectivity(sel) => out_lhs.min(out_rhs) + (sel * out_lhs * out_rhs),
+            EstimationType::Min => out_lhs.min(out_rhs),
+        };
+        Ok(out_lhs + out_rhs < index_join_cost)
+    } else {
+        Ok(false)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_join<'b>(
     db: &'b AnnotationGraph,
     config: &Config,
@@ -178,8 +234,14 @@ fn create_join<'b>(
     exec_right: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'b>,
     idx_left: usize,
     idx_right: usize,
+    timeout: &TimeoutCheck,
 ) -> Result<Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'b>> {
-    if exec_right.as_nodesearch().is_some()
+    let equi_join_kind = op_entry.op.equi_join_kind();
+    let prefer_hash_join = equi_join_kind.is_some()
+        && hash_join_is_cheaper(&op_entry.op, exec_left.as_ref(), exec_right.as_ref())?;
+
+    if !prefer_hash_join
+        && exec_right.as_nodesearch().is_some()
         && let BinaryOperator::Index(op) = op_entry.op
     {
         // we can directly use an index join
@@ -194,7 +256,7 @@ fn create_join<'b>(
         );
     }
 
-    if exec_left.as_nodesearch().is_some() {
+    if !prefer_hash_join && exec_left.as_nodesearch().is_some() {
         // avoid a nested loop join by switching the operand and using an index join when possible
         if let Some(BinaryOperator::Index(inverse_op)) = op_entry.op.get_inverse_operator(db)? {
             let inverse_args = BinaryOperatorArguments {
@@ -215,18 +277,104 @@ fn create_join<'b>(
         }
     }
 
+    if let Some(equi_join_kind) = equi_join_kind {
+        if equi_join_kind == EquiJoinKind::SameNode
+            && idx_left == 0
+            && idx_right == 0
+            && exec_left.is_sorted_by_text()
+            && exec_right.is_sorted_by_text()
+        {
+            // both sides are sorted by the joined node
+            let join = MergeJoin::new(db, op_entry, exec_left, exec_right, idx_left, idx_right)?;
+            return Ok(Box::new(join));
+        }
+        let join = HashJoin::new(
+            op_entry,
+            exec_left,
+            exec_right,
+            idx_left,
+            idx_right,
+            timeout.clone(),
+        )?;
+        return Ok(Box::new(join));
+    }
+
     // use nested loop as "fallback"
     if config.use_parallel_joins {
         let join = parallel::nestedloop::NestedLoop::new(
-            op_entry, exec_left, exec_right, idx_left, idx_right,
+            op_entry,
+            exec_left,
+            exec_right,
+            idx_left,
+            idx_right,
+            timeout.clone(),
         )?;
         Ok(Box::new(join))
     } else {
-        let join = NestedLoop::new(op_entry, exec_left, exec_right, idx_left, idx_right)?;
+        let join = NestedLoop::new(
+            op_entry,
+            exec_left,
+            exec_right,
+            idx_left,
+            idx_right,
+            timeout.clone(),
+        )?;
         Ok(Box::new(join))
     }
 }
 
+impl NotExistsSpecEntry {
+    /// Returns true if the subquery has at least one result when its
+    /// correlated variables are fixed to the given nodes.
+    fn has_result(
+        &self,
+        db: &AnnotationGraph,
+        config: &Config,
+        correlated_nodes: &[(&str, NodeID)],
+        timeout: TimeoutCheck,
+    ) -> Result<bool> {
+        let mut subquery = self.subquery.clone();
+        for (var, node) in correlated_nodes {
+            let node_name = db
+                .get_node_annos()
+                .get_value_for_item(node, &NODE_NAME_KEY)?
+                .ok_or_else(|| GraphAnnisError::NoSuchNodeID(node.to_string()))?;
+            // Add a node that is identical to the outer node. The "@" in
+            // the variable name avoids conflicts with variables of the query.
+            let fixed_var = subquery.add_node(
+                NodeSearchSpec::ExactValue {
+                    ns: Some(ANNIS_NS.into()),
+                    name: NODE_NAME.into(),
+                    val: Some(node_name.into()),
+                    is_meta: false,
+                },
+                Some(&format!("{}@outer", var)),
+            );
+            subquery.add_operator(Arc::new(IdenticalNodeSpec), &fixed_var, var, true)?;
+        }
+        let has_result = match subquery.make_exec_node(db, config, timeout) {
+            // It is enough to find a single result
+            Ok(mut plan) => plan.next().transpose()?.is_some(),
+            Err(GraphAnnisError::ImpossibleSearch(_)) => false,
+            Err(e) => return Err(e),
+        };
+        Ok(has_result)
+    }
+}
+
+impl std::fmt::Display for NotExistsSpecEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let nodes = self
+            .subquery
+            .nodes
+            .iter()
+            .filter(|n| !self.correlated.iter().any(|(var, _)| var == &n.var))
+            .map(|n| n.spec.to_string())
+            .join(" & ");
+        write!(f, "!exists {{{}}}", nodes)
+    }
+}
+
 impl Conjunction {
     /// Create new conjunction without a configured offset for its variable IDs.
     /// e.g. if there is no parent disjunction or only one alternative.
@@ -235,6 +383,7 @@ impl Conjunction {
             nodes: vec![],
             binary_operators: vec![],
             unary_operators: vec![],
+            not_exists: vec![],
             variables: HashMap::default(),
             location_in_query: HashMap::default(),
             include_in_output: HashSet::default(),
@@ -248,6 +397,7 @@ impl Conjunction {
             nodes: vec![],
             binary_operators: vec![],
             unary_operators: vec![],
+            not_exists: vec![],
             variables: HashMap::default(),
             location_in_query: HashMap::default(),
             include_in_output: HashSet::default(),
@@ -255,6 +405,61 @@ impl Conjunction {
         }
     }
 
+    /// Describes the conjunction in AQL syntax, which is the same for queries
+    /// which only differ in their formatting (e.g. whitespace).
+    ///
+    /// Optional nodes are marked with `?` and nodes that are not included in
+    /// the output with `@`. Operands are referenced by their position.
+    pub(crate) fn canonical_description(&self) -> String {
+        let mut parts = Vec::new();
+        for n in &self.nodes {
+            let optional = if n.optional { "?" } else { "" };
+            let output = if self.include_in_output.contains(&n.var) {
+                ""
+            } else {
+                "@"
+            };
+            parts.push(format!(
+                "{}{}{}#{}",
+                n.var,
+                optional,
+                output,
+                n.spec.canonical_description()
+            ));
+        }
+        for op in &self.binary_operators {
+            // Operators without global reflexivity are marked with `~`
+            let reflexivity = if op.args.global_reflexivity { "" } else { "~" };
+            parts.push(format!(
+                "#{} {}{} #{}",
+                op.args.left + 1,
+                op.op.canonical_description(),
+                reflexivity,
+                op.args.right + 1
+            ));
+        }
+        for op in &self.unary_operators {
+            parts.push(format!(
+                "#{}{}",
+                op.idx + self.var_idx_offset + 1,
+                op.op.canonical_description()
+            ));
+        }
+        for n in &self.not_exists {
+            let correlated = n
+                .correlated
+                .iter()
+                .map(|(var, pos)| format!("{}=#{}", var, pos + 1))
+                .join(", ");
+            parts.push(format!(
+                "!exists({}) {{{}}}",
+                correlated,
+                n.subquery.canonical_description()
+            ));
+        }
+        parts.join(" & ")
+    }
+
     pub fn into_disjunction(self) -> Disjunction {
         Disjunction::new(vec![self])
     }
@@ -344,6 +549,28 @@ impl Conjunction {
         self.add_operator_from_query(op, var_left, var_right, None, global_reflexivity)
     }
 
+    /// Only include matches where the first (non-optional) node is part of
+    /// a (sub-) corpus found by the given `subcorpus` node search.
+    pub(crate) fn restrict_to_subcorpus(&mut self, subcorpus: NodeSearchSpec) -> Result<()> {
+        let first_var = self
+            .nodes
+            .iter()
+            .find(|n| !n.optional)
+            .map(|n| n.var.clone());
+        if let Some(first_var) = first_var {
+            let subcorpus_var = self.add_node_from_query(subcorpus, None, None, false, false);
+            self.add_operator(
+                Arc::new(PartOfSubCorpusSpec {
+                    dist: RangeSpec::Unbound,
+                }),
+                &first_var,
+                &subcorpus_var,
+                true,
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn add_operator_from_query(
         &mut self,
         op: Arc<dyn BinaryOperatorSpec>,
@@ -367,6 +594,48 @@ impl Conjunction {
         Ok(())
     }
 
+    /// Add a subquery that must not have any result for a match of this
+    /// conjunction. The `correlated` variables must exist both in the subquery
+    /// and in this conjunction. When executing the query, they are fixed to
+    /// the nodes of each match of this conjunction.
+    pub fn add_not_exists_from_query(
+        &mut self,
+        subquery: Conjunction,
+        correlated: Vec<String>,
+        location: Option<LineColumnRange>,
+    ) -> Result<()> {
+        if correlated.is_empty() {
+            return Err(GraphAnnisError::AQLSemanticError(AQLError {
+                desc: "A \"!exists\" subquery must reference at least one node of the outer query"
+                    .into(),
+                location,
+            }));
+        }
+        subquery.check_components_connected()?;
+
+        let mut correlated_pos = Vec::with_capacity(correlated.len());
+        for var in correlated {
+            subquery.resolve_variable_pos(&var, location.clone())?;
+            let node = self.resolve_variable(&var, location.clone())?;
+            if node.optional {
+                return Err(GraphAnnisError::AQLSemanticError(AQLError {
+                    desc: format!(
+                        "Optional variable \"#{}\" can't be used in a \"!exists\" subquery",
+                        var
+                    ),
+                    location,
+                }));
+            }
+            let pos = self.resolve_variable_pos(&var, location.clone())? - self.var_idx_offset;
+            correlated_pos.push((var, pos));
+        }
+        self.not_exists.push(NotExistsSpecEntry {
+            subquery,
+            correlated: correlated_pos,
+        });
+        Ok(())
+    }
+
     pub fn num_of_nodes(&self) -> usize {
         self.nodes.len()
     }
@@ -439,6 +708,9 @@ impl Conjunction {
         for n in &self.nodes {
             result.extend(n.spec.necessary_components(db));
         }
+        for e in &self.not_exists {
+            result.extend(e.subquery.necessary_components(db));
+        }
         result
     }
 
@@ -467,7 +739,7 @@ impl Conjunction {
             config,
             best_operator_order.clone(),
             output_size_cache,
-            timeout,
+            timeout.clone(),
         )?;
         let mut best_cost: usize = initial_plan
             .get_desc()
@@ -514,7 +786,7 @@ impl Conjunction {
                     config,
                     op_order.clone(),
                     output_size_cache,
-                    timeout,
+                    timeout.clone(),
                 )?;
                 let alt_cost = alt_plan
                     .get_desc()
@@ -561,7 +833,9 @@ impl Conjunction {
             if let Some(node_search_cost) = desc.cost.as_ref() {
                 for e in op_spec_entries {
                     let op_spec = &e.op;
-                    if e.args.left - self.var_idx_offset == desc.component_nr {
+                    if e.args.left - self.var_idx_offset == desc.component_nr
+                        && op_spec.lhs_is_component_source()
+                    {
                         // get the necessary components and count the number of nodes in these components
                         let components = op_spec.necessary_components(db);
                         if !components.is_empty() {
@@ -571,11 +845,19 @@ impl Conjunction {
                             for c in &components {
                                 if let Some(gs) = db.get_graphstorage(c) {
                                     // check if we can apply an even more restrictive edge annotation search
-                                    if let Some(edge_anno_spec) = op_spec.get_edge_anno_spec() {
+                                    let edge_anno_specs = op_spec.get_edge_anno_specs();
+                                    if !edge_anno_specs.is_empty() {
                                         let anno_storage: &dyn EdgeAnnotationStorage =
                                             gs.get_anno_storage();
-                                        let edge_anno_est =
-                                            edge_anno_spec.guess_max_count(anno_storage)?;
+                                        // All conditions must be fulfilled, so the
+                                        // most selective one limits the result
+                                        let mut edge_anno_est = usize::MAX;
+                                        for edge_anno_spec in edge_anno_specs {
+                                            edge_anno_est = std::cmp::min(
+                                                edge_anno_est,
+                                                edge_anno_spec.guess_max_count(anno_storage)?,
+                                            );
+                                        }
                                         estimated_component_search += edge_anno_est;
                                         estimation_valid = true;
                                     } else if let Some(stats) = gs.get_statistics() {
@@ -594,7 +876,7 @@ impl Conjunction {
                                     node_search_desc,
                                     Some(desc),
                                     components,
-                                    op_spec.get_edge_anno_spec(),
+                                    op_spec.get_edge_anno_specs(),
                                     timeout,
                                 );
                                 if let Ok(poc_search) = poc_search {
@@ -630,7 +912,7 @@ impl Conjunction {
             g,
             self.location_in_query.get(n_var).cloned(),
             output_size_cache,
-            timeout,
+            timeout.clone(),
         );
         match node_search {
             Ok(mut node_search) => {
@@ -661,6 +943,7 @@ impl Conjunction {
                     impl_description: orig_impl_desc,
                     query_fragment: orig_query_frag,
                     cost,
+                    statistics: None,
                 };
                 node_search.set_desc(Some(new_desc));
 
@@ -727,6 +1010,7 @@ impl Conjunction {
             Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
         >,
         helper: &mut ExecutionPlanHelper,
+        timeout: &TimeoutCheck,
     ) -> Result<()> {
         let mut op: BinaryOperator<'a> = op_spec_entry
             .op
@@ -804,11 +1088,11 @@ impl Conjunction {
                     .ok_or(GraphAnnisError::RHSOperandNotFound)?);
 
                 let join = create_join(
-                    g, config, op_entry, exec_left, exec_right, idx_left, idx_right,
+                    g, config, op_entry, exec_left, exec_right, idx_left, idx_right, timeout,
                 )?;
                 Ok(join)
             };
-        let new_exec = new_exec?;
+        let new_exec = with_statistics(config, new_exec?);
 
         let new_component_nr = new_exec
             .get_desc()
@@ -846,10 +1130,22 @@ impl Conjunction {
         // 1. add all non-optional nodes
         for node_nr in 0..self.nodes.len() {
             if !self.nodes[node_nr].optional {
-                self.add_node_to_exec_plan(node_nr, db, timeout, output_size_cache, &mut output)?;
+                self.add_node_to_exec_plan(
+                    node_nr,
+                    db,
+                    timeout.clone(),
+                    output_size_cache,
+                    &mut output,
+                )?;
             }
         }
 
+        output.component2exec = output
+            .component2exec
+            .into_iter()
+            .map(|(component_nr, exec)| (component_nr, with_statistics(config, exec)))
+            .collect();
+
         // 2. add unary operators as filter to the existing node search
         for op_spec_entry in self.unary_operators.iter() {
             let child_exec = output
@@ -864,9 +1160,10 @@ impl Conjunction {
             };
             let filter_exec = Filter::new_unary(child_exec, 0, op_entry);
 
-            output
-                .component2exec
-                .insert(op_spec_entry.idx, Box::new(filter_exec));
+            output.component2exec.insert(
+                op_spec_entry.idx,
+                with_statistics(config, Box::new(filter_exec)),
+            );
         }
 
         // 3. add the joins which produce the results in operand order
@@ -878,6 +1175,7 @@ impl Conjunction {
                 config,
                 &mut output.component2exec,
                 &mut output.helper,
+                &timeout,
             )?;
         }
 
@@ -887,11 +1185,48 @@ impl Conjunction {
         }
 
         // it must be checked before that all components are connected
-        output.component2exec.into_values().next().ok_or_else(|| {
+        let mut result = output.component2exec.into_values().next().ok_or_else(|| {
             GraphAnnisError::ImpossibleSearch(String::from(
                 "could not find execution node for query component",
             ))
-        })
+        })?;
+
+        // 4. remove all matches for which a "!exists" subquery has a result
+        for entry in self.not_exists.iter() {
+            let desc = result
+                .get_desc()
+                .ok_or(GraphAnnisError::PlanDescriptionMissing)?;
+            let mut correlated = Vec::with_capacity(entry.correlated.len());
+            for (var, node_nr) in entry.correlated.iter() {
+                let idx = *desc
+                    .node_pos
+                    .get(node_nr)
+                    .ok_or(GraphAnnisError::LHSOperandNotFound)?;
+                correlated.push((var.as_str(), idx));
+            }
+            // The statistics of the subqueries are not part of the analysis
+            let subquery_config = Config {
+                analyze: false,
+                ..config.clone()
+            };
+            let timeout = timeout.clone();
+            let has_result = move |tuple: &MatchGroup| {
+                let nodes: Vec<_> = correlated
+                    .iter()
+                    .map(|(var, idx)| (*var, tuple[*idx].node))
+                    .collect();
+                entry.has_result(db, &subquery_config, &nodes, timeout.clone())
+            };
+            result = with_statistics(
+                config,
+                Box::new(Filter::new_not_exists(
+                    result,
+                    entry.to_string(),
+                    has_result,
+                )),
+            );
+        }
+        Ok(result)
     }
 
     fn check_components_connected(&self) -> Result<()> {
@@ -968,8 +1303,12 @@ impl Conjunction {
         self.check_components_connected()?;
 
         let mut output_size_cache = HashMap::new();
-        let operator_order =
-            self.optimize_join_order_heuristics(db, config, &mut output_size_cache, timeout)?;
+        let operator_order = self.optimize_join_order_heuristics(
+            db,
+            config,
+            &mut output_size_cache,
+            timeout.clone(),
+        )?;
         self.make_exec_plan_with_order(db, config, operator_order, &mut output_size_cache, timeout)
     }
 }
@@ -202,3 +202,23 @@ fn semantic_error_optional_non_negated() {
         Ok(_) => panic!("Query must return an error"),
     }
 }
+
+#[test]
+fn canonical_description() {
+    let describe = |query: &str| aql::parse(query, false).unwrap().canonical_description();
+
+    assert_eq!(
+        "(1#tok & 2#pos=\"NN\" & #1 .2,10 #2 & #2:arity=2,2)",
+        describe("tok .2,10 pos=\"NN\" & #2:arity=2")
+    );
+    // The formatting of the query does not matter
+    assert_eq!(
+        describe("tok . pos=\"NN\""),
+        describe("  tok\n.   pos = \"NN\"")
+    );
+    // Operators and searches with the same parameters are distinguished
+    assert_ne!(describe("tok . tok"), describe("tok ^ tok"));
+    let meta = aql::parse("tok & meta::doc=\"a\"", true).unwrap();
+    assert_ne!(describe("tok & doc=\"a\""), meta.canonical_description());
+    assert_ne!(describe("tok . tok"), describe("tok . tok | tok"));
+}
@@ -1,6 +1,7 @@
 use super::conjunction::Conjunction;
 use crate::{AnnotationGraph, annis::db::aql::model::AnnotationComponentType};
 use graphannis_core::types::Component;
+use itertools::Itertools;
 use std::collections::HashSet;
 
 /// A disjunction is a parsed and normalized AQL query.
@@ -13,6 +14,15 @@ impl Disjunction {
         Disjunction { alternatives }
     }
 
+    /// Describes the query in AQL syntax, which is the same for queries which
+    /// only differ in their formatting (e.g. whitespace).
+    pub(crate) fn canonical_description(&self) -> String {
+        self.alternatives
+            .iter()
+            .map(|alt| format!("({})", alt.canonical_description()))
+            .join(" | ")
+    }
+
     pub(crate) fn necessary_components(
         &self,
         db: &AnnotationGraph,
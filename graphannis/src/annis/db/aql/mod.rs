@@ -19,7 +19,7 @@ use crate::annis::db::aql::conjunction::Conjunction;
 use crate::annis::db::aql::disjunction::Disjunction;
 use crate::annis::db::aql::operators::{
     EqualValueSpec, IdenticalNodeSpec, NegatedOpSpec, NonExistingUnaryOperatorSpec,
-    PartOfSubCorpusSpec, RangeSpec,
+    PartOfSubCorpusSpec, RangeSpec, TextEndSpec,
 };
 use crate::annis::db::exec::nodesearch::NodeSearchSpec;
 use crate::annis::db::plan::ExecutionPlan;
@@ -29,7 +29,9 @@ use crate::annis::types::{LineColumn, LineColumnRange};
 use crate::annis::util::TimeoutCheck;
 use lalrpop_util::ParseError;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -40,6 +42,9 @@ thread_local! {
 #[derive(Clone, Default, Debug)]
 pub struct Config {
     pub use_parallel_joins: bool,
+    /// Collect the actual number of produced tuples and the execution time
+    /// for each execution node.
+    pub analyze: bool,
 }
 
 /// Executes an query on an [`AnnotationGraph`](AnnotationGraph)
@@ -77,7 +82,10 @@ pub fn execute_query_on_graph<'a>(
 
     let timeout = TimeoutCheck::new(timeout);
 
-    let config = Config { use_parallel_joins };
+    let config = Config {
+        use_parallel_joins,
+        analyze: false,
+    };
     let it = ExecutionPlan::from_disjunction(query, graph, &config, timeout)?
         .map_ok(|mg| {
             // Rewrite match group to only include nodes that are included in the query
@@ -112,10 +120,29 @@ fn map_conjunction(
 ) -> Result<Conjunction> {
     let mut q = Conjunction::with_offset(var_idx_offset);
     // collect and sort all node searches according to their start position in the text
-    let (pos_to_node, pos_to_endpos) = calculate_node_positions(&c, offsets, quirks_mode)?;
+    let (mut pos_to_node, mut pos_to_endpos) = calculate_node_positions(&c, offsets, quirks_mode)?;
+
+    // The nodes of "!exists" subqueries are numbered like all other nodes, but
+    // are only added as optional nodes, which are not part of the output.
+    let mut subquery_positions = BTreeSet::new();
+    for literal in c.iter() {
+        if let ast::Literal::NotExists { literals, .. } = literal {
+            let (sub_pos_to_node, sub_pos_to_endpos) =
+                calculate_node_positions(literals, offsets, quirks_mode)?;
+            for (start_pos, (spec, variable, _)) in sub_pos_to_node {
+                subquery_positions.insert(start_pos);
+                pos_to_node.insert(start_pos, (spec, variable, true));
+            }
+            pos_to_endpos.extend(sub_pos_to_endpos);
+        }
+    }
 
     // add all nodes specs in order of their start position
     let mut pos_to_node_id = add_node_specs_by_start(&mut q, pos_to_node, pos_to_endpos, offsets)?;
+    let subquery_vars: HashSet<String> = subquery_positions
+        .iter()
+        .filter_map(|start_pos| pos_to_node_id.get(start_pos).cloned())
+        .collect();
 
     // add all unary operators as filter(s) to the referenced nodes
     for literal in c.iter() {
@@ -130,10 +157,31 @@ fn map_conjunction(
                 end: Some(get_line_and_column_for_pos(pos.end, offsets)),
             });
 
+            check_not_in_subquery(&var, &subquery_vars, op_pos.clone())?;
             q.add_unary_operator_from_query(make_unary_operator_spec(op.clone()), &var, op_pos)?;
         }
     }
 
+    // add all "!exists" subqueries, which are correlated with the nodes of
+    // this conjunction
+    for literal in c.iter() {
+        if let ast::Literal::NotExists { literals, pos } = literal {
+            let location = Some(LineColumnRange {
+                start: get_line_and_column_for_pos(pos.start, offsets),
+                end: Some(get_line_and_column_for_pos(pos.end, offsets)),
+            });
+            let (subquery, correlated) = map_not_exists_subquery(
+                &q,
+                literals,
+                &pos_to_node_id,
+                &subquery_vars,
+                offsets,
+                quirks_mode,
+            )?;
+            q.add_not_exists_from_query(subquery, correlated, location)?;
+        }
+    }
+
     let mut num_pointing_or_dominance_joins: HashMap<String, usize> = HashMap::default();
 
     // finally add all binary operators
@@ -173,6 +221,9 @@ fn map_conjunction(
                 end: Some(get_line_and_column_for_pos(pos.end, offsets)),
             });
 
+            check_not_in_subquery(&var_left, &subquery_vars, op_pos.clone())?;
+            check_not_in_subquery(&var_right, &subquery_vars, op_pos.clone())?;
+
             let node_left = q.resolve_variable(&var_left, op_pos.clone())?;
             let node_right = q.resolve_variable(&var_right, op_pos.clone())?;
 
@@ -260,6 +311,23 @@ fn map_conjunction(
         }
     }
 
+    // Add the last token of each text search as additional node, so that the
+    // whole span of the matched text is part of the result.
+    for (_, var) in pos_to_node_id.iter() {
+        let node = q.resolve_variable(var, None)?;
+        if node.optional {
+            continue;
+        }
+        if let Some((val, regex_flags)) = node.spec.text_search_value() {
+            let op = TextEndSpec {
+                val: val.to_string(),
+                regex_flags,
+            };
+            let end_var = q.add_node(NodeSearchSpec::AnyToken, None);
+            q.add_operator(Arc::new(op), var, &end_var, false)?;
+        }
+    }
+
     if quirks_mode {
         // Add additional nodes to the query to emulate the old behavior of distributing
         // joins for pointing and dominance operators on different query nodes.
@@ -279,6 +347,169 @@ fn map_conjunction(
     Ok(q)
 }
 
+/// Returns an error if the variable belongs to a `!exists` subquery and is
+/// used outside of it.
+fn check_not_in_subquery(
+    var: &str,
+    subquery_vars: &HashSet<String>,
+    location: Option<LineColumnRange>,
+) -> Result<()> {
+    if subquery_vars.contains(var) {
+        Err(GraphAnnisError::AQLSemanticError(AQLError {
+            desc: format!(
+                "Variable \"#{}\" is part of a \"!exists\" subquery and can only be used inside it",
+                var
+            ),
+            location,
+        }))
+    } else {
+        Ok(())
+    }
+}
+
+/// Adds the variable to the subquery if it is not already part of it. Nodes
+/// from the outer conjunction are added with their original node search and
+/// remembered as correlated node.
+fn add_subquery_operand(
+    var: &str,
+    q: &Conjunction,
+    subquery: &mut Conjunction,
+    subquery_vars: &HashSet<String>,
+    correlated: &mut Vec<String>,
+    location: Option<LineColumnRange>,
+) -> Result<()> {
+    if subquery.resolve_variable_pos(var, None).is_ok() {
+        return Ok(());
+    }
+    check_not_in_subquery(var, subquery_vars, location.clone())?;
+    let node = q.resolve_variable(var, location.clone())?;
+    subquery.add_node_from_query(node.spec, Some(var), location, false, false);
+    correlated.push(var.to_string());
+    Ok(())
+}
+
+/// Maps the literals of a `!exists { ... }` subquery to their own conjunction.
+/// Returns this conjunction and the variables of the nodes from the outer
+/// conjunction `q` that are referenced in the subquery.
+fn map_not_exists_subquery(
+    q: &Conjunction,
+    literals: &[ast::Literal],
+    pos_to_node_id: &BTreeMap<usize, String>,
+    subquery_vars: &HashSet<String>,
+    offsets: &BTreeMap<usize, usize>,
+    quirks_mode: bool,
+) -> Result<(Conjunction, Vec<String>)> {
+    let mut subquery = Conjunction::new();
+    let mut correlated = Vec::new();
+
+    // use the same variables as the placeholder nodes in the outer conjunction
+    let (pos_to_node, pos_to_endpos) = calculate_node_positions(literals, offsets, quirks_mode)?;
+    for (start_pos, (node_spec, variable, optional)) in pos_to_node {
+        let start = get_line_and_column_for_pos(start_pos, offsets);
+        let end = pos_to_endpos
+            .get(&start_pos)
+            .map(|end_pos| get_line_and_column_for_pos(*end_pos, offsets));
+        let location = Some(LineColumnRange { start, end });
+        if optional {
+            return Err(GraphAnnisError::AQLSemanticError(AQLError {
+                desc: "Optional nodes are not allowed in a \"!exists\" subquery".into(),
+                location,
+            }));
+        }
+        let variable = pos_to_node_id.get(&start_pos).cloned().or(variable);
+        subquery.add_node_from_query(node_spec, variable.as_deref(), location, false, false);
+    }
+
+    for literal in literals {
+        match literal {
+            ast::Literal::UnaryOp { node_ref, op, pos } => {
+                let var = match node_ref {
+                    ast::NodeRef::ID(id) => id.to_string(),
+                    ast::NodeRef::Name(name) => name.clone(),
+                };
+                let op_pos: Option<LineColumnRange> = pos.as_ref().map(|pos| LineColumnRange {
+                    start: get_line_and_column_for_pos(pos.start, offsets),
+                    end: Some(get_line_and_column_for_pos(pos.end, offsets)),
+                });
+                add_subquery_operand(
+                    &var,
+                    q,
+                    &mut subquery,
+                    subquery_vars,
+                    &mut correlated,
+                    op_pos.clone(),
+                )?;
+                subquery.add_unary_operator_from_query(
+                    make_unary_operator_spec(op.clone()),
+                    &var,
+                    op_pos,
+                )?;
+            }
+            ast::Literal::BinaryOp {
+                lhs,
+                op,
+                rhs,
+                pos,
+                negated,
+            } => {
+                let op_pos: Option<LineColumnRange> = pos.as_ref().map(|pos| LineColumnRange {
+                    start: get_line_and_column_for_pos(pos.start, offsets),
+                    end: Some(get_line_and_column_for_pos(pos.end, offsets)),
+                });
+                let mut operand_vars = Vec::with_capacity(2);
+                for operand in [lhs, rhs] {
+                    let var = match operand {
+                        ast::Operand::Literal { pos, .. } => {
+                            pos_to_node_id.get(&pos.start).cloned().unwrap_or_default()
+                        }
+                        ast::Operand::NodeRef(ast::NodeRef::ID(id)) => id.to_string(),
+                        ast::Operand::NodeRef(ast::NodeRef::Name(name)) => name.clone(),
+                    };
+                    add_subquery_operand(
+                        &var,
+                        q,
+                        &mut subquery,
+                        subquery_vars,
+                        &mut correlated,
+                        op_pos.clone(),
+                    )?;
+                    operand_vars.push(var);
+                }
+                let node_left = subquery.resolve_variable(&operand_vars[0], op_pos.clone())?;
+                let node_right = subquery.resolve_variable(&operand_vars[1], op_pos.clone())?;
+                let mut op_spec =
+                    make_binary_operator_spec(op.clone(), node_left.spec, node_right.spec)?;
+                if *negated {
+                    op_spec = Arc::new(NegatedOpSpec {
+                        negated_op: op_spec,
+                    });
+                }
+                subquery.add_operator_from_query(
+                    op_spec,
+                    &operand_vars[0],
+                    &operand_vars[1],
+                    op_pos,
+                    !quirks_mode,
+                )?;
+            }
+            ast::Literal::NodeSearch { .. } => {
+                // already added
+            }
+            ast::Literal::LegacyMetaSearch { pos, .. } | ast::Literal::NotExists { pos, .. } => {
+                return Err(GraphAnnisError::AQLSemanticError(AQLError {
+                    desc: "Legacy metadata searches and nested subqueries are not allowed in a \"!exists\" subquery".into(),
+                    location: Some(LineColumnRange {
+                        start: get_line_and_column_for_pos(pos.start, offsets),
+                        end: Some(get_line_and_column_for_pos(pos.end, offsets)),
+                    }),
+                }));
+            }
+        }
+    }
+
+    Ok((subquery, correlated))
+}
+
 type PosToNodeMap = BTreeMap<usize, (NodeSearchSpec, Option<String>, bool)>;
 type PosToEndPosMap = BTreeMap<usize, usize>;
 
@@ -332,6 +563,9 @@ fn calculate_node_positions(
             ast::Literal::UnaryOp { .. } => {
                 // can only have node reference, not a literal
             }
+            ast::Literal::NotExists { .. } => {
+                // nodes of subqueries are handled separately
+            }
             ast::Literal::LegacyMetaSearch { pos, .. } => {
                 if !quirks_mode {
                     let start = get_line_and_column_for_pos(pos.start, offsets);
@@ -574,6 +808,8 @@ fn make_binary_operator_spec(
         ast::BinaryOpSpec::LeftAlignment(spec) => Arc::new(spec),
         ast::BinaryOpSpec::RightAlignment(spec) => Arc::new(spec),
         ast::BinaryOpSpec::IdenticalNode(spec) => Arc::new(spec),
+        ast::BinaryOpSpec::Sibling(spec) => Arc::new(spec),
+        ast::BinaryOpSpec::CommonAncestor(spec) => Arc::new(spec),
         ast::BinaryOpSpec::ValueComparison(cmp) => match cmp {
             ast::ComparisonOperator::Equal => Arc::new(EqualValueSpec {
                 spec_left,
@@ -593,6 +829,8 @@ fn make_binary_operator_spec(
 fn make_unary_operator_spec(op: ast::UnaryOpSpec) -> Arc<dyn UnaryOperatorSpec> {
     match op {
         ast::UnaryOpSpec::Arity(spec) => Arc::new(spec),
+        ast::UnaryOpSpec::Root(spec) => Arc::new(spec),
+        ast::UnaryOpSpec::TokenArity(spec) => Arc::new(spec),
     }
 }
 
@@ -673,6 +911,8 @@ mod tests {
     use std::{fs::File, path::PathBuf};
 
     use super::*;
+    use crate::update::{GraphUpdate, UpdateEvent};
+    use graphannis_core::graph::{ANNIS_NS, NODE_NAME_KEY};
 
     #[test]
     fn query_on_annotation_graph() {
@@ -707,4 +947,544 @@ mod tests {
         assert_eq!(1, matches[2].len());
         assert_eq!(1, matches[3].len());
     }
+
+    #[test]
+    fn query_root_unary_operator() {
+        let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = File::open(cargo_dir.join("tests/SaltSampleCorpus.graphml")).unwrap();
+        let (graph, _config_str): (AnnotationGraph, _) =
+            graphannis_core::graph::serialization::graphml::import(input_file, false, |_status| {})
+                .unwrap();
+
+        let query = parse("const & #1:root", false).unwrap();
+        let roots: Result<Vec<_>> = execute_query_on_graph(&graph, &query, true, None)
+            .unwrap()
+            .collect();
+        let query = parse("const=\"ROOT\"", false).unwrap();
+        let root_cat: Result<Vec<_>> = execute_query_on_graph(&graph, &query, true, None)
+            .unwrap()
+            .collect();
+
+        let mut roots: Vec<_> = roots.unwrap().into_iter().map(|mg| mg[0].node).collect();
+        roots.sort_unstable();
+        let mut root_cat: Vec<_> = root_cat.unwrap().into_iter().map(|mg| mg[0].node).collect();
+        root_cat.sort_unstable();
+        assert_eq!(4, roots.len());
+        assert_eq!(root_cat, roots);
+    }
+
+    #[test]
+    fn query_tokenarity_unary_operator() {
+        let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = File::open(cargo_dir.join("tests/SaltSampleCorpus.graphml")).unwrap();
+        let (graph, _config_str): (AnnotationGraph, _) =
+            graphannis_core::graph::serialization::graphml::import(input_file, false, |_status| {})
+                .unwrap();
+
+        let count = |aql: &str| {
+            let query = parse(aql, false).unwrap();
+            execute_query_on_graph(&graph, &query, true, None)
+                .unwrap()
+                .count()
+        };
+
+        assert_eq!(count("tok"), count("tok & #1:tokenarity=1"));
+        assert_eq!(0, count("tok & #1:tokenarity=2,5"));
+        // The sentence root covers all 11 token of each document
+        assert_eq!(4, count("const=\"ROOT\" & #1:tokenarity=11"));
+        assert_eq!(0, count("const=\"ROOT\" & #1:tokenarity=1,10"));
+        assert_eq!(count("const"), count("const & #1:tokenarity=*"));
+    }
+
+    #[test]
+    fn parse_unknown_unary_operator() {
+        assert!(matches!(
+            parse("tok & #1:unknown", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+        assert!(matches!(
+            parse("tok & #1:unknown=1", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+        // "root" is still a valid annotation name
+        assert!(parse("default_ns:root=\"value\"", false).is_ok());
+    }
+
+    fn create_numeric_test_graph() -> AnnotationGraph {
+        let mut g = AnnotationGraph::with_default_graphstorages(false).unwrap();
+        let mut u = GraphUpdate::new();
+        let values = ["1800", "1850", "1900.5", "-20", "unknown"];
+        for (i, val) in values.iter().enumerate() {
+            let node_name = format!("doc1/n{}", i);
+            u.add_event(UpdateEvent::AddNode {
+                node_name: node_name.clone(),
+                node_type: "node".to_string(),
+            })
+            .unwrap();
+            u.add_event(UpdateEvent::AddNodeLabel {
+                node_name: node_name.clone(),
+                anno_ns: "test".to_string(),
+                anno_name: "year".to_string(),
+                anno_value: val.to_string(),
+            })
+            .unwrap();
+            if i > 0 {
+                u.add_event(UpdateEvent::AddEdge {
+                    source_node: "doc1/n0".to_string(),
+                    target_node: node_name.clone(),
+                    layer: "test".to_string(),
+                    component_type: "Dominance".to_string(),
+                    component_name: "".to_string(),
+                })
+                .unwrap();
+                u.add_event(UpdateEvent::AddEdgeLabel {
+                    source_node: "doc1/n0".to_string(),
+                    target_node: node_name,
+                    layer: "test".to_string(),
+                    component_type: "Dominance".to_string(),
+                    component_name: "".to_string(),
+                    anno_ns: "test".to_string(),
+                    anno_name: "weight".to_string(),
+                    anno_value: format!("{}.5", i),
+                })
+                .unwrap();
+            }
+        }
+        g.apply_update(&mut u, |_| {}).unwrap();
+        g
+    }
+
+    #[test]
+    fn query_numeric_comparison() {
+        let graph = create_numeric_test_graph();
+        let count = |aql: &str| {
+            let query = parse(aql, false).unwrap();
+            execute_query_on_graph(&graph, &query, true, None)
+                .unwrap()
+                .count()
+        };
+
+        assert_eq!(2, count("year>1800"));
+        assert_eq!(3, count("year>=1800"));
+        assert_eq!(1, count("test:year<1800"));
+        assert_eq!(2, count("year<=1800"));
+        assert_eq!(1, count("year < -19.5"));
+        assert_eq!(1, count("year>1800 & year<1900 & #1 _ident_ #2"));
+
+        // Numeric comparisons for edge annotations
+        assert_eq!(3, count("node >[weight>2] node"));
+        assert_eq!(1, count("node & year>=1850 & #1 >[test:weight<=1.5] #2"));
+        assert_eq!(0, count("node >[weight<1] node"));
+    }
+
+    #[test]
+    fn parse_numeric_comparison() {
+        // The dominance operator with a distance must still be parsed correctly
+        let query = parse("node & node & #1 >2 #2", false).unwrap();
+        assert_eq!(1, query.alternatives.len());
+        assert!(parse("node >2,4 node", false).is_ok());
+        assert!(parse("year>-5", false).is_ok());
+
+        // Numeric comparisons are only possible for annotation names
+        assert!(matches!(
+            parse("node>5", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+        assert!(matches!(
+            parse("year=\"1850\">5", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+        assert!(matches!(
+            parse("node >[weight>dep 5] node", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+    }
+
+    #[test]
+    fn query_regex_with_flags() {
+        let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let input_file = File::open(cargo_dir.join("tests/SaltSampleCorpus.graphml")).unwrap();
+        let (graph, _config_str): (AnnotationGraph, _) =
+            graphannis_core::graph::serialization::graphml::import(input_file, false, |_status| {})
+                .unwrap();
+
+        let count = |aql: &str| {
+            let query = parse(aql, false).unwrap();
+            execute_query_on_graph(&graph, &query, true, None)
+                .unwrap()
+                .count()
+        };
+
+        assert_eq!(0, count("tok=/is/"));
+        assert_eq!(4, count("tok=/is/i"));
+        assert_eq!(4, count("/IS/i"));
+        assert_eq!(count("tok") - 4, count("tok!=/is/i"));
+        assert_eq!(count("pos=/VB.*/"), count("pos=/vb.*/i"));
+        assert_eq!(count("lemma!=/be/"), count("lemma!=/BE/i"));
+        assert_eq!(0, count("tok=/ïs/i"));
+        assert_eq!(4, count("tok=/ïs/id"));
+    }
+
+    #[test]
+    fn parse_regex_flags() {
+        assert!(parse("tok=/is/i", false).is_ok());
+        assert!(parse("pos=/N.*/di & tok", false).is_ok());
+        assert!(matches!(
+            parse("tok=/is/x", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+    }
+
+    fn create_multi_tier_test_graph() -> AnnotationGraph {
+        let mut g = AnnotationGraph::with_default_graphstorages(false).unwrap();
+        let mut u = GraphUpdate::new();
+        let annos = [
+            ("n0", "tier", "gloss_A", "house"),
+            ("n1", "tier", "gloss_B", "house"),
+            ("n2", "tier", "gloss_B", "tree"),
+            ("n3", "other", "lemma", "house"),
+            ("n3", "tier", "lemma", "House"),
+        ];
+        for (node, anno_ns, anno_name, anno_value) in annos {
+            let node_name = format!("doc1/{}", node);
+            u.add_event(UpdateEvent::AddNode {
+                node_name: node_name.clone(),
+                node_type: "node".to_string(),
+            })
+            .unwrap();
+            u.add_event(UpdateEvent::AddNodeLabel {
+                node_name,
+                anno_ns: anno_ns.to_string(),
+                anno_name: anno_name.to_string(),
+                anno_value: anno_value.to_string(),
+            })
+            .unwrap();
+        }
+        g.apply_update(&mut u, |_| {}).unwrap();
+        g
+    }
+
+    #[test]
+    fn query_anno_name_pattern() {
+        let graph = create_multi_tier_test_graph();
+        let count = |aql: &str| {
+            let query = parse(aql, false).unwrap();
+            execute_query_on_graph(&graph, &query, true, None)
+                .unwrap()
+                .count()
+        };
+
+        assert_eq!(2, count("/gloss_.*/=\"house\""));
+        assert_eq!(1, count("/GLOSS_.*/i!=\"house\""));
+        assert_eq!(2, count("/gloss_.*/=/h.*/"));
+        assert_eq!(3, count("tier:/gloss_.*/"));
+        assert_eq!(3, count("*:/gloss_.*/"));
+        assert_eq!(4, count("tier:*"));
+        assert_eq!(2, count("*:lemma"));
+        assert_eq!(count("lemma"), count("*:lemma"));
+        // The internal "annis" namespace is only searched when given explicitly
+        assert_eq!(3, count("*:*=\"house\""));
+        assert_eq!(1, count("annis:*=\"doc1/n0\""));
+        assert_eq!(2, count("node & /gloss_.*/=\"house\" & #1 _ident_ #2"));
+        assert_eq!(
+            0,
+            count("/gloss_.*/=\"house\" & #1 _ident_ #2 & tier:lemma")
+        );
+    }
+
+    #[test]
+    fn parse_anno_name_pattern() {
+        assert!(parse("/gloss_.*/=\"house\" & ns:/tier.*/i", false).is_ok());
+        // a regular expression without namespace and value is still a search for a span
+        let query = parse("/gloss_.*/", false).unwrap();
+        assert_eq!(
+            "/gloss_.*/",
+            query.alternatives[0].get_node_descriptions()[0].query_fragment
+        );
+        let query = parse("*:/gloss_.*/=\"house\"", false).unwrap();
+        assert_eq!(
+            "*:/gloss_.*/=\"house\"",
+            query.alternatives[0].get_node_descriptions()[0].query_fragment
+        );
+        assert!(matches!(
+            parse("/gloss_.*/x=\"house\"", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+    }
+
+    fn create_text_test_graph() -> AnnotationGraph {
+        let mut g = AnnotationGraph::with_default_graphstorages(false).unwrap();
+        let mut u = GraphUpdate::new();
+        // The text is "The man, in the middle."
+        let token = [
+            ("The", None),
+            ("man", Some("")),
+            (",", Some(" ")),
+            ("in", Some(" ")),
+            ("the", Some(" ")),
+            ("middle", Some("")),
+            (".", None),
+        ];
+        for (i, (value, whitespace_after)) in token.iter().enumerate() {
+            crate::annis::db::example_generator::create_token_node(
+                &mut u,
+                &format!("doc1#t{}", i),
+                value,
+                None,
+                *whitespace_after,
+                None,
+            );
+            if i > 0 {
+                u.add_event(UpdateEvent::AddEdge {
+                    source_node: format!("doc1#t{}", i - 1),
+                    target_node: format!("doc1#t{}", i),
+                    layer: ANNIS_NS.to_string(),
+                    component_type: "Ordering".to_string(),
+                    component_name: "".to_string(),
+                })
+                .unwrap();
+            }
+        }
+        g.apply_update(&mut u, |_| {}).unwrap();
+        g
+    }
+
+    #[test]
+    fn query_text_search() {
+        let graph = create_text_test_graph();
+        let find = |aql: &str| -> Vec<Vec<String>> {
+            let query = parse(aql, false).unwrap();
+            let mut result: Vec<Vec<String>> = execute_query_on_graph(&graph, &query, true, None)
+                .unwrap()
+                .map(|m| {
+                    m.unwrap()
+                        .iter()
+                        .map(|m| {
+                            graph
+                                .get_node_annos()
+                                .get_value_for_item(&m.node, &NODE_NAME_KEY)
+                                .unwrap()
+                                .unwrap()
+                                .to_string()
+                        })
+                        .collect()
+                })
+                .collect();
+            result.sort();
+            result
+        };
+
+        // The first and last token of the text are part of the match
+        assert_eq!(
+            vec![vec!["doc1#t3", "doc1#t5"]],
+            find("text=\"in the middle\"")
+        );
+        assert_eq!(vec![vec!["doc1#t3", "doc1#t5"]], find("text=/in the \\w+/"));
+        // Whitespace between token is taken from the annotations
+        assert_eq!(vec![vec!["doc1#t1", "doc1#t3"]], find("text=\"man, in\""));
+        assert!(find("text=\"man , in\"").is_empty());
+        assert_eq!(vec![vec!["doc1#t5", "doc1#t6"]], find("text=\"middle.\""));
+        // If there is no whitespace annotation, a space is assumed
+        assert_eq!(vec![vec!["doc1#t0", "doc1#t1"]], find("text=\"The man\""));
+        // Matches must start and end at token boundaries
+        assert!(find("text=\"n the\"").is_empty());
+        assert!(find("text=\"in th\"").is_empty());
+        // Text matching a single token
+        assert_eq!(
+            vec![vec!["doc1#t0", "doc1#t0"], vec!["doc1#t4", "doc1#t4"]],
+            find("text=/the/i")
+        );
+        // Regular expressions can match several text spans starting at the same token
+        assert_eq!(
+            vec![
+                vec!["doc1#t3", "doc1#t3"],
+                vec!["doc1#t3", "doc1#t4"],
+                vec!["doc1#t3", "doc1#t5"],
+                vec!["doc1#t3", "doc1#t6"]
+            ],
+            find("text=/in.*/")
+        );
+        // Matches starting at different token can end at the same token
+        assert_eq!(
+            vec![
+                vec!["doc1#t0", "doc1#t5"],
+                vec!["doc1#t1", "doc1#t5"],
+                vec!["doc1#t2", "doc1#t5"],
+                vec!["doc1#t3", "doc1#t5"],
+                vec!["doc1#t4", "doc1#t5"],
+                vec!["doc1#t5", "doc1#t5"]
+            ],
+            find("text=/.*middle/i")
+        );
+        // Text searches can be combined with other operators
+        assert_eq!(
+            vec![vec!["doc1#t1", "doc1#t3", "doc1#t5"]],
+            find("tok=\"man\" & text=/in the \\w+/ & #1 .2 #2")
+        );
+        assert_eq!(
+            vec![vec!["doc1#t3", "doc1#t5", "doc1#t5"]],
+            find("text=/in the \\w+/ & tok & #1 .2 #2")
+        );
+    }
+
+    #[test]
+    fn parse_text_search() {
+        let query = parse("text=/in the.*/i", false).unwrap();
+        let nodes = query.alternatives[0].get_node_descriptions();
+        assert_eq!(2, nodes.len());
+        assert_eq!("text=/in the.*/i", nodes[0].query_fragment);
+        // The last token of the text is added as additional node
+        assert_eq!("tok", nodes[1].query_fragment);
+        // "text" can still be used as annotation name
+        assert!(parse("text & ns:text=\"abc\" & *:text=\"abc\"", false).is_ok());
+        let query = parse("*:text!=\"abc\" & text:pos=\"NN\" & *:text=\"abc\"", false).unwrap();
+        let nodes = query.alternatives[0].get_node_descriptions();
+        let fragments: Vec<_> = nodes.into_iter().map(|n| n.query_fragment).collect();
+        assert_eq!(
+            vec!["text!=\"abc\"", "text:pos=\"NN\"", "text=\"abc\""],
+            fragments
+        );
+        // Text searches can't be negated
+        assert!(matches!(
+            parse("text!=\"abc\"", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+        assert!(matches!(
+            parse("text!=/a.*/", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+        assert!(matches!(
+            parse("text=/(in/", false).and_then(|q| {
+                let graph = create_text_test_graph();
+                execute_query_on_graph(&graph, &q, true, None).map(|_| ())
+            }),
+            Err(GraphAnnisError::AQLSemanticError(_))
+        ));
+    }
+
+    fn create_constituent_test_graph() -> AnnotationGraph {
+        let mut g = AnnotationGraph::with_default_graphstorages(false).unwrap();
+        let mut u = GraphUpdate::new();
+        for (node_name, cat) in [
+            ("doc1/s", "S"),
+            ("doc1/np1", "NP"),
+            ("doc1/np2", "NP"),
+            ("doc1/cp", "CP"),
+        ] {
+            u.add_event(UpdateEvent::AddNode {
+                node_name: node_name.to_string(),
+                node_type: "node".to_string(),
+            })
+            .unwrap();
+            u.add_event(UpdateEvent::AddNodeLabel {
+                node_name: node_name.to_string(),
+                anno_ns: "test".to_string(),
+                anno_name: "cat".to_string(),
+                anno_value: cat.to_string(),
+            })
+            .unwrap();
+        }
+        for (source, target) in [
+            ("doc1/s", "doc1/np1"),
+            ("doc1/s", "doc1/np2"),
+            ("doc1/np1", "doc1/cp"),
+        ] {
+            u.add_event(UpdateEvent::AddEdge {
+                source_node: source.to_string(),
+                target_node: target.to_string(),
+                layer: "test".to_string(),
+                component_type: "Dominance".to_string(),
+                component_name: "".to_string(),
+            })
+            .unwrap();
+        }
+        g.apply_update(&mut u, |_| {}).unwrap();
+        g
+    }
+
+    fn find_node_names(graph: &AnnotationGraph, query: &str) -> Vec<String> {
+        let query = parse(query, false).unwrap();
+        let it = execute_query_on_graph(graph, &query, true, None).unwrap();
+        let mut result: Vec<String> = it
+            .map(|m| {
+                let m = m.unwrap();
+                assert_eq!(1, m.len());
+                graph
+                    .get_node_annos()
+                    .get_value_for_item(&m[0].node, &NODE_NAME_KEY)
+                    .unwrap()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn query_not_exists() {
+        let graph = create_constituent_test_graph();
+
+        assert_eq!(
+            vec!["doc1/np2"],
+            find_node_names(&graph, "cat=\"NP\" & !exists { cat=\"CP\" & #1 > #2 }")
+        );
+        assert_eq!(
+            vec!["doc1/cp", "doc1/np2", "doc1/s"],
+            find_node_names(&graph, "cat & !exists { cat=\"CP\" & #1 > #2 }")
+        );
+        assert_eq!(
+            vec!["doc1/cp", "doc1/np2"],
+            find_node_names(&graph, "cat & !exists { cat=\"CP\" & #1 >* #2 }")
+        );
+        // Subquery with several nodes
+        assert_eq!(
+            vec!["doc1/cp", "doc1/np1", "doc1/np2"],
+            find_node_names(
+                &graph,
+                "cat & !exists { cat=\"NP\" & cat=\"CP\" & #1 > #2 & #2 > #3 }"
+            )
+        );
+        // Unary operator for the outer node inside the subquery
+        assert_eq!(
+            vec!["doc1/cp", "doc1/np2", "doc1/s"],
+            find_node_names(
+                &graph,
+                "cat & !exists { cat=\"CP\" & #1 > #2 & #1:arity=1 }"
+            )
+        );
+    }
+
+    #[test]
+    fn parse_not_exists() {
+        let query = parse("cat & !exists { cat=\"CP\" & #1 > #2 }", false).unwrap();
+        let nodes = query.alternatives[0].get_node_descriptions();
+        assert_eq!(2, nodes.len());
+        assert!(!nodes[0].optional);
+        assert!(nodes[1].optional);
+
+        // Nodes of the subquery can't be used outside of it
+        assert!(matches!(
+            parse("cat & !exists { cat=\"CP\" & #1 > #2 } & #2 > #1", false),
+            Err(GraphAnnisError::AQLSemanticError(_))
+        ));
+        // The subquery must be correlated with the outer query
+        assert!(matches!(
+            parse("cat & !exists { cat=\"CP\" }", false),
+            Err(GraphAnnisError::AQLSemanticError(_))
+        ));
+        // All nodes of the subquery must be connected
+        assert!(matches!(
+            parse("cat & !exists { cat=\"CP\" & cat & #1 > #2 }", false),
+            Err(GraphAnnisError::AQLSemanticError(_))
+        ));
+        assert!(matches!(
+            parse("cat & !exists { cat=\"CP\"? & #1 !> #2 }", false),
+            Err(GraphAnnisError::AQLSemanticError(_))
+        ));
+        assert!(matches!(
+            parse("cat & !exists { }", false),
+            Err(GraphAnnisError::AQLSyntaxError(_))
+        ));
+    }
 }
@@ -41,6 +41,10 @@ lazy_static! {
         ns: ANNIS_NS.into(),
         name: TOK.into(),
     });
+    pub static ref DOCUMENT_KEY: Arc<AnnoKey> = Arc::from(AnnoKey {
+        ns: ANNIS_NS.into(),
+        name: "doc".into(),
+    });
 }
 
 /// Specifies the type of component of the annotation graph. The types of this enum carray certain semantics about the edges of the graph components their are used in.
@@ -51,6 +51,10 @@ impl UnaryOperatorSpec for AritySpec {
             allowed_range: self.children.clone(),
         }))
     }
+
+    fn canonical_description(&self) -> String {
+        format!(":arity={}", self.children)
+    }
 }
 
 struct ArityOperator {
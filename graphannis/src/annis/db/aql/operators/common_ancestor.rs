@@ -0,0 +1,278 @@
+use crate::annis::db::aql::model::AnnotationComponentType;
+use crate::annis::db::exec::CostEstimate;
+use crate::annis::errors::GraphAnnisError;
+use crate::annis::operator::{
+    BinaryOperator, BinaryOperatorBase, BinaryOperatorIndex, BinaryOperatorSpec, EstimationType,
+};
+use crate::errors::Result;
+use crate::graph::{GraphStorage, Match};
+use crate::{AnnotationGraph, try_as_boxed_iter};
+use graphannis_core::{
+    graph::{DEFAULT_ANNO_KEY, NODE_TYPE_KEY},
+    types::{Component, NodeID},
+};
+use rustc_hash::FxHashSet;
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::ops::Bound;
+use std::sync::Arc;
+
+/// Specification of the sibling operator `$`, which matches two different
+/// nodes that have the same parent in a dominance component.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SiblingSpec {
+    pub name: String,
+}
+
+/// Specification of the common ancestor operator `$*`, which matches two
+/// different nodes that are both dominated by the same node.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CommonAncestorSpec {
+    pub name: String,
+}
+
+fn dominance_components(
+    db: &AnnotationGraph,
+    name: &str,
+) -> Vec<Component<AnnotationComponentType>> {
+    db.get_all_components(Some(AnnotationComponentType::Dominance), Some(name))
+}
+
+impl BinaryOperatorSpec for SiblingSpec {
+    fn necessary_components(
+        &self,
+        db: &AnnotationGraph,
+    ) -> HashSet<Component<AnnotationComponentType>> {
+        HashSet::from_iter(dominance_components(db, &self.name))
+    }
+
+    fn create_operator<'a>(
+        &self,
+        db: &'a AnnotationGraph,
+        _cost_estimate: Option<(&CostEstimate, &CostEstimate)>,
+    ) -> Result<BinaryOperator<'a>> {
+        let op = CommonAncestor::new(db, &self.name, Bound::Included(1))?;
+        Ok(BinaryOperator::Index(Box::new(op)))
+    }
+
+    fn canonical_description(&self) -> String {
+        format!("${}", self.name)
+    }
+
+    fn lhs_is_component_source(&self) -> bool {
+        // The LHS is a child and not the parent node
+        false
+    }
+
+    #[cfg(test)]
+    fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
+        self
+    }
+
+    #[cfg(test)]
+    fn any_ref(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl BinaryOperatorSpec for CommonAncestorSpec {
+    fn necessary_components(
+        &self,
+        db: &AnnotationGraph,
+    ) -> HashSet<Component<AnnotationComponentType>> {
+        HashSet::from_iter(dominance_components(db, &self.name))
+    }
+
+    fn create_operator<'a>(
+        &self,
+        db: &'a AnnotationGraph,
+        _cost_estimate: Option<(&CostEstimate, &CostEstimate)>,
+    ) -> Result<BinaryOperator<'a>> {
+        let op = CommonAncestor::new(db, &self.name, Bound::Unbounded)?;
+        Ok(BinaryOperator::Index(Box::new(op)))
+    }
+
+    fn canonical_description(&self) -> String {
+        format!("${}*", self.name)
+    }
+
+    fn lhs_is_component_source(&self) -> bool {
+        // The LHS is a descendant and not the ancestor node
+        false
+    }
+
+    #[cfg(test)]
+    fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
+        self
+    }
+
+    #[cfg(test)]
+    fn any_ref(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Operator implementation for both `$` and `$*`. The only difference is the
+/// maximum distance between the common ancestor and the two operands.
+#[derive(Clone)]
+struct CommonAncestor {
+    gs: Vec<Arc<dyn GraphStorage>>,
+    name: String,
+    max_dist: Bound<usize>,
+    max_nodes_estimate: usize,
+}
+
+impl CommonAncestor {
+    fn new(db: &AnnotationGraph, name: &str, max_dist: Bound<usize>) -> Result<CommonAncestor> {
+        let mut gs: Vec<Arc<dyn GraphStorage>> = Vec::new();
+        for c in dominance_components(db, name) {
+            let gs_for_component = db.get_graphstorage(&c).ok_or_else(|| {
+                GraphAnnisError::ImpossibleSearch(format!("Component {} does not exist", &c))
+            })?;
+            gs.push(gs_for_component);
+        }
+
+        let max_nodes_estimate = db.get_node_annos().guess_max_count(
+            Some(&NODE_TYPE_KEY.ns),
+            &NODE_TYPE_KEY.name,
+            "node",
+            "node",
+        )?;
+
+        Ok(CommonAncestor {
+            gs,
+            name: name.to_string(),
+            max_dist,
+            max_nodes_estimate,
+        })
+    }
+
+    /// Get all ancestors of the given node that need to be considered as
+    /// common ancestor in the given graph storage.
+    fn ancestors(&self, gs: &dyn GraphStorage, node: NodeID) -> Result<Vec<NodeID>> {
+        let mut ancestors = Vec::new();
+        for a in gs.find_connected_inverse(node, 1, self.max_dist) {
+            ancestors.push(a?);
+        }
+        if self.max_dist == Bound::Unbounded {
+            // Every descendant of an ancestor is also a descendant of the
+            // root nodes above it, so it is enough to start from the roots.
+            // Cyclic components might not have any root node, in this case
+            // all ancestors are used.
+            let mut roots = Vec::with_capacity(1);
+            for a in ancestors.iter() {
+                if !gs.has_ingoing_edges(*a)? {
+                    roots.push(*a);
+                }
+            }
+            if !roots.is_empty() {
+                return Ok(roots);
+            }
+        }
+        Ok(ancestors)
+    }
+}
+
+impl std::fmt::Display for CommonAncestor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let unbound_frag = if self.max_dist == Bound::Unbounded {
+            "*"
+        } else {
+            ""
+        };
+        if self.name.is_empty() {
+            write!(f, "${}", unbound_frag)
+        } else {
+            write!(f, "${}{}", self.name, unbound_frag)
+        }
+    }
+}
+
+impl BinaryOperatorBase for CommonAncestor {
+    fn filter_match(&self, lhs: &Match, rhs: &Match) -> Result<bool> {
+        if lhs.node == rhs.node {
+            return Ok(false);
+        }
+        for gs in &self.gs {
+            for a in self.ancestors(gs.as_ref(), lhs.node)? {
+                if gs.is_connected(a, rhs.node, 1, self.max_dist)? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    fn is_reflexive(&self) -> bool {
+        false
+    }
+
+    fn get_inverse_operator<'a>(
+        &self,
+        _graph: &'a AnnotationGraph,
+    ) -> Result<Option<BinaryOperator<'a>>> {
+        // The operator is symmetric
+        Ok(Some(BinaryOperator::Index(Box::new(self.clone()))))
+    }
+
+    fn estimation_type(&self) -> Result<EstimationType> {
+        if self.gs.is_empty() {
+            // will not find anything
+            return Ok(EstimationType::Selectivity(0.0));
+        }
+
+        let max_nodes = std::cmp::max(self.max_nodes_estimate, 1) as f64;
+
+        let mut worst_sel: f64 = 0.0;
+        for gs in &self.gs {
+            let gs_selectivity = if let Some(stats) = gs.get_statistics() {
+                let reachable = if self.max_dist == Bound::Unbounded {
+                    // Assume each node of the component belongs to one of the
+                    // rooted sub-graphs and that they have the same size.
+                    (stats.nodes as f64) / (std::cmp::max(stats.root_nodes, 1) as f64)
+                } else {
+                    // The siblings are the other children of the parent node.
+                    stats.avg_fan_out
+                };
+                reachable / max_nodes
+            } else {
+                0.01
+            };
+            worst_sel = worst_sel.max(gs_selectivity);
+        }
+
+        Ok(EstimationType::Selectivity(worst_sel.min(1.0)))
+    }
+}
+
+impl BinaryOperatorIndex for CommonAncestor {
+    fn retrieve_matches(&self, lhs: &Match) -> Box<dyn Iterator<Item = Result<Match>>> {
+        let mut result: FxHashSet<NodeID> = FxHashSet::default();
+        for gs in &self.gs {
+            let ancestors = try_as_boxed_iter!(self.ancestors(gs.as_ref(), lhs.node));
+            for a in ancestors {
+                for candidate in gs.find_connected(a, 1, self.max_dist) {
+                    let candidate = try_as_boxed_iter!(candidate);
+                    if candidate != lhs.node {
+                        result.insert(candidate);
+                    }
+                }
+            }
+        }
+        let mut result: Vec<_> = result.into_iter().collect();
+        result.sort_unstable();
+        Box::new(result.into_iter().map(|n| {
+            Ok(Match {
+                node: n,
+                anno_key: DEFAULT_ANNO_KEY.clone(),
+            })
+        }))
+    }
+
+    fn as_binary_operator(&self) -> &dyn BinaryOperatorBase {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests;
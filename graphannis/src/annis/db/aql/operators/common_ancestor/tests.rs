@@ -0,0 +1,86 @@
+use std::{collections::BTreeSet, fs::File, ops::Bound, path::PathBuf};
+
+use graphannis_core::types::NodeID;
+
+use super::CommonAncestor;
+use crate::{
+    AnnotationGraph,
+    annis::db::aql::{execute_query_on_graph, parse},
+};
+
+fn load_salt_sample() -> AnnotationGraph {
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let input_file = File::open(cargo_dir.join("tests/SaltSampleCorpus.graphml")).unwrap();
+    let (graph, _config_str): (AnnotationGraph, _) =
+        graphannis_core::graph::serialization::graphml::import(input_file, false, |_status| {})
+            .unwrap();
+    graph
+}
+
+/// Execute the query and return the set of (different) node pairs at the
+/// given positions of the match group.
+fn node_pairs(
+    graph: &AnnotationGraph,
+    aql: &str,
+    lhs: usize,
+    rhs: usize,
+) -> BTreeSet<(NodeID, NodeID)> {
+    let query = parse(aql, false).unwrap();
+    execute_query_on_graph(graph, &query, false, None)
+        .unwrap()
+        .map(|mg| {
+            let mg = mg.unwrap();
+            (mg[lhs].node, mg[rhs].node)
+        })
+        .filter(|(lhs, rhs)| lhs != rhs)
+        .collect()
+}
+
+#[test]
+fn sibling_same_as_explicit_parent() {
+    let graph = load_salt_sample();
+
+    let siblings = node_pairs(&graph, "node $ node", 0, 1);
+    let explicit = node_pairs(&graph, "node & node & node & #1 > #2 & #1 > #3", 1, 2);
+
+    assert_eq!(false, siblings.is_empty());
+    assert_eq!(explicit, siblings);
+}
+
+#[test]
+fn common_ancestor_same_as_explicit_ancestor() {
+    let graph = load_salt_sample();
+
+    let common_ancestor = node_pairs(&graph, "node $* node", 0, 1);
+    let explicit = node_pairs(&graph, "node & node & node & #1 >* #2 & #1 >* #3", 1, 2);
+
+    assert_eq!(false, common_ancestor.is_empty());
+    assert_eq!(explicit, common_ancestor);
+    // siblings must be a subset of the nodes with a common ancestor
+    let siblings = node_pairs(&graph, "node $ node", 0, 1);
+    assert_eq!(true, siblings.is_subset(&common_ancestor));
+    assert_eq!(true, siblings.len() < common_ancestor.len());
+}
+
+#[test]
+fn sibling_with_non_existing_component_name() {
+    let graph = load_salt_sample();
+
+    assert_eq!(true, node_pairs(&graph, "node $dep node", 0, 1).is_empty());
+    assert_eq!(
+        true,
+        node_pairs(&graph, "node $dep * node", 0, 1).is_empty()
+    );
+}
+
+#[test]
+fn display_operator_with_name() {
+    let graph = load_salt_sample();
+
+    let sibling = CommonAncestor::new(&graph, "dep", Bound::Included(1)).unwrap();
+    assert_eq!("$dep", sibling.to_string());
+    let common_ancestor = CommonAncestor::new(&graph, "dep", Bound::Unbounded).unwrap();
+    assert_eq!("$dep*", common_ancestor.to_string());
+    let common_ancestor = CommonAncestor::new(&graph, "", Bound::Unbounded).unwrap();
+    assert_eq!("$*", common_ancestor.to_string());
+}
@@ -10,6 +10,7 @@ use crate::errors::Result;
 use crate::graph::{GraphStatistic, GraphStorage, Match};
 use crate::{AnnotationGraph, try_as_boxed_iter};
 use graphannis_core::{
+    annostorage::{numeric_range_contains, parse_numeric_value},
     graph::{ANNIS_NS, DEFAULT_ANNO_KEY, NODE_TYPE_KEY},
     types::{Component, Edge, NodeID},
 };
@@ -22,7 +23,7 @@ use std::sync::Arc;
 struct BaseEdgeOpSpec {
     pub components: Vec<Component<AnnotationComponentType>>,
     pub dist: RangeSpec,
-    pub edge_anno: Option<EdgeAnnoSearchSpec>,
+    pub edge_anno: Vec<EdgeAnnoSearchSpec>,
     pub is_reflexive: bool,
     pub op_str: Option<String>,
     pub inverse_operator_needs_cost_check: bool,
@@ -56,6 +57,16 @@ impl BaseEdgeOp {
     }
 }
 
+/// Describes an edge operator with the given operator string, distance and
+/// edge annotation conditions in AQL syntax.
+fn edge_op_description(op_str: &str, dist: &RangeSpec, edge_anno: &[EdgeAnnoSearchSpec]) -> String {
+    if edge_anno.is_empty() {
+        format!("{}{}", op_str, dist)
+    } else {
+        format!("{}{}[{}]", op_str, dist, edge_anno.iter().join(" & "))
+    }
+}
+
 fn calculate_max_node_estimate(
     db: &AnnotationGraph,
     spec: &BaseEdgeOpSpec,
@@ -115,7 +126,20 @@ impl BinaryOperatorSpec for BaseEdgeOpSpec {
         optional_op.map(|op| BinaryOperator::Index(Box::new(op)))
     }
 
-    fn get_edge_anno_spec(&self) -> Option<EdgeAnnoSearchSpec> {
+    fn canonical_description(&self) -> String {
+        let components = self.components.iter().join(", ");
+        format!(
+            "{} {{{}}}",
+            edge_op_description(
+                self.op_str.as_deref().unwrap_or("?"),
+                &self.dist,
+                &self.edge_anno
+            ),
+            components
+        )
+    }
+
+    fn get_edge_anno_specs(&self) -> Vec<EdgeAnnoSearchSpec> {
         self.edge_anno.clone()
     }
 
@@ -130,14 +154,29 @@ impl BinaryOperatorSpec for BaseEdgeOpSpec {
     }
 }
 
+/// Check that the edge fulfills all edge annotation conditions.
+fn check_edge_annotations(
+    edge_annos: &[EdgeAnnoSearchSpec],
+    gs: &dyn GraphStorage,
+    source: NodeID,
+    target: NodeID,
+) -> Result<bool> {
+    for edge_anno in edge_annos {
+        if !check_edge_annotation(edge_anno, gs, source, target)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
 fn check_edge_annotation(
-    edge_anno: &Option<EdgeAnnoSearchSpec>,
+    edge_anno: &EdgeAnnoSearchSpec,
     gs: &dyn GraphStorage,
     source: NodeID,
     target: NodeID,
 ) -> Result<bool> {
     match edge_anno {
-        Some(EdgeAnnoSearchSpec::ExactValue { ns, name, val }) => {
+        EdgeAnnoSearchSpec::ExactValue { ns, name, val } => {
             for a in gs
                 .get_anno_storage()
                 .get_annotations_for_item(&Edge { source, target })?
@@ -160,7 +199,7 @@ fn check_edge_annotation(
             }
             Ok(false)
         }
-        Some(EdgeAnnoSearchSpec::NotExactValue { ns, name, val }) => {
+        EdgeAnnoSearchSpec::NotExactValue { ns, name, val } => {
             for a in gs
                 .get_anno_storage()
                 .get_annotations_for_item(&Edge { source, target })?
@@ -182,8 +221,14 @@ fn check_edge_annotation(
             }
             Ok(false)
         }
-        Some(EdgeAnnoSearchSpec::RegexValue { ns, name, val }) => {
-            let full_match_pattern = graphannis_core::util::regex_full_match(val);
+        EdgeAnnoSearchSpec::RegexValue {
+            ns,
+            name,
+            val,
+            flags,
+        } => {
+            let full_match_pattern =
+                graphannis_core::util::regex_full_match_with_flags(val, *flags);
             let re = regex::Regex::new(&full_match_pattern);
             if let Ok(re) = re {
                 for a in gs
@@ -209,8 +254,14 @@ fn check_edge_annotation(
             }
             Ok(false)
         }
-        Some(EdgeAnnoSearchSpec::NotRegexValue { ns, name, val }) => {
-            let full_match_pattern = graphannis_core::util::regex_full_match(val);
+        EdgeAnnoSearchSpec::NotRegexValue {
+            ns,
+            name,
+            val,
+            flags,
+        } => {
+            let full_match_pattern =
+                graphannis_core::util::regex_full_match_with_flags(val, *flags);
             let re = regex::Regex::new(&full_match_pattern);
             if let Ok(re) = re {
                 for a in gs
@@ -236,7 +287,30 @@ fn check_edge_annotation(
             }
             Ok(false)
         }
-        None => Ok(true),
+        EdgeAnnoSearchSpec::NumericValue { ns, name, cmp, val } => {
+            if let Some((lower, upper)) = cmp.value_range(val) {
+                for a in gs
+                    .get_anno_storage()
+                    .get_annotations_for_item(&Edge { source, target })?
+                {
+                    if name != &a.key.name {
+                        continue;
+                    }
+                    if let Some(template_ns) = ns
+                        && template_ns != &a.key.ns
+                    {
+                        continue;
+                    }
+                    if let Some(anno_val) = parse_numeric_value(&a.val)
+                        && numeric_range_contains(anno_val, lower, upper)
+                    {
+                        // all checks passed, this edge has the correct annotation
+                        return Ok(true);
+                    }
+                }
+            }
+            Ok(false)
+        }
     }
 }
 
@@ -265,10 +339,10 @@ impl BaseEdgeOp {}
 
 impl std::fmt::Display for BaseEdgeOp {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let anno_frag = if let Some(ref edge_anno) = self.spec.edge_anno {
-            format!("[{}]", edge_anno)
-        } else {
+        let anno_frag = if self.spec.edge_anno.is_empty() {
             String::from("")
+        } else {
+            format!("[{}]", self.spec.edge_anno.iter().join(" & "))
         };
 
         if let Some(ref op_str) = self.spec.op_str {
@@ -292,7 +366,7 @@ impl BinaryOperatorBase for BaseEdgeOp {
                     lhs.node,
                     self.spec.dist.min_dist(),
                     self.spec.dist.max_dist(),
-                )? && check_edge_annotation(
+                )? && check_edge_annotations(
                     &self.spec.edge_anno,
                     e.as_ref(),
                     rhs.node,
@@ -305,7 +379,7 @@ impl BinaryOperatorBase for BaseEdgeOp {
                 rhs.node,
                 self.spec.dist.min_dist(),
                 self.spec.dist.max_dist(),
-            )? && check_edge_annotation(
+            )? && check_edge_annotations(
                 &self.spec.edge_anno,
                 e.as_ref(),
                 lhs.node,
@@ -405,71 +479,31 @@ impl BinaryOperatorBase for BaseEdgeOp {
     }
 
     fn edge_anno_selectivity(&self) -> Result<Option<f64>> {
-        if let Some(ref edge_anno) = self.spec.edge_anno {
-            let mut worst_sel = 0.0;
-            for g in &self.gs {
-                let g: &Arc<dyn GraphStorage> = g;
-                let anno_storage = g.get_anno_storage();
-                let num_of_annos = anno_storage.number_of_annotations()?;
-                if num_of_annos == 0 {
-                    // we won't be able to find anything if there are no
-                    // annotations
-                    return Ok(Some(0.0));
-                } else {
-                    let guessed_count = match edge_anno {
-                        EdgeAnnoSearchSpec::ExactValue { val, ns, name } => {
-                            if let Some(val) = val {
-                                anno_storage.guess_max_count(
-                                    ns.as_ref().map(String::as_str),
-                                    name,
-                                    val,
-                                    val,
-                                )?
-                            } else {
-                                anno_storage.number_of_annotations_by_name(
-                                    ns.as_ref().map(String::as_str),
-                                    name,
-                                )?
-                            }
-                        }
-                        EdgeAnnoSearchSpec::NotExactValue { val, ns, name } => {
-                            let total = anno_storage.number_of_annotations_by_name(
-                                ns.as_ref().map(String::as_str),
-                                name,
-                            )?;
-                            total
-                                - anno_storage.guess_max_count(
-                                    ns.as_ref().map(String::as_str),
-                                    name,
-                                    val,
-                                    val,
-                                )?
-                        }
-                        EdgeAnnoSearchSpec::RegexValue { val, ns, name } => anno_storage
-                            .guess_max_count_regex(ns.as_ref().map(String::as_str), name, val)?,
-                        EdgeAnnoSearchSpec::NotRegexValue { val, ns, name } => {
-                            let total = anno_storage.number_of_annotations_by_name(
-                                ns.as_ref().map(String::as_str),
-                                name,
-                            )?;
-                            total
-                                - anno_storage.guess_max_count_regex(
-                                    ns.as_ref().map(String::as_str),
-                                    name,
-                                    val,
-                                )?
-                        }
-                    };
-                    let g_sel: f64 = (guessed_count as f64) / (num_of_annos as f64);
-                    if g_sel > worst_sel {
-                        worst_sel = g_sel;
-                    }
+        if self.spec.edge_anno.is_empty() {
+            return Ok(Some(1.0));
+        }
+        let mut worst_sel = 0.0;
+        for g in &self.gs {
+            let g: &Arc<dyn GraphStorage> = g;
+            let anno_storage = g.get_anno_storage();
+            let num_of_annos = anno_storage.number_of_annotations()?;
+            if num_of_annos == 0 {
+                // we won't be able to find anything if there are no
+                // annotations
+                return Ok(Some(0.0));
+            } else {
+                // Assume the conditions are independent of each other
+                let mut g_sel: f64 = 1.0;
+                for edge_anno in &self.spec.edge_anno {
+                    let guessed_count = edge_anno.guess_max_count(anno_storage)?;
+                    g_sel *= (guessed_count as f64) / (num_of_annos as f64);
+                }
+                if g_sel > worst_sel {
+                    worst_sel = g_sel;
                 }
             }
-            Ok(Some(worst_sel))
-        } else {
-            Ok(Some(1.0))
         }
+        Ok(Some(worst_sel))
     }
 }
 
@@ -487,7 +521,7 @@ impl BinaryOperatorIndex for BaseEdgeOp {
                     .fuse()
                     .map(move |candidate| {
                         let candidate = candidate?;
-                        let has_annotation = check_edge_annotation(
+                        let has_annotation = check_edge_annotations(
                             &self.spec.edge_anno,
                             self.gs[0].as_ref(),
                             candidate,
@@ -512,7 +546,7 @@ impl BinaryOperatorIndex for BaseEdgeOp {
                     .fuse()
                     .map(move |candidate| {
                         let candidate = candidate?;
-                        let has_annotation = check_edge_annotation(
+                        let has_annotation = check_edge_annotations(
                             &self.spec.edge_anno,
                             self.gs[0].as_ref(),
                             lhs.node,
@@ -550,7 +584,7 @@ impl BinaryOperatorIndex for BaseEdgeOp {
                             .fuse()
                             .map(move |candidate| {
                                 let candidate = candidate?;
-                                let has_annotation = check_edge_annotation(
+                                let has_annotation = check_edge_annotations(
                                     &self.spec.edge_anno,
                                     e.as_ref(),
                                     candidate,
@@ -581,7 +615,7 @@ impl BinaryOperatorIndex for BaseEdgeOp {
                             .fuse()
                             .map(move |candidate| {
                                 let candidate = candidate?;
-                                let has_annotation = check_edge_annotation(
+                                let has_annotation = check_edge_annotations(
                                     &self.spec.edge_anno,
                                     e.as_ref(),
                                     lhs.node,
@@ -618,7 +652,7 @@ impl BinaryOperatorIndex for BaseEdgeOp {
 pub struct DominanceSpec {
     pub name: String,
     pub dist: RangeSpec,
-    pub edge_anno: Option<EdgeAnnoSearchSpec>,
+    pub edge_anno: Vec<EdgeAnnoSearchSpec>,
 }
 
 impl BinaryOperatorSpec for DominanceSpec {
@@ -654,6 +688,15 @@ impl BinaryOperatorSpec for DominanceSpec {
         base.create_operator(db, cost_estimate)
     }
 
+    fn canonical_description(&self) -> String {
+        let op_str = if self.name.is_empty() {
+            String::from(">")
+        } else {
+            format!(">{} ", &self.name)
+        };
+        edge_op_description(&op_str, &self.dist, &self.edge_anno)
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
@@ -669,7 +712,7 @@ impl BinaryOperatorSpec for DominanceSpec {
 pub struct PointingSpec {
     pub name: String,
     pub dist: RangeSpec,
-    pub edge_anno: Option<EdgeAnnoSearchSpec>,
+    pub edge_anno: Vec<EdgeAnnoSearchSpec>,
 }
 
 impl BinaryOperatorSpec for PointingSpec {
@@ -706,6 +749,15 @@ impl BinaryOperatorSpec for PointingSpec {
         base.create_operator(db, cost_estimate)
     }
 
+    fn canonical_description(&self) -> String {
+        let op_str = if self.name.is_empty() {
+            String::from("->")
+        } else {
+            format!("->{} ", self.name)
+        };
+        edge_op_description(&op_str, &self.dist, &self.edge_anno)
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
@@ -758,7 +810,7 @@ impl BinaryOperatorSpec for PartOfSubCorpusSpec {
             op_str: Some(String::from("@")),
             components,
             dist: self.dist.clone(),
-            edge_anno: None,
+            edge_anno: Vec::default(),
             is_reflexive: false,
             inverse_operator_needs_cost_check,
         };
@@ -766,6 +818,10 @@ impl BinaryOperatorSpec for PartOfSubCorpusSpec {
         base.create_operator(db, cost_estimate)
     }
 
+    fn canonical_description(&self) -> String {
+        format!("@{}", self.dist)
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
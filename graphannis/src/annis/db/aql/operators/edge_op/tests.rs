@@ -1,11 +1,12 @@
+use assert_matches::assert_matches;
 use graphannis_core::graph::{
     ANNIS_NS,
     update::{GraphUpdate, UpdateEvent},
 };
-use std::assert_matches;
 
 use crate::{
     AnnotationGraph,
+    annis::db::aql::{execute_query_on_graph, parse},
     annis::{
         db::{
             aql::{
@@ -15,7 +16,7 @@ use crate::{
             example_generator,
             exec::CostEstimate,
         },
-        operator::{BinaryOperatorBase, BinaryOperatorSpec, EstimationType},
+        operator::{BinaryOperatorBase, BinaryOperatorSpec, EdgeAnnoSearchSpec, EstimationType},
     },
 };
 
@@ -128,12 +129,12 @@ fn cycle_component_estimation() {
     // Define an operator that operates on the generated dep component and a realistic cost estimate for LHS and RHS
     let unbound_spec = PointingSpec {
         name: "dep".to_string(),
-        edge_anno: None,
+        edge_anno: Vec::default(),
         dist: RangeSpec::Unbound,
     };
     let direct_spec1 = PointingSpec {
         name: "dep".to_string(),
-        edge_anno: None,
+        edge_anno: Vec::default(),
         dist: RangeSpec::Bound {
             min_dist: 1,
             max_dist: 1,
@@ -141,7 +142,7 @@ fn cycle_component_estimation() {
     };
     let direct_spec2 = PointingSpec {
         name: "dep".to_string(),
-        edge_anno: None,
+        edge_anno: Vec::default(),
         dist: RangeSpec::Bound {
             min_dist: 2,
             max_dist: 2,
@@ -182,3 +183,100 @@ fn cycle_component_estimation() {
         direct_op2.estimation_type().unwrap()
     );
 }
+
+fn create_edge_anno_test_graph() -> AnnotationGraph {
+    let mut update = GraphUpdate::new();
+
+    example_generator::create_corpus_structure_simple(&mut update);
+    example_generator::create_tokens(&mut update, Some("root/doc1"), Some("root/doc1"));
+
+    let edges = [
+        (0, vec![("func", "OA"), ("case", "acc")]),
+        (1, vec![("func", "OA"), ("case", "dat")]),
+        (2, vec![("func", "SB"), ("case", "acc")]),
+        (3, vec![]),
+    ];
+    for (t, annos) in edges {
+        let source_node = format!("root/doc1#tok{t}");
+        let target_node = format!("root/doc1#tok{}", t + 1);
+        update
+            .add_event(UpdateEvent::AddEdge {
+                source_node: source_node.clone(),
+                target_node: target_node.clone(),
+                layer: "default_ns".to_string(),
+                component_type: "Pointing".to_string(),
+                component_name: "dep".to_string(),
+            })
+            .unwrap();
+        for (anno_name, anno_value) in annos {
+            update
+                .add_event(UpdateEvent::AddEdgeLabel {
+                    source_node: source_node.clone(),
+                    target_node: target_node.clone(),
+                    layer: "default_ns".to_string(),
+                    component_type: "Pointing".to_string(),
+                    component_name: "dep".to_string(),
+                    anno_ns: "default_ns".to_string(),
+                    anno_name: anno_name.to_string(),
+                    anno_value: anno_value.to_string(),
+                })
+                .unwrap();
+        }
+    }
+
+    let mut g = AnnotationGraph::with_default_graphstorages(false).unwrap();
+    g.apply_update(&mut update, |_| {}).unwrap();
+    g
+}
+
+/// Test that all edge annotation conditions must be fulfilled by the same edge
+#[test]
+fn multiple_edge_annotations() {
+    let g = create_edge_anno_test_graph();
+
+    let count = |aql: &str| {
+        let query = parse(aql, false).unwrap();
+        execute_query_on_graph(&g, &query, true, None)
+            .unwrap()
+            .count()
+    };
+    assert_eq!(2, count("tok ->dep[func=\"OA\"] tok"));
+    assert_eq!(1, count("tok ->dep[func=\"OA\" & case=\"acc\"] tok"));
+    assert_eq!(1, count("tok ->dep[func=/O./ & case!=\"acc\"] tok"));
+    assert_eq!(0, count("tok ->dep[func=\"SB\" & case=\"dat\"] tok"));
+    assert_eq!(
+        2,
+        count("tok ->dep[func=/.*/ & default_ns:case=\"acc\"] tok")
+    );
+
+    let spec_with_annos = |edge_anno: Vec<EdgeAnnoSearchSpec>| PointingSpec {
+        name: "dep".to_string(),
+        edge_anno,
+        dist: RangeSpec::Bound {
+            min_dist: 1,
+            max_dist: 1,
+        },
+    };
+    let func_oa = EdgeAnnoSearchSpec::ExactValue {
+        ns: None,
+        name: "func".to_string(),
+        val: Some("OA".to_string()),
+    };
+    let case_acc = EdgeAnnoSearchSpec::ExactValue {
+        ns: None,
+        name: "case".to_string(),
+        val: Some("acc".to_string()),
+    };
+    let single_op = spec_with_annos(vec![func_oa.clone()])
+        .create_operator(&g, None)
+        .unwrap();
+    let multiple_op = spec_with_annos(vec![func_oa, case_acc])
+        .create_operator(&g, None)
+        .unwrap();
+
+    // Adding a condition makes the operator more selective
+    let single_sel = single_op.edge_anno_selectivity().unwrap().unwrap();
+    let multiple_sel = multiple_op.edge_anno_selectivity().unwrap().unwrap();
+    assert!(multiple_sel > 0.0);
+    assert!(multiple_sel < single_sel);
+}
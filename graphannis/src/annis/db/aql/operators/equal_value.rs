@@ -12,7 +12,7 @@ use crate::{
 use graphannis_core::annostorage::NodeAnnotationStorage;
 use graphannis_core::{annostorage::ValueSearch, graph::ANNIS_NS, types::Component};
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq)]
 pub struct EqualValueSpec {
@@ -42,6 +42,14 @@ impl BinaryOperatorSpec for EqualValueSpec {
         })))
     }
 
+    fn canonical_description(&self) -> String {
+        if self.negated {
+            String::from("!=")
+        } else {
+            String::from("==")
+        }
+    }
+
     fn is_binding(&self) -> bool {
         false
     }
@@ -81,7 +89,10 @@ impl EqualValue<'_> {
             NodeSearchSpec::ExactValue { .. }
             | NodeSearchSpec::NotExactValue { .. }
             | NodeSearchSpec::RegexValue { .. }
-            | NodeSearchSpec::NotRegexValue { .. } => {
+            | NodeSearchSpec::NotRegexValue { .. }
+            | NodeSearchSpec::NumericValue { .. }
+            | NodeSearchSpec::AnnoNamePattern { .. }
+            | NodeSearchSpec::Documents { .. } => {
                 let val = self.node_annos.get_value_for_item(&m.node, &m.anno_key)?;
                 Ok(val)
             }
@@ -89,7 +100,9 @@ impl EqualValue<'_> {
             | NodeSearchSpec::ExactTokenValue { .. }
             | NodeSearchSpec::NotExactTokenValue { .. }
             | NodeSearchSpec::RegexTokenValue { .. }
-            | NodeSearchSpec::NotRegexTokenValue { .. } => {
+            | NodeSearchSpec::NotRegexTokenValue { .. }
+            | NodeSearchSpec::ExactTextValue { .. }
+            | NodeSearchSpec::RegexTextValue { .. } => {
                 let val = self.node_annos.get_value_for_item(&m.node, &TOKEN_KEY)?;
                 Ok(val)
             }
@@ -97,24 +110,82 @@ impl EqualValue<'_> {
         }
     }
 
+    /// Estimate the probability that the values of both operands are equal,
+    /// using the most common values of the annotations. Returns `None` if there
+    /// are no statistics for the most common values.
+    fn selectivity_from_most_common_values(&self) -> Result<Option<f64>> {
+        let (Some((ns_left, name_left)), Some((ns_right, name_right))) = (
+            EqualValue::anno_def_for_spec(&self.spec_left),
+            EqualValue::anno_def_for_spec(&self.spec_right),
+        ) else {
+            return Ok(None);
+        };
+        let mcv_left = self.node_annos.get_most_common_values(ns_left, name_left)?;
+        let mcv_right = self
+            .node_annos
+            .get_most_common_values(ns_right, name_right)?;
+        let total_left = self
+            .node_annos
+            .number_of_annotations_by_name(ns_left, name_left)?;
+        let total_right = self
+            .node_annos
+            .number_of_annotations_by_name(ns_right, name_right)?;
+        if mcv_left.is_empty() || mcv_right.is_empty() || total_left == 0 || total_right == 0 {
+            return Ok(None);
+        }
+
+        let freq_right: HashMap<&str, f64> = mcv_right
+            .iter()
+            .map(|(val, count)| (val.as_ref(), *count as f64 / total_right as f64))
+            .collect();
+
+        // Sum up the probability for each common value that both operands have this value
+        let mut sel = 0.0;
+        let mut mcv_freq_left = 0.0;
+        for (val, count) in mcv_left.iter() {
+            let freq_left = *count as f64 / total_left as f64;
+            mcv_freq_left += freq_left;
+            if let Some(freq_right) = freq_right.get(val.as_ref()) {
+                sel += freq_left * freq_right;
+            }
+        }
+
+        // The other values of the LHS can't match a RHS value more often than
+        // the least common value of the RHS list.
+        let mcv_freq_right: f64 = freq_right.values().sum();
+        let remaining_left = (1.0 - mcv_freq_left).max(0.0);
+        let remaining_right = (1.0 - mcv_freq_right).max(0.0);
+        if let Some((_, least_common_count)) = mcv_right.last() {
+            let least_common_right = *least_common_count as f64 / total_right as f64;
+            sel += remaining_left * least_common_right.min(remaining_right);
+        }
+
+        Ok(Some(sel.clamp(0.0, 1.0)))
+    }
+
     fn anno_def_for_spec(spec: &NodeSearchSpec) -> Option<(Option<&str>, &str)> {
         match spec {
             NodeSearchSpec::ExactValue { ns, name, .. }
             | NodeSearchSpec::NotExactValue { ns, name, .. }
             | NodeSearchSpec::RegexValue { ns, name, .. }
-            | NodeSearchSpec::NotRegexValue { ns, name, .. } => {
+            | NodeSearchSpec::NotRegexValue { ns, name, .. }
+            | NodeSearchSpec::NumericValue { ns, name, .. } => {
                 Some((ns.as_ref().map(String::as_str), name))
             }
             NodeSearchSpec::AnyToken
             | NodeSearchSpec::ExactTokenValue { .. }
             | NodeSearchSpec::NotExactTokenValue { .. }
             | NodeSearchSpec::RegexTokenValue { .. }
-            | NodeSearchSpec::NotRegexTokenValue { .. } => {
+            | NodeSearchSpec::NotRegexTokenValue { .. }
+            | NodeSearchSpec::ExactTextValue { .. }
+            | NodeSearchSpec::RegexTextValue { .. } => {
                 let ns = Some(ANNIS_NS);
                 let name = TOK;
                 Some((ns, name))
             }
-            NodeSearchSpec::AnyNode => None,
+            NodeSearchSpec::AnyNode
+            | NodeSearchSpec::AnnoNamePattern { .. }
+            | NodeSearchSpec::Documents { .. } => None,
         }
     }
 }
@@ -137,6 +208,13 @@ impl BinaryOperatorBase for EqualValue<'_> {
     }
 
     fn estimation_type(&self) -> Result<EstimationType> {
+        if let Some(sel) = self.selectivity_from_most_common_values()? {
+            if self.negated {
+                return Ok(EstimationType::Selectivity(1.0 - sel));
+            } else {
+                return Ok(EstimationType::Selectivity(sel));
+            }
+        }
         if let Some((ns, name)) = EqualValue::anno_def_for_spec(&self.spec_left)
             && let Some(most_frequent_value_left) =
                 self.node_annos.guess_most_frequent_value(ns, name)?
@@ -161,6 +239,24 @@ impl BinaryOperatorBase for EqualValue<'_> {
         Ok(EstimationType::Selectivity(0.5))
     }
 
+    fn equi_join_kind(&self) -> Option<EquiJoinKind> {
+        if self.negated {
+            None
+        } else {
+            Some(EquiJoinKind::SameValue)
+        }
+    }
+
+    fn equi_join_key(&self, m: &Match, lhs: bool) -> Result<Option<EquiJoinKey>> {
+        let spec = if lhs {
+            &self.spec_left
+        } else {
+            &self.spec_right
+        };
+        let val = self.value_for_match(m, spec)?;
+        Ok(val.map(|val| EquiJoinKey::Value(val.into_owned())))
+    }
+
     fn get_inverse_operator<'b>(
         &self,
         graph: &'b AnnotationGraph,
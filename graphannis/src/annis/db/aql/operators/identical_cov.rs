@@ -66,6 +66,10 @@ impl BinaryOperatorSpec for IdenticalCoverageSpec {
         optional_op.map(|op| BinaryOperator::Index(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        String::from("_=_")
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
@@ -27,6 +27,10 @@ impl BinaryOperatorSpec for IdenticalNodeSpec {
         Ok(BinaryOperator::Index(Box::new(IdenticalNode {})))
     }
 
+    fn canonical_description(&self) -> String {
+        String::from("_ident_")
+    }
+
     #[cfg(test)]
     fn into_any(self: std::sync::Arc<Self>) -> std::sync::Arc<dyn std::any::Any> {
         self
@@ -56,6 +60,14 @@ impl BinaryOperatorBase for IdenticalNode {
         Ok(EstimationType::Min)
     }
 
+    fn equi_join_kind(&self) -> Option<EquiJoinKind> {
+        Some(EquiJoinKind::SameNode)
+    }
+
+    fn equi_join_key(&self, m: &Match, _lhs: bool) -> Result<Option<EquiJoinKey>> {
+        Ok(Some(EquiJoinKey::Node(m.node)))
+    }
+
     fn get_inverse_operator<'a>(
         &self,
         _graph: &'a AnnotationGraph,
@@ -58,6 +58,10 @@ impl BinaryOperatorSpec for InclusionSpec {
         optional_op.map(|op| BinaryOperator::Index(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        String::from("_i_")
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
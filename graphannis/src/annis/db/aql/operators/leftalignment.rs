@@ -37,6 +37,10 @@ impl BinaryOperatorSpec for LeftAlignmentSpec {
         optional_op.map(|op| BinaryOperator::Index(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        String::from("_l_")
+    }
+
     #[cfg(test)]
     fn into_any(self: std::sync::Arc<Self>) -> std::sync::Arc<dyn std::any::Any> {
         self
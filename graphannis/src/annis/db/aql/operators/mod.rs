@@ -38,6 +38,7 @@ impl fmt::Display for RangeSpec {
 }
 
 mod arity;
+mod common_ancestor;
 mod edge_op;
 mod equal_value;
 mod identical_cov;
@@ -50,8 +51,12 @@ mod non_existing;
 mod overlap;
 mod precedence;
 mod rightalignment;
+mod root;
+mod text_end;
+mod tokenarity;
 
 pub use self::arity::AritySpec;
+pub use self::common_ancestor::{CommonAncestorSpec, SiblingSpec};
 pub use self::edge_op::{DominanceSpec, PartOfSubCorpusSpec, PointingSpec};
 pub use self::equal_value::EqualValueSpec;
 pub use self::identical_cov::IdenticalCoverageSpec;
@@ -64,3 +69,6 @@ pub use self::non_existing::NonExistingUnaryOperatorSpec;
 pub use self::overlap::OverlapSpec;
 pub use self::precedence::PrecedenceSpec;
 pub use self::rightalignment::RightAlignmentSpec;
+pub use self::root::RootSpec;
+pub use self::text_end::TextEndSpec;
+pub use self::tokenarity::TokenAritySpec;
@@ -68,6 +68,10 @@ impl BinaryOperatorSpec for NearSpec {
         optional_op.map(|op| BinaryOperator::Index(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        format!("^{}", self)
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
@@ -39,6 +39,10 @@ impl BinaryOperatorSpec for NegatedOpSpec {
         Ok(BinaryOperator::Base(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        format!("!{}", self.negated_op.canonical_description())
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
@@ -121,6 +121,22 @@ impl UnaryOperatorSpec for NonExistingUnaryOperatorSpec {
 
         Ok(negated_op)
     }
+
+    fn canonical_description(&self) -> String {
+        if self.target_left {
+            format!(
+                "!({} {} x)",
+                self.target.canonical_description(),
+                self.op.canonical_description()
+            )
+        } else {
+            format!(
+                "!(x {} {})",
+                self.op.canonical_description(),
+                self.target.canonical_description()
+            )
+        }
+    }
 }
 
 struct NonExistingUnaryOperatorIndex<'a> {
@@ -59,6 +59,14 @@ impl BinaryOperatorSpec for OverlapSpec {
         optional_op.map(|op| BinaryOperator::Index(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        if self.reflexive {
+            String::from("_o_reflexive_")
+        } else {
+            String::from("_o_")
+        }
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
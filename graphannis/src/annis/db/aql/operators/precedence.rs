@@ -78,6 +78,10 @@ impl BinaryOperatorSpec for PrecedenceSpec {
         Ok(BinaryOperator::Index(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        format!(".{}", self)
+    }
+
     #[cfg(test)]
     fn into_any(self: Arc<Self>) -> Arc<dyn std::any::Any> {
         self
@@ -39,6 +39,10 @@ impl BinaryOperatorSpec for RightAlignmentSpec {
         Ok(BinaryOperator::Index(Box::new(op)))
     }
 
+    fn canonical_description(&self) -> String {
+        String::from("_r_")
+    }
+
     #[cfg(test)]
     fn into_any(self: std::sync::Arc<Self>) -> std::sync::Arc<dyn std::any::Any> {
         self
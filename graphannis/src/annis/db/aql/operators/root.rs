@@ -0,0 +1,105 @@
+use crate::annis::operator::EstimationType;
+use crate::{
+    AnnotationGraph,
+    annis::{
+        db::aql::model::AnnotationComponentType,
+        operator::{UnaryOperator, UnaryOperatorSpec},
+    },
+    errors::Result,
+    graph::{GraphStorage, Match},
+};
+use graphannis_core::{graph::NODE_TYPE_KEY, types::Component};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Specification of the `:root` operator, which filters nodes that have no
+/// ingoing edge in any dominance or pointing component.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RootSpec;
+
+impl UnaryOperatorSpec for RootSpec {
+    fn necessary_components(
+        &self,
+        db: &AnnotationGraph,
+    ) -> HashSet<Component<AnnotationComponentType>> {
+        let mut result = HashSet::default();
+        result.extend(db.get_all_components(Some(AnnotationComponentType::Dominance), None));
+        result.extend(db.get_all_components(Some(AnnotationComponentType::Pointing), None));
+        result
+    }
+
+    fn create_operator(&self, db: &AnnotationGraph) -> Result<Box<dyn UnaryOperator>> {
+        // collect all relevant graph storages
+        let mut graphstorages = Vec::default();
+
+        for component in db.get_all_components(Some(AnnotationComponentType::Dominance), None) {
+            if let Some(gs) = db.get_graphstorage(&component) {
+                graphstorages.push(gs);
+            }
+        }
+        for component in db.get_all_components(Some(AnnotationComponentType::Pointing), None) {
+            if let Some(gs) = db.get_graphstorage(&component) {
+                graphstorages.push(gs);
+            }
+        }
+
+        let max_nodes_estimate = db.get_node_annos().guess_max_count(
+            Some(&NODE_TYPE_KEY.ns),
+            &NODE_TYPE_KEY.name,
+            "node",
+            "node",
+        )?;
+
+        Ok(Box::new(RootOperator {
+            graphstorages,
+            max_nodes_estimate,
+        }))
+    }
+
+    fn canonical_description(&self) -> String {
+        String::from(":root")
+    }
+}
+
+struct RootOperator {
+    graphstorages: Vec<Arc<dyn GraphStorage>>,
+    max_nodes_estimate: usize,
+}
+
+impl std::fmt::Display for RootOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, ":root")
+    }
+}
+
+impl UnaryOperator for RootOperator {
+    fn filter_match(&self, m: &Match) -> Result<bool> {
+        for gs in self.graphstorages.iter() {
+            if gs.has_ingoing_edges(m.node)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn estimation_type(&self) -> EstimationType {
+        if self.max_nodes_estimate == 0 {
+            return EstimationType::Selectivity(1.0);
+        }
+        // Each non-root node of a component has an ingoing edge. Since the
+        // components might share nodes, the sum of all non-root nodes is only
+        // an upper bound for the nodes that are excluded.
+        let mut non_root_nodes = 0;
+        for gs in self.graphstorages.iter() {
+            if let Some(stats) = gs.get_statistics() {
+                non_root_nodes += stats.nodes - stats.root_nodes;
+            } else {
+                // use default
+                return EstimationType::Selectivity(0.1);
+            }
+        }
+        let sel = 1.0 - (non_root_nodes as f64 / self.max_nodes_estimate as f64);
+        // Avoid an estimation of zero, because the components are not disjunct
+        EstimationType::Selectivity(sel.clamp(0.01, 1.0))
+    }
+}
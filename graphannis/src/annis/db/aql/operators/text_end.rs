@@ -0,0 +1,112 @@
+use crate::AnnotationGraph;
+use crate::annis::db::aql::model::AnnotationComponentType;
+use crate::annis::db::exec::CostEstimate;
+use crate::annis::db::exec::textsearch::TextMatcher;
+use crate::annis::db::exec::tokensearch::AnyTokenSearch;
+use crate::{annis::operator::*, errors::Result, graph::Match};
+use graphannis_core::annostorage::NodeAnnotationStorage;
+use graphannis_core::graph::DEFAULT_ANNO_KEY;
+use graphannis_core::types::Component;
+use graphannis_core::util::RegexFlags;
+use std::collections::HashSet;
+
+/// Connects the first token of a text search (e.g. `text="in the middle"`)
+/// with the last token of the matching text.
+///
+/// This operator is not part of the query language, but added automatically
+/// for each text search when a query is parsed.
+#[derive(Debug, Clone, PartialOrd, Ord, Hash, PartialEq, Eq)]
+pub struct TextEndSpec {
+    pub val: String,
+    pub regex_flags: Option<RegexFlags>,
+}
+
+impl BinaryOperatorSpec for TextEndSpec {
+    fn necessary_components(
+        &self,
+        db: &AnnotationGraph,
+    ) -> HashSet<Component<AnnotationComponentType>> {
+        AnyTokenSearch::necessary_components(db)
+    }
+
+    fn create_operator<'a>(
+        &self,
+        db: &'a AnnotationGraph,
+        _cost_estimate: Option<(&CostEstimate, &CostEstimate)>,
+    ) -> Result<BinaryOperator<'a>> {
+        let matcher = TextMatcher::new(db, &self.val, self.regex_flags, None)?;
+        Ok(BinaryOperator::Index(Box::new(TextEnd {
+            matcher,
+            node_annos: db.get_node_annos(),
+            spec: self.clone(),
+        })))
+    }
+
+    fn lhs_is_component_source(&self) -> bool {
+        false
+    }
+
+    fn canonical_description(&self) -> String {
+        if let Some(flags) = self.regex_flags {
+            format!("_text_end_/{}/{}", self.val, flags)
+        } else {
+            format!("_text_end_\"{}\"", self.val)
+        }
+    }
+
+    #[cfg(test)]
+    fn into_any(self: std::sync::Arc<Self>) -> std::sync::Arc<dyn std::any::Any> {
+        self
+    }
+
+    #[cfg(test)]
+    fn any_ref(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+pub struct TextEnd<'a> {
+    matcher: TextMatcher,
+    node_annos: &'a dyn NodeAnnotationStorage,
+    spec: TextEndSpec,
+}
+
+impl std::fmt::Display for TextEnd<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.spec.canonical_description())
+    }
+}
+
+impl BinaryOperatorBase for TextEnd<'_> {
+    fn filter_match(&self, lhs: &Match, rhs: &Match) -> Result<bool> {
+        let mut found = false;
+        self.matcher
+            .for_each_match_end(lhs.node, self.node_annos, |end| {
+                found = end == rhs.node;
+                !found
+            })?;
+        Ok(found)
+    }
+
+    fn estimation_type(&self) -> Result<EstimationType> {
+        Ok(EstimationType::Min)
+    }
+}
+
+impl BinaryOperatorIndex for TextEnd<'_> {
+    fn retrieve_matches(&self, lhs: &Match) -> Box<dyn Iterator<Item = Result<Match>>> {
+        match self.matcher.match_ends(lhs.node, self.node_annos) {
+            Ok(ends) => Box::new(ends.into_iter().map(|end| {
+                Ok(Match {
+                    node: end,
+                    anno_key: DEFAULT_ANNO_KEY.clone(),
+                })
+            })),
+            Err(e) => Box::new(std::iter::once(Err(e))),
+        }
+    }
+
+    fn as_binary_operator(&self) -> &dyn BinaryOperatorBase {
+        self
+    }
+}
@@ -0,0 +1,139 @@
+use super::RangeSpec;
+use crate::annis::db::token_helper::{self, TokenHelper};
+use crate::annis::errors::GraphAnnisError;
+use crate::annis::operator::EstimationType;
+use crate::{
+    AnnotationGraph,
+    annis::{
+        db::aql::model::AnnotationComponentType,
+        operator::{UnaryOperator, UnaryOperatorSpec},
+    },
+    errors::Result,
+    graph::{GraphStorage, Match},
+};
+use graphannis_core::{graph::ANNIS_NS, types::Component};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Specification of the `:tokenarity` operator, which filters nodes by the
+/// number of tokens they cover.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenAritySpec {
+    pub tokens: RangeSpec,
+}
+
+lazy_static! {
+    static ref COMPONENT_ORDER: Component<AnnotationComponentType> = {
+        Component::new(
+            AnnotationComponentType::Ordering,
+            ANNIS_NS.into(),
+            "".into(),
+        )
+    };
+}
+
+impl UnaryOperatorSpec for TokenAritySpec {
+    fn necessary_components(
+        &self,
+        db: &AnnotationGraph,
+    ) -> HashSet<Component<AnnotationComponentType>> {
+        let mut result = HashSet::default();
+        result.insert(COMPONENT_ORDER.clone());
+        result.extend(token_helper::necessary_components(db));
+        result
+    }
+
+    fn create_operator<'a>(
+        &'a self,
+        db: &'a AnnotationGraph,
+    ) -> Result<Box<dyn UnaryOperator + 'a>> {
+        let gs_order = db.get_graphstorage(&COMPONENT_ORDER).ok_or_else(|| {
+            GraphAnnisError::ImpossibleSearch(
+                "Ordering component is missing (needed by :tokenarity operator)".to_string(),
+            )
+        })?;
+
+        Ok(Box::new(TokenArityOperator {
+            gs_order,
+            tok_helper: TokenHelper::new(db)?,
+            allowed_range: self.tokens.clone(),
+        }))
+    }
+
+    fn canonical_description(&self) -> String {
+        format!(":tokenarity={}", self.tokens)
+    }
+}
+
+struct TokenArityOperator<'a> {
+    gs_order: Arc<dyn GraphStorage>,
+    tok_helper: TokenHelper<'a>,
+    allowed_range: RangeSpec,
+}
+
+impl std::fmt::Display for TokenArityOperator<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, ":tokenarity={}", self.allowed_range)
+    }
+}
+
+impl UnaryOperator for TokenArityOperator<'_> {
+    fn filter_match(&self, m: &Match) -> Result<bool> {
+        let (left, right) = self.tok_helper.left_right_token_for(m.node)?;
+        let num_tokens = if let (Some(left), Some(right)) = (left, right) {
+            if let Some(dist) = self.gs_order.distance(left, right)? {
+                dist + 1
+            } else {
+                // The tokens are not connected in the ordering
+                return Ok(false);
+            }
+        } else {
+            // Nodes without any covered token (e.g. corpus nodes) are never included
+            return Ok(false);
+        };
+
+        if num_tokens >= self.allowed_range.min_dist() {
+            match self.allowed_range.max_dist() {
+                std::ops::Bound::Unbounded => Ok(true),
+                std::ops::Bound::Included(max_dist) => Ok(num_tokens <= max_dist),
+                std::ops::Bound::Excluded(max_dist) => Ok(num_tokens < max_dist),
+            }
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn estimation_type(&self) -> EstimationType {
+        if let RangeSpec::Bound { min_dist, max_dist } = self.allowed_range {
+            // Each non-token node has a coverage edge for every token it
+            // covers, so the maximum fan-out of the coverage components is
+            // the maximum number of covered tokens.
+            let mut max_covered = 1;
+            for gs in self.tok_helper.get_gs_coverage() {
+                if let Some(stats) = gs.get_statistics() {
+                    max_covered = max_covered.max(stats.max_fan_out);
+                } else {
+                    // use default
+                    return EstimationType::Selectivity(0.1);
+                }
+            }
+
+            if min_dist > max_covered {
+                // no node covers that many token
+                return EstimationType::Selectivity(0.0);
+            }
+
+            // clip to asssumed maximum
+            let max_dist = std::cmp::min(max_dist, max_covered);
+            let min_dist = std::cmp::min(min_dist, max_dist);
+
+            // Without a histogram of the span lengths, assume all lengths are equally likely
+            let spec_range_len = max_dist - min_dist + 1;
+            let sel = spec_range_len as f64 / (max_covered as f64);
+            EstimationType::Selectivity(sel.min(1.0))
+        } else {
+            // this range spec allows any number of covered tokens
+            EstimationType::Selectivity(1.0)
+        }
+    }
+}
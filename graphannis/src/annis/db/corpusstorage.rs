@@ -10,16 +10,19 @@ use crate::annis::db::sort_matches::CollationType;
 use crate::annis::db::token_helper;
 use crate::annis::db::token_helper::TokenHelper;
 use crate::annis::errors::*;
+use crate::annis::types::{ApproximateCount, CorpusSizeInfo, CountExtra, ResultCacheStatistics};
 use crate::annis::types::{
     CorpusConfiguration, CorpusSizeUnit, FrequencyTable, FrequencyTableRow,
+    NormalizedFrequencyTable, NormalizedFrequencyTableRow, QueryAnalysis,
     QueryAttributeDescription,
 };
-use crate::annis::types::{CorpusSizeInfo, CountExtra};
-use crate::annis::util::TimeoutCheck;
 use crate::annis::util::quicksort;
+use crate::annis::util::sortablecontainer::{SortableContainer, SpillingContainer};
+use crate::annis::util::{CancellationToken, TimeoutCheck};
 use crate::{AnnotationGraph, graph::Match};
 use fmt::Display;
 use fs2::FileExt;
+use graphannis_core::annostorage::ondisk::CompressionOptions;
 use graphannis_core::annostorage::symboltable::SymbolTable;
 use graphannis_core::annostorage::{
     NodeAnnotationStorage, match_group_resolve_symbol_ids, match_group_with_symbol_ids,
@@ -28,16 +31,21 @@ use graphannis_core::errors::Result as CoreResult;
 use graphannis_core::{
     annostorage::{MatchGroup, ValueSearch},
     graph::{
-        ANNIS_NS, NODE_NAME, NODE_NAME_KEY, NODE_TYPE, storage::GraphStatistic, update::GraphUpdate,
+        ANNIS_NS, NODE_NAME, NODE_NAME_KEY, NODE_TYPE,
+        integrity::{self, IntegrityProblem, IntegrityReport},
+        storage::{GraphStatistic, GraphStorage},
+        update::GraphUpdate,
     },
     types::{AnnoKey, Annotation, Component, NodeID},
 };
 use itertools::Itertools;
 use linked_hash_map::LinkedHashMap;
 use memory_stats::memory_stats;
-use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use percent_encoding::{
+    AsciiSet, CONTROLS, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode,
+};
 use rand::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs::File;
 use std::fs::OpenOptions;
@@ -57,13 +65,15 @@ use std::{
     io::{BufReader, Write},
 };
 
-use aql::model::AnnotationComponentType;
+use aql::model::{AnnotationComponentType, DOCUMENT_KEY, TOKEN_KEY};
 
+use self::result_cache::{CachedResult, ResultCache, ResultCacheKey, ResultKind};
 use self::subgraph::new_subgraph_iterator;
 
 use super::aql::model::AQLGlobalStatistics;
 use super::sort_matches::SortCache;
 
+mod result_cache;
 mod subgraph;
 
 #[cfg(test)]
@@ -172,7 +182,7 @@ impl fmt::Display for CorpusInfo {
 }
 
 /// Defines the order of results of a `find` query.
-#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, Default)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, Default)]
 #[repr(C)]
 pub enum ResultOrder {
     /// Order results by their document name and the the text position of the match.
@@ -187,6 +197,95 @@ pub enum ResultOrder {
     NotSorted,
 }
 
+/// Position after the last match of a page of `find` results.
+///
+/// A cursor allows to continue with the next page without sorting the matches
+/// of all previous pages again. It can be converted to an opaque string with
+/// `to_string()` and parsed again with `parse()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FindCursor {
+    corpus_name: String,
+    order: ResultOrder,
+    /// Node name and annotation key for each node of the last match.
+    last_match: Vec<(String, AnnoKey)>,
+}
+
+impl FindCursor {
+    /// Resolve the node names of the last match in the given graph.
+    fn resolve(&self, db: &AnnotationGraph) -> Result<MatchGroup> {
+        let mut result = MatchGroup::new();
+        for (node_name, anno_key) in &self.last_match {
+            let node = db
+                .get_node_annos()
+                .get_node_id_from_name(node_name)?
+                .ok_or_else(|| {
+                    GraphAnnisError::InvalidFindCursor(format!("node {node_name} not found"))
+                })?;
+            result.push(Match {
+                node,
+                anno_key: Arc::new(anno_key.clone()),
+            });
+        }
+        Ok(result)
+    }
+}
+
+impl Display for FindCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let order = match self.order {
+            ResultOrder::Inverted => "i",
+            _ => "n",
+        };
+        let mut parts = vec![self.corpus_name.as_str(), order];
+        for (node_name, anno_key) in &self.last_match {
+            parts.push(&anno_key.ns);
+            parts.push(&anno_key.name);
+            parts.push(node_name);
+        }
+        let encoded = parts
+            .into_iter()
+            .map(|p| utf8_percent_encode(p, NON_ALPHANUMERIC))
+            .join(".");
+        write!(f, "{}", encoded)
+    }
+}
+
+impl FromStr for FindCursor {
+    type Err = GraphAnnisError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || GraphAnnisError::InvalidFindCursor(s.to_string());
+
+        let parts: Vec<Cow<str>> = s
+            .split('.')
+            .map(|p| percent_decode_str(p).decode_utf8().map_err(|_| invalid()))
+            .collect::<Result<_>>()?;
+        if parts.len() < 5 || !(parts.len() - 2).is_multiple_of(3) {
+            return Err(invalid());
+        }
+        let order = match parts[1].as_ref() {
+            "n" => ResultOrder::Normal,
+            "i" => ResultOrder::Inverted,
+            _ => return Err(invalid()),
+        };
+        let last_match = parts[2..]
+            .chunks(3)
+            .map(|m| {
+                let anno_key = AnnoKey {
+                    ns: m[0].as_ref().into(),
+                    name: m[1].as_ref().into(),
+                };
+                (m[2].to_string(), anno_key)
+            })
+            .collect();
+        Ok(FindCursor {
+            corpus_name: parts[0].to_string(),
+            order,
+            last_match,
+        })
+    }
+}
+
 struct PreparationResult {
     query: Disjunction,
     db_entry: Arc<RwLock<CacheEntry>>,
@@ -222,6 +321,48 @@ impl FromStr for FrequencyDefEntry {
     }
 }
 
+/// Criterion by which the matches of a grouped count query are grouped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GroupByEntry {
+    /// Group by the document that contains the first node of a match. The
+    /// value is the node name of the document.
+    Document,
+    /// Group by an annotation value of a query node, like in a frequency query.
+    Annotation(FrequencyDefEntry),
+    /// Group by a metadata annotation (e.g. `genre`) of the first node of a
+    /// match. The value is taken from the nearest document or (sub-) corpus
+    /// that contains the node and has this annotation.
+    Meta {
+        /// The namespace of the metadata annotation.
+        #[serde(default)]
+        ns: Option<String>,
+        /// The name of the metadata annotation.
+        name: String,
+    },
+}
+
+impl FromStr for GroupByEntry {
+    type Err = GraphAnnisError;
+
+    /// Parses `doc` as [`GroupByEntry::Document`], `meta:<annotation name>` as
+    /// [`GroupByEntry::Meta`] and all other values as frequency definition,
+    /// e.g. `1:pos`.
+    fn from_str(s: &str) -> std::result::Result<GroupByEntry, Self::Err> {
+        if s == "doc" {
+            Ok(GroupByEntry::Document)
+        } else if let Some(qname) = s.strip_prefix("meta:") {
+            let anno_key = graphannis_core::util::split_qname(qname);
+            Ok(GroupByEntry::Meta {
+                ns: anno_key.0.map(String::from),
+                name: String::from(anno_key.1),
+            })
+        } else {
+            Ok(GroupByEntry::Annotation(s.parse()?))
+        }
+    }
+}
+
 /// An enum over all supported query languages of graphANNIS.
 ///
 /// Currently, only the ANNIS Query Language (AQL) and its variants are supported, but this enum allows us to add a support for older query language versions
@@ -274,10 +415,22 @@ pub enum CacheStrategy {
 }
 
 #[derive(Clone, Copy)]
-struct FindArguments {
+struct FindArguments<'a> {
     offset: usize,
     limit: Option<usize>,
     order: ResultOrder,
+    /// Only include the matches after this one
+    cursor: Option<&'a FindCursor>,
+}
+
+/// Result of executing a find query on a single corpus.
+struct SingleCorpusFindResult {
+    match_count: usize,
+    skipped: usize,
+    /// True if the callback requested to stop
+    stopped: bool,
+    /// Node name and annotation key for each node of the last match given to the callback
+    last_match: Option<Vec<(String, AnnoKey)>>,
 }
 
 impl Display for CacheStrategy {
@@ -357,6 +510,12 @@ const QUIRKS_SALT_URI_ENCODE_SET: &AsciiSet = &CONTROLS
 
 const DB_LOCK_FILE_NAME: &str = "db.lock";
 
+/// Separates the corpus name from the snapshot name, e.g. in `pcc2@v2`.
+pub const SNAPSHOT_SEPARATOR: char = '@';
+
+const SNAPSHOTS_FOLDER: &str = "snapshots";
+const TEMPORARY_SNAPSHOT_PREFIX: &str = "temporary-graphannis-snapshot";
+
 /// Common arguments to all search queries.
 #[derive(Debug, Clone)]
 pub struct SearchQuery<'a, S: AsRef<str>> {
@@ -368,6 +527,9 @@ pub struct SearchQuery<'a, S: AsRef<str>> {
     pub query_language: QueryLanguage,
     /// If not `None`, the query will be aborted after running for the given amount of time.
     pub timeout: Option<Duration>,
+    /// If not `None`, the query will be aborted with a
+    /// [`GraphAnnisError::Cancelled`] error as soon as the token is cancelled.
+    pub cancellation: Option<CancellationToken>,
 }
 
 /// A thread-safe API for managing corpora stored in a common location on the file system.
@@ -382,6 +544,11 @@ pub struct CorpusStorage {
     corpus_cache: RwLock<LinkedHashMap<String, Arc<RwLock<CacheEntry>>>>,
     query_config: aql::Config,
     active_background_workers: Arc<(Mutex<usize>, Condvar)>,
+    result_cache: Mutex<ResultCache>,
+    /// Maximum number of bytes the intermediate results of a single query can use.
+    query_memory_limit: Mutex<Option<usize>>,
+    /// Counts the modifications of each corpus, so that cached query results of older versions are not used.
+    corpus_modifications: Mutex<HashMap<String, u64>>,
 }
 
 fn init_locale() {
@@ -394,6 +561,137 @@ fn init_locale() {
     }
 }
 
+/// Returns the fully qualified annotation key if the namespace is given or
+/// all node annotation keys with this name otherwise.
+fn get_matching_anno_keys(
+    db: &AnnotationGraph,
+    ns: &Option<String>,
+    name: &str,
+) -> Result<Vec<AnnoKey>> {
+    if let Some(ns) = ns {
+        Ok(vec![AnnoKey {
+            ns: ns.into(),
+            name: name.into(),
+        }])
+    } else {
+        Ok(db.get_node_annos().get_qnames(name)?)
+    }
+}
+
+/// Get the matching annotation keys for each frequency definition entry,
+/// together with the position of the referenced node in the match.
+fn get_frequency_anno_keys(
+    db: &AnnotationGraph,
+    query: &Disjunction,
+    definition: &[FrequencyDefEntry],
+) -> Result<Vec<(usize, Vec<AnnoKey>)>> {
+    let mut annokeys: Vec<(usize, Vec<AnnoKey>)> = Vec::default();
+    for def in definition.iter() {
+        if let Some(node_ref) = query.get_variable_pos(&def.node_ref) {
+            annokeys.push((node_ref, get_matching_anno_keys(db, &def.ns, &def.name)?));
+        }
+    }
+    Ok(annokeys)
+}
+
+/// Estimated number of bytes that are needed for a new row of a frequency table.
+fn estimated_frequency_tuple_size(tuple: &[String]) -> usize {
+    let values_size: usize = tuple
+        .iter()
+        .map(|v| std::mem::size_of::<String>() + v.len())
+        .sum();
+    std::mem::size_of::<Vec<String>>() + values_size + std::mem::size_of::<usize>()
+}
+
+/// Extract the annotation values (by their keys) from the nodes of a match.
+fn get_frequency_tuple(
+    db: &AnnotationGraph,
+    annokeys: &[(usize, Vec<AnnoKey>)],
+    mgroup: &MatchGroup,
+) -> Result<Vec<String>> {
+    let mut tuple: Vec<String> = Vec::with_capacity(annokeys.len());
+    for (node_ref, anno_keys) in annokeys {
+        let mut tuple_val: String = String::default();
+        if *node_ref < mgroup.len() {
+            let m: &Match = &mgroup[*node_ref];
+            for k in anno_keys.iter() {
+                if let Some(val) = db.get_node_annos().get_value_for_item(&m.node, k)? {
+                    tuple_val = val.to_string();
+                }
+            }
+        }
+        tuple.push(tuple_val);
+    }
+    Ok(tuple)
+}
+
+/// Returns the nearest node with an `annis:doc` annotation that contains the
+/// given node.
+fn find_document_for_node(
+    db: &AnnotationGraph,
+    part_of_gs: &[&dyn GraphStorage],
+    node: NodeID,
+) -> Result<Option<NodeID>> {
+    for gs in part_of_gs {
+        for ancestor in gs.find_connected(node, 1, std::ops::Bound::Unbounded) {
+            let ancestor = ancestor?;
+            if db
+                .get_node_annos()
+                .has_value_for_item(&ancestor, &DOCUMENT_KEY)?
+            {
+                return Ok(Some(ancestor));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Calculates Juilland's D for the number of matches in each document. Since
+/// the documents can have different sizes, the relative frequency in each
+/// document is used. Returns `None` if there are less than two documents or
+/// no matches at all.
+fn juilland_d(document_sizes: &[u64], document_count: &FxHashMap<usize, usize>) -> Option<f64> {
+    let n = document_sizes.len();
+    if n < 2 {
+        return None;
+    }
+    let relative_frequencies: Vec<f64> = document_sizes
+        .iter()
+        .enumerate()
+        .map(|(idx, size)| {
+            let count = document_count.get(&idx).copied().unwrap_or_default();
+            (count as f64) / (*size as f64)
+        })
+        .collect();
+    let mean = relative_frequencies.iter().sum::<f64>() / (n as f64);
+    if mean <= 0.0 {
+        return None;
+    }
+    let variance = relative_frequencies
+        .iter()
+        .map(|f| (f - mean).powi(2))
+        .sum::<f64>()
+        / (n as f64);
+    let coefficient_of_variation = variance.sqrt() / mean;
+    Some(1.0 - coefficient_of_variation / ((n - 1) as f64).sqrt())
+}
+
+/// Creates a frequency table from the counts of the value tuples, sorted from
+/// the largest to the smallest count.
+fn create_frequency_table(
+    tuple_frequency: FxHashMap<Vec<String>, usize>,
+) -> FrequencyTable<String> {
+    let mut result: FrequencyTable<String> = FrequencyTable::default();
+    for (tuple, count) in tuple_frequency {
+        result.push(FrequencyTableRow {
+            values: tuple,
+            count,
+        });
+    }
+    result.sort_by(|a, b| a.count.cmp(&b.count).reverse());
+    result
+}
+
 /// Creates a new vector with the capacity to hold the expected number of items, but make sure the
 /// capacity is memory aligned with the page size (only full pages are allocated).
 fn new_vector_with_memory_aligned_capacity<T>(expected_len: usize) -> Vec<T> {
@@ -421,7 +719,10 @@ impl CorpusStorage {
     ) -> Result<CorpusStorage> {
         init_locale();
 
-        let query_config = aql::Config { use_parallel_joins };
+        let query_config = aql::Config {
+            use_parallel_joins,
+            analyze: false,
+        };
 
         #[allow(clippy::mutex_atomic)]
         let active_background_workers = Arc::new((Mutex::new(0), Condvar::new()));
@@ -432,6 +733,9 @@ impl CorpusStorage {
             corpus_cache: RwLock::new(LinkedHashMap::new()),
             query_config,
             active_background_workers,
+            result_cache: Mutex::new(ResultCache::new(0)),
+            query_memory_limit: Mutex::new(None),
+            corpus_modifications: Mutex::new(HashMap::new()),
         };
 
         Ok(cs)
@@ -447,7 +751,10 @@ impl CorpusStorage {
     pub fn with_auto_cache_size(db_dir: &Path, use_parallel_joins: bool) -> Result<CorpusStorage> {
         init_locale();
 
-        let query_config = aql::Config { use_parallel_joins };
+        let query_config = aql::Config {
+            use_parallel_joins,
+            analyze: false,
+        };
 
         // get the amount of available memory, use a quarter of it per default
         let cache_strategy: CacheStrategy = CacheStrategy::PercentOfFreeMemory(25.0);
@@ -462,6 +769,9 @@ impl CorpusStorage {
             corpus_cache: RwLock::new(LinkedHashMap::new()),
             query_config,
             active_background_workers,
+            result_cache: Mutex::new(ResultCache::new(0)),
+            query_memory_limit: Mutex::new(None),
+            corpus_modifications: Mutex::new(HashMap::new()),
         };
 
         Ok(cs)
@@ -660,7 +970,10 @@ impl CorpusStorage {
             db
         } else {
             let mut db = AnnotationGraph::new(false)?;
-            db.open(&db_path)?;
+            let recovery = db.open(&db_path)?;
+            if !recovery.is_empty() {
+                warn!("Recovered corpus {}: {}", corpus_name, recovery);
+            }
             db
         };
 
@@ -920,7 +1233,7 @@ impl CorpusStorage {
     /// - `format` - The format in which this corpus data is stored.
     /// - `corpus_name` - Optionally override the name of the new corpus for file formats that already provide a corpus name. This only works if the imported file location only contains one corpus.
     /// - `disk_based` - If `true`, prefer disk-based annotation and graph storages instead of memory-only ones.
-    /// - `overwrite_existing` - If `true`, overwrite existing corpora. Otherwise ignore.
+    /// - `overwrite_existing` - If `true`, overwrite existing corpora. Otherwise ignore. The snapshots of an overwritten corpus are kept.
     /// - `progress_callback` - A callback function to which the import progress is reported to.
     ///
     /// Returns the name of the imported corpus.
@@ -983,6 +1296,9 @@ impl CorpusStorage {
         self.update_corpus_size_info(&mut config, &graph);
 
         let corpus_name = corpus_name.unwrap_or(orig_name);
+        if self.is_snapshot_name(&corpus_name) {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name));
+        }
         let db_path = self.corpus_directory_on_disk(&corpus_name);
 
         let mut cache_lock = self.corpus_cache.write()?;
@@ -1001,9 +1317,12 @@ impl CorpusStorage {
                 .as_ref()
                 .map(|db_entry| db_entry.write())
                 .transpose()?;
+            self.remove_snapshots_from_cache(cache, &corpus_name)?;
 
+            // The snapshots of the old corpus are kept, their files are only
+            // hard links and are not affected by removing the corpus files
             if db_path.is_dir()
-                && let Err(e) = std::fs::remove_dir_all(&db_path)
+                && let Err(e) = remove_corpus_files(&db_path)
             {
                 error!("Error when removing existing files {}", e);
             }
@@ -1054,6 +1373,7 @@ impl CorpusStorage {
             corpus_name.clone(),
             Arc::new(RwLock::new(CacheEntry::NotLoaded)),
         );
+        self.corpus_modified(&corpus_name)?;
         check_cache_size_and_remove_with_cache(cache, &self.cache_strategy, vec![&corpus_name])?;
         info!(
             "Corpus cache after importing {corpus_name}: {}",
@@ -1358,7 +1678,14 @@ impl CorpusStorage {
 
     /// Delete a corpus from this corpus storage.
     /// Returns `true` if the corpus was successfully deleted and `false` if no such corpus existed.
+    ///
+    /// All snapshots of the corpus are deleted as well. Use
+    /// [`delete_snapshot`](CorpusStorage::delete_snapshot) to only delete a
+    /// single snapshot.
     pub fn delete(&self, corpus_name: &str) -> Result<bool> {
+        if self.find_snapshot(corpus_name).is_some() {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name.to_string()));
+        }
         let db_path = self.corpus_directory_on_disk(corpus_name);
 
         let mut cache_lock = self.corpus_cache.write()?;
@@ -1372,6 +1699,10 @@ impl CorpusStorage {
             .as_ref()
             .map(|db_entry| db_entry.write())
             .transpose()?;
+        self.corpus_modified(corpus_name)?;
+
+        // The snapshots are deleted together with the corpus
+        self.remove_snapshots_from_cache(cache, corpus_name)?;
 
         if db_path.is_dir() {
             std::fs::remove_dir_all(db_path).map_err(|e| {
@@ -1389,11 +1720,119 @@ impl CorpusStorage {
         }
     }
 
+    /// Create a snapshot with the name `snapshot_name` of the current version
+    /// of a corpus.
+    ///
+    /// The snapshot can be queried like a corpus with the name
+    /// `corpus_name@snapshot_name` and is not changed by later updates of the
+    /// corpus. Unchanged files are shared with the corpus using hard links.
+    pub fn create_snapshot(&self, corpus_name: &str, snapshot_name: &str) -> Result<()> {
+        if snapshot_name.is_empty() || snapshot_name.contains(SNAPSHOT_SEPARATOR) {
+            return Err(GraphAnnisError::InvalidSnapshotName(
+                snapshot_name.to_string(),
+            ));
+        }
+        if self.find_snapshot(corpus_name).is_some() {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name.to_string()));
+        }
+        // Loading the corpus restores it if saving it has been interrupted
+        let db_entry = self.get_loaded_entry(corpus_name, false, false)?;
+        // Updates and background writers must not change the files while they are linked
+        let _lock = db_entry.write()?;
+
+        let snapshot_path = self.snapshot_directory_on_disk(corpus_name, snapshot_name);
+        if snapshot_path.exists() {
+            return Err(GraphAnnisError::SnapshotExists(format!(
+                "{corpus_name}{SNAPSHOT_SEPARATOR}{snapshot_name}"
+            )));
+        }
+        let snapshots_path = self
+            .escaped_directory_on_disk(corpus_name)
+            .join(SNAPSHOTS_FOLDER);
+        std::fs::create_dir_all(&snapshots_path)?;
+        // Create the snapshot in a temporary folder and rename it when it is complete
+        let tmp_dir = tempfile::Builder::new()
+            .prefix(TEMPORARY_SNAPSHOT_PREFIX)
+            .tempdir_in(&snapshots_path)?;
+        for entry in self.escaped_directory_on_disk(corpus_name).read_dir()? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            if file_name == "current" || file_name == "files" {
+                // Files of the graph and linked files are never changed in place
+                hard_link_directory(&entry.path(), &tmp_dir.path().join(&file_name))?;
+            } else if entry.file_type()?.is_file() {
+                // Other files like the corpus configuration can be overwritten
+                std::fs::copy(entry.path(), tmp_dir.path().join(&file_name))?;
+            }
+        }
+        std::fs::rename(tmp_dir.path(), &snapshot_path)?;
+        info!("Created snapshot {snapshot_name} of corpus {corpus_name}");
+        Ok(())
+    }
+
+    /// List the names of all snapshots of a corpus.
+    pub fn list_snapshots(&self, corpus_name: &str) -> Result<Vec<String>> {
+        let db_path = self.escaped_directory_on_disk(corpus_name);
+        if !db_path.is_dir() {
+            return Err(GraphAnnisError::NoSuchCorpus(corpus_name.to_string()));
+        }
+        let mut result = Vec::new();
+        let snapshots_path = db_path.join(SNAPSHOTS_FOLDER);
+        if snapshots_path.is_dir() {
+            for entry in snapshots_path.read_dir()? {
+                let entry = entry?;
+                let directory_name = entry.file_name();
+                let directory_name = directory_name.to_string_lossy();
+                if entry.file_type()?.is_dir()
+                    && !directory_name.starts_with(TEMPORARY_SNAPSHOT_PREFIX)
+                {
+                    let snapshot_name = percent_decode_str(&directory_name);
+                    result.push(snapshot_name.decode_utf8_lossy().to_string());
+                }
+            }
+        }
+        result.sort();
+        Ok(result)
+    }
+
+    /// Delete the snapshot with the name `snapshot_name` of a corpus.
+    /// Returns `true` if the snapshot was deleted and `false` if no such snapshot existed.
+    pub fn delete_snapshot(&self, corpus_name: &str, snapshot_name: &str) -> Result<bool> {
+        let snapshot_corpus_name = format!("{corpus_name}{SNAPSHOT_SEPARATOR}{snapshot_name}");
+        let snapshot_path = self.snapshot_directory_on_disk(corpus_name, snapshot_name);
+
+        let mut cache_lock = self.corpus_cache.write()?;
+        let cache = &mut *cache_lock;
+
+        // Queries might still use the snapshot and need to finish first
+        let db_entry = cache.remove(&snapshot_corpus_name);
+        let _lock = db_entry
+            .as_ref()
+            .map(|db_entry| db_entry.write())
+            .transpose()?;
+        self.corpus_modified(&snapshot_corpus_name)?;
+
+        if snapshot_path.is_dir() {
+            std::fs::remove_dir_all(snapshot_path).map_err(|e| {
+                CorpusStorageError::RemoveFileForCorpus {
+                    corpus: snapshot_corpus_name,
+                    source: e,
+                }
+            })?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
     /// Creates a new empty corpus with the given name.
     ///
     /// Use [`apply_update`](CorpusStorage::apply_update) to add elements to the corpus. Returns whether a
     /// new corpus was created.
     pub fn create_empty_corpus(&self, corpus_name: &str, disk_based: bool) -> Result<bool> {
+        if self.is_snapshot_name(corpus_name) {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name.to_string()));
+        }
         let db_path = self.corpus_directory_on_disk(corpus_name);
 
         let mut cache_lock = self.corpus_cache.write()?;
@@ -1403,6 +1842,7 @@ impl CorpusStorage {
             Ok(false)
         } else {
             self.load_entry_with_lock(&mut cache_lock, corpus_name, true, disk_based)?;
+            self.corpus_modified(corpus_name)?;
             Ok(true)
         }
     }
@@ -1411,6 +1851,9 @@ impl CorpusStorage {
     ///
     /// It is ensured that the update process is atomic and that the changes are persisted to disk if the result is `Ok`.
     pub fn apply_update(&self, corpus_name: &str, update: &mut GraphUpdate) -> Result<()> {
+        if self.find_snapshot(corpus_name).is_some() {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name.to_string()));
+        }
         let db_entry = self.get_loaded_entry(corpus_name, true, false)?;
         {
             let mut lock = db_entry.write()?;
@@ -1418,6 +1861,7 @@ impl CorpusStorage {
 
             db.apply_update(update, |_| {})?;
         }
+        self.corpus_modified(corpus_name)?;
         // start background thread to persists the results
         let active_background_workers = self.active_background_workers.clone();
         {
@@ -1434,6 +1878,107 @@ impl CorpusStorage {
         Ok(())
     }
 
+    /// Set the maximum memory size of the cache for query results in bytes.
+    ///
+    /// The results of `count`, `count_extra` and `find` are cached, so that
+    /// repeating the same query does not need to execute it again. A size of
+    /// `0` disables the cache, which is the default.
+    pub fn set_result_cache_size(&self, max_size: usize) -> Result<()> {
+        let mut result_cache = self.result_cache.lock()?;
+        result_cache.set_max_size(max_size);
+        Ok(())
+    }
+
+    /// Limit the memory in bytes that a single query can use for its
+    /// intermediate results, e.g. the rows of a frequency table or the
+    /// matches that need to be sorted. Queries that exceed the limit fail
+    /// with a [`GraphAnnisError::MemoryLimitExceeded`] error, except for
+    /// sorting the matches, which is then done in temporary files on disk.
+    /// `None` disables the limit, which is the default.
+    pub fn set_query_memory_limit(&self, limit: Option<usize>) -> Result<()> {
+        *self.query_memory_limit.lock()? = limit;
+        Ok(())
+    }
+
+    /// Create the check for the timeout, cancellation and memory limit of a query.
+    fn query_limits<S: AsRef<str>>(&self, query: &SearchQuery<S>) -> Result<TimeoutCheck> {
+        let memory_limit = *self.query_memory_limit.lock()?;
+        Ok(
+            TimeoutCheck::with_cancellation(query.timeout, query.cancellation.clone())
+                .with_memory_limit(memory_limit),
+        )
+    }
+
+    /// Return how often the query result cache could be used and how much memory it uses.
+    pub fn result_cache_statistics(&self) -> Result<ResultCacheStatistics> {
+        let result_cache = self.result_cache.lock()?;
+        Ok(result_cache.statistics())
+    }
+
+    /// Invalidate all cached query results for the corpus.
+    fn corpus_modified(&self, corpus_name: &str) -> Result<()> {
+        let mut corpus_modifications = self.corpus_modifications.lock()?;
+        *corpus_modifications
+            .entry(corpus_name.to_string())
+            .or_default() += 1;
+        Ok(())
+    }
+
+    /// Get the key for the query result cache or `None` if the cache is disabled.
+    fn result_cache_key<S: AsRef<str>>(
+        &self,
+        query: &SearchQuery<S>,
+        kind: ResultKind,
+    ) -> Result<Option<ResultCacheKey>> {
+        if !self.result_cache.lock()?.is_enabled() {
+            return Ok(None);
+        }
+        let quirks_mode = match query.query_language {
+            QueryLanguage::AQL => false,
+            QueryLanguage::AQLQuirksV3 => true,
+        };
+        let parsed_query = aql::parse(query.query, quirks_mode)?;
+
+        let corpus_modifications = self.corpus_modifications.lock()?;
+        let corpora = query
+            .corpus_names
+            .iter()
+            .map(|cn| {
+                let cn = cn.as_ref();
+                let modifications = corpus_modifications.get(cn).copied().unwrap_or_default();
+                (cn.to_string(), modifications)
+            })
+            .collect();
+
+        Ok(Some(ResultCacheKey {
+            corpora,
+            query: parsed_query.canonical_description(),
+            quirks_mode,
+            kind,
+        }))
+    }
+
+    fn get_cached_result(&self, key: Option<&ResultCacheKey>) -> Result<Option<CachedResult>> {
+        if let Some(key) = key {
+            let mut result_cache = self.result_cache.lock()?;
+            Ok(result_cache.get(key))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn insert_cached_result(
+        &self,
+        key: Option<ResultCacheKey>,
+        result: CachedResult,
+    ) -> Result<()> {
+        if let Some(key) = key {
+            let mut result_cache = self.result_cache.lock()?;
+            result_cache.insert(key, result);
+        }
+        Ok(())
+    }
+
     fn prepare_query<F>(
         &self,
         corpus_name: &str,
@@ -1512,11 +2057,67 @@ impl CorpusStorage {
         Ok(())
     }
 
+    /// Check the integrity of a corpus on disk and return the found problems.
+    ///
+    /// The node annotation storage, all components, their statistics and the
+    /// files linked by the corpus are checked. The corpus is unloaded and no
+    /// other corpus can be accessed while the check is running.
+    ///
+    /// - `corpus_name` - The name of the corpus to check.
+    /// - `repair` - If `true`, all problems that can be repaired without losing data are repaired (see [`IntegrityProblem::is_repairable`]).
+    pub fn check_integrity(&self, corpus_name: &str, repair: bool) -> Result<IntegrityReport> {
+        if repair && self.find_snapshot(corpus_name).is_some() {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name.to_string()));
+        }
+        let db_path = self.corpus_directory_on_disk(corpus_name);
+        if !db_path.is_dir() {
+            return Err(GraphAnnisError::NoSuchCorpus(corpus_name.to_string()));
+        }
+
+        let mut cache_lock = self.corpus_cache.write()?;
+        let cache = &mut *cache_lock;
+
+        // If there is a cache entry, acquire an exclusive lock for it because
+        // other queries or background writers might still have access to it and need to finish first.
+        // The corpus is loaded again from disk when it is used after the check.
+        let db_entry = cache.remove(corpus_name);
+        let _lock = db_entry
+            .as_ref()
+            .map(|db_entry| db_entry.write())
+            .transpose()?;
+
+        let mut report = integrity::check_integrity::<AnnotationComponentType>(&db_path, repair)?;
+
+        // Opening the corpus would restore the backup folder or discard update
+        // batches, which must only happen when repairing the corpus
+        if !report.problems.iter().any(|p| p.is_changed_by_open()) {
+            let mut graph = AnnotationGraph::new(false)?;
+            // Problems when opening the corpus have already been reported
+            if graph.open(&db_path).is_ok()
+                && let Some(linked_files) = self.get_linked_files(corpus_name, &graph)?
+            {
+                for linked_file in linked_files {
+                    let (node_name, path) = linked_file?;
+                    if !path.is_file() {
+                        report
+                            .problems
+                            .push(IntegrityProblem::MissingLinkedFile { node_name, path });
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Optimize the node annotation and graph storage implementations of the given corpus.
     /// - `corpus_name` - The corpus name to optimize.
     /// - `disk_based` - If `true`, prefer disk-based annotation and graph storages instead of memory-only ones.
     #[doc(hidden)]
     pub fn reoptimize_implementation(&self, corpus_name: &str, disk_based: bool) -> Result<()> {
+        if self.find_snapshot(corpus_name).is_some() {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name.to_string()));
+        }
         let graph_entry = self.get_loaded_entry(corpus_name, false, disk_based)?;
         let mut lock = graph_entry.write()?;
         let graph: &mut AnnotationGraph = get_write_or_error(&mut lock)?;
@@ -1532,6 +2133,32 @@ impl CorpusStorage {
         Ok(())
     }
 
+    /// Set how the node annotations of a corpus are compressed on disk and
+    /// save the corpus with the new options.
+    ///
+    /// The options are stored with the corpus and are used whenever it is
+    /// saved again. Saving the corpus also converts annotations of older
+    /// versions of graphANNIS to the current format. Node annotations that
+    /// are held in main memory are not compressed.
+    pub fn set_annotation_compression(
+        &self,
+        corpus_name: &str,
+        options: CompressionOptions,
+    ) -> Result<()> {
+        if self.find_snapshot(corpus_name).is_some() {
+            return Err(GraphAnnisError::ReadOnlySnapshot(corpus_name.to_string()));
+        }
+        let graph_entry = self.get_loaded_entry(corpus_name, false, false)?;
+        let mut lock = graph_entry.write()?;
+        let graph: &mut AnnotationGraph = get_write_or_error(&mut lock)?;
+
+        graph.ensure_loaded_all()?;
+        graph.get_node_annos_mut().set_compression(options)?;
+        graph.background_sync_wal_updates()?;
+
+        Ok(())
+    }
+
     /// Parses a `query` and checks if it is valid.
     ///
     /// - `corpus_names` - The name of the corpora the query would be executed on (needed to catch certain corpus-specific semantic errors).
@@ -1553,7 +2180,7 @@ impl CorpusStorage {
             // also get the semantic errors by creating an execution plan on the actual Graph
             let lock = prep.db_entry.read()?;
             let db = get_read_or_error(&lock)?;
-            ExecutionPlan::from_disjunction(&prep.query, db, &self.query_config, timeout)?;
+            ExecutionPlan::from_disjunction(&prep.query, db, &self.query_config, timeout.clone())?;
         }
         Ok(true)
     }
@@ -1577,20 +2204,78 @@ impl CorpusStorage {
             // acquire read-only lock and plan
             let lock = prep.db_entry.read()?;
             let db = get_read_or_error(&lock)?;
-            let plan =
-                ExecutionPlan::from_disjunction(&prep.query, db, &self.query_config, timeout)?;
+            let plan = ExecutionPlan::from_disjunction(
+                &prep.query,
+                db,
+                &self.query_config,
+                timeout.clone(),
+            )?;
 
             all_plans.push(format!("{}:\n{}", cn.as_ref(), plan));
         }
         Ok(all_plans.join("\n"))
     }
 
+    /// Executes a `query` and returns the execution plan for each corpus,
+    /// together with the actual number of tuples produced by each execution
+    /// node and the time spent in it.
+    ///
+    /// In contrast to [`plan`](CorpusStorage::plan), this allows to compare
+    /// the estimates of the query planner with the actual cardinalities.
+    ///
+    /// - `query` - The search query definition.
+    pub fn analyze<S: AsRef<str>>(&self, query: SearchQuery<S>) -> Result<Vec<QueryAnalysis>> {
+        let timeout = self.query_limits(&query)?;
+        let config = aql::Config {
+            analyze: true,
+            ..self.query_config.clone()
+        };
+
+        let mut result = Vec::with_capacity(query.corpus_names.len());
+        for cn in query.corpus_names {
+            let prep =
+                self.prepare_query(cn.as_ref(), query.query, query.query_language, |_| vec![])?;
+
+            // acquire read-only lock and execute query
+            let lock = prep.db_entry.read()?;
+            let db = get_read_or_error(&lock)?;
+
+            let start_time = std::time::Instant::now();
+            let mut plan =
+                ExecutionPlan::from_disjunction(&prep.query, db, &config, timeout.clone())?;
+            let mut match_count: u64 = 0;
+            for m in plan.by_ref() {
+                m?;
+                match_count += 1;
+                if match_count.is_multiple_of(TIMEOUT_CHECK_TUPLE_COUNT) {
+                    timeout.check()?;
+                }
+            }
+            timeout.check()?;
+
+            result.push(QueryAnalysis {
+                corpus_name: cn.as_ref().to_string(),
+                alternatives: plan.analysis(),
+                match_count,
+                time_ms: start_time.elapsed().as_secs_f64() * 1000.0,
+            });
+        }
+        Ok(result)
+    }
+
     /// Count the number of results for a `query`.
     /// - `query` - The search query definition.
     ///
     /// Returns the count as number.
     pub fn count<S: AsRef<str>>(&self, query: SearchQuery<S>) -> Result<u64> {
-        let timeout = TimeoutCheck::new(query.timeout);
+        let cache_key = self.result_cache_key(&query, ResultKind::Count)?;
+        if let Some(CachedResult::Count(total_count)) =
+            self.get_cached_result(cache_key.as_ref())?
+        {
+            return Ok(total_count);
+        }
+
+        let timeout = self.query_limits(&query)?;
         let mut total_count: u64 = 0;
 
         for cn in query.corpus_names {
@@ -1600,8 +2285,12 @@ impl CorpusStorage {
             // acquire read-only lock and execute query
             let lock = prep.db_entry.read()?;
             let db = get_read_or_error(&lock)?;
-            let plan =
-                ExecutionPlan::from_disjunction(&prep.query, db, &self.query_config, timeout)?;
+            let plan = ExecutionPlan::from_disjunction(
+                &prep.query,
+                db,
+                &self.query_config,
+                timeout.clone(),
+            )?;
 
             for _ in plan {
                 total_count += 1;
@@ -1613,6 +2302,7 @@ impl CorpusStorage {
             timeout.check()?;
         }
 
+        self.insert_cached_result(cache_key, CachedResult::Count(total_count))?;
         Ok(total_count)
     }
 
@@ -1620,7 +2310,14 @@ impl CorpusStorage {
     ///
     /// - `query` - The search query definition.
     pub fn count_extra<S: AsRef<str>>(&self, query: SearchQuery<S>) -> Result<CountExtra> {
-        let timeout = TimeoutCheck::new(query.timeout);
+        let cache_key = self.result_cache_key(&query, ResultKind::CountExtra)?;
+        if let Some(CachedResult::CountExtra(result)) =
+            self.get_cached_result(cache_key.as_ref())?
+        {
+            return Ok(result);
+        }
+
+        let timeout = self.query_limits(&query)?;
 
         let annis_doc_key = AnnoKey {
             name: "doc".into(),
@@ -1639,8 +2336,12 @@ impl CorpusStorage {
             // acquire read-only lock and execute query
             let lock = prep.db_entry.read()?;
             let db: &AnnotationGraph = get_read_or_error(&lock)?;
-            let plan =
-                ExecutionPlan::from_disjunction(&prep.query, db, &self.query_config, timeout)?;
+            let plan = ExecutionPlan::from_disjunction(
+                &prep.query,
+                db,
+                &self.query_config,
+                timeout.clone(),
+            )?;
 
             let mut known_partof_ancestors: FxHashSet<NodeID> = FxHashSet::default();
 
@@ -1682,20 +2383,163 @@ impl CorpusStorage {
             timeout.check()?;
         }
 
-        Ok(CountExtra {
+        let result = CountExtra {
             match_count,
             document_count,
-        })
+        };
+        self.insert_cached_result(cache_key, CachedResult::CountExtra(result.clone()))?;
+        Ok(result)
     }
 
-    fn create_find_iterator_for_query<'b>(
-        &'b self,
-        db: &'b AnnotationGraph,
-        query: &'b Disjunction,
-        find_arguments: FindArguments,
-        quirks_mode: bool,
-        timeout: TimeoutCheck,
-    ) -> Result<(FindIterator<'b>, Option<usize>)> {
+    /// Estimate the number of results for a `query` by only searching in a random sample of the documents.
+    ///
+    /// The number of matches in the sampled documents is extrapolated to all
+    /// documents of the corpus. Corpora with no more than `max_documents`
+    /// documents are searched completely and their count is exact. Matches
+    /// that are not part of any document (e.g. corpus nodes) are only counted
+    /// for these corpora.
+    ///
+    /// - `query` - The search query definition.
+    /// - `max_documents` - Maximum number of documents that are searched in each corpus. Must be larger than zero.
+    pub fn count_approximate<S: AsRef<str>>(
+        &self,
+        query: SearchQuery<S>,
+        max_documents: usize,
+    ) -> Result<ApproximateCount> {
+        if max_documents == 0 {
+            return Err(GraphAnnisError::EmptyDocumentSample);
+        }
+        let timeout = self.query_limits(&query)?;
+        let mut rng = rand::rng();
+
+        let mut estimated_count = 0.0;
+        let mut variance = 0.0;
+        let mut found_count: u64 = 0;
+        let mut approximate = false;
+
+        for cn in query.corpus_names {
+            let prep =
+                self.prepare_query(cn.as_ref(), query.query, query.query_language, |db| {
+                    db.get_all_components(Some(AnnotationComponentType::PartOf), None)
+                })?;
+
+            // acquire read-only lock and execute query
+            let lock = prep.db_entry.read()?;
+            let db: &AnnotationGraph = get_read_or_error(&lock)?;
+
+            let all_documents: Vec<NodeID> = db
+                .get_node_annos()
+                .exact_anno_search(Some(ANNIS_NS), &DOCUMENT_KEY.name, ValueSearch::Any)
+                .map_ok(|m| m.node)
+                .collect::<std::result::Result<_, _>>()?;
+
+            if all_documents.len() <= max_documents {
+                // Searching in all documents is not more expensive than the sample
+                let plan = ExecutionPlan::from_disjunction(
+                    &prep.query,
+                    db,
+                    &self.query_config,
+                    timeout.clone(),
+                )?;
+                let mut corpus_count: u64 = 0;
+                for m in plan {
+                    m?;
+                    corpus_count += 1;
+                    if corpus_count.is_multiple_of(TIMEOUT_CHECK_TUPLE_COUNT) {
+                        timeout.check()?;
+                    }
+                }
+                estimated_count += corpus_count as f64;
+                found_count += corpus_count;
+                continue;
+            }
+
+            // Restrict the query to the matches in the sampled documents
+            let sampled_documents: Vec<NodeID> = all_documents
+                .sample(&mut rng, max_documents)
+                .copied()
+                .collect();
+            let subcorpus = NodeSearchSpec::Documents {
+                nodes: sampled_documents.clone(),
+            };
+            let mut restricted_query = prep.query;
+            for alt in restricted_query.alternatives.iter_mut() {
+                alt.restrict_to_subcorpus(subcorpus.clone())?;
+            }
+
+            let part_of_gs: Vec<_> = db
+                .get_all_components(Some(AnnotationComponentType::PartOf), None)
+                .iter()
+                .filter_map(|c| db.get_graphstorage_as_ref(c))
+                .collect();
+
+            // Count the matches for each sampled document, including the ones without matches
+            let mut matches_per_document: FxHashMap<NodeID, u64> =
+                sampled_documents.iter().map(|d| (*d, 0)).collect();
+            let plan = ExecutionPlan::from_disjunction(
+                &restricted_query,
+                db,
+                &self.query_config,
+                timeout.clone(),
+            )?;
+            for (match_nr, m) in plan.enumerate() {
+                let m = m?;
+                if let Some(first) = m.first()
+                    && let Some(document) = find_document_for_node(db, &part_of_gs, first.node)?
+                    && let Some(count) = matches_per_document.get_mut(&document)
+                {
+                    *count += 1;
+                }
+                if (match_nr as u64).is_multiple_of(TIMEOUT_CHECK_TUPLE_COUNT) {
+                    timeout.check()?;
+                }
+            }
+
+            // Extrapolate the mean number of matches per document and
+            // estimate the variance of this cluster sample
+            let population_size = all_documents.len() as f64;
+            let sample_size = matches_per_document.len() as f64;
+            let sample_count: u64 = matches_per_document.values().sum();
+            let mean = sample_count as f64 / sample_size;
+            let sample_variance = if matches_per_document.len() > 1 {
+                matches_per_document
+                    .values()
+                    .map(|c| (*c as f64 - mean).powi(2))
+                    .sum::<f64>()
+                    / (sample_size - 1.0)
+            } else {
+                0.0
+            };
+            estimated_count += population_size * mean;
+            variance +=
+                population_size.powi(2) * (1.0 - sample_size / population_size) * sample_variance
+                    / sample_size;
+            found_count += sample_count;
+            approximate = true;
+
+            timeout.check()?;
+        }
+
+        // Use the 95% confidence interval, which can't include less than the
+        // number of matches that have actually been found
+        let margin = 1.96 * variance.sqrt();
+        Ok(ApproximateCount {
+            match_count: estimated_count.round() as u64,
+            lower_bound: (estimated_count - margin).max(found_count as f64).round() as u64,
+            upper_bound: (estimated_count + margin).round() as u64,
+            approximate,
+        })
+    }
+
+    fn create_find_iterator_for_query<'b>(
+        &'b self,
+        db: &'b AnnotationGraph,
+        query: &'b Disjunction,
+        find_arguments: FindArguments,
+        cursor: Option<&MatchGroup>,
+        quirks_mode: bool,
+        timeout: TimeoutCheck,
+    ) -> Result<FindIterator<'b>> {
         let mut query_config = self.query_config.clone();
         if find_arguments.order == ResultOrder::NotSorted {
             // Do execute query in parallel if the order should not be sorted to have a more stable result ordering.
@@ -1704,7 +2548,7 @@ impl CorpusStorage {
             query_config.use_parallel_joins = false;
         }
 
-        let plan = ExecutionPlan::from_disjunction(query, db, &query_config, timeout)?;
+        let plan = ExecutionPlan::from_disjunction(query, db, &query_config, timeout.clone())?;
 
         // Try to find the relANNIS version by getting the attribute value which should be attached to the
         // toplevel corpus node.
@@ -1726,11 +2570,11 @@ impl CorpusStorage {
                 }
             }
         }
-        let mut expected_size: Option<usize> = None;
         let base_it: FindIterator = if find_arguments.order == ResultOrder::NotSorted
             || (find_arguments.order == ResultOrder::Normal
                 && plan.is_sorted_by_text()
-                && !quirks_mode)
+                && !quirks_mode
+                && cursor.is_none())
         {
             // If the output is already sorted correctly, directly return the iterator.
             // Quirks mode may change the order of the results, thus don't use the shortcut
@@ -1742,115 +2586,141 @@ impl CorpusStorage {
                 .fixed_key_size(size_of::<usize>())
                 .max_value_size(512);
             let mut anno_key_symbols: SymbolTable<AnnoKey> = SymbolTable::new();
-            let mut tmp_results: BtreeIndex<usize, Vec<(NodeID, usize)>> =
-                BtreeIndex::with_capacity(btree_config, estimated_result_size)?;
-
-            if find_arguments.order == ResultOrder::Randomized {
-                // Use a unique random index for each match to force a random order
-                let mut rng = rand::rng();
-
-                for mgroup in plan {
-                    let mgroup = mgroup?;
-                    let mut idx: u64 = rng.random();
-                    while tmp_results.contains_key(&(idx as usize))? {
-                        idx = rng.random();
-                    }
-                    let m = match_group_with_symbol_ids(&mgroup, &mut anno_key_symbols)?;
-                    tmp_results.insert(idx as usize, m)?;
-                }
-            } else {
-                // Insert results in the order as they are given by the iterator
-                for (idx, mgroup) in plan.enumerate() {
-                    let mgroup = mgroup?;
-                    // add all matches to temporary container
-                    let m = match_group_with_symbol_ids(&mgroup, &mut anno_key_symbols)?;
-                    tmp_results.insert(idx, m)?;
-                }
 
-                let token_helper = TokenHelper::new(db).ok();
-                let component_order = Component::new(
-                    AnnotationComponentType::Ordering,
-                    ANNIS_NS.into(),
-                    "".into(),
-                );
+            let unresolved_results: Box<dyn Iterator<Item = Result<Vec<(NodeID, usize)>>>> =
+                if find_arguments.order == ResultOrder::Randomized {
+                    let mut tmp_results: BtreeIndex<usize, Vec<(NodeID, usize)>> =
+                        BtreeIndex::with_capacity(btree_config, estimated_result_size)?;
+                    // Use a unique random index for each match to force a random order
+                    let mut rng = rand::rng();
 
-                let collation = if quirks_mode && !relannis_version_33 {
-                    CollationType::Locale
+                    for mgroup in plan {
+                        let mgroup = mgroup?;
+                        let mut idx: u64 = rng.random();
+                        while tmp_results.contains_key(&(idx as usize))? {
+                            idx = rng.random();
+                        }
+                        let m = match_group_with_symbol_ids(&mgroup, &mut anno_key_symbols)?;
+                        tmp_results.insert(idx as usize, m)?;
+                    }
+                    Box::new(tmp_results.into_iter()?.map(|entry| Ok(entry?.1)))
                 } else {
-                    CollationType::Default
-                };
+                    // The matches are kept in memory if the memory limit allows it
+                    let mut tmp_results: SpillingContainer<Vec<(NodeID, usize)>> =
+                        SpillingContainer::new(btree_config, estimated_result_size, timeout)?;
+                    let token_helper = TokenHelper::new(db).ok();
+                    let component_order = Component::new(
+                        AnnotationComponentType::Ordering,
+                        ANNIS_NS.into(),
+                        "".into(),
+                    );
+
+                    let collation = if quirks_mode && !relannis_version_33 {
+                        CollationType::Locale
+                    } else {
+                        CollationType::Default
+                    };
 
-                let gs_order = db.get_graphstorage(&component_order);
-                let mut cache = SortCache::new(gs_order);
-                let order_func = |m1: &Vec<(NodeID, usize)>,
-                                  m2: &Vec<(NodeID, usize)>|
-                 -> Result<std::cmp::Ordering> {
-                    // Get matches from symbol ID
-                    let m1 = match_group_resolve_symbol_ids(m1, &anno_key_symbols)?;
-                    let m2 = match_group_resolve_symbol_ids(m2, &anno_key_symbols)?;
-
-                    // Compare the matches
-                    if find_arguments.order == ResultOrder::Inverted {
-                        let result = cache
-                            .compare_matchgroup_by_text_pos(
+                    let gs_order = db.get_graphstorage(&component_order);
+                    let mut cache = SortCache::new(gs_order);
+
+                    // Insert results in the order as they are given by the iterator
+                    for mgroup in plan {
+                        let mgroup = mgroup?;
+                        if let Some(cursor) = cursor {
+                            // Only the matches after the cursor need to be sorted
+                            let cmp = cache.compare_matchgroup_by_text_pos(
+                                &mgroup,
+                                cursor,
+                                db.get_node_annos(),
+                                token_helper.as_ref(),
+                                collation,
+                                quirks_mode,
+                            )?;
+                            let after_cursor = if find_arguments.order == ResultOrder::Inverted {
+                                cmp == std::cmp::Ordering::Less
+                            } else {
+                                cmp == std::cmp::Ordering::Greater
+                            };
+                            if !after_cursor {
+                                continue;
+                            }
+                        }
+                        // add all matches to temporary container
+                        let m = match_group_with_symbol_ids(&mgroup, &mut anno_key_symbols)?;
+                        let size = size_of::<Vec<(NodeID, usize)>>()
+                            + (m.len() * size_of::<(NodeID, usize)>());
+                        tmp_results.push(m, size)?;
+                    }
+                    let order_func = |m1: &Vec<(NodeID, usize)>,
+                                      m2: &Vec<(NodeID, usize)>|
+                     -> Result<std::cmp::Ordering> {
+                        // Get matches from symbol ID
+                        let m1 = match_group_resolve_symbol_ids(m1, &anno_key_symbols)?;
+                        let m2 = match_group_resolve_symbol_ids(m2, &anno_key_symbols)?;
+
+                        // Compare the matches
+                        if find_arguments.order == ResultOrder::Inverted {
+                            let result = cache
+                                .compare_matchgroup_by_text_pos(
+                                    &m1,
+                                    &m2,
+                                    db.get_node_annos(),
+                                    token_helper.as_ref(),
+                                    collation,
+                                    quirks_mode,
+                                )?
+                                .reverse();
+                            Ok(result)
+                        } else {
+                            let result = cache.compare_matchgroup_by_text_pos(
                                 &m1,
                                 &m2,
                                 db.get_node_annos(),
                                 token_helper.as_ref(),
                                 collation,
                                 quirks_mode,
-                            )?
-                            .reverse();
-                        Ok(result)
+                            )?;
+                            Ok(result)
+                        }
+                    };
+
+                    let sort_size = if let Some(limit) = find_arguments.limit {
+                        // we won't need to sort all items
+                        find_arguments.offset + limit
                     } else {
-                        let result = cache.compare_matchgroup_by_text_pos(
-                            &m1,
-                            &m2,
-                            db.get_node_annos(),
-                            token_helper.as_ref(),
-                            collation,
-                            quirks_mode,
-                        )?;
-                        Ok(result)
-                    }
-                };
+                        // sort all items if unlimited iterator is requested
+                        tmp_results.try_len()?
+                    };
 
-                let sort_size = if let Some(limit) = find_arguments.limit {
-                    // we won't need to sort all items
-                    find_arguments.offset + limit
-                } else {
-                    // sort all items if unlimited iterator is requested
-                    tmp_results.len()
+                    quicksort::sort_first_n_items(&mut tmp_results, sort_size, order_func)?;
+                    tmp_results.into_iter()?
                 };
-
-                quicksort::sort_first_n_items(&mut tmp_results, sort_size, order_func)?;
-            }
-            expected_size = Some(tmp_results.len());
-            let iterator = tmp_results.into_iter()?.map(move |unresolved_match_group| {
-                match unresolved_match_group {
-                    Ok((_idx, unresolved_match_group)) => {
-                        let result = match_group_resolve_symbol_ids(
-                            &unresolved_match_group,
-                            &anno_key_symbols,
-                        )?;
-                        Ok(result)
-                    }
-                    Err(e) => Err(e.into()),
-                }
+            let iterator = unresolved_results.map(move |unresolved_match_group| {
+                let result =
+                    match_group_resolve_symbol_ids(&unresolved_match_group?, &anno_key_symbols)?;
+                Ok(result)
             });
             Box::from(iterator)
         };
 
-        Ok((base_it, expected_size))
+        Ok(base_it)
     }
 
-    fn find_in_single_corpus<S: AsRef<str>>(
+    /// Execute the query on a single corpus and call `callback` for each
+    /// match.
+    fn find_in_single_corpus<S, F>(
         &self,
         query: &SearchQuery<S>,
         corpus_name: &str,
         find_arguments: FindArguments,
         timeout: TimeoutCheck,
-    ) -> Result<(Vec<String>, usize)> {
+        callback: &mut F,
+    ) -> Result<SingleCorpusFindResult>
+    where
+        S: AsRef<str>,
+        F: FnMut(String) -> bool,
+    {
         let prep = self.prepare_query(corpus_name, query.query, query.query_language, |db| {
             let mut additional_components = vec![Component::new(
                 AnnotationComponentType::Ordering,
@@ -1876,22 +2746,19 @@ impl CorpusStorage {
             QueryLanguage::AQLQuirksV3 => true,
         };
 
-        let (mut base_it, expected_size) = self.create_find_iterator_for_query(
+        let cursor = find_arguments
+            .cursor
+            .map(|cursor| cursor.resolve(db))
+            .transpose()?;
+        let mut base_it = self.create_find_iterator_for_query(
             db,
             &prep.query,
             find_arguments,
+            cursor.as_ref(),
             quirks_mode,
-            timeout,
+            timeout.clone(),
         )?;
 
-        let mut results: Vec<String> = if let Some(expected_size) = expected_size {
-            new_vector_with_memory_aligned_capacity(expected_size)
-        } else if let Some(limit) = find_arguments.limit {
-            new_vector_with_memory_aligned_capacity(limit)
-        } else {
-            Vec::new()
-        };
-
         // skip the first entries
         let mut skipped = 0;
         while skipped < find_arguments.offset && base_it.next().is_some() {
@@ -1908,76 +2775,43 @@ impl CorpusStorage {
                 Box::new(base_it)
             };
 
+        let mut match_count = 0;
+        let mut last_match = None;
+        let mut stopped = false;
         for (match_nr, m) in base_it.enumerate() {
             let m = m?;
-            let mut match_desc = String::new();
-
-            let mut any_nodes_added = false;
-
-            for (node_nr, singlematch) in m.iter().enumerate() {
-                // check if query node actually should be included
-                let include_in_output = prep
-                    .query
-                    .get_variable_by_node_nr(node_nr)
-                    .is_some_and(|var| prep.query.is_included_in_output(&var));
-
-                if include_in_output {
-                    if any_nodes_added {
-                        match_desc.push(' ');
-                    }
-                    any_nodes_added = true;
-
-                    let singlematch_anno_key = &singlematch.anno_key;
-                    if singlematch_anno_key.ns != ANNIS_NS || singlematch_anno_key.name != NODE_TYPE
-                    {
-                        if !singlematch_anno_key.ns.is_empty() {
-                            let encoded_anno_ns: Cow<str> =
-                                utf8_percent_encode(&singlematch_anno_key.ns, NODE_NAME_ENCODE_SET)
-                                    .into();
-                            match_desc.push_str(&encoded_anno_ns);
-                            match_desc.push_str("::");
-                        }
-                        let encoded_anno_name: Cow<str> =
-                            utf8_percent_encode(&singlematch_anno_key.name, NODE_NAME_ENCODE_SET)
-                                .into();
-                        match_desc.push_str(&encoded_anno_name);
-                        match_desc.push_str("::");
-                    }
-
-                    if let Some(node_name) = db
-                        .get_node_annos()
-                        .get_value_for_item(&singlematch.node, &NODE_NAME_KEY)?
-                    {
-                        if quirks_mode {
-                            // Unescape all parts of the name and re-escape with
-                            // quirks-mode compatible character encoding set
-                            let re_encoded_name = node_name
-                                .split('/')
-                                .map(|n| {
-                                    let decoded_name =
-                                        percent_encoding::percent_decode_str(n).decode_utf8_lossy();
-                                    let re_encoded_name: Cow<str> = utf8_percent_encode(
-                                        &decoded_name,
-                                        QUIRKS_SALT_URI_ENCODE_SET,
-                                    )
-                                    .into();
-                                    re_encoded_name.to_string()
-                                })
-                                .join("/");
-                            match_desc.push_str(&re_encoded_name);
-                        } else {
-                            match_desc.push_str(&node_name);
-                        }
-                    }
-                }
+            let match_desc = match_group_description(db, &prep.query, &m, quirks_mode)?;
+            match_count += 1;
+            last_match = Some(m);
+            if !callback(match_desc) {
+                stopped = true;
+                break;
             }
-            results.push(match_desc);
             if match_nr % 1_000 == 0 {
                 timeout.check()?;
             }
         }
 
-        Ok((results, skipped))
+        let last_match = if let Some(m) = last_match {
+            let mut node_names = Vec::with_capacity(m.len());
+            for singlematch in m {
+                let node_name = db
+                    .get_node_annos()
+                    .get_value_for_item(&singlematch.node, &NODE_NAME_KEY)?
+                    .ok_or(GraphAnnisError::NoSuchNodeID(singlematch.node.to_string()))?;
+                node_names.push((node_name.to_string(), singlematch.anno_key.as_ref().clone()));
+            }
+            Some(node_names)
+        } else {
+            None
+        };
+
+        Ok(SingleCorpusFindResult {
+            match_count,
+            skipped,
+            stopped,
+            last_match,
+        })
     }
 
     /// Find all results for a `query` and return the match ID for each result.
@@ -1998,77 +2832,302 @@ impl CorpusStorage {
         limit: Option<usize>,
         order: ResultOrder,
     ) -> Result<Vec<String>> {
-        let timeout = TimeoutCheck::new(query.timeout);
+        // A random order must be different for each query execution
+        let cache_key = if order == ResultOrder::Randomized {
+            None
+        } else {
+            let kind = ResultKind::Find {
+                offset,
+                limit,
+                order,
+            };
+            self.result_cache_key(&query, kind)?
+        };
+        if let Some(CachedResult::Find(result)) = self.get_cached_result(cache_key.as_ref())? {
+            return Ok(result);
+        }
 
-        // Sort corpus names
-        let mut corpus_names: Vec<String> = query
-            .corpus_names
-            .iter()
-            .map(|c| c.as_ref().into())
-            .collect();
+        let timeout = self.query_limits(&query)?;
+        let find_arguments = FindArguments {
+            limit,
+            offset,
+            order,
+            cursor: None,
+        };
+        let mut result = if let Some(limit) = limit {
+            new_vector_with_memory_aligned_capacity(limit)
+        } else {
+            Vec::new()
+        };
+        let mut result_memory = timeout.memory_reservation();
+        let mut memory_error = None;
+        self.find_with_arguments(query, find_arguments, timeout.clone(), |m| {
+            // All matches are collected in memory
+            if let Err(e) = result_memory.grow(std::mem::size_of::<String>() + m.len()) {
+                memory_error = Some(e);
+                return false;
+            }
+            result.push(m);
+            true
+        })?;
+        if let Some(e) = memory_error {
+            return Err(e);
+        }
+        if cache_key.is_some() {
+            self.insert_cached_result(cache_key, CachedResult::Find(result.clone()))?;
+        }
+        Ok(result)
+    }
 
-        let mut find_arguments = FindArguments {
+    /// Find all results for a `query` and call `callback` with the match ID
+    /// of each result as soon as it is available.
+    ///
+    /// In contrast to [find(...)](#method.find), the matches are not collected
+    /// in memory, which allows processing large result sets. The callback
+    /// must return `false` to stop the search early, e.g. when the receiver
+    /// of the matches is gone. The arguments and the format of the match IDs
+    /// are the same as for [find(...)](#method.find).
+    pub fn find_iter<S, F>(
+        &self,
+        query: SearchQuery<S>,
+        offset: usize,
+        limit: Option<usize>,
+        order: ResultOrder,
+        callback: F,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+        F: FnMut(String) -> bool,
+    {
+        let find_arguments = FindArguments {
             limit,
             offset,
             order,
+            cursor: None,
         };
+        let timeout = self.query_limits(&query)?;
+        self.find_with_arguments(query, find_arguments, timeout, callback)?;
+        Ok(())
+    }
 
-        match corpus_names.len() {
-            0 => Ok(Vec::new()),
-            1 => self
-                .find_in_single_corpus(&query, corpus_names[0].as_str(), find_arguments, timeout)
-                .map(|r| r.0),
-            _ => {
-                if order == ResultOrder::Randomized {
-                    // This is still oddly ordered, because results from one corpus will always be grouped together.
-                    // But it still better than just output the same corpus first.
-                    let mut rng = rand::rng();
-                    corpus_names.shuffle(&mut rng);
-                } else if order == ResultOrder::Inverted {
-                    corpus_names.sort();
-                    corpus_names.reverse();
+    /// Find a page of results for a `query`, which starts after the given `cursor`.
+    ///
+    /// Instead of an offset, the position of the page is given by the cursor
+    /// returned for the previous page. Since only the matches after the
+    /// cursor need to be sorted, this is much faster than using a large
+    /// offset with [find(...)](#method.find).
+    ///
+    /// - `query` - The search query definition.
+    /// - `cursor` - The cursor of the previous page or `None` for the first page.
+    /// - `limit` - Return at most `n` matches, where `n` is the limit.
+    /// - `order` - Specify the order of the matches. Only `ResultOrder::Normal` and `ResultOrder::Inverted` are supported.
+    ///
+    /// Returns the match IDs (see [find(...)](#method.find)) and the cursor to
+    /// get the next page. If the page has less than `limit` matches, there is
+    /// no next page and no cursor is returned.
+    pub fn find_with_cursor<S: AsRef<str>>(
+        &self,
+        query: SearchQuery<S>,
+        cursor: Option<&FindCursor>,
+        limit: usize,
+        order: ResultOrder,
+    ) -> Result<(Vec<String>, Option<FindCursor>)> {
+        if order != ResultOrder::Normal && order != ResultOrder::Inverted {
+            return Err(GraphAnnisError::InvalidFindCursor(format!(
+                "cursors can't be used with result order {:?}",
+                order
+            )));
+        }
+        if let Some(cursor) = cursor
+            && cursor.order != order
+        {
+            return Err(GraphAnnisError::InvalidFindCursor(format!(
+                "cursor was created for result order {:?}",
+                cursor.order
+            )));
+        }
+
+        let find_arguments = FindArguments {
+            limit: Some(limit),
+            offset: 0,
+            order,
+            cursor,
+        };
+        let timeout = self.query_limits(&query)?;
+        let mut matches = new_vector_with_memory_aligned_capacity(limit);
+        let cursor_for_last_match =
+            self.find_with_arguments(query, find_arguments, timeout, |m| {
+                matches.push(m);
+                true
+            })?;
+        if matches.len() == limit {
+            Ok((matches, cursor_for_last_match))
+        } else {
+            Ok((matches, None))
+        }
+    }
+
+    /// Draw a uniform random sample of the results for a `query` and return the match ID for each sampled result.
+    ///
+    /// The matches are sampled while the query is executed, so only the
+    /// sample and not all matches are held in memory. The same `seed` will
+    /// always return the same sample as long as the corpora are not changed.
+    ///
+    /// - `query` - The search query definition.
+    /// - `n` - The size of the sample. If the query has less than `n` results, all results are returned.
+    /// - `seed` - The seed for the random number generator.
+    ///
+    /// Returns a vector of match IDs (see [find(...)](#method.find)) in the order in which the matches have been found.
+    pub fn sample<S: AsRef<str>>(
+        &self,
+        query: SearchQuery<S>,
+        n: usize,
+        seed: u64,
+    ) -> Result<Vec<String>> {
+        let cache_key = self.result_cache_key(&query, ResultKind::Sample { n, seed })?;
+        if let Some(CachedResult::Find(result)) = self.get_cached_result(cache_key.as_ref())? {
+            return Ok(result);
+        }
+
+        let timeout = self.query_limits(&query)?;
+        // The order of the matches must be the same for each execution
+        let mut query_config = self.query_config.clone();
+        query_config.use_parallel_joins = false;
+
+        let mut corpus_names: Vec<&str> = query.corpus_names.iter().map(|c| c.as_ref()).collect();
+        corpus_names.sort_unstable();
+
+        let quirks_mode = match query.query_language {
+            QueryLanguage::AQL => false,
+            QueryLanguage::AQLQuirksV3 => true,
+        };
+
+        // Reservoir sampling: each match replaces a random entry of the sample
+        // with a probability of n/(number of matches so far)
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut reservoir: Vec<(u64, String)> = Vec::new();
+        let mut reservoir_memory = timeout.memory_reservation();
+        let mut match_nr: u64 = 0;
+
+        for cn in corpus_names {
+            let prep = self.prepare_query(cn, query.query, query.query_language, |_| vec![])?;
+
+            // acquire read-only lock and execute query
+            let lock = prep.db_entry.read()?;
+            let db = get_read_or_error(&lock)?;
+            let plan =
+                ExecutionPlan::from_disjunction(&prep.query, db, &query_config, timeout.clone())?;
+
+            for m in plan {
+                let m = m?;
+                if reservoir.len() < n {
+                    let match_desc = match_group_description(db, &prep.query, &m, quirks_mode)?;
+                    reservoir_memory.grow(size_of::<(u64, String)>() + match_desc.len())?;
+                    reservoir.push((match_nr, match_desc));
                 } else {
-                    corpus_names.sort();
+                    let idx = rng.random_range(0..=match_nr);
+                    if idx < n as u64 {
+                        let match_desc = match_group_description(db, &prep.query, &m, quirks_mode)?;
+                        reservoir[idx as usize] = (match_nr, match_desc);
+                    }
+                }
+                match_nr += 1;
+                if match_nr.is_multiple_of(TIMEOUT_CHECK_TUPLE_COUNT) {
+                    timeout.check()?;
                 }
+            }
 
-                let mut result = Vec::new();
-                for cn in corpus_names {
-                    let (single_result, skipped) =
-                        self.find_in_single_corpus(&query, cn.as_ref(), find_arguments, timeout)?;
-
-                    // Adjust limit and offset according to the found matches for the next corpus.
-                    let single_result_length = single_result.len();
-                    let remaining_limit = limit.map(|limit| limit.saturating_sub(result.len()));
-                    if let Some(remaining_limit) = remaining_limit {
-                        if single_result_length <= remaining_limit {
-                            // All results for this corpus fit inside the limit
-                            result.extend(single_result);
-                        } else {
-                            // Only add as many items as allowed by the limit
-                            result.extend(single_result[0..remaining_limit].iter().cloned());
-                        }
-                    } else {
-                        // Add all results since there is no limit
-                        result.extend(single_result);
-                    }
+            timeout.check()?;
+        }
 
-                    if let Some(limit) = limit
-                        && result.len() == limit
-                    {
-                        // Searching in the first corpora already yielded enough results
-                        break;
-                    }
-                    if skipped < offset {
-                        find_arguments.offset -= skipped;
-                    } else {
-                        find_arguments.offset = 0;
-                    }
+        reservoir.sort_unstable_by_key(|(match_nr, _)| *match_nr);
+        let result: Vec<String> = reservoir.into_iter().map(|(_, m)| m).collect();
+        self.insert_cached_result(cache_key, CachedResult::Find(result.clone()))?;
+        Ok(result)
+    }
 
-                    timeout.check()?;
+    /// Execute a find query on all corpora and return a cursor for the last
+    /// match given to the callback.
+    fn find_with_arguments<S, F>(
+        &self,
+        query: SearchQuery<S>,
+        mut find_arguments: FindArguments,
+        timeout: TimeoutCheck,
+        mut callback: F,
+    ) -> Result<Option<FindCursor>>
+    where
+        S: AsRef<str>,
+        F: FnMut(String) -> bool,
+    {
+        // Sort corpus names
+        let mut corpus_names: Vec<String> = query
+            .corpus_names
+            .iter()
+            .map(|c| c.as_ref().into())
+            .collect();
+
+        if find_arguments.order == ResultOrder::Randomized {
+            // This is still oddly ordered, because results from one corpus will always be grouped together.
+            // But it still better than just output the same corpus first.
+            let mut rng = rand::rng();
+            corpus_names.shuffle(&mut rng);
+        } else if find_arguments.order == ResultOrder::Inverted {
+            corpus_names.sort();
+            corpus_names.reverse();
+        } else {
+            corpus_names.sort();
+        }
+
+        if let Some(cursor) = find_arguments.cursor {
+            // All corpora before the one of the cursor have been part of previous pages
+            let cursor_corpus_idx = corpus_names
+                .iter()
+                .position(|cn| cn == &cursor.corpus_name)
+                .ok_or_else(|| {
+                    GraphAnnisError::InvalidFindCursor(format!(
+                        "corpus {} is not part of the query",
+                        cursor.corpus_name
+                    ))
+                })?;
+            corpus_names.drain(0..cursor_corpus_idx);
+        }
+
+        let mut cursor_for_last_match = None;
+        for cn in corpus_names {
+            let single_result = self.find_in_single_corpus(
+                &query,
+                cn.as_ref(),
+                find_arguments,
+                timeout.clone(),
+                &mut callback,
+            )?;
+            if let Some(m) = single_result.last_match {
+                cursor_for_last_match = Some(FindCursor {
+                    corpus_name: cn,
+                    order: find_arguments.order,
+                    last_match: m,
+                });
+            }
+            if single_result.stopped {
+                break;
+            }
+
+            // Adjust limit and offset according to the found matches for the next corpus.
+            if let Some(remaining_limit) = find_arguments.limit {
+                let remaining_limit = remaining_limit.saturating_sub(single_result.match_count);
+                if remaining_limit == 0 {
+                    // Searching in the first corpora already yielded enough results
+                    break;
                 }
-                Ok(result)
+                find_arguments.limit = Some(remaining_limit);
             }
+            find_arguments.offset = find_arguments.offset.saturating_sub(single_result.skipped);
+            // The cursor only applies to its own corpus
+            find_arguments.cursor = None;
+
+            timeout.check()?;
         }
+        Ok(cursor_for_last_match)
     }
 
     /// Return the copy of a subgraph which includes the given list of node annotation identifiers,
@@ -2117,12 +3176,14 @@ impl CorpusStorage {
     /// - `query` - The query which defines included nodes.
     /// - `query_language` - The query language of the query (e.g. AQL).
     /// - `component_type_filter` - If set, only include edges of that belong to a component of the given type.
+    /// - `cancellation` - If set, the query is aborted as soon as the token is cancelled.
     pub fn subgraph_for_query(
         &self,
         corpus_name: &str,
         query: &str,
         query_language: QueryLanguage,
         component_type_filter: Option<AnnotationComponentType>,
+        cancellation: Option<CancellationToken>,
     ) -> Result<AnnotationGraph> {
         let prep = self.prepare_query(corpus_name, query, query_language, |g| {
             g.get_all_components(component_type_filter.clone(), None)
@@ -2141,7 +3202,8 @@ impl CorpusStorage {
             &match_idx,
             &self.query_config,
             component_type_filter,
-            TimeoutCheck::new(None),
+            TimeoutCheck::with_cancellation(None, cancellation)
+                .with_memory_limit(*self.query_memory_limit.lock()?),
         )
     }
 
@@ -2276,9 +3338,10 @@ impl CorpusStorage {
         query: SearchQuery<S>,
         definition: Vec<FrequencyDefEntry>,
     ) -> Result<FrequencyTable<String>> {
-        let timeout = TimeoutCheck::new(query.timeout);
+        let timeout = self.query_limits(&query)?;
 
         let mut tuple_frequency: FxHashMap<Vec<String>, usize> = FxHashMap::default();
+        let mut tuple_frequency_memory = timeout.memory_reservation();
 
         for cn in query.corpus_names {
             let prep =
@@ -2289,45 +3352,23 @@ impl CorpusStorage {
             let db: &AnnotationGraph = get_read_or_error(&lock)?;
 
             // get the matching annotation keys for each definition entry
-            let mut annokeys: Vec<(usize, Vec<AnnoKey>)> = Vec::default();
-            for def in definition.iter() {
-                if let Some(node_ref) = prep.query.get_variable_pos(&def.node_ref) {
-                    if let Some(ns) = &def.ns {
-                        // add the single fully qualified annotation key
-                        annokeys.push((
-                            node_ref,
-                            vec![AnnoKey {
-                                ns: ns.clone(),
-                                name: def.name.clone(),
-                            }],
-                        ));
-                    } else {
-                        // add all matching annotation keys
-                        annokeys.push((node_ref, db.get_node_annos().get_qnames(&def.name)?));
-                    }
-                }
-            }
+            let annokeys = get_frequency_anno_keys(db, &prep.query, &definition)?;
 
-            let plan =
-                ExecutionPlan::from_disjunction(&prep.query, db, &self.query_config, timeout)?;
+            let plan = ExecutionPlan::from_disjunction(
+                &prep.query,
+                db,
+                &self.query_config,
+                timeout.clone(),
+            )?;
             let mut total_count: u64 = 0;
 
             for mgroup in plan {
                 let mgroup = mgroup?;
 
                 // for each match, extract the defined annotation (by its key) from the result node
-                let mut tuple: Vec<String> = Vec::with_capacity(annokeys.len());
-                for (node_ref, anno_keys) in &annokeys {
-                    let mut tuple_val: String = String::default();
-                    if *node_ref < mgroup.len() {
-                        let m: &Match = &mgroup[*node_ref];
-                        for k in anno_keys.iter() {
-                            if let Some(val) = db.get_node_annos().get_value_for_item(&m.node, k)? {
-                                tuple_val = val.to_string();
-                            }
-                        }
-                    }
-                    tuple.push(tuple_val);
+                let tuple = get_frequency_tuple(db, &annokeys, &mgroup)?;
+                if !tuple_frequency.contains_key(&tuple) {
+                    tuple_frequency_memory.grow(estimated_frequency_tuple_size(&tuple))?;
                 }
                 // add the tuple to the frequency count
                 let tuple_count: &mut usize = tuple_frequency.entry(tuple).or_insert(0);
@@ -2340,21 +3381,292 @@ impl CorpusStorage {
             }
         }
 
-        // output the frequency
-        let mut result: FrequencyTable<String> = FrequencyTable::default();
-        for (tuple, count) in tuple_frequency {
-            result.push(FrequencyTableRow {
+        Ok(create_frequency_table(tuple_frequency))
+    }
+
+    /// Execute a frequency query and normalize the frequencies by the corpus
+    /// size.
+    ///
+    /// - `query` - The search query definition.
+    /// - `definition` - A list of frequency query definitions.
+    ///
+    /// Returns a frequency table of strings, which also includes the number of
+    /// matches per million token and how the matches are dispersed over the
+    /// documents. The size of the corpora and documents is measured in base
+    /// token and matches are assigned to the document of their first node.
+    pub fn frequency_normalized<S: AsRef<str>>(
+        &self,
+        query: SearchQuery<S>,
+        definition: Vec<FrequencyDefEntry>,
+    ) -> Result<NormalizedFrequencyTable<String>> {
+        let timeout = self.query_limits(&query)?;
+
+        // The number of base token for each document of all corpora
+        let mut document_sizes: Vec<u64> = Vec::new();
+        let mut total_size: u64 = 0;
+        // Total number of matches and number of matches per document for each tuple
+        let mut tuple_frequency: FxHashMap<Vec<String>, (usize, FxHashMap<usize, usize>)> =
+            FxHashMap::default();
+        let mut tuple_frequency_memory = timeout.memory_reservation();
+
+        for cn in query.corpus_names {
+            let prep =
+                self.prepare_query(cn.as_ref(), query.query, query.query_language, |db| {
+                    let mut additional_components =
+                        db.get_all_components(Some(AnnotationComponentType::PartOf), None);
+                    additional_components.extend(token_helper::necessary_components(db));
+                    additional_components
+                })?;
+
+            // acquire read-only lock and execute query
+            let lock = prep.db_entry.read()?;
+            let db: &AnnotationGraph = get_read_or_error(&lock)?;
+
+            let mut part_of_gs = Vec::new();
+            for c in db.get_all_components(Some(AnnotationComponentType::PartOf), None) {
+                if let Some(gs) = db.get_graphstorage_as_ref(&c) {
+                    part_of_gs.push(gs);
+                }
+            }
+
+            // count the base token of each document
+            let token_helper = TokenHelper::new(db)?;
+            let mut document_idx: FxHashMap<NodeID, usize> = FxHashMap::default();
+            for m in db.get_node_annos().exact_anno_search(
+                Some(&TOKEN_KEY.ns),
+                &TOKEN_KEY.name,
+                ValueSearch::Any,
+            ) {
+                let n = m?.node;
+                if token_helper.has_outgoing_coverage_edges(n)? {
+                    continue;
+                }
+                total_size += 1;
+                if let Some(doc) = find_document_for_node(db, &part_of_gs, n)? {
+                    let idx = *document_idx.entry(doc).or_insert_with(|| {
+                        document_sizes.push(0);
+                        document_sizes.len() - 1
+                    });
+                    document_sizes[idx] += 1;
+                }
+                if total_size.is_multiple_of(TIMEOUT_CHECK_TUPLE_COUNT) {
+                    timeout.check()?;
+                }
+            }
+
+            let annokeys = get_frequency_anno_keys(db, &prep.query, &definition)?;
+
+            let plan = ExecutionPlan::from_disjunction(
+                &prep.query,
+                db,
+                &self.query_config,
+                timeout.clone(),
+            )?;
+            let mut total_count: u64 = 0;
+
+            for mgroup in plan {
+                let mgroup = mgroup?;
+
+                let tuple = get_frequency_tuple(db, &annokeys, &mgroup)?;
+                if !tuple_frequency.contains_key(&tuple) {
+                    tuple_frequency_memory.grow(
+                        estimated_frequency_tuple_size(&tuple)
+                            + std::mem::size_of::<FxHashMap<usize, usize>>(),
+                    )?;
+                }
+                let (tuple_count, document_count) = tuple_frequency.entry(tuple).or_default();
+                *tuple_count += 1;
+
+                if let Some(m) = mgroup.first()
+                    && let Some(doc) = find_document_for_node(db, &part_of_gs, m.node)?
+                    && let Some(idx) = document_idx.get(&doc)
+                {
+                    if !document_count.contains_key(idx) {
+                        tuple_frequency_memory.grow(2 * std::mem::size_of::<usize>())?;
+                    }
+                    *document_count.entry(*idx).or_default() += 1;
+                }
+
+                total_count += 1;
+                if total_count.is_multiple_of(TIMEOUT_CHECK_TUPLE_COUNT) {
+                    timeout.check()?;
+                }
+            }
+        }
+
+        let mut result: NormalizedFrequencyTable<String> = NormalizedFrequencyTable::default();
+        for (tuple, (count, document_count)) in tuple_frequency {
+            let per_million = if total_size > 0 {
+                (count as f64) * 1_000_000.0 / (total_size as f64)
+            } else {
+                0.0
+            };
+            result.push(NormalizedFrequencyTableRow {
                 values: tuple,
                 count,
+                per_million,
+                range: document_count.len(),
+                juilland_d: juilland_d(&document_sizes, &document_count),
             });
         }
-
         // sort the output (largest to smallest)
         result.sort_by(|a, b| a.count.cmp(&b.count).reverse());
 
         Ok(result)
     }
 
+    /// Count the number of results for a `query`, grouped by the given criteria.
+    ///
+    /// - `query` - The search query definition.
+    /// - `group_by` - A list of criteria, e.g. the document or a metadata annotation.
+    ///
+    /// Returns a table with a row for each combination of group values and
+    /// the number of matches for this combination.
+    pub fn count_grouped<S: AsRef<str>>(
+        &self,
+        query: SearchQuery<S>,
+        group_by: Vec<GroupByEntry>,
+    ) -> Result<FrequencyTable<String>> {
+        let timeout = self.query_limits(&query)?;
+
+        let annis_doc_key = AnnoKey {
+            name: "doc".into(),
+            ns: ANNIS_NS.into(),
+        };
+        let needs_part_of = group_by
+            .iter()
+            .any(|g| !matches!(g, GroupByEntry::Annotation(_)));
+
+        let mut tuple_frequency: FxHashMap<Vec<String>, usize> = FxHashMap::default();
+        let mut tuple_frequency_memory = timeout.memory_reservation();
+
+        for cn in query.corpus_names {
+            let prep =
+                self.prepare_query(cn.as_ref(), query.query, query.query_language, |db| {
+                    if needs_part_of {
+                        db.get_all_components(Some(AnnotationComponentType::PartOf), None)
+                    } else {
+                        vec![]
+                    }
+                })?;
+
+            // acquire read-only lock and execute query
+            let lock = prep.db_entry.read()?;
+            let db: &AnnotationGraph = get_read_or_error(&lock)?;
+
+            // get the matching annotation keys for each group definition
+            let mut group_keys: Vec<(Option<usize>, Vec<AnnoKey>)> = Vec::default();
+            for g in group_by.iter() {
+                match g {
+                    GroupByEntry::Document => {
+                        group_keys.push((None, vec![annis_doc_key.clone()]));
+                    }
+                    GroupByEntry::Annotation(def) => {
+                        let node_ref = prep.query.get_variable_pos(&def.node_ref);
+                        let keys = if node_ref.is_some() {
+                            get_matching_anno_keys(db, &def.ns, &def.name)?
+                        } else {
+                            vec![]
+                        };
+                        group_keys.push((node_ref, keys));
+                    }
+                    GroupByEntry::Meta { ns, name } => {
+                        group_keys.push((None, get_matching_anno_keys(db, ns, name)?));
+                    }
+                }
+            }
+
+            let mut part_of_gs = Vec::new();
+            if needs_part_of {
+                for c in db.get_all_components(Some(AnnotationComponentType::PartOf), None) {
+                    if let Some(gs) = db.get_graphstorage_as_ref(&c) {
+                        part_of_gs.push(gs);
+                    }
+                }
+            }
+
+            let plan = ExecutionPlan::from_disjunction(
+                &prep.query,
+                db,
+                &self.query_config,
+                timeout.clone(),
+            )?;
+            let mut total_count: u64 = 0;
+
+            for mgroup in plan {
+                let mgroup = mgroup?;
+
+                // The document and meta data are taken from the (sub-) corpora
+                // containing the first node, ordered by their distance
+                let mut ancestors = Vec::new();
+                if let Some(m) = mgroup.first() {
+                    for gs in &part_of_gs {
+                        for a in gs.find_connected(m.node, 1, std::ops::Bound::Unbounded) {
+                            ancestors.push(a?);
+                        }
+                    }
+                }
+
+                let mut tuple: Vec<String> = Vec::with_capacity(group_by.len());
+                for (g, (node_ref, anno_keys)) in group_by.iter().zip(group_keys.iter()) {
+                    let mut tuple_val: String = String::default();
+                    match g {
+                        GroupByEntry::Document => {
+                            for a in ancestors.iter() {
+                                if db.get_node_annos().has_value_for_item(a, &annis_doc_key)? {
+                                    if let Some(doc_name) =
+                                        db.get_node_annos().get_value_for_item(a, &NODE_NAME_KEY)?
+                                    {
+                                        tuple_val = doc_name.to_string();
+                                    }
+                                    break;
+                                }
+                            }
+                        }
+                        GroupByEntry::Annotation(_) => {
+                            if let Some(m) = node_ref.and_then(|node_ref| mgroup.get(node_ref)) {
+                                for k in anno_keys.iter() {
+                                    if let Some(val) =
+                                        db.get_node_annos().get_value_for_item(&m.node, k)?
+                                    {
+                                        tuple_val = val.to_string();
+                                    }
+                                }
+                            }
+                        }
+                        GroupByEntry::Meta { .. } => {
+                            'ancestors: for a in ancestors.iter() {
+                                for k in anno_keys.iter() {
+                                    if let Some(val) =
+                                        db.get_node_annos().get_value_for_item(a, k)?
+                                    {
+                                        tuple_val = val.to_string();
+                                        break 'ancestors;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    tuple.push(tuple_val);
+                }
+                if !tuple_frequency.contains_key(&tuple) {
+                    tuple_frequency_memory.grow(estimated_frequency_tuple_size(&tuple))?;
+                }
+                let tuple_count: &mut usize = tuple_frequency.entry(tuple).or_insert(0);
+                *tuple_count += 1;
+
+                total_count += 1;
+                if total_count.is_multiple_of(TIMEOUT_CHECK_TUPLE_COUNT) {
+                    timeout.check()?;
+                }
+            }
+
+            timeout.check()?;
+        }
+
+        Ok(create_frequency_table(tuple_frequency))
+    }
+
     /// Parses a `query`and return a list of descriptions for its nodes.
     ///
     /// - `query` - The query to be analyzed.
@@ -2541,6 +3853,11 @@ impl CorpusStorage {
     }
 
     fn corpus_directory_on_disk(&self, corpus_name: &str) -> PathBuf {
+        self.find_snapshot(corpus_name)
+            .unwrap_or_else(|| self.escaped_directory_on_disk(corpus_name))
+    }
+
+    fn escaped_directory_on_disk(&self, corpus_name: &str) -> PathBuf {
         let escaped_corpus_name: Cow<str> =
             utf8_percent_encode(corpus_name, PATH_ENCODE_SET).into();
         let db_path: PathBuf = [self.db_dir.to_string_lossy().as_ref(), &escaped_corpus_name]
@@ -2549,6 +3866,65 @@ impl CorpusStorage {
         db_path
     }
 
+    fn snapshot_directory_on_disk(&self, corpus_name: &str, snapshot_name: &str) -> PathBuf {
+        let escaped_snapshot_name: Cow<str> =
+            utf8_percent_encode(snapshot_name, PATH_ENCODE_SET).into();
+        self.escaped_directory_on_disk(corpus_name)
+            .join(SNAPSHOTS_FOLDER)
+            .join(escaped_snapshot_name.as_ref())
+    }
+
+    /// Returns the directory of the snapshot if the name has the form
+    /// `corpus@snapshot` and there is no corpus with exactly this name.
+    fn find_snapshot(&self, corpus_name: &str) -> Option<PathBuf> {
+        let (corpus, snapshot) = corpus_name.rsplit_once(SNAPSHOT_SEPARATOR)?;
+        if self.escaped_directory_on_disk(corpus_name).is_dir() {
+            return None;
+        }
+        let snapshot_path = self.snapshot_directory_on_disk(corpus, snapshot);
+        snapshot_path.is_dir().then_some(snapshot_path)
+    }
+
+    /// Returns `true` if the name has the form `corpus@snapshot` for an
+    /// existing corpus and there is no corpus with exactly this name. Such
+    /// names refer to snapshots and can't be used for new corpora.
+    fn is_snapshot_name(&self, corpus_name: &str) -> bool {
+        if let Some((corpus, _)) = corpus_name.rsplit_once(SNAPSHOT_SEPARATOR) {
+            !self.escaped_directory_on_disk(corpus_name).is_dir()
+                && self.escaped_directory_on_disk(corpus).is_dir()
+        } else {
+            false
+        }
+    }
+
+    /// Remove all loaded snapshots of a corpus from the cache and invalidate
+    /// the cached query results of all its snapshots, including the ones that
+    /// are only stored on disk.
+    fn remove_snapshots_from_cache(
+        &self,
+        cache: &mut LinkedHashMap<String, Arc<RwLock<CacheEntry>>>,
+        corpus_name: &str,
+    ) -> Result<()> {
+        let snapshot_prefix = format!("{corpus_name}{SNAPSHOT_SEPARATOR}");
+        let mut snapshots: HashSet<String> = cache
+            .keys()
+            .filter(|name| name.starts_with(&snapshot_prefix))
+            .cloned()
+            .collect();
+        for snapshot in snapshots.iter() {
+            cache.remove(snapshot);
+        }
+        if self.escaped_directory_on_disk(corpus_name).is_dir() {
+            for snapshot_name in self.list_snapshots(corpus_name)? {
+                snapshots.insert(format!("{snapshot_prefix}{snapshot_name}"));
+            }
+        }
+        for snapshot in snapshots {
+            self.corpus_modified(&snapshot)?;
+        }
+        Ok(())
+    }
+
     /// Return the current size and loaded corpora as debug string.
     fn report_corpus_cache_info<S: Display>(&self, message: S) -> Result<()> {
         let cache = self.corpus_cache.read()?;
@@ -2598,6 +3974,73 @@ impl Drop for CorpusStorage {
     }
 }
 
+/// Create the match ID of a match group, which consists of the node
+/// annotation identifiers of all nodes included in the output, separated by
+/// spaces.
+fn match_group_description(
+    db: &AnnotationGraph,
+    query: &Disjunction,
+    m: &MatchGroup,
+    quirks_mode: bool,
+) -> Result<String> {
+    let mut match_desc = String::new();
+
+    let mut any_nodes_added = false;
+
+    for (node_nr, singlematch) in m.iter().enumerate() {
+        // check if query node actually should be included
+        let include_in_output = query
+            .get_variable_by_node_nr(node_nr)
+            .is_some_and(|var| query.is_included_in_output(&var));
+
+        if include_in_output {
+            if any_nodes_added {
+                match_desc.push(' ');
+            }
+            any_nodes_added = true;
+
+            let singlematch_anno_key = &singlematch.anno_key;
+            if singlematch_anno_key.ns != ANNIS_NS || singlematch_anno_key.name != NODE_TYPE {
+                if !singlematch_anno_key.ns.is_empty() {
+                    let encoded_anno_ns: Cow<str> =
+                        utf8_percent_encode(&singlematch_anno_key.ns, NODE_NAME_ENCODE_SET).into();
+                    match_desc.push_str(&encoded_anno_ns);
+                    match_desc.push_str("::");
+                }
+                let encoded_anno_name: Cow<str> =
+                    utf8_percent_encode(&singlematch_anno_key.name, NODE_NAME_ENCODE_SET).into();
+                match_desc.push_str(&encoded_anno_name);
+                match_desc.push_str("::");
+            }
+
+            if let Some(node_name) = db
+                .get_node_annos()
+                .get_value_for_item(&singlematch.node, &NODE_NAME_KEY)?
+            {
+                if quirks_mode {
+                    // Unescape all parts of the name and re-escape with
+                    // quirks-mode compatible character encoding set
+                    let re_encoded_name = node_name
+                        .split('/')
+                        .map(|n| {
+                            let decoded_name =
+                                percent_encoding::percent_decode_str(n).decode_utf8_lossy();
+                            let re_encoded_name: Cow<str> =
+                                utf8_percent_encode(&decoded_name, QUIRKS_SALT_URI_ENCODE_SET)
+                                    .into();
+                            re_encoded_name.to_string()
+                        })
+                        .join("/");
+                    match_desc.push_str(&re_encoded_name);
+                } else {
+                    match_desc.push_str(&node_name);
+                }
+            }
+        }
+    }
+    Ok(match_desc)
+}
+
 fn get_read_or_error<'a>(lock: &'a RwLockReadGuard<CacheEntry>) -> Result<&'a AnnotationGraph> {
     if let CacheEntry::Loaded(db) = &**lock {
         Ok(db)
@@ -2771,6 +4214,39 @@ fn create_lockfile_for_directory(db_dir: &Path) -> Result<File> {
     Ok(lock_file)
 }
 
+/// Recreate the folder `source` at `target` with hard links to all files. If
+/// a file can't be linked, e.g. because the file system does not support it,
+/// it is copied.
+fn hard_link_directory(source: &Path, target: &Path) -> Result<()> {
+    std::fs::create_dir_all(target)?;
+    for entry in source.read_dir()? {
+        let entry = entry?;
+        let target_path = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            hard_link_directory(&entry.path(), &target_path)?;
+        } else if std::fs::hard_link(entry.path(), &target_path).is_err() {
+            std::fs::copy(entry.path(), &target_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove all files and folders of the corpus at `db_path`, except its
+/// snapshots.
+fn remove_corpus_files(db_path: &Path) -> std::io::Result<()> {
+    for entry in db_path.read_dir()? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            if entry.file_name() != SNAPSHOTS_FOLDER {
+                std::fs::remove_dir_all(entry.path())?;
+            }
+        } else {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
 fn sync_wal_updates_in_background(
     db_entry: Arc<RwLock<CacheEntry>>,
     active_background_workers: Arc<(Mutex<usize>, Condvar)>,
@@ -0,0 +1,136 @@
+use lru::LruCache;
+
+use super::ResultOrder;
+use crate::annis::types::{CountExtra, ResultCacheStatistics};
+
+/// The kind of query result, including all arguments that change the result.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum ResultKind {
+    Count,
+    CountExtra,
+    Find {
+        offset: usize,
+        limit: Option<usize>,
+        order: ResultOrder,
+    },
+    Sample {
+        n: usize,
+        seed: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct ResultCacheKey {
+    /// Name of each corpus together with its modification counter.
+    pub(super) corpora: Vec<(String, u64)>,
+    /// Normalized description of the parsed query.
+    pub(super) query: String,
+    pub(super) quirks_mode: bool,
+    pub(super) kind: ResultKind,
+}
+
+impl ResultCacheKey {
+    fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.query.len()
+            + self
+                .corpora
+                .iter()
+                .map(|(name, _)| name.len() + std::mem::size_of::<(String, u64)>())
+                .sum::<usize>()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum CachedResult {
+    Count(u64),
+    CountExtra(CountExtra),
+    Find(Vec<String>),
+}
+
+impl CachedResult {
+    fn estimated_size(&self) -> usize {
+        let heap_size = match self {
+            CachedResult::Count(_) | CachedResult::CountExtra(_) => 0,
+            CachedResult::Find(matches) => matches
+                .iter()
+                .map(|m| m.len() + std::mem::size_of::<String>())
+                .sum(),
+        };
+        std::mem::size_of::<Self>() + heap_size
+    }
+}
+
+/// A cache for query results, which removes the least recently used results
+/// when the estimated memory size of all entries exceeds the maximum size.
+pub(super) struct ResultCache {
+    entries: LruCache<ResultCacheKey, (CachedResult, usize)>,
+    max_size: usize,
+    used_size: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl ResultCache {
+    pub(super) fn new(max_size: usize) -> ResultCache {
+        ResultCache {
+            entries: LruCache::unbounded(),
+            max_size,
+            used_size: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        self.max_size > 0
+    }
+
+    pub(super) fn get(&mut self, key: &ResultCacheKey) -> Option<CachedResult> {
+        if let Some((result, _)) = self.entries.get(key) {
+            self.hits += 1;
+            Some(result.clone())
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    pub(super) fn insert(&mut self, key: ResultCacheKey, result: CachedResult) {
+        let size = key.estimated_size() + result.estimated_size();
+        if size > self.max_size {
+            // Don't remove all other entries for a result that is too large anyway
+            return;
+        }
+        if let Some((_, old_size)) = self.entries.put(key, (result, size)) {
+            self.used_size -= old_size;
+        }
+        self.used_size += size;
+        self.remove_least_recently_used();
+    }
+
+    pub(super) fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.remove_least_recently_used();
+    }
+
+    pub(super) fn statistics(&self) -> ResultCacheStatistics {
+        ResultCacheStatistics {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            used_size: self.used_size,
+            max_size: self.max_size,
+        }
+    }
+
+    fn remove_least_recently_used(&mut self) {
+        while self.used_size > self.max_size {
+            if let Some((_, (_, size))) = self.entries.pop_lru() {
+                self.used_size -= size;
+            } else {
+                break;
+            }
+        }
+    }
+}
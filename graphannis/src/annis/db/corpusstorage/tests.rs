@@ -11,7 +11,10 @@ use crate::annis::db::corpusstorage::{CacheEntry, get_read_or_error};
 use crate::annis::db::example_generator::create_token_node;
 use crate::annis::db::{aql::model::AnnotationComponentType, example_generator};
 use crate::annis::errors::GraphAnnisError;
-use crate::corpusstorage::{ImportFormat, QueryLanguage, ResultOrder};
+use crate::corpusstorage::{
+    ApproximateCount, CancellationToken, CompressionOptions, FindCursor, GroupByEntry,
+    ImportFormat, IntegrityProblem, IntegrityReport, QueryLanguage, ResultOrder,
+};
 use crate::errors::Result;
 use crate::update::{GraphUpdate, UpdateEvent};
 use crate::{AnnotationGraph, CorpusStorage};
@@ -21,6 +24,7 @@ use graphannis_core::types::{Component, Edge};
 use graphannis_core::{graph::DEFAULT_NS, types::NodeID};
 use itertools::Itertools;
 use pretty_assertions::assert_eq;
+use rustc_hash::FxHashMap;
 
 use super::SearchQuery;
 
@@ -177,6 +181,7 @@ fn apply_update_add_and_delete_nodes() {
         query: "node",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
 
     let node_count = cs.count(node_query.clone()).unwrap();
@@ -187,6 +192,7 @@ fn apply_update_add_and_delete_nodes() {
         query: "node ->dep node",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let edge_count = cs.count(dep_query.clone()).unwrap();
     assert_eq!(1, edge_count);
@@ -803,6 +809,7 @@ fn subgraph_with_segmentation() {
         query: "node .seg,1,2 node",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
 
     assert_eq!(5, cs.count(query).unwrap());
@@ -1082,6 +1089,7 @@ fn find_with_multiple_corpora() {
         query: "pos=\"VB\"".into(),
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let results = cs
         .find(q.clone(), 0, Some(10), ResultOrder::Normal)
@@ -1118,6 +1126,579 @@ fn find_with_multiple_corpora() {
     assert_debug_snapshot!("find_with_multiple_corpora_inverted_5", results);
 }
 
+#[test]
+fn find_iter_with_multiple_corpora() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    let mut corpus_names = Vec::new();
+    for i in 0..3 {
+        let corpus_name = format!("{i}");
+        cs.import_from_fs(
+            &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+            ImportFormat::GraphML,
+            Some(corpus_name.clone()),
+            false,
+            true,
+            |_| {},
+        )
+        .unwrap();
+        corpus_names.push(corpus_name);
+    }
+
+    let q = SearchQuery {
+        corpus_names: &corpus_names,
+        query: "pos=\"VB\"",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    for order in [ResultOrder::Normal, ResultOrder::Inverted] {
+        let all = cs.find(q.clone(), 0, None, order).unwrap();
+        assert_eq!(12, all.len());
+        for (offset, limit) in [
+            (0, None),
+            (0, Some(5)),
+            (3, Some(6)),
+            (10, None),
+            (12, Some(1)),
+        ] {
+            let expected: Vec<_> = all
+                .iter()
+                .skip(offset)
+                .take(limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect();
+            let mut streamed = Vec::new();
+            cs.find_iter(q.clone(), offset, limit, order, |m| {
+                streamed.push(m);
+                true
+            })
+            .unwrap();
+            assert_eq!(expected, streamed);
+        }
+    }
+
+    // Stop after the first two matches, even if there are more
+    let mut streamed = Vec::new();
+    cs.find_iter(q.clone(), 0, None, ResultOrder::Normal, |m| {
+        streamed.push(m);
+        streamed.len() < 2
+    })
+    .unwrap();
+    assert_eq!(2, streamed.len());
+}
+
+#[test]
+fn find_with_cursor() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    let mut corpus_names = Vec::new();
+    for i in 0..3 {
+        let corpus_name = format!("{i}");
+        cs.import_from_fs(
+            &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+            ImportFormat::GraphML,
+            Some(corpus_name.clone()),
+            false,
+            true,
+            |_| {},
+        )
+        .unwrap();
+        corpus_names.push(corpus_name);
+    }
+
+    for query in ["pos=\"VB\"", "tok . tok"] {
+        let q = SearchQuery {
+            corpus_names: &corpus_names,
+            query,
+            query_language: QueryLanguage::AQL,
+            timeout: None,
+            cancellation: None,
+        };
+        for order in [ResultOrder::Normal, ResultOrder::Inverted] {
+            let all = cs.find(q.clone(), 0, None, order).unwrap();
+            for limit in [4, 7] {
+                // Get all pages by using the cursor of the previous page
+                let mut paged = Vec::new();
+                let mut cursor: Option<FindCursor> = None;
+                loop {
+                    let (page, next_cursor) = cs
+                        .find_with_cursor(q.clone(), cursor.as_ref(), limit, order)
+                        .unwrap();
+                    assert!(page.len() <= limit);
+                    paged.extend(page);
+                    if let Some(next_cursor) = next_cursor {
+                        // The cursor must survive a round trip as string
+                        let next_cursor: FindCursor = next_cursor.to_string().parse().unwrap();
+                        cursor = Some(next_cursor);
+                    } else {
+                        break;
+                    }
+                }
+                assert_eq!(all, paged);
+            }
+        }
+    }
+
+    let q = SearchQuery {
+        corpus_names: &corpus_names,
+        query: "pos=\"VB\"",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let (_, cursor) = cs
+        .find_with_cursor(q.clone(), None, 2, ResultOrder::Normal)
+        .unwrap();
+    let cursor = cursor.unwrap();
+    // Cursors are only valid for the same order and corpora
+    assert!(
+        cs.find_with_cursor(q.clone(), Some(&cursor), 2, ResultOrder::Inverted)
+            .is_err()
+    );
+    let other_corpora = SearchQuery {
+        corpus_names: &["1", "2"],
+        query: "pos=\"VB\"",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    assert!(
+        cs.find_with_cursor(other_corpora, Some(&cursor), 2, ResultOrder::Normal)
+            .is_err()
+    );
+    assert!(
+        cs.find_with_cursor(q, None, 2, ResultOrder::Randomized)
+            .is_err()
+    );
+    assert!("not a cursor".parse::<FindCursor>().is_err());
+}
+
+#[test]
+fn result_cache() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+    cs.set_result_cache_size(1024 * 1024).unwrap();
+
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "pos=\"VB\"",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    assert_eq!(4, cs.count(q.clone()).unwrap());
+    assert_eq!(4, cs.count(q.clone()).unwrap());
+    let stats = cs.result_cache_statistics().unwrap();
+    assert_eq!(1, stats.hits);
+    assert_eq!(1, stats.misses);
+    assert_eq!(1, stats.entries);
+
+    // The same query with different whitespace uses the same cache entry
+    let q_whitespace = SearchQuery {
+        corpus_names: &["test"],
+        query: "  pos=\"VB\"\n",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    assert_eq!(4, cs.count(q_whitespace).unwrap());
+    assert_eq!(2, cs.result_cache_statistics().unwrap().hits);
+
+    // Different kinds of results are cached separately
+    assert_eq!(4, cs.count_extra(q.clone()).unwrap().match_count);
+    let matches = cs.find(q.clone(), 0, None, ResultOrder::Normal).unwrap();
+    assert_eq!(
+        matches,
+        cs.find(q.clone(), 0, None, ResultOrder::Normal).unwrap()
+    );
+    let stats = cs.result_cache_statistics().unwrap();
+    assert_eq!(3, stats.hits);
+    assert_eq!(3, stats.misses);
+    assert_eq!(3, stats.entries);
+    assert!(stats.used_size > 0);
+
+    // Updating the corpus must invalidate the cached results
+    let mut u = GraphUpdate::new();
+    u.add_event(UpdateEvent::AddNode {
+        node_name: "rootCorpus/subCorpus1/doc1#newToken".to_string(),
+        node_type: "node".to_string(),
+    })
+    .unwrap();
+    u.add_event(UpdateEvent::AddNodeLabel {
+        node_name: "rootCorpus/subCorpus1/doc1#newToken".to_string(),
+        anno_ns: "salt".to_string(),
+        anno_name: "pos".to_string(),
+        anno_value: "VB".to_string(),
+    })
+    .unwrap();
+    cs.apply_update("test", &mut u).unwrap();
+    assert_eq!(5, cs.count(q.clone()).unwrap());
+    assert_eq!(3, cs.result_cache_statistics().unwrap().hits);
+
+    // Results that are larger than the cache are not added
+    cs.set_result_cache_size(1024).unwrap();
+    let q_tok = SearchQuery {
+        corpus_names: &["test"],
+        query: "tok",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    cs.find(q_tok, 0, None, ResultOrder::Normal).unwrap();
+    let stats = cs.result_cache_statistics().unwrap();
+    assert!(stats.used_size <= 1024);
+    assert_eq!(1024, stats.max_size);
+
+    // Disable the cache
+    cs.set_result_cache_size(0).unwrap();
+    cs.count(q.clone()).unwrap();
+    let stats = cs.result_cache_statistics().unwrap();
+    assert_eq!(0, stats.entries);
+    assert_eq!(3, stats.hits);
+}
+
+#[test]
+fn cancel_query() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    let cancellation = CancellationToken::new();
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "tok . tok",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: Some(cancellation.clone()),
+    };
+    // The query is not affected as long as the token is not cancelled
+    assert_eq!(40, cs.count(q.clone()).unwrap());
+
+    cancellation.cancel();
+    assert!(matches!(
+        cs.count(q.clone()),
+        Err(GraphAnnisError::Cancelled)
+    ));
+    assert!(matches!(
+        cs.find(q.clone(), 0, None, ResultOrder::Normal),
+        Err(GraphAnnisError::Cancelled)
+    ));
+    assert!(matches!(
+        cs.frequency(q, vec!["1:tok".parse().unwrap()]),
+        Err(GraphAnnisError::Cancelled)
+    ));
+    assert!(matches!(
+        cs.subgraph_for_query(
+            "test",
+            "tok . tok",
+            QueryLanguage::AQL,
+            None,
+            Some(cancellation)
+        ),
+        Err(GraphAnnisError::Cancelled)
+    ));
+}
+
+#[test]
+fn count_approximate() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "tok . tok",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+
+    // All 4 documents are searched
+    let result = cs.count_approximate(q.clone(), 4).unwrap();
+    assert_eq!(
+        ApproximateCount {
+            match_count: 40,
+            lower_bound: 40,
+            upper_bound: 40,
+            approximate: false,
+        },
+        result
+    );
+
+    // Each document has the same number of matches, so the estimation must
+    // be exact even if only a sample is searched
+    let result = cs.count_approximate(q, 2).unwrap();
+    assert_eq!(
+        ApproximateCount {
+            match_count: 40,
+            lower_bound: 40,
+            upper_bound: 40,
+            approximate: true,
+        },
+        result
+    );
+
+    // A different number of matches per document results in an interval
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "pos=\"NN\"",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let result = cs.count_approximate(q.clone(), 3).unwrap();
+    assert!(result.approximate);
+    assert!(result.lower_bound <= result.match_count);
+    assert!(result.match_count <= result.upper_bound);
+
+    // At least one document must be searched
+    assert!(matches!(
+        cs.count_approximate(q, 0),
+        Err(GraphAnnisError::EmptyDocumentSample)
+    ));
+}
+
+#[test]
+fn sample() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "tok . tok",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let all_matches = cs.find(q.clone(), 0, None, ResultOrder::NotSorted).unwrap();
+    assert_eq!(40, all_matches.len());
+
+    let sample = cs.sample(q.clone(), 5, 42).unwrap();
+    assert_eq!(5, sample.len());
+    assert!(sample.iter().all(|m| all_matches.contains(m)));
+    // The sample must not contain the same match twice
+    assert_eq!(5, sample.iter().unique().count());
+
+    // The same seed always gives the same sample
+    assert_eq!(sample, cs.sample(q.clone(), 5, 42).unwrap());
+    // The sample contains all matches in the order they have been found if
+    // there are less than requested
+    assert_eq!(all_matches, cs.sample(q.clone(), 100, 42).unwrap());
+
+    assert!(cs.sample(q, 0, 42).unwrap().is_empty());
+}
+
+#[test]
+fn query_memory_limit() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "tok",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let unlimited_matches = cs.find(q.clone(), 0, Some(3), ResultOrder::Normal).unwrap();
+
+    cs.set_query_memory_limit(Some(500)).unwrap();
+
+    // The frequency table can't be held in memory and the query is aborted
+    assert!(matches!(
+        cs.frequency(q.clone(), vec!["1:tok".parse().unwrap()]),
+        Err(GraphAnnisError::MemoryLimitExceeded(500))
+    ));
+    // Sorting the matches spills them to disk and still returns the same result
+    let limited_matches = cs.find(q.clone(), 0, Some(3), ResultOrder::Normal).unwrap();
+    assert_eq!(unlimited_matches, limited_matches);
+
+    cs.set_query_memory_limit(None).unwrap();
+    assert!(cs.frequency(q, vec!["1:tok".parse().unwrap()]).is_ok());
+}
+
+#[test]
+fn equi_joins() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    let query = |aql: &'static str| SearchQuery {
+        corpus_names: &["test"],
+        query: aql,
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+
+    // Both sides are filtered token searches, which are sorted by text
+    let merge_query = "tok & tok & #1:tokenarity=1 & #2:tokenarity=1 & #1 _ident_ #2";
+    let plan = cs.plan(&["test"], merge_query, QueryLanguage::AQL).unwrap();
+    assert!(plan.contains("mergejoin"), "{}", plan);
+    assert_eq!(44, cs.count(query(merge_query)).unwrap());
+    let index_join_results = cs
+        .find(
+            query("tok & tok & #1 _ident_ #2"),
+            0,
+            None,
+            ResultOrder::Normal,
+        )
+        .unwrap();
+    let merge_join_results = cs
+        .find(query(merge_query), 0, None, ResultOrder::Normal)
+        .unwrap();
+    assert_eq!(index_join_results, merge_join_results);
+
+    // Annotation searches are not sorted, so a hash join is used
+    let hash_query = "pos & pos & #1:tokenarity=1 & #2:tokenarity=1 & #1 _ident_ #2";
+    let plan = cs.plan(&["test"], hash_query, QueryLanguage::AQL).unwrap();
+    assert!(plan.contains("hashjoin"), "{}", plan);
+    assert_eq!(
+        cs.count(query("pos & #1:tokenarity=1")).unwrap(),
+        cs.count(query(hash_query)).unwrap()
+    );
+
+    // Value comparisons use a hash join, but the negated comparison can't be
+    // executed as equi-join and is only a filter. The expected counts are the
+    // ones of the nested loop plan used before hash joins were introduced.
+    let equal_query = "pos & pos & #1 == #2 & #1 .* #2";
+    let plan = cs.plan(&["test"], equal_query, QueryLanguage::AQL).unwrap();
+    assert!(plan.contains("hashjoin"), "{}", plan);
+    let not_equal_query = "pos & pos & #1 != #2 & #1 .* #2";
+    let plan = cs
+        .plan(&["test"], not_equal_query, QueryLanguage::AQL)
+        .unwrap();
+    assert!(!plan.contains("hashjoin"), "{}", plan);
+
+    assert_eq!(5, cs.count(query(equal_query)).unwrap());
+    assert_eq!(215, cs.count(query(not_equal_query)).unwrap());
+    // Both comparisons partition the results of the join without comparison
+    assert_eq!(220, cs.count(query("pos & pos & #1 .* #2")).unwrap());
+}
+
+#[test]
+fn analyze() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "pos=\"VB\" . tok | pos=\"VB\"",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let expected_count = cs.count(q.clone()).unwrap();
+
+    let result = cs.analyze(q).unwrap();
+    assert_eq!(1, result.len());
+    let analysis = &result[0];
+    assert_eq!("test", analysis.corpus_name);
+    assert_eq!(expected_count, analysis.match_count);
+    assert_eq!(2, analysis.alternatives.len());
+
+    // The single node search produces the matches directly
+    let node_search = analysis.alternatives[1].as_ref().unwrap();
+    assert_eq!(Some(1), node_search.node_nr);
+    assert_eq!(Some(4), node_search.actual_output);
+    assert!(node_search.estimation_error.is_some());
+    assert!(node_search.children.is_empty());
+
+    // The join has the node searches as children
+    let join = analysis.alternatives[0].as_ref().unwrap();
+    assert_eq!(None, join.node_nr);
+    assert_eq!(Some(4), join.actual_output);
+    assert_eq!(2, join.children.len());
+    assert_eq!(Some(4), join.children[0].actual_output);
+    assert!(join.time_ms >= join.children[0].time_ms);
+
+    let text = analysis.to_string();
+    assert!(text.contains("actual: 4"));
+    assert!(text.ends_with(&format!(
+        "{} matches in {:.3} ms",
+        expected_count, analysis.time_ms
+    )));
+}
+
 fn compare_edge_annos(
     annos1: &dyn EdgeAnnotationStorage,
     annos2: &dyn EdgeAnnotationStorage,
@@ -1254,6 +1835,128 @@ fn compare_corpora(g1: &AnnotationGraph, g2: &AnnotationGraph) {
     }
 }
 
+#[test]
+fn count_grouped() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "tok",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let result = cs.count_grouped(q, vec![GroupByEntry::Document]).unwrap();
+    let result: Vec<_> = result
+        .into_iter()
+        .map(|row| (row.values, row.count))
+        .sorted()
+        .collect();
+    assert_eq!(
+        vec![
+            (vec!["rootCorpus/subCorpus1/doc1".to_string()], 11),
+            (vec!["rootCorpus/subCorpus1/doc2".to_string()], 11),
+            (vec!["rootCorpus/subCorpus2/doc3".to_string()], 11),
+            (vec!["rootCorpus/subCorpus2/doc4".to_string()], 11),
+        ],
+        result
+    );
+
+    // The meta data can be defined at any corpus containing the match
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "pos=\"VB\"",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let group_by: Vec<GroupByEntry> = ["meta:annis::relannis-version", "1:pos"]
+        .iter()
+        .map(|g| g.parse().unwrap())
+        .collect();
+    let result = cs.count_grouped(q, group_by).unwrap();
+    assert_eq!(1, result.len());
+    assert_eq!(vec!["3.3", "VB"], result[0].values);
+    assert_eq!(4, result[0].count);
+}
+
+#[test]
+fn frequency_normalized() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    // Each of the 4 documents has 11 token and one "VB"
+    let q = SearchQuery {
+        corpus_names: &["test"],
+        query: "pos=/VB.*/",
+        query_language: QueryLanguage::AQL,
+        timeout: None,
+        cancellation: None,
+    };
+    let definition = vec!["1:pos".parse().unwrap()];
+    let raw = cs.frequency(q.clone(), definition.clone()).unwrap();
+    let result = cs.frequency_normalized(q, definition).unwrap();
+    assert_eq!(raw.len(), result.len());
+
+    let vb = result.iter().find(|row| row.values == vec!["VB"]).unwrap();
+    assert_eq!(4, vb.count);
+    assert_eq!(4, vb.range);
+    assert!((vb.per_million - 4.0 * 1_000_000.0 / 44.0).abs() < 0.001);
+    assert!((vb.juilland_d.unwrap() - 1.0).abs() < 0.001);
+
+    for row in result {
+        let raw_row = raw.iter().find(|r| r.values == row.values).unwrap();
+        assert_eq!(raw_row.count, row.count);
+        assert!(row.range <= 4);
+        let d = row.juilland_d.unwrap();
+        assert!((0.0..=1.0 + f64::EPSILON).contains(&d));
+    }
+}
+
+#[test]
+fn juilland_d_for_documents() {
+    let document_sizes = vec![100, 100, 200, 100];
+    // Same relative frequency in all documents
+    let even: FxHashMap<usize, usize> = [(0, 1), (1, 1), (2, 2), (3, 1)].into_iter().collect();
+    assert!((super::juilland_d(&document_sizes, &even).unwrap() - 1.0).abs() < 0.0001);
+    // Only in a single document
+    let single: FxHashMap<usize, usize> = [(2, 10)].into_iter().collect();
+    assert!(super::juilland_d(&document_sizes, &single).unwrap().abs() < 0.0001);
+    // Two documents: relative frequencies 0.02 and 0.01
+    let uneven: FxHashMap<usize, usize> = [(0, 2), (1, 1)].into_iter().collect();
+    let d = super::juilland_d(&[100, 100], &uneven).unwrap();
+    assert!((d - (1.0 - 1.0 / 3.0)).abs() < 0.0001);
+
+    assert_eq!(None, super::juilland_d(&[100], &even));
+    assert_eq!(
+        None,
+        super::juilland_d(&document_sizes, &FxHashMap::default())
+    );
+}
+
 #[test]
 fn import_salt_sample() {
     let tmp = tempfile::tempdir().unwrap();
@@ -1332,6 +2035,7 @@ fn import_special_character_corpus_name() {
         query: "lemma",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let token = cs.count_extra(q.clone()).unwrap();
     assert_eq!(44, token.match_count);
@@ -1349,6 +2053,7 @@ fn import_special_character_corpus_name() {
         query: "lemma",
         query_language: QueryLanguage::AQLQuirksV3,
         timeout: None,
+        cancellation: None,
     };
     let matches_quirks = cs.find(q_quirks, 0, Some(1), ResultOrder::Normal).unwrap();
     assert_eq!(1, matches_quirks.len());
@@ -1401,6 +2106,121 @@ fn import_relative_corpus_with_linked_file() {
     assert_eq!("The content of this file is not important.", file_content);
 }
 
+#[test]
+fn check_integrity() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/CorpusWithLinkedFile.graphml"),
+        ImportFormat::GraphML,
+        None,
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(
+        IntegrityReport::default(),
+        cs.check_integrity("CorpusWithLinkedFile", false).unwrap()
+    );
+
+    // Simulate an interrupted save operation, broken statistics and a deleted linked file
+    let corpus_dir = tmp.path().join("CorpusWithLinkedFile");
+    let temporary_folder = corpus_dir.join("temporary-graphannis-backupABC");
+    std::fs::create_dir(&temporary_folder).unwrap();
+    let stats_file = corpus_dir.join("current/gs/Ordering/annis/stats.toml");
+    assert!(stats_file.is_file());
+    std::fs::write(&stats_file, "not a valid [statistics").unwrap();
+    let linked_file = corpus_dir.join("files/linked_file.txt");
+    std::fs::remove_file(&linked_file).unwrap();
+
+    let report = cs.check_integrity("CorpusWithLinkedFile", false).unwrap();
+    assert!(report.repaired.is_empty());
+    assert_eq!(3, report.problems.len());
+    assert_eq!(
+        IntegrityProblem::TemporaryFolder {
+            path: temporary_folder.clone()
+        },
+        report.problems[0]
+    );
+    assert!(matches!(
+        &report.problems[1],
+        IntegrityProblem::InvalidComponentStatistics { component, .. } if component == "Ordering/annis/"
+    ));
+    assert!(matches!(
+        &report.problems[2],
+        IntegrityProblem::MissingLinkedFile { node_name, .. } if node_name == "linked_file.txt"
+    ));
+
+    // Only the missing file can't be repaired
+    let report = cs.check_integrity("CorpusWithLinkedFile", true).unwrap();
+    assert_eq!(2, report.repaired.len());
+    assert_eq!(1, report.problems.len());
+    assert!(!temporary_folder.exists());
+    assert!(!stats_file.exists());
+
+    let report = cs.check_integrity("CorpusWithLinkedFile", false).unwrap();
+    assert!(report.repaired.is_empty());
+    assert_eq!(1, report.problems.len());
+
+    // The corpus can be loaded again
+    assert!(cs.info("CorpusWithLinkedFile").is_ok());
+    assert!(matches!(
+        cs.check_integrity("NotExisting", false),
+        Err(GraphAnnisError::NoSuchCorpus(_))
+    ));
+}
+
+#[test]
+fn check_integrity_keeps_update_logs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/CorpusWithLinkedFile.graphml"),
+        ImportFormat::GraphML,
+        None,
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+
+    // Simulate an interrupted and an unreadable update batch
+    let current_dir = tmp.path().join("CorpusWithLinkedFile/current");
+    let incomplete_log = current_dir.join("temporary-graphannis-update-logABC");
+    std::fs::create_dir(&incomplete_log).unwrap();
+    let unreadable_log = current_dir.join("update_log.bin");
+    std::fs::write(&unreadable_log, "not an update").unwrap();
+
+    // Checking without repairing must not remove any of the update logs
+    let report = cs.check_integrity("CorpusWithLinkedFile", false).unwrap();
+    assert!(report.repaired.is_empty());
+    assert_eq!(2, report.problems.len());
+    assert_eq!(
+        IntegrityProblem::IncompleteUpdateLog {
+            path: incomplete_log.clone()
+        },
+        report.problems[0]
+    );
+    assert!(matches!(
+        &report.problems[1],
+        IntegrityProblem::InvalidUpdateLog { .. }
+    ));
+    assert!(incomplete_log.is_dir());
+    assert!(unreadable_log.is_file());
+
+    // Only the incomplete batch can be removed without losing data
+    let report = cs.check_integrity("CorpusWithLinkedFile", true).unwrap();
+    assert_eq!(1, report.repaired.len());
+    assert_eq!(1, report.problems.len());
+    assert!(!incomplete_log.exists());
+    assert!(unreadable_log.is_file());
+}
+
 #[test]
 fn import_existing_cached_corpus_no_overwrite() {
     let tmp = tempfile::tempdir().unwrap();
@@ -1546,7 +2366,8 @@ fn load_legacy_binary_corpus_1() {
             corpus_names: &["sample-memory-based-1.5"],
             query_language: QueryLanguage::AQL,
             query: "tok",
-            timeout: None
+            timeout: None,
+            cancellation: None,
         })
         .unwrap()
     );
@@ -1581,7 +2402,8 @@ fn load_legacy_binary_corpus_3() {
             corpus_names: &["sample-memory-based-3.2"],
             query_language: QueryLanguage::AQL,
             query: "tok",
-            timeout: None
+            timeout: None,
+            cancellation: None,
         })
         .unwrap()
     );
@@ -1641,6 +2463,7 @@ fn optional_node_first_in_query() {
         query: "node? !> Inf-Struct=\"contrast-focus\"",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs.find(q, 0, None, ResultOrder::Normal).unwrap();
     assert_eq!(4, result.len());
@@ -1668,6 +2491,7 @@ fn optional_node_first_in_query() {
         query: "Inf-Struct=\"contrast-focus\" & node? & #2 !> #1",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs.find(q, 0, None, ResultOrder::Normal).unwrap();
     assert_eq!(4, result.len());
@@ -1744,6 +2568,7 @@ fn reoptimize_corpussizeconfig() {
             query_language: QueryLanguage::AQL,
             query: "tok",
             timeout: None,
+            cancellation: None,
         })
         .unwrap();
     assert_eq!(44, token_count);
@@ -1783,6 +2608,7 @@ fn reoptimize_corpussizeconfig() {
             query_language: QueryLanguage::AQL,
             query: "tok",
             timeout: None,
+            cancellation: None,
         })
         .unwrap();
     assert_eq!(45, token_count);
@@ -1793,3 +2619,253 @@ fn reoptimize_corpussizeconfig() {
         toml::to_string_pretty(&corpus_config).unwrap()
     );
 }
+
+#[test]
+fn snapshots() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+    cs.create_snapshot("test", "v1").unwrap();
+
+    // Unchanged files are shared with the corpus
+    let stats_file = "current/gs/Ordering/annis/stats.toml";
+    assert!(
+        is_same_file(
+            tmp.path().join("test").join(stats_file),
+            tmp.path().join("test/snapshots/v1").join(stats_file)
+        )
+        .unwrap()
+    );
+
+    let mut u = GraphUpdate::new();
+    u.add_event(UpdateEvent::AddNode {
+        node_name: "rootCorpus/subCorpus1/doc1#newToken".to_string(),
+        node_type: "node".to_string(),
+    })
+    .unwrap();
+    u.add_event(UpdateEvent::AddNodeLabel {
+        node_name: "rootCorpus/subCorpus1/doc1#newToken".to_string(),
+        anno_ns: "salt".to_string(),
+        anno_name: "pos".to_string(),
+        anno_value: "VB".to_string(),
+    })
+    .unwrap();
+    cs.apply_update("test", &mut u).unwrap();
+    cs.create_snapshot("test", "v2").unwrap();
+
+    let count = |cs: &CorpusStorage, corpus_name: &str| {
+        cs.count(SearchQuery {
+            corpus_names: &[corpus_name],
+            query: "pos=\"VB\"",
+            query_language: QueryLanguage::AQL,
+            timeout: None,
+            cancellation: None,
+        })
+    };
+    assert_eq!(5, count(&cs, "test").unwrap());
+    assert_eq!(4, count(&cs, "test@v1").unwrap());
+    assert_eq!(5, count(&cs, "test@v2").unwrap());
+
+    // Snapshots are no corpora on their own and can't be changed
+    let corpora: Vec<String> = cs.list().unwrap().into_iter().map(|c| c.name).collect();
+    assert_eq!(vec!["test".to_string()], corpora);
+    assert_eq!(vec!["v1", "v2"], cs.list_snapshots("test").unwrap());
+    assert!(matches!(
+        cs.apply_update("test@v1", &mut GraphUpdate::new()),
+        Err(GraphAnnisError::ReadOnlySnapshot(_))
+    ));
+    assert!(matches!(
+        cs.create_snapshot("test", "v1"),
+        Err(GraphAnnisError::SnapshotExists(_))
+    ));
+    assert!(matches!(
+        cs.create_snapshot("test", "v@3"),
+        Err(GraphAnnisError::InvalidSnapshotName(_))
+    ));
+    assert!(matches!(
+        cs.create_snapshot("test@v1", "v3"),
+        Err(GraphAnnisError::ReadOnlySnapshot(_))
+    ));
+    assert!(matches!(
+        cs.reoptimize_implementation("test@v1", true),
+        Err(GraphAnnisError::ReadOnlySnapshot(_))
+    ));
+    assert!(matches!(
+        cs.delete("test@v1"),
+        Err(GraphAnnisError::ReadOnlySnapshot(_))
+    ));
+    assert!(matches!(
+        cs.create_empty_corpus("test@v3", false),
+        Err(GraphAnnisError::ReadOnlySnapshot(_))
+    ));
+    for corpus_name in ["test@v1", "test@v3"] {
+        assert!(matches!(
+            cs.import_from_fs(
+                &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+                ImportFormat::GraphML,
+                Some(corpus_name.into()),
+                false,
+                true,
+                |_| {},
+            ),
+            Err(GraphAnnisError::ReadOnlySnapshot(_))
+        ));
+    }
+    assert_eq!(4, count(&cs, "test@v1").unwrap());
+
+    // Overwriting the corpus keeps the snapshots, but removes them from the
+    // corpus cache like deleting the corpus
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+    assert!(!cs.corpus_cache.read().unwrap().contains_key("test@v1"));
+    assert_eq!(4, count(&cs, "test").unwrap());
+    assert_eq!(4, count(&cs, "test@v1").unwrap());
+    assert_eq!(5, count(&cs, "test@v2").unwrap());
+    assert_eq!(vec!["v1", "v2"], cs.list_snapshots("test").unwrap());
+
+    // The snapshots are kept when the corpus storage is opened again
+    std::mem::drop(cs);
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    assert_eq!(4, count(&cs, "test").unwrap());
+    assert_eq!(4, count(&cs, "test@v1").unwrap());
+
+    assert_eq!(true, cs.delete_snapshot("test", "v1").unwrap());
+    assert_eq!(false, cs.delete_snapshot("test", "v1").unwrap());
+    assert!(matches!(
+        count(&cs, "test@v1"),
+        Err(GraphAnnisError::NoSuchCorpus(_))
+    ));
+    assert_eq!(vec!["v2"], cs.list_snapshots("test").unwrap());
+    assert_eq!(5, count(&cs, "test@v2").unwrap());
+}
+
+#[test]
+fn snapshot_results_are_not_cached_after_delete() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.set_result_cache_size(1024 * 1024).unwrap();
+    let count = |cs: &CorpusStorage| {
+        cs.count(SearchQuery {
+            corpus_names: &["test@v1"],
+            query: "tok",
+            query_language: QueryLanguage::AQL,
+            timeout: None,
+            cancellation: None,
+        })
+        .unwrap()
+    };
+
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        true,
+        |_| {},
+    )
+    .unwrap();
+    cs.create_snapshot("test", "v1").unwrap();
+    assert_eq!(44, count(&cs));
+
+    // Unload the snapshot, so that only its folder on disk is left
+    cs.unload("test@v1").unwrap();
+    assert!(!cs.corpus_cache.read().unwrap().contains_key("test@v1"));
+
+    // Re-create the snapshot with the same name for a different corpus
+    cs.delete("test").unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/single_sentence.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        false,
+        false,
+        |_| {},
+    )
+    .unwrap();
+    cs.create_snapshot("test", "v1").unwrap();
+    assert_eq!(8, count(&cs));
+}
+
+#[test]
+fn annotation_compression() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    cs.import_from_fs(
+        &cargo_dir.join("tests/SaltSampleCorpus.graphml"),
+        ImportFormat::GraphML,
+        Some("test".into()),
+        true,
+        true,
+        |_| {},
+    )
+    .unwrap();
+    cs.set_annotation_compression(
+        "test",
+        CompressionOptions {
+            block_compression: true,
+            max_dictionary_size: 16,
+        },
+    )
+    .unwrap();
+
+    let count = |cs: &CorpusStorage| {
+        cs.count(SearchQuery {
+            corpus_names: &["test"],
+            query: "pos=\"VB\"",
+            query_language: QueryLanguage::AQL,
+            timeout: None,
+            cancellation: None,
+        })
+    };
+    assert_eq!(4, count(&cs).unwrap());
+
+    // The options are used when the corpus is loaded and saved again
+    cs.unload("test").unwrap();
+    assert_eq!(4, count(&cs).unwrap());
+    let mut u = GraphUpdate::new();
+    u.add_event(UpdateEvent::AddNode {
+        node_name: "rootCorpus/subCorpus1/doc1#newToken".to_string(),
+        node_type: "node".to_string(),
+    })
+    .unwrap();
+    u.add_event(UpdateEvent::AddNodeLabel {
+        node_name: "rootCorpus/subCorpus1/doc1#newToken".to_string(),
+        anno_ns: "salt".to_string(),
+        anno_name: "pos".to_string(),
+        anno_value: "VB".to_string(),
+    })
+    .unwrap();
+    cs.apply_update("test", &mut u).unwrap();
+    drop(cs);
+
+    let cs = CorpusStorage::with_auto_cache_size(tmp.path(), true).unwrap();
+    assert_eq!(5, count(&cs).unwrap());
+    let compression_file = tmp
+        .path()
+        .join("test/current/nodes_diskmap_v2/compression.toml");
+    let options: CompressionOptions =
+        toml::from_str(&std::fs::read_to_string(compression_file).unwrap()).unwrap();
+    assert!(options.block_compression);
+    assert_eq!(16, options.max_dictionary_size);
+}
@@ -0,0 +1,92 @@
+use super::nodesearch::NodeSearch;
+use super::{ExecutionNode, ExecutionNodeDesc};
+use crate::errors::Result;
+use graphannis_core::annostorage::MatchGroup;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// The actual number of produced tuples and the time spent in an execution
+/// node, collected while the query is executed.
+#[derive(Debug, Default)]
+pub struct ExecutionStatistics {
+    executed: AtomicBool,
+    output: AtomicUsize,
+    nanos: AtomicU64,
+}
+
+impl ExecutionStatistics {
+    /// Returns the number of produced tuples or `None` if the execution node
+    /// has never been iterated.
+    pub fn actual_output(&self) -> Option<usize> {
+        if self.executed.load(Ordering::Relaxed) {
+            Some(self.output.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    /// Time spent in the execution node, including the time of its child
+    /// nodes.
+    pub fn time(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Wraps an execution node and collects the [`ExecutionStatistics`] while
+/// iterating over it. The statistics are shared with the description of the
+/// node, so they are also available in the description of all parent nodes.
+pub struct StatisticsCollector<'a> {
+    inner: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+    desc: Option<ExecutionNodeDesc>,
+    statistics: Arc<ExecutionStatistics>,
+}
+
+impl<'a> StatisticsCollector<'a> {
+    pub fn new(
+        inner: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+    ) -> StatisticsCollector<'a> {
+        let statistics = Arc::new(ExecutionStatistics::default());
+        let desc = inner.get_desc().map(|orig_desc| {
+            let mut desc = orig_desc.clone();
+            desc.statistics = Some(statistics.clone());
+            desc
+        });
+        StatisticsCollector {
+            inner,
+            desc,
+            statistics,
+        }
+    }
+}
+
+impl ExecutionNode for StatisticsCollector<'_> {
+    fn as_nodesearch(&self) -> Option<&NodeSearch<'_>> {
+        self.inner.as_nodesearch()
+    }
+
+    fn get_desc(&self) -> Option<&ExecutionNodeDesc> {
+        self.desc.as_ref()
+    }
+
+    fn is_sorted_by_text(&self) -> bool {
+        self.inner.is_sorted_by_text()
+    }
+}
+
+impl Iterator for StatisticsCollector<'_> {
+    type Item = Result<MatchGroup>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = Instant::now();
+        let result = self.inner.next();
+        let elapsed = start.elapsed().as_nanos() as u64;
+
+        self.statistics.executed.store(true, Ordering::Relaxed);
+        self.statistics.nanos.fetch_add(elapsed, Ordering::Relaxed);
+        if let Some(Ok(_)) = &result {
+            self.statistics.output.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+}
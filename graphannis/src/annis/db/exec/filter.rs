@@ -9,6 +9,8 @@ use crate::errors::Result;
 pub struct Filter<'a> {
     it: Box<dyn Iterator<Item = Result<MatchGroup>> + 'a>,
     desc: Option<ExecutionNodeDesc>,
+    /// Filtering does not change the order of the tuples
+    is_sorted: bool,
 }
 
 fn calculate_binary_outputsize(op: &dyn BinaryOperatorBase, num_tuples: usize) -> Result<usize> {
@@ -66,12 +68,14 @@ impl<'a> Filter<'a> {
                     op_entry.args.left, op_entry.op, op_entry.args.right
                 ),
                 cost: cost_est,
+                statistics: None,
                 lhs: Some(Box::new(orig_desc.clone())),
                 rhs: None,
             })
         } else {
             None
         };
+        let is_sorted = exec.is_sorted_by_text();
         let it = exec
             .map(move |tuple| {
                 let tuple = tuple?;
@@ -82,6 +86,7 @@ impl<'a> Filter<'a> {
         Ok(Filter {
             desc,
             it: Box::new(it),
+            is_sorted,
         })
     }
 
@@ -107,12 +112,14 @@ impl<'a> Filter<'a> {
                 impl_description: String::from("filter"),
                 query_fragment: format!("#{}{}", op_entry.node_nr, op_entry.op,),
                 cost: cost_est,
+                statistics: None,
                 lhs: Some(Box::new(orig_desc.clone())),
                 rhs: None,
             })
         } else {
             None
         };
+        let is_sorted = exec.is_sorted_by_text();
         let it = exec
             .map(move |tuple| {
                 let tuple = tuple?;
@@ -126,6 +133,52 @@ impl<'a> Filter<'a> {
         Filter {
             desc,
             it: Box::new(it),
+            is_sorted,
+        }
+    }
+
+    /// Create a filter that only keeps the tuples for which the
+    /// `has_result` function of a subquery returns `false`.
+    pub fn new_not_exists<F>(
+        exec: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+        query_fragment: String,
+        has_result: F,
+    ) -> Filter<'a>
+    where
+        F: Fn(&MatchGroup) -> Result<bool> + 'a,
+    {
+        let desc = exec.get_desc().map(|orig_desc| {
+            let cost_est = orig_desc.cost.as_ref().map(|orig_cost| CostEstimate {
+                output: orig_cost.output,
+                processed_in_step: orig_cost.processed_in_step,
+                intermediate_sum: orig_cost.intermediate_sum + orig_cost.processed_in_step,
+            });
+            ExecutionNodeDesc {
+                component_nr: orig_desc.component_nr,
+                node_pos: orig_desc.node_pos.clone(),
+                impl_description: String::from("filter"),
+                query_fragment,
+                cost: cost_est,
+                statistics: None,
+                lhs: Some(Box::new(orig_desc.clone())),
+                rhs: None,
+            }
+        });
+        let is_sorted = exec.is_sorted_by_text();
+        let it = exec
+            .map(move |tuple| {
+                let tuple = tuple?;
+                if has_result(&tuple)? {
+                    Ok(None)
+                } else {
+                    Ok(Some(tuple))
+                }
+            })
+            .filter_map_ok(|t| t);
+        Filter {
+            desc,
+            it: Box::new(it),
+            is_sorted,
         }
     }
 }
@@ -134,6 +187,10 @@ impl ExecutionNode for Filter<'_> {
     fn get_desc(&self) -> Option<&ExecutionNodeDesc> {
         self.desc.as_ref()
     }
+
+    fn is_sorted_by_text(&self) -> bool {
+        self.is_sorted
+    }
 }
 
 impl Iterator for Filter<'_> {
@@ -0,0 +1,150 @@
+use super::{ExecutionNode, ExecutionNodeDesc, check_reflexivity};
+use crate::annis::db::aql::conjunction::BinaryOperatorEntry;
+use crate::annis::operator::{BinaryOperator, BinaryOperatorBase, EquiJoinKey};
+use crate::annis::util::{MemoryReservation, TimeoutCheck, estimated_match_group_size};
+use crate::errors::Result;
+use crate::try_as_option;
+use graphannis_core::annostorage::MatchGroup;
+use std::collections::{HashMap, VecDeque};
+
+/// A join for operators that compare a single key of both operands for
+/// equality (e.g. the value of an annotation). All tuples of the side with
+/// the smaller estimated output are collected into a hash table, which is then
+/// probed with each tuple of the other side.
+pub struct HashJoin<'a> {
+    build: Option<Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>>,
+    probe: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+    op: BinaryOperator<'a>,
+    build_idx: usize,
+    probe_idx: usize,
+    left_is_build: bool,
+    table: HashMap<EquiJoinKey, Vec<MatchGroup>>,
+    match_buffer: VecDeque<MatchGroup>,
+    desc: ExecutionNodeDesc,
+    global_reflexivity: bool,
+    table_memory: MemoryReservation,
+}
+
+impl<'a> HashJoin<'a> {
+    pub fn new(
+        op_entry: BinaryOperatorEntry<'a>,
+        lhs: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+        rhs: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+        lhs_idx: usize,
+        rhs_idx: usize,
+        timeout: TimeoutCheck,
+    ) -> Result<HashJoin<'a>> {
+        let mut left_is_build = true;
+        if let (Some(desc_lhs), Some(desc_rhs)) = (lhs.get_desc(), rhs.get_desc())
+            && let (Some(cost_lhs), Some(cost_rhs)) = (&desc_lhs.cost, &desc_rhs.cost)
+            && cost_lhs.output > cost_rhs.output
+        {
+            left_is_build = false;
+        }
+
+        // each tuple of both sides is only processed once
+        let processed_func = |_, out_lhs: usize, out_rhs: usize| out_lhs + out_rhs;
+
+        let desc = ExecutionNodeDesc::join(
+            &op_entry.op,
+            lhs.get_desc(),
+            rhs.get_desc(),
+            if left_is_build {
+                "hashjoin L-R"
+            } else {
+                "hashjoin R-L"
+            },
+            &format!(
+                "#{} {} #{}",
+                op_entry.args.left, op_entry.op, op_entry.args.right
+            ),
+            &processed_func,
+        )?;
+
+        let (build, probe, build_idx, probe_idx) = if left_is_build {
+            (lhs, rhs, lhs_idx, rhs_idx)
+        } else {
+            (rhs, lhs, rhs_idx, lhs_idx)
+        };
+
+        Ok(HashJoin {
+            build: Some(build),
+            probe,
+            op: op_entry.op,
+            build_idx,
+            probe_idx,
+            left_is_build,
+            table: HashMap::default(),
+            match_buffer: VecDeque::new(),
+            desc,
+            global_reflexivity: op_entry.args.global_reflexivity,
+            table_memory: timeout.memory_reservation(),
+        })
+    }
+
+    fn build_table(&mut self) -> Result<()> {
+        if let Some(build) = self.build.take() {
+            for m in build {
+                let m = m?;
+                if let Some(key) = self
+                    .op
+                    .equi_join_key(&m[self.build_idx], self.left_is_build)?
+                {
+                    self.table_memory.grow(estimated_match_group_size(&m))?;
+                    self.table.entry(key).or_default().push(m);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn probe_next(&mut self, m_probe: MatchGroup) -> Result<()> {
+        if let Some(key) = self
+            .op
+            .equi_join_key(&m_probe[self.probe_idx], !self.left_is_build)?
+            && let Some(candidates) = self.table.get(&key)
+        {
+            for m_build in candidates {
+                let (m_lhs, lhs_idx, m_rhs, rhs_idx) = if self.left_is_build {
+                    (m_build, self.build_idx, &m_probe, self.probe_idx)
+                } else {
+                    (&m_probe, self.probe_idx, m_build, self.build_idx)
+                };
+                if check_reflexivity(
+                    &self.op,
+                    self.global_reflexivity,
+                    m_lhs,
+                    lhs_idx,
+                    m_rhs,
+                    rhs_idx,
+                ) {
+                    let mut result = m_lhs.clone();
+                    result.extend(m_rhs.iter().cloned());
+                    self.match_buffer.push_back(result);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionNode for HashJoin<'_> {
+    fn get_desc(&self) -> Option<&ExecutionNodeDesc> {
+        Some(&self.desc)
+    }
+}
+
+impl Iterator for HashJoin<'_> {
+    type Item = Result<MatchGroup>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        try_as_option!(self.build_table());
+        loop {
+            if let Some(result) = self.match_buffer.pop_front() {
+                return Some(Ok(result));
+            }
+            let m_probe = try_as_option!(self.probe.next()?);
+            try_as_option!(self.probe_next(m_probe));
+        }
+    }
+}
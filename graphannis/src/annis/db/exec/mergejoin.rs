@@ -0,0 +1,179 @@
+use super::{ExecutionNode, ExecutionNodeDesc, check_reflexivity};
+use crate::AnnotationGraph;
+use crate::annis::db::aql::conjunction::BinaryOperatorEntry;
+use crate::annis::db::aql::model::AnnotationComponentType;
+use crate::annis::db::sort_matches::{CollationType, SortCache};
+use crate::annis::db::token_helper::TokenHelper;
+use crate::annis::operator::BinaryOperator;
+use crate::errors::Result;
+use crate::try_as_option;
+use graphannis_core::annostorage::{MatchGroup, NodeAnnotationStorage};
+use graphannis_core::graph::ANNIS_NS;
+use graphannis_core::graph::storage::GraphStorage;
+use graphannis_core::types::{Component, NodeID};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::iter::Peekable;
+use std::sync::Arc;
+
+/// A join for operators that require both operands to be the same node, where
+/// both inputs are sorted by the text position of the joined node. Both inputs
+/// are only iterated once and no index lookups are necessary.
+pub struct MergeJoin<'a> {
+    lhs: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+    rhs: Peekable<Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>>,
+    op: BinaryOperator<'a>,
+    lhs_idx: usize,
+    rhs_idx: usize,
+    /// All RHS tuples that have the same node as the last LHS tuple.
+    rhs_group: Vec<MatchGroup>,
+    match_buffer: VecDeque<MatchGroup>,
+    node_annos: &'a dyn NodeAnnotationStorage,
+    token_helper: Option<TokenHelper<'a>>,
+    sort_cache: SortCache,
+    desc: ExecutionNodeDesc,
+    global_reflexivity: bool,
+}
+
+impl<'a> MergeJoin<'a> {
+    pub fn new(
+        db: &'a AnnotationGraph,
+        op_entry: BinaryOperatorEntry<'a>,
+        lhs: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+        rhs: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
+        lhs_idx: usize,
+        rhs_idx: usize,
+    ) -> Result<MergeJoin<'a>> {
+        // each tuple of both sides is only processed once
+        let processed_func = |_, out_lhs: usize, out_rhs: usize| out_lhs + out_rhs;
+
+        let desc = ExecutionNodeDesc::join(
+            &op_entry.op,
+            lhs.get_desc(),
+            rhs.get_desc(),
+            "mergejoin",
+            &format!(
+                "#{} {} #{}",
+                op_entry.args.left, op_entry.op, op_entry.args.right
+            ),
+            &processed_func,
+        )?;
+
+        let gs_order: Option<Arc<dyn GraphStorage>> = db.get_graphstorage(&Component::new(
+            AnnotationComponentType::Ordering,
+            ANNIS_NS.into(),
+            "".into(),
+        ));
+
+        Ok(MergeJoin {
+            lhs,
+            rhs: rhs.peekable(),
+            op: op_entry.op,
+            lhs_idx,
+            rhs_idx,
+            rhs_group: Vec::new(),
+            match_buffer: VecDeque::new(),
+            node_annos: db.get_node_annos(),
+            token_helper: TokenHelper::new(db).ok(),
+            sort_cache: SortCache::new(gs_order),
+            desc,
+            global_reflexivity: op_entry.args.global_reflexivity,
+        })
+    }
+
+    /// Collect all RHS tuples with the given node, skipping the ones that
+    /// are sorted before it.
+    fn collect_rhs_group(&mut self, m_lhs: &MatchGroup) -> Result<()> {
+        let node: NodeID = m_lhs[self.lhs_idx].node;
+        if let Some(first) = self.rhs_group.first()
+            && first[self.rhs_idx].node == node
+        {
+            // the previous LHS tuple had the same node, reuse the group
+            return Ok(());
+        }
+        self.rhs_group.clear();
+
+        loop {
+            let cmp = match self.rhs.peek() {
+                Some(Ok(m_rhs)) => {
+                    if m_rhs[self.rhs_idx].node == node {
+                        Ordering::Equal
+                    } else {
+                        self.sort_cache.compare_match_by_text_pos(
+                            &m_lhs[self.lhs_idx],
+                            &m_rhs[self.rhs_idx],
+                            self.node_annos,
+                            self.token_helper.as_ref(),
+                            CollationType::Default,
+                            false,
+                        )?
+                    }
+                }
+                Some(Err(_)) => {
+                    // consume the error and return it
+                    if let Some(Err(e)) = self.rhs.next() {
+                        return Err(e);
+                    }
+                    continue;
+                }
+                None => break,
+            };
+            match cmp {
+                Ordering::Equal => {
+                    if let Some(Ok(m_rhs)) = self.rhs.next() {
+                        if m_rhs[self.rhs_idx].node == node {
+                            self.rhs_group.push(m_rhs);
+                        } else {
+                            // different node at the same position, this can't be a match
+                            break;
+                        }
+                    }
+                }
+                Ordering::Greater => {
+                    // the RHS tuple is before the LHS tuple and can't have a match anymore
+                    self.rhs.next();
+                }
+                Ordering::Less => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionNode for MergeJoin<'_> {
+    fn get_desc(&self) -> Option<&ExecutionNodeDesc> {
+        Some(&self.desc)
+    }
+}
+
+impl Iterator for MergeJoin<'_> {
+    type Item = Result<MatchGroup>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.match_buffer.pop_front() {
+                return Some(Ok(result));
+            }
+            let m_lhs = try_as_option!(self.lhs.next()?);
+            try_as_option!(self.collect_rhs_group(&m_lhs));
+            if self.rhs_group.is_empty() && self.rhs.peek().is_none() {
+                // no more matches possible
+                return None;
+            }
+            for m_rhs in &self.rhs_group {
+                if check_reflexivity(
+                    &self.op,
+                    self.global_reflexivity,
+                    &m_lhs,
+                    self.lhs_idx,
+                    m_rhs,
+                    self.rhs_idx,
+                ) {
+                    let mut result = m_lhs.clone();
+                    result.extend(m_rhs.iter().cloned());
+                    self.match_buffer.push_back(result);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
+use self::analyze::ExecutionStatistics;
 use self::nodesearch::NodeSearch;
 use crate::{
     annis::operator::{BinaryOperatorBase, EstimationType},
+    annis::types::AnalyzedExecutionNode,
     errors::Result,
     graph::Match,
 };
@@ -31,6 +33,8 @@ pub struct ExecutionNodeDesc {
     pub impl_description: String,
     pub query_fragment: String,
     pub cost: Option<CostEstimate>,
+    /// Actual execution statistics, only collected when the query is analyzed.
+    pub statistics: Option<Arc<ExecutionStatistics>>,
 }
 
 fn calculate_outputsize<Op: BinaryOperatorBase + ?Sized>(
@@ -76,6 +80,7 @@ impl ExecutionNodeDesc {
             impl_description: String::from(""),
             query_fragment,
             cost,
+            statistics: None,
         }
     }
 
@@ -140,6 +145,7 @@ impl ExecutionNodeDesc {
             impl_description: String::from(impl_description),
             query_fragment: String::from(query_fragment),
             cost,
+            statistics: None,
         })
     }
 
@@ -182,6 +188,48 @@ impl ExecutionNodeDesc {
         }
         result
     }
+
+    /// Compares the estimated cost with the actual statistics that have been
+    /// collected for this node and all its child nodes.
+    pub fn analysis(&self) -> AnalyzedExecutionNode {
+        let estimated_output = self.cost.as_ref().map(|c| c.output);
+        let actual_output = self.statistics.as_ref().and_then(|s| s.actual_output());
+        let estimation_error =
+            if let (Some(estimated), Some(actual)) = (estimated_output, actual_output) {
+                Some(actual as f64 / std::cmp::max(estimated, 1) as f64)
+            } else {
+                None
+            };
+        let time_ms = self
+            .statistics
+            .as_ref()
+            .map(|s| s.time().as_secs_f64() * 1000.0)
+            .unwrap_or_default();
+
+        let node_nr = if self.lhs.is_none() && self.rhs.is_none() {
+            Some(self.node_pos.keys().next().cloned().unwrap_or(0) + 1)
+        } else {
+            None
+        };
+
+        let children = self
+            .lhs
+            .iter()
+            .chain(self.rhs.iter())
+            .map(|c| c.analysis())
+            .collect();
+
+        AnalyzedExecutionNode {
+            node_nr,
+            impl_description: self.impl_description.clone(),
+            query_fragment: self.query_fragment.clone(),
+            estimated_output,
+            actual_output,
+            estimation_error,
+            time_ms,
+            children,
+        }
+    }
 }
 
 /// Filter function for the value of a given match, but assumes the given match has already the
@@ -209,6 +257,23 @@ pub trait ExecutionNode: Iterator {
     }
 }
 
+/// Returns `true` if joining the `lhs` and `rhs` tuples does not violate the
+/// reflexivity of the operator.
+pub(crate) fn check_reflexivity<Op: BinaryOperatorBase + ?Sized>(
+    op: &Op,
+    global_reflexivity: bool,
+    lhs: &[Match],
+    lhs_idx: usize,
+    rhs: &[Match],
+    rhs_idx: usize,
+) -> bool {
+    op.is_reflexive()
+        || (global_reflexivity
+            && lhs[lhs_idx].different_to_all(rhs)
+            && rhs[rhs_idx].different_to_all(lhs))
+        || (!global_reflexivity && lhs[lhs_idx].different_to(&rhs[rhs_idx]))
+}
+
 pub struct EmptyResultSet;
 
 impl Iterator for EmptyResultSet {
@@ -229,9 +294,13 @@ impl ExecutionNode for EmptyResultSet {
     }
 }
 
+pub mod analyze;
 pub mod filter;
+pub mod hashjoin;
 pub mod indexjoin;
+pub mod mergejoin;
 pub mod nestedloop;
 pub mod nodesearch;
 pub mod parallel;
+pub mod textsearch;
 pub mod tokensearch;
@@ -3,6 +3,7 @@ use graphannis_core::annostorage::MatchGroup;
 use super::{ExecutionNode, ExecutionNodeDesc};
 use crate::annis::db::aql::conjunction::BinaryOperatorEntry;
 use crate::annis::operator::{BinaryOperator, BinaryOperatorBase};
+use crate::annis::util::{MemoryReservation, TimeoutCheck, estimated_match_group_size};
 use crate::errors::Result;
 use crate::try_as_option;
 use std::iter::Peekable;
@@ -20,6 +21,7 @@ pub struct NestedLoop<'a> {
     desc: ExecutionNodeDesc,
 
     global_reflexivity: bool,
+    inner_cache_memory: MemoryReservation,
 }
 
 impl<'a> NestedLoop<'a> {
@@ -29,6 +31,7 @@ impl<'a> NestedLoop<'a> {
         rhs: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
         lhs_idx: usize,
         rhs_idx: usize,
+        timeout: TimeoutCheck,
     ) -> Result<NestedLoop<'a>> {
         let mut left_is_outer = true;
         if let (Some(desc_lhs), Some(desc_rhs)) = (lhs.get_desc(), rhs.get_desc())
@@ -71,6 +74,7 @@ impl<'a> NestedLoop<'a> {
                 pos_inner_cache: None,
                 left_is_outer,
                 global_reflexivity: op_entry.args.global_reflexivity,
+                inner_cache_memory: timeout.memory_reservation(),
             };
             Ok(join)
         } else {
@@ -96,6 +100,7 @@ impl<'a> NestedLoop<'a> {
                 pos_inner_cache: None,
                 left_is_outer,
                 global_reflexivity: op_entry.args.global_reflexivity,
+                inner_cache_memory: timeout.memory_reservation(),
             };
             Ok(join)
         }
@@ -145,6 +150,10 @@ impl Iterator for NestedLoop<'_> {
                 } else {
                     for m_inner in &mut self.inner {
                         let mut m_inner = try_as_option!(m_inner);
+                        try_as_option!(
+                            self.inner_cache_memory
+                                .grow(estimated_match_group_size(&m_inner))
+                        );
                         self.inner_cache.push(m_inner.clone());
 
                         let filter_true = if self.left_is_outer {
@@ -2,29 +2,36 @@ use super::MatchValueFilterFunc;
 use super::{ExecutionNode, ExecutionNodeDesc, NodeSearchDesc};
 use crate::AnnotationGraph;
 use crate::annis::db::aql::model::AnnotationComponentType;
+use crate::annis::db::exec::textsearch::TextMatcher;
 use crate::annis::db::exec::tokensearch;
 use crate::annis::db::exec::tokensearch::AnyTokenSearch;
 use crate::annis::errors::*;
-use crate::annis::operator::EdgeAnnoSearchSpec;
+use crate::annis::operator::{EdgeAnnoSearchSpec, NumericComparison};
 use crate::annis::types::LineColumnRange;
 use crate::annis::util::TimeoutCheck;
 use crate::{
-    annis::{db::aql::model::TOKEN_KEY, util},
+    annis::{
+        db::aql::model::{DOCUMENT_KEY, TOKEN_KEY},
+        util,
+    },
     graph::Match,
 };
-use graphannis_core::annostorage::EdgeAnnotationStorage;
+use graphannis_core::annostorage::{EdgeAnnotationStorage, NodeAnnotationStorage};
 use graphannis_core::errors::GraphAnnisCoreError;
 use graphannis_core::graph::{ANNIS_NS, NODE_NAME};
+use graphannis_core::util::RegexFlags;
 use graphannis_core::{
-    annostorage::{MatchGroup, ValueSearch},
+    annostorage::{MatchGroup, ValueSearch, numeric_range_contains, parse_numeric_value},
     graph::{NODE_TYPE_KEY, storage::GraphStorage},
-    types::{Component, NodeID},
+    types::{AnnoKey, Component, NodeID},
 };
 use itertools::Itertools;
+use rustc_hash::FxHashSet;
 use smallvec::smallvec;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::sync::Arc;
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
 
 /// An [ExecutionNode](#impl-ExecutionNode) which wraps base node (annotation) searches.
 pub struct NodeSearch<'a> {
@@ -61,14 +68,31 @@ pub enum NodeSearchSpec {
         ns: Option<String>,
         name: String,
         val: String,
+        flags: RegexFlags,
         is_meta: bool,
     },
     NotRegexValue {
         ns: Option<String>,
         name: String,
         val: String,
+        flags: RegexFlags,
         is_meta: bool,
     },
+    NumericValue {
+        ns: Option<String>,
+        name: String,
+        cmp: NumericComparison,
+        val: String,
+    },
+    /// Search for annotations with any name that matches the regular
+    /// expression `name_pattern`, e.g. `/gloss_.*/="house"`. If no namespace
+    /// is given, annotations in the internal `annis` namespace are ignored.
+    AnnoNamePattern {
+        ns: Option<String>,
+        name_pattern: String,
+        name_flags: RegexFlags,
+        val: AnnoValueCondition,
+    },
     ExactTokenValue {
         val: String,
         leafs_only: bool,
@@ -78,22 +102,131 @@ pub enum NodeSearchSpec {
     },
     RegexTokenValue {
         val: String,
+        flags: RegexFlags,
         leafs_only: bool,
     },
     NotRegexTokenValue {
         val: String,
+        flags: RegexFlags,
+    },
+    /// Search for the first token of a sequence of consecutive token, whose
+    /// text (including the whitespace between the token) is equal to `val`.
+    ExactTextValue {
+        val: String,
+    },
+    /// Search for the first token of a sequence of consecutive token, whose
+    /// text (including the whitespace between the token) matches the regular
+    /// expression `val`.
+    RegexTextValue {
+        val: String,
+        flags: RegexFlags,
     },
     AnyToken,
     AnyNode,
+    /// Search for the document nodes with the given IDs. This is not part of
+    /// AQL, but used to restrict a query to a sample of the documents.
+    Documents {
+        nodes: Vec<NodeID>,
+    },
+}
+
+/// Condition for the value of an annotation when the annotation name is not
+/// fixed.
+#[derive(Clone, Debug, PartialOrd, Ord, Hash, PartialEq, Eq)]
+pub enum AnnoValueCondition {
+    Any,
+    Exact(String),
+    NotExact(String),
+    Regex(String, RegexFlags),
+    NotRegex(String, RegexFlags),
+}
+
+impl AnnoValueCondition {
+    /// Get the specification to search for this value condition in the
+    /// annotation with the given qualified name.
+    pub fn to_spec(&self, ns: Option<String>, name: String) -> NodeSearchSpec {
+        match self {
+            AnnoValueCondition::Any => NodeSearchSpec::ExactValue {
+                ns,
+                name,
+                val: None,
+                is_meta: false,
+            },
+            AnnoValueCondition::Exact(val) => NodeSearchSpec::ExactValue {
+                ns,
+                name,
+                val: Some(val.clone()),
+                is_meta: false,
+            },
+            AnnoValueCondition::NotExact(val) => NodeSearchSpec::NotExactValue {
+                ns,
+                name,
+                val: val.clone(),
+                is_meta: false,
+            },
+            AnnoValueCondition::Regex(val, flags) => NodeSearchSpec::RegexValue {
+                ns,
+                name,
+                val: val.clone(),
+                flags: *flags,
+                is_meta: false,
+            },
+            AnnoValueCondition::NotRegex(val, flags) => NodeSearchSpec::NotRegexValue {
+                ns,
+                name,
+                val: val.clone(),
+                flags: *flags,
+                is_meta: false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for AnnoValueCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnnoValueCondition::Any => Ok(()),
+            AnnoValueCondition::Exact(val) => write!(f, "=\"{}\"", val),
+            AnnoValueCondition::NotExact(val) => write!(f, "!=\"{}\"", val),
+            AnnoValueCondition::Regex(val, flags) => write!(f, "=/{}/{}", val, flags),
+            AnnoValueCondition::NotRegex(val, flags) => write!(f, "!=/{}/{}", val, flags),
+        }
+    }
 }
 
 impl NodeSearchSpec {
+    /// Describes the search in AQL syntax, including whether only metadata is
+    /// searched. Specifications with the same description find the same nodes.
+    pub fn canonical_description(&self) -> String {
+        match self {
+            NodeSearchSpec::ExactValue { is_meta: true, .. }
+            | NodeSearchSpec::NotExactValue { is_meta: true, .. }
+            | NodeSearchSpec::RegexValue { is_meta: true, .. }
+            | NodeSearchSpec::NotRegexValue { is_meta: true, .. } => format!("meta::{}", self),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Returns the value and the regular expression flags (if the value is a
+    /// regular expression) when this is a search for text spanning token.
+    pub fn text_search_value(&self) -> Option<(&str, Option<RegexFlags>)> {
+        match self {
+            NodeSearchSpec::ExactTextValue { val } => Some((val, None)),
+            NodeSearchSpec::RegexTextValue { val, flags } => Some((val, Some(*flags))),
+            _ => None,
+        }
+    }
+
     pub fn necessary_components(
         &self,
         db: &AnnotationGraph,
     ) -> HashSet<Component<AnnotationComponentType>> {
         match self {
-            NodeSearchSpec::AnyToken => tokensearch::AnyTokenSearch::necessary_components(db),
+            NodeSearchSpec::AnyToken
+            | NodeSearchSpec::ExactTextValue { .. }
+            | NodeSearchSpec::RegexTextValue { .. } => {
+                tokensearch::AnyTokenSearch::necessary_components(db)
+            }
             NodeSearchSpec::ExactTokenValue {
                 leafs_only: true, ..
             }
@@ -115,13 +248,16 @@ impl NodeSearchSpec {
             NodeSearchSpec::ExactValue { ns, name, .. }
             | NodeSearchSpec::NotExactValue { ns, name, .. }
             | NodeSearchSpec::RegexValue { ns, name, .. }
-            | NodeSearchSpec::NotRegexValue { ns, name, .. } => {
+            | NodeSearchSpec::NotRegexValue { ns, name, .. }
+            | NodeSearchSpec::NumericValue { ns, name, .. } => {
                 (ns.to_owned(), Some(name.to_owned()))
             }
             NodeSearchSpec::ExactTokenValue { .. }
             | NodeSearchSpec::NotExactTokenValue { .. }
             | NodeSearchSpec::RegexTokenValue { .. }
             | NodeSearchSpec::NotRegexTokenValue { .. }
+            | NodeSearchSpec::ExactTextValue { .. }
+            | NodeSearchSpec::RegexTextValue { .. }
             | NodeSearchSpec::AnyToken => {
                 (Some(TOKEN_KEY.ns.clone()), Some(TOKEN_KEY.name.clone()))
             }
@@ -129,6 +265,12 @@ impl NodeSearchSpec {
                 Some(NODE_TYPE_KEY.ns.clone()),
                 Some(NODE_TYPE_KEY.name.clone()),
             ),
+            NodeSearchSpec::Documents { .. } => (
+                Some(DOCUMENT_KEY.ns.clone()),
+                Some(DOCUMENT_KEY.name.clone()),
+            ),
+            // All annotations are needed, the name is checked by the value filter
+            NodeSearchSpec::AnnoNamePattern { .. } => (None, None),
         }
     }
 
@@ -164,8 +306,9 @@ impl NodeSearchSpec {
                     }
                 }));
             }
-            NodeSearchSpec::RegexValue { val, .. } => {
-                let full_match_pattern = graphannis_core::util::regex_full_match(val);
+            NodeSearchSpec::RegexValue { val, flags, .. } => {
+                let full_match_pattern =
+                    graphannis_core::util::regex_full_match_with_flags(val, *flags);
                 let re = regex::Regex::new(&full_match_pattern);
                 match re {
                     Ok(re) => {
@@ -181,14 +324,15 @@ impl NodeSearchSpec {
                     }
                     Err(e) => {
                         return Err(GraphAnnisError::AQLSemanticError(AQLError {
-                            desc: format!("/{}/ -> {}", val, e),
+                            desc: format!("/{}/{} -> {}", val, flags, e),
                             location: location_in_query,
                         }));
                     }
                 }
             }
-            NodeSearchSpec::NotRegexValue { val, .. } => {
-                let full_match_pattern = graphannis_core::util::regex_full_match(val);
+            NodeSearchSpec::NotRegexValue { val, flags, .. } => {
+                let full_match_pattern =
+                    graphannis_core::util::regex_full_match_with_flags(val, *flags);
                 let re = regex::Regex::new(&full_match_pattern);
                 match re {
                     Ok(re) => {
@@ -204,12 +348,46 @@ impl NodeSearchSpec {
                     }
                     Err(e) => {
                         return Err(GraphAnnisError::AQLSemanticError(AQLError {
-                            desc: format!("/{}/ -> {}", val, e),
+                            desc: format!("/{}/{} -> {}", val, flags, e),
                             location: location_in_query,
                         }));
                     }
                 }
             }
+            NodeSearchSpec::NumericValue { cmp, val, .. } => {
+                let (lower, upper) = numeric_value_range(*cmp, val, location_in_query)?;
+                filters.push(Box::new(move |m, node_annos| {
+                    if let Some(anno_val) = node_annos.get_value_for_item(&m.node, &m.anno_key)?
+                        && let Some(anno_val) = parse_numeric_value(&anno_val)
+                    {
+                        Ok(numeric_range_contains(anno_val, lower, upper))
+                    } else {
+                        Ok(false)
+                    }
+                }));
+            }
+            NodeSearchSpec::AnnoNamePattern {
+                ns,
+                name_pattern,
+                name_flags,
+                val,
+            } => {
+                let name_regex =
+                    compile_name_pattern(name_pattern, *name_flags, location_in_query.clone())?;
+                let ns = ns.clone();
+                filters.push(Box::new(move |m, _| {
+                    let ns_matches = if let Some(ns) = &ns {
+                        &m.anno_key.ns == ns
+                    } else {
+                        m.anno_key.ns != ANNIS_NS
+                    };
+                    Ok(ns_matches && name_regex.is_match(&m.anno_key.name))
+                }));
+                // The value filters only depend on the value condition and not
+                // on the annotation name
+                let value_spec = val.to_spec(None, name_pattern.clone());
+                filters.extend(value_spec.get_value_filter(g, location_in_query)?);
+            }
             NodeSearchSpec::ExactTokenValue { val, leafs_only } => {
                 let val = val.clone();
                 filters.push(Box::new(move |m, node_annos| {
@@ -234,8 +412,13 @@ impl NodeSearchSpec {
                 }));
                 filters.push(create_token_leaf_filter(g));
             }
-            NodeSearchSpec::RegexTokenValue { val, leafs_only } => {
-                let full_match_pattern = graphannis_core::util::regex_full_match(val);
+            NodeSearchSpec::RegexTokenValue {
+                val,
+                flags,
+                leafs_only,
+            } => {
+                let full_match_pattern =
+                    graphannis_core::util::regex_full_match_with_flags(val, *flags);
                 let re = regex::Regex::new(&full_match_pattern);
                 match re {
                     Ok(re) => filters.push(Box::new(move |m, node_annos| {
@@ -247,7 +430,7 @@ impl NodeSearchSpec {
                     })),
                     Err(e) => {
                         return Err(GraphAnnisError::AQLSemanticError(AQLError {
-                            desc: format!("/{}/ -> {}", val, e),
+                            desc: format!("/{}/{} -> {}", val, flags, e),
                             location: location_in_query,
                         }));
                     }
@@ -256,8 +439,9 @@ impl NodeSearchSpec {
                     filters.push(create_token_leaf_filter(g));
                 }
             }
-            NodeSearchSpec::NotRegexTokenValue { val } => {
-                let full_match_pattern = graphannis_core::util::regex_full_match(val);
+            NodeSearchSpec::NotRegexTokenValue { val, flags } => {
+                let full_match_pattern =
+                    graphannis_core::util::regex_full_match_with_flags(val, *flags);
                 let re = regex::Regex::new(&full_match_pattern);
                 match re {
                     Ok(re) => filters.push(Box::new(move |m, node_annos| {
@@ -269,13 +453,45 @@ impl NodeSearchSpec {
                     })),
                     Err(e) => {
                         return Err(GraphAnnisError::AQLSemanticError(AQLError {
-                            desc: format!("/{}/ -> {}", val, e),
+                            desc: format!("/{}/{} -> {}", val, flags, e),
                             location: location_in_query,
                         }));
                     }
                 };
                 filters.push(create_token_leaf_filter(g));
             }
+            NodeSearchSpec::ExactTextValue { .. } | NodeSearchSpec::RegexTextValue { .. } => {
+                filters.push(create_token_leaf_filter(g));
+                if let Some((val, regex_flags)) = self.text_search_value() {
+                    let matcher = TextMatcher::new(g, val, regex_flags, location_in_query)?;
+                    let leaf_filter = create_token_leaf_filter(g);
+                    // Find all match starts once when the filter is used the
+                    // first time instead of walking the text for each candidate.
+                    let match_starts: Mutex<Option<FxHashSet<NodeID>>> = Mutex::new(None);
+                    filters.push(Box::new(move |m, node_annos| {
+                        let mut match_starts = match_starts.lock()?;
+                        if match_starts.is_none() {
+                            let mut result = FxHashSet::default();
+                            for t in node_annos.exact_anno_search(
+                                Some(&TOKEN_KEY.ns),
+                                &TOKEN_KEY.name,
+                                None.into(),
+                            ) {
+                                result.extend(text_match_starts(
+                                    &matcher,
+                                    &leaf_filter,
+                                    &t?,
+                                    node_annos,
+                                )?);
+                            }
+                            *match_starts = Some(result);
+                        }
+                        Ok(match_starts
+                            .as_ref()
+                            .is_some_and(|starts| starts.contains(&m.node)))
+                    }));
+                }
+            }
             NodeSearchSpec::AnyToken => {
                 filters.push(create_token_leaf_filter(g));
             }
@@ -289,6 +505,10 @@ impl NodeSearchSpec {
                 });
                 filters.push(filter_func)
             }
+            NodeSearchSpec::Documents { nodes } => {
+                let nodes: FxHashSet<NodeID> = nodes.iter().copied().collect();
+                filters.push(Box::new(move |m, _| Ok(nodes.contains(&m.node))));
+            }
         }
 
         Ok(filters)
@@ -316,18 +536,37 @@ impl fmt::Display for NodeSearchSpec {
                     write!(f, "{}!=\"{}\"", name, &val)
                 }
             }
-            NodeSearchSpec::RegexValue { ns, name, val, .. } => {
+            NodeSearchSpec::RegexValue {
+                ns,
+                name,
+                val,
+                flags,
+                ..
+            } => {
                 if let Some(ns) = ns {
-                    write!(f, "{}:{}=/{}/", ns, name, &val)
+                    write!(f, "{}:{}=/{}/{}", ns, name, &val, flags)
                 } else {
-                    write!(f, "{}=/{}/", name, &val)
+                    write!(f, "{}=/{}/{}", name, &val, flags)
                 }
             }
-            NodeSearchSpec::NotRegexValue { ns, name, val, .. } => {
+            NodeSearchSpec::NotRegexValue {
+                ns,
+                name,
+                val,
+                flags,
+                ..
+            } => {
+                if let Some(ns) = ns {
+                    write!(f, "{}:{}!=/{}/{}", ns, name, &val, flags)
+                } else {
+                    write!(f, "{}!=/{}/{}", name, &val, flags)
+                }
+            }
+            NodeSearchSpec::NumericValue { ns, name, cmp, val } => {
                 if let Some(ns) = ns {
-                    write!(f, "{}:{}!=/{}/", ns, name, &val)
+                    write!(f, "{}:{}{}{}", ns, name, cmp, val)
                 } else {
-                    write!(f, "{}!=/{}/", name, &val)
+                    write!(f, "{}{}{}", name, cmp, val)
                 }
             }
             NodeSearchSpec::ExactTokenValue { val, leafs_only } => {
@@ -338,20 +577,89 @@ impl fmt::Display for NodeSearchSpec {
                 }
             }
             NodeSearchSpec::NotExactTokenValue { val } => write!(f, "tok!=\"{}\"", val),
-            NodeSearchSpec::RegexTokenValue { val, leafs_only } => {
+            NodeSearchSpec::RegexTokenValue {
+                val,
+                flags,
+                leafs_only,
+            } => {
                 if *leafs_only {
-                    write!(f, "tok=/{}/", val)
+                    write!(f, "tok=/{}/{}", val, flags)
                 } else {
-                    write!(f, "/{}/", val)
+                    write!(f, "/{}/{}", val, flags)
                 }
             }
-            NodeSearchSpec::NotRegexTokenValue { val } => write!(f, "tok!=/{}/", val),
+            NodeSearchSpec::NotRegexTokenValue { val, flags } => {
+                write!(f, "tok!=/{}/{}", val, flags)
+            }
+            NodeSearchSpec::AnnoNamePattern {
+                ns,
+                name_pattern,
+                name_flags,
+                val,
+            } => {
+                let ns = ns.as_deref().unwrap_or("*");
+                write!(f, "{}:/{}/{}{}", ns, name_pattern, name_flags, val)
+            }
+            NodeSearchSpec::ExactTextValue { val } => write!(f, "text=\"{}\"", val),
+            NodeSearchSpec::RegexTextValue { val, flags } => write!(f, "text=/{}/{}", val, flags),
             NodeSearchSpec::AnyToken => write!(f, "tok"),
             NodeSearchSpec::AnyNode => write!(f, "node"),
+            NodeSearchSpec::Documents { nodes } => {
+                write!(f, "{}:{}", DOCUMENT_KEY.ns, DOCUMENT_KEY.name)?;
+                // Only the node IDs distinguish two document searches
+                write!(f, "{{{}}}", nodes.iter().join(","))
+            }
         }
     }
 }
 
+/// Compile the regular expression for matching annotation names.
+fn compile_name_pattern(
+    name_pattern: &str,
+    name_flags: RegexFlags,
+    location_in_query: Option<LineColumnRange>,
+) -> Result<regex::Regex> {
+    let full_match_pattern =
+        graphannis_core::util::regex_full_match_with_flags(name_pattern, name_flags);
+    regex::Regex::new(&full_match_pattern).map_err(|e| {
+        GraphAnnisError::AQLSemanticError(AQLError {
+            desc: format!("/{}/{} -> {}", name_pattern, name_flags, e),
+            location: location_in_query,
+        })
+    })
+}
+
+/// Get the range of values that match a numeric comparison with the given
+/// reference value.
+fn numeric_value_range(
+    cmp: NumericComparison,
+    val: &str,
+    location_in_query: Option<LineColumnRange>,
+) -> Result<(Bound<f64>, Bound<f64>)> {
+    cmp.value_range(val).ok_or_else(|| {
+        GraphAnnisError::AQLSemanticError(AQLError {
+            desc: format!("\"{}\" is not a valid number", val),
+            location: location_in_query,
+        })
+    })
+}
+
+/// Returns all token where a match of the text search starts, if the given
+/// token is the first one of a text. Otherwise, the text has already been
+/// searched from its first token on and an empty list is returned.
+fn text_match_starts(
+    matcher: &TextMatcher,
+    leaf_filter: &MatchValueFilterFunc,
+    token: &Match,
+    node_annos: &dyn NodeAnnotationStorage,
+) -> Result<Vec<NodeID>> {
+    let mut result = Vec::new();
+    if leaf_filter(token, node_annos)? && matcher.is_text_start(token.node)? {
+        matcher.for_each_match_start(token.node, node_annos, |start| result.push(start))?;
+    }
+    Ok(result)
+}
+
 fn create_token_leaf_filter(g: &AnnotationGraph) -> MatchValueFilterFunc {
     let cov_gs: Vec<Arc<dyn GraphStorage>> = g
         .get_all_components(Some(AnnotationComponentType::Coverage), None)
@@ -388,12 +696,12 @@ impl<'a> NodeSearch<'a> {
     ) -> Result<NodeSearch<'a>> {
         let query_fragment = format!("{}", spec);
 
-        let filters = spec.get_value_filter(db, location_in_query)?;
+        let filters = spec.get_value_filter(db, location_in_query.clone())?;
         let common_args = CommonArguments {
             query_fragment,
             node_nr,
             output_size_cache,
-            timeout,
+            timeout: timeout.clone(),
         };
         match spec {
             NodeSearchSpec::ExactValue {
@@ -426,10 +734,13 @@ impl<'a> NodeSearch<'a> {
                 ns,
                 name,
                 val,
+                flags,
                 is_meta,
             } => {
                 // check if the regex can be replaced with an exact value search
-                if let Some(exaxt_value) = util::exact_value_for_regex(&val) {
+                if flags.is_empty()
+                    && let Some(exaxt_value) = util::exact_value_for_regex(&val)
+                {
                     NodeSearch::new_annosearch_exact(
                         db,
                         (ns, name),
@@ -443,6 +754,7 @@ impl<'a> NodeSearch<'a> {
                         db,
                         (ns, name),
                         &val,
+                        flags,
                         false,
                         filters,
                         is_meta,
@@ -454,10 +766,13 @@ impl<'a> NodeSearch<'a> {
                 ns,
                 name,
                 val,
+                flags,
                 is_meta,
             } => {
                 // check if the regex can be replaced with an exact value search
-                if let Some(exact_value) = util::exact_value_for_regex(&val) {
+                if flags.is_empty()
+                    && let Some(exact_value) = util::exact_value_for_regex(&val)
+                {
                     NodeSearch::new_annosearch_exact(
                         db,
                         (ns, name),
@@ -471,6 +786,7 @@ impl<'a> NodeSearch<'a> {
                         db,
                         (ns, name),
                         &val,
+                        flags,
                         true,
                         filters,
                         is_meta,
@@ -478,12 +794,39 @@ impl<'a> NodeSearch<'a> {
                     )
                 }
             }
+            NodeSearchSpec::NumericValue { ns, name, cmp, val } => {
+                let range = numeric_value_range(cmp, &val, location_in_query)?;
+                NodeSearch::new_annosearch_numeric(db, (ns, name), range, filters, common_args)
+            }
+            NodeSearchSpec::AnnoNamePattern {
+                ns,
+                name_pattern,
+                name_flags,
+                val,
+            } => {
+                let name_regex =
+                    compile_name_pattern(&name_pattern, name_flags, location_in_query)?;
+                let anno_keys = db
+                    .get_node_annos()
+                    .annotation_keys()?
+                    .into_iter()
+                    .filter(|k| {
+                        let ns_matches = if let Some(ns) = &ns {
+                            &k.ns == ns
+                        } else {
+                            k.ns != ANNIS_NS
+                        };
+                        ns_matches && name_regex.is_match(&k.name)
+                    })
+                    .collect();
+                NodeSearch::new_annosearch_name_pattern(db, anno_keys, &val, filters, common_args)
+            }
             NodeSearchSpec::ExactTokenValue { val, leafs_only } => NodeSearch::new_tokensearch(
                 db,
                 ValueSearch::Some(val),
                 filters,
                 leafs_only,
-                false,
+                None,
                 common_args,
             ),
             NodeSearchSpec::NotExactTokenValue { val } => NodeSearch::new_tokensearch(
@@ -491,25 +834,37 @@ impl<'a> NodeSearch<'a> {
                 ValueSearch::NotSome(val),
                 filters,
                 true,
-                false,
+                None,
                 common_args,
             ),
-            NodeSearchSpec::RegexTokenValue { val, leafs_only } => NodeSearch::new_tokensearch(
+            NodeSearchSpec::RegexTokenValue {
+                val,
+                flags,
+                leafs_only,
+            } => NodeSearch::new_tokensearch(
                 db,
                 ValueSearch::Some(val),
                 filters,
                 leafs_only,
-                true,
+                Some(flags),
                 common_args,
             ),
-            NodeSearchSpec::NotRegexTokenValue { val } => NodeSearch::new_tokensearch(
+            NodeSearchSpec::NotRegexTokenValue { val, flags } => NodeSearch::new_tokensearch(
                 db,
                 ValueSearch::NotSome(val),
                 filters,
                 true,
-                true,
+                Some(flags),
                 common_args,
             ),
+            NodeSearchSpec::ExactTextValue { val } => {
+                let matcher = TextMatcher::new(db, &val, None, location_in_query)?;
+                NodeSearch::new_textsearch(db, matcher, &val, None, filters, common_args)
+            }
+            NodeSearchSpec::RegexTextValue { val, flags } => {
+                let matcher = TextMatcher::new(db, &val, Some(flags), location_in_query)?;
+                NodeSearch::new_textsearch(db, matcher, &val, Some(flags), filters, common_args)
+            }
             NodeSearchSpec::AnyToken => NodeSearch::new_anytoken_search(db, common_args),
             NodeSearchSpec::AnyNode => {
                 let it = db
@@ -559,6 +914,33 @@ impl<'a> NodeSearch<'a> {
                     timeout,
                 })
             }
+            NodeSearchSpec::Documents { nodes } => {
+                let est_output = std::cmp::max(1, nodes.len());
+                let it = nodes.into_iter().map(|node| {
+                    Ok(smallvec![Match {
+                        node,
+                        anno_key: DOCUMENT_KEY.clone(),
+                    }])
+                });
+                Ok(NodeSearch {
+                    it: Box::new(it),
+                    desc: Some(ExecutionNodeDesc::empty_with_fragment(
+                        common_args.node_nr,
+                        common_args.query_fragment,
+                        est_output,
+                    )),
+                    node_search_desc: Arc::new(NodeSearchDesc {
+                        qname: (
+                            Some(DOCUMENT_KEY.ns.clone()),
+                            Some(DOCUMENT_KEY.name.clone()),
+                        ),
+                        cond: filters,
+                        const_output: None,
+                    }),
+                    is_sorted: false,
+                    timeout,
+                })
+            }
         }
     }
 
@@ -680,19 +1062,25 @@ impl<'a> NodeSearch<'a> {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn new_annosearch_regex(
         db: &'a AnnotationGraph,
         qname: (Option<String>, String),
         pattern: &str,
+        flags: RegexFlags,
         negated: bool,
         filters: Vec<MatchValueFilterFunc>,
         is_meta: bool,
         common_args: CommonArguments,
     ) -> Result<NodeSearch<'a>> {
         // match_regex works only with values
-        let base_it =
-            db.get_node_annos()
-                .regex_anno_search(qname.0.as_deref(), &qname.1, pattern, negated);
+        let base_it = db.get_node_annos().regex_anno_search(
+            qname.0.as_deref(),
+            &qname.1,
+            pattern,
+            flags,
+            negated,
+        );
 
         let const_output = if is_meta {
             Some(NODE_TYPE_KEY.clone())
@@ -733,30 +1121,36 @@ impl<'a> NodeSearch<'a> {
                 Box::new(base_it.map(|it| it.map_err(GraphAnnisError::from)))
             };
 
-        let est_output = if let Some(cached) =
-            common_args.output_size_cache.get(&common_args.node_nr)
-        {
-            *cached
-        } else if negated {
-            let total = db
-                .get_node_annos()
-                .number_of_annotations_by_name(qname.0.as_deref(), &qname.1)?;
-            let est_for_regex =
-                db.get_node_annos()
-                    .guess_max_count_regex(qname.0.as_deref(), &qname.1, pattern)?;
-            if total == est_for_regex {
-                // The regex estimation did  a fallback to "every value is
-                // possible" and thus is not really an accurate representation
-                // of the possible values. Return the worst case estimate for
-                // negation (all matches) instead.
-                total
+        let est_output =
+            if let Some(cached) = common_args.output_size_cache.get(&common_args.node_nr) {
+                *cached
+            } else if negated {
+                let total = db
+                    .get_node_annos()
+                    .number_of_annotations_by_name(qname.0.as_deref(), &qname.1)?;
+                let est_for_regex = db.get_node_annos().guess_max_count_regex(
+                    qname.0.as_deref(),
+                    &qname.1,
+                    pattern,
+                    flags,
+                )?;
+                if total == est_for_regex {
+                    // The regex estimation did  a fallback to "every value is
+                    // possible" and thus is not really an accurate representation
+                    // of the possible values. Return the worst case estimate for
+                    // negation (all matches) instead.
+                    total
+                } else {
+                    total - est_for_regex
+                }
             } else {
-                total - est_for_regex
-            }
-        } else {
-            db.get_node_annos()
-                .guess_max_count_regex(qname.0.as_deref(), &qname.1, pattern)?
-        };
+                db.get_node_annos().guess_max_count_regex(
+                    qname.0.as_deref(),
+                    &qname.1,
+                    pattern,
+                    flags,
+                )?
+            };
         common_args
             .output_size_cache
             .insert(common_args.node_nr, est_output);
@@ -783,12 +1177,149 @@ impl<'a> NodeSearch<'a> {
         })
     }
 
+    fn new_annosearch_numeric(
+        db: &'a AnnotationGraph,
+        qname: (Option<String>, String),
+        range: (Bound<f64>, Bound<f64>),
+        filters: Vec<MatchValueFilterFunc>,
+        common_args: CommonArguments,
+    ) -> Result<NodeSearch<'a>> {
+        let base_it = db
+            .get_node_annos()
+            .numeric_anno_search(qname.0.as_deref(), &qname.1, range.0, range.1)
+            .map(|it| it.map_err(GraphAnnisError::from));
+
+        let est_output =
+            if let Some(cached) = common_args.output_size_cache.get(&common_args.node_nr) {
+                *cached
+            } else {
+                db.get_node_annos().guess_max_count_numeric(
+                    qname.0.as_deref(),
+                    &qname.1,
+                    range.0,
+                    range.1,
+                )?
+            };
+
+        // always assume at least one output item otherwise very small selectivity can fool the planner
+        let est_output = std::cmp::max(1, est_output);
+        common_args
+            .output_size_cache
+            .insert(common_args.node_nr, est_output);
+
+        let it = base_it.map_ok(|n| smallvec![n]);
+
+        Ok(NodeSearch {
+            it: Box::new(it),
+            desc: Some(ExecutionNodeDesc::empty_with_fragment(
+                common_args.node_nr,
+                common_args.query_fragment,
+                est_output,
+            )),
+            node_search_desc: Arc::new(NodeSearchDesc {
+                qname: (qname.0, Some(qname.1)),
+                cond: filters,
+                const_output: None,
+            }),
+            is_sorted: false,
+            timeout: common_args.timeout,
+        })
+    }
+
+    fn new_annosearch_name_pattern(
+        db: &'a AnnotationGraph,
+        anno_keys: Vec<AnnoKey>,
+        val: &AnnoValueCondition,
+        filters: Vec<MatchValueFilterFunc>,
+        common_args: CommonArguments,
+    ) -> Result<NodeSearch<'a>> {
+        let node_annos = db.get_node_annos();
+        let mut base_iterators = Vec::with_capacity(anno_keys.len());
+        let mut guessed_count = 0;
+        for k in anno_keys.iter() {
+            let ns = Some(k.ns.as_str());
+            let it = match val {
+                AnnoValueCondition::Any => node_annos.exact_anno_search(ns, &k.name, None.into()),
+                AnnoValueCondition::Exact(val) => {
+                    node_annos.exact_anno_search(ns, &k.name, ValueSearch::Some(val))
+                }
+                AnnoValueCondition::NotExact(val) => {
+                    node_annos.exact_anno_search(ns, &k.name, ValueSearch::NotSome(val))
+                }
+                AnnoValueCondition::Regex(val, flags) => {
+                    node_annos.regex_anno_search(ns, &k.name, val, *flags, false)
+                }
+                AnnoValueCondition::NotRegex(val, flags) => {
+                    node_annos.regex_anno_search(ns, &k.name, val, *flags, true)
+                }
+            };
+            base_iterators.push(it);
+
+            if !common_args
+                .output_size_cache
+                .contains_key(&common_args.node_nr)
+            {
+                let total = node_annos.number_of_annotations_by_name(ns, &k.name)?;
+                guessed_count += match val {
+                    AnnoValueCondition::Any => total,
+                    AnnoValueCondition::Exact(val) => {
+                        node_annos.guess_max_count(ns, &k.name, val, val)?
+                    }
+                    AnnoValueCondition::NotExact(val) => {
+                        total.saturating_sub(node_annos.guess_max_count(ns, &k.name, val, val)?)
+                    }
+                    AnnoValueCondition::Regex(val, flags) => {
+                        node_annos.guess_max_count_regex(ns, &k.name, val, *flags)?
+                    }
+                    AnnoValueCondition::NotRegex(val, flags) => total.saturating_sub(
+                        node_annos.guess_max_count_regex(ns, &k.name, val, *flags)?,
+                    ),
+                };
+            }
+        }
+
+        let est_output =
+            if let Some(cached) = common_args.output_size_cache.get(&common_args.node_nr) {
+                *cached
+            } else {
+                guessed_count
+            };
+        common_args
+            .output_size_cache
+            .insert(common_args.node_nr, est_output);
+
+        // always assume at least one output item otherwise very small selectivity can fool the planner
+        let est_output = std::cmp::max(1, est_output);
+
+        let it = base_iterators
+            .into_iter()
+            .flatten()
+            .map(|m| m.map_err(GraphAnnisError::from))
+            .map_ok(|n| smallvec![n]);
+
+        Ok(NodeSearch {
+            it: Box::new(it),
+            desc: Some(ExecutionNodeDesc::empty_with_fragment(
+                common_args.node_nr,
+                common_args.query_fragment,
+                est_output,
+            )),
+            node_search_desc: Arc::new(NodeSearchDesc {
+                qname: (None, None),
+                cond: filters,
+                const_output: None,
+            }),
+            is_sorted: false,
+            timeout: common_args.timeout,
+        })
+    }
+
     fn new_tokensearch(
         db: &'a AnnotationGraph,
         val: ValueSearch<String>,
         filters: Vec<MatchValueFilterFunc>,
         leafs_only: bool,
-        match_regex: bool,
+        match_regex: Option<RegexFlags>,
         common_args: CommonArguments,
     ) -> Result<NodeSearch<'a>> {
         let it_base: Box<dyn Iterator<Item = Result<Match>>> = match val {
@@ -800,11 +1331,12 @@ impl<'a> NodeSearch<'a> {
                 Box::new(it)
             }
             ValueSearch::Some(ref val) => {
-                let it = if match_regex {
+                let it = if let Some(flags) = match_regex {
                     db.get_node_annos().regex_anno_search(
                         Some(&TOKEN_KEY.ns),
                         &TOKEN_KEY.name,
                         val,
+                        flags,
                         false,
                     )
                 } else {
@@ -817,11 +1349,12 @@ impl<'a> NodeSearch<'a> {
                 Box::new(it.map(|it| it.map_err(GraphAnnisError::from)))
             }
             ValueSearch::NotSome(ref val) => {
-                let it = if match_regex {
+                let it = if let Some(flags) = match_regex {
                     db.get_node_annos().regex_anno_search(
                         Some(&TOKEN_KEY.ns),
                         &TOKEN_KEY.name,
                         val,
+                        flags,
                         true,
                     )
                 } else {
@@ -872,11 +1405,12 @@ impl<'a> NodeSearch<'a> {
         // TODO: is_leaf should be part of the estimation
         let est_output = match val {
             ValueSearch::Some(ref val) => {
-                if match_regex {
+                if let Some(flags) = match_regex {
                     db.get_node_annos().guess_max_count_regex(
                         Some(&TOKEN_KEY.ns),
                         &TOKEN_KEY.name,
                         val,
+                        flags,
                     )?
                 } else {
                     db.get_node_annos().guess_max_count(
@@ -891,11 +1425,12 @@ impl<'a> NodeSearch<'a> {
                 let total_count = db
                     .get_node_annos()
                     .number_of_annotations_by_name(Some(&TOKEN_KEY.ns), &TOKEN_KEY.name)?;
-                let positive_count = if match_regex {
+                let positive_count = if let Some(flags) = match_regex {
                     db.get_node_annos().guess_max_count_regex(
                         Some(&TOKEN_KEY.ns),
                         &TOKEN_KEY.name,
                         &val,
+                        flags,
                     )?
                 } else {
                     db.get_node_annos().guess_max_count(
@@ -931,6 +1466,67 @@ impl<'a> NodeSearch<'a> {
         })
     }
 
+    fn new_textsearch(
+        db: &'a AnnotationGraph,
+        matcher: TextMatcher,
+        val: &str,
+        regex_flags: Option<RegexFlags>,
+        filters: Vec<MatchValueFilterFunc>,
+        common_args: CommonArguments,
+    ) -> Result<NodeSearch<'a>> {
+        let node_annos = db.get_node_annos();
+        let leaf_filter = create_token_leaf_filter(db);
+        let it = node_annos
+            .exact_anno_search(Some(&TOKEN_KEY.ns), &TOKEN_KEY.name, None.into())
+            .map(|m| m.map_err(GraphAnnisError::from))
+            .map(move |m| text_match_starts(&matcher, &leaf_filter, &m?, node_annos))
+            .flatten_ok()
+            .map_ok(|node| {
+                smallvec![Match {
+                    node,
+                    anno_key: NODE_TYPE_KEY.clone(),
+                }]
+            });
+
+        let est_output =
+            if let Some(cached) = common_args.output_size_cache.get(&common_args.node_nr) {
+                *cached
+            } else if regex_flags.is_none()
+                && let Some(first_char) = val.chars().next()
+            {
+                // The value of the first token of an exact text is a prefix of
+                // the text, which is sorted between its first character and
+                // the whole text.
+                let mut lower = [0; 4];
+                let lower = first_char.encode_utf8(&mut lower);
+                node_annos.guess_max_count(Some(&TOKEN_KEY.ns), &TOKEN_KEY.name, lower, val)?
+            } else {
+                node_annos.number_of_annotations_by_name(Some(&TOKEN_KEY.ns), &TOKEN_KEY.name)?
+            };
+        common_args
+            .output_size_cache
+            .insert(common_args.node_nr, est_output);
+
+        // always assume at least one output item otherwise very small selectivity can fool the planner
+        let est_output = std::cmp::max(1, est_output);
+
+        Ok(NodeSearch {
+            it: Box::new(it),
+            desc: Some(ExecutionNodeDesc::empty_with_fragment(
+                common_args.node_nr,
+                common_args.query_fragment,
+                est_output,
+            )),
+            node_search_desc: Arc::new(NodeSearchDesc {
+                qname: (Some(TOKEN_KEY.ns.clone()), Some(TOKEN_KEY.name.clone())),
+                cond: filters,
+                const_output: Some(NODE_TYPE_KEY.clone()),
+            }),
+            is_sorted: false,
+            timeout: common_args.timeout,
+        })
+    }
+
     fn new_anytoken_search(
         db: &'a AnnotationGraph,
         common_args: CommonArguments,
@@ -991,7 +1587,7 @@ impl<'a> NodeSearch<'a> {
         node_search_desc: Arc<NodeSearchDesc>,
         desc: Option<&ExecutionNodeDesc>,
         components: HashSet<Component<AnnotationComponentType>>,
-        edge_anno_spec: Option<EdgeAnnoSearchSpec>,
+        edge_anno_specs: Vec<EdgeAnnoSearchSpec>,
         timeout: TimeoutCheck,
     ) -> Result<NodeSearch<'a>> {
         let node_search_desc_1 = node_search_desc.clone();
@@ -1004,12 +1600,15 @@ impl<'a> NodeSearch<'a> {
                     dyn Iterator<Item = std::result::Result<NodeID, GraphAnnisCoreError>>,
                 > {
                     if let Some(gs) = db.get_graphstorage_as_ref(&c) {
-                        if let Some(EdgeAnnoSearchSpec::ExactValue {
-                            ref ns,
-                            ref name,
-                            ref val,
-                        }) = edge_anno_spec
-                        {
+                        // Use the first exact edge annotation condition to find
+                        // candidates, the others are checked by the operator
+                        let exact_edge_anno = edge_anno_specs.iter().find_map(|spec| match spec {
+                            EdgeAnnoSearchSpec::ExactValue { ns, name, val } => {
+                                Some((ns, name, val))
+                            }
+                            _ => None,
+                        });
+                        if let Some((ns, name, val)) = exact_edge_anno {
                             // for each component get the source nodes with this edge annotation
                             let anno_storage: &dyn EdgeAnnotationStorage = gs.get_anno_storage();
 
@@ -1,6 +1,7 @@
 use super::super::{ExecutionNode, ExecutionNodeDesc};
 use crate::annis::db::aql::conjunction::BinaryOperatorEntry;
 use crate::annis::operator::BinaryOperatorBase;
+use crate::annis::util::{MemoryReservation, TimeoutCheck, estimated_match_group_size};
 use crate::errors::Result;
 use graphannis_core::annostorage::MatchGroup;
 use rayon::prelude::*;
@@ -26,6 +27,7 @@ pub struct NestedLoop<'a> {
     desc: ExecutionNodeDesc,
 
     global_reflexivity: bool,
+    inner_cache_memory: MemoryReservation,
 }
 
 type MatchCandidate = (Arc<MatchGroup>, Arc<MatchGroup>, Sender<Result<MatchGroup>>);
@@ -37,6 +39,7 @@ impl<'a> NestedLoop<'a> {
         rhs: Box<dyn ExecutionNode<Item = Result<MatchGroup>> + 'a>,
         lhs_idx: usize,
         rhs_idx: usize,
+        timeout: TimeoutCheck,
     ) -> Result<NestedLoop<'a>> {
         let mut left_is_outer = true;
         if let (Some(desc_lhs), Some(desc_rhs)) = (lhs.get_desc(), rhs.get_desc())
@@ -82,6 +85,7 @@ impl<'a> NestedLoop<'a> {
                 global_reflexivity: op_entry.args.global_reflexivity,
                 match_candidate_buffer: Vec::with_capacity(MAX_BUFFER_SIZE),
                 current_outer: None,
+                inner_cache_memory: timeout.memory_reservation(),
             };
             Ok(join)
         } else {
@@ -110,6 +114,7 @@ impl<'a> NestedLoop<'a> {
                 global_reflexivity: op_entry.args.global_reflexivity,
                 match_candidate_buffer: Vec::with_capacity(MAX_BUFFER_SIZE),
                 current_outer: None,
+                inner_cache_memory: timeout.memory_reservation(),
             };
             Ok(join)
         }
@@ -163,6 +168,13 @@ impl<'a> NestedLoop<'a> {
                             for m_inner in &mut self.inner {
                                 match m_inner {
                                     Ok(m_inner) => {
+                                        if let Err(e) = self
+                                            .inner_cache_memory
+                                            .grow(estimated_match_group_size(&m_inner))
+                                        {
+                                            tx.send(Err(e)).ok();
+                                            return;
+                                        }
                                         let m_inner: Arc<MatchGroup> = Arc::from(m_inner);
 
                                         self.inner_cache.push(m_inner.clone());
@@ -0,0 +1,279 @@
+use crate::AnnotationGraph;
+use crate::annis::db::aql::model::{
+    AnnotationComponentType, TOK_WHITESPACE_AFTER, TOK_WHITESPACE_BEFORE, TOKEN_KEY,
+};
+use crate::annis::errors::{AQLError, GraphAnnisError, Result};
+use crate::annis::types::LineColumnRange;
+use graphannis_core::annostorage::NodeAnnotationStorage;
+use graphannis_core::graph::{ANNIS_NS, storage::GraphStorage};
+use graphannis_core::types::{AnnoKey, Component, NodeID};
+use graphannis_core::util::RegexFlags;
+use regex_automata::dfa::{Automaton, StartKind, dense};
+use regex_automata::util::primitives::StateID;
+use regex_automata::util::start;
+use regex_automata::{Anchored, MatchKind};
+use std::sync::Arc;
+
+/// Maximum size in bytes of the automaton for a single text search.
+const DFA_SIZE_LIMIT: usize = 10 * (1 << 20);
+
+/// Matches a string or regular expression against the text of consecutive
+/// token, which is reconstructed from the token values and the whitespace
+/// between them.
+///
+/// Each text is walked only once: all possible match starts are tracked at
+/// the same time and the ones that are in the same state of the automaton are
+/// merged, because they can only be completed by the same token.
+///
+/// If there is neither a `tok-whitespace-after` annotation for a token nor a
+/// `tok-whitespace-before` annotation for the following token, a single space
+/// is assumed between them.
+pub struct TextMatcher {
+    dfa: dense::DFA<Vec<u32>>,
+    start_state: StateID,
+    order_gs: Option<Arc<dyn GraphStorage>>,
+    whitespace_before_key: AnnoKey,
+    whitespace_after_key: AnnoKey,
+}
+
+impl TextMatcher {
+    /// Create a new matcher for the given value. If `regex_flags` is `None`,
+    /// the value is matched as exact string, otherwise it is a regular
+    /// expression with these flags.
+    pub fn new(
+        db: &AnnotationGraph,
+        val: &str,
+        regex_flags: Option<RegexFlags>,
+        location_in_query: Option<LineColumnRange>,
+    ) -> Result<TextMatcher> {
+        let pattern = if let Some(flags) = regex_flags {
+            graphannis_core::util::regex_with_flags(val, flags).to_string()
+        } else {
+            regex_syntax::escape(val)
+        };
+        let semantic_error = |desc: String| {
+            GraphAnnisError::AQLSemanticError(AQLError {
+                desc: format!("/{}/ -> {}", val, desc),
+                location: location_in_query.clone(),
+            })
+        };
+        // Report all possible match ends and not only the leftmost-first one,
+        // so that a match can end at any token
+        let dfa = dense::Builder::new()
+            .configure(
+                dense::Config::new()
+                    .match_kind(MatchKind::All)
+                    .start_kind(StartKind::Anchored)
+                    .dfa_size_limit(Some(DFA_SIZE_LIMIT))
+                    .determinize_size_limit(Some(DFA_SIZE_LIMIT)),
+            )
+            .build(&pattern)
+            .map_err(|e| semantic_error(e.to_string()))?;
+        let start_state = dfa
+            .start_state(&start::Config::new().anchored(Anchored::Yes))
+            .map_err(|e| semantic_error(e.to_string()))?;
+
+        let order_gs = db.get_graphstorage(&Component::new(
+            AnnotationComponentType::Ordering,
+            ANNIS_NS.into(),
+            "".into(),
+        ));
+
+        Ok(TextMatcher {
+            dfa,
+            start_state,
+            order_gs,
+            whitespace_before_key: AnnoKey {
+                ns: ANNIS_NS.into(),
+                name: TOK_WHITESPACE_BEFORE.into(),
+            },
+            whitespace_after_key: AnnoKey {
+                ns: ANNIS_NS.into(),
+                name: TOK_WHITESPACE_AFTER.into(),
+            },
+        })
+    }
+
+    /// Returns true if the token is the first one of a text, i.e. there is
+    /// no token before it.
+    pub fn is_text_start(&self, token: NodeID) -> Result<bool> {
+        if let Some(order_gs) = &self.order_gs {
+            Ok(order_gs
+                .get_ingoing_edges(token)
+                .next()
+                .transpose()?
+                .is_none())
+        } else {
+            Ok(true)
+        }
+    }
+
+    /// Walk along the text beginning with the token `first` and call
+    /// `on_match` for each token where a text match starts.
+    pub fn for_each_match_start<F>(
+        &self,
+        first: NodeID,
+        node_annos: &dyn NodeAnnotationStorage,
+        mut on_match: F,
+    ) -> Result<()>
+    where
+        F: FnMut(NodeID),
+    {
+        // The token where a match could still start, grouped by the state of
+        // the automaton after reading the text from this token on
+        let mut active: Vec<(StateID, Vec<NodeID>)> = Vec::new();
+        let mut next_active = Vec::new();
+
+        let mut current = first;
+        while let Some(token_value) = node_annos.get_value_for_item(&current, &TOKEN_KEY)? {
+            add_partial_match(&mut active, self.start_state, vec![current]);
+            self.feed(&mut active, &mut next_active, &token_value);
+            // A match that ends at this token does not need to be continued,
+            // because it is only relevant where a match starts.
+            active.retain(|(state, starts)| {
+                if self.dfa.is_match_state(self.dfa.next_eoi_state(*state)) {
+                    starts.iter().for_each(|start| on_match(*start));
+                    false
+                } else {
+                    true
+                }
+            });
+
+            // Continue with the whitespace and the value of the next token
+            let next = if let Some(order_gs) = &self.order_gs {
+                order_gs.get_outgoing_edges(current).next().transpose()?
+            } else {
+                None
+            };
+            let Some(next) = next else {
+                break;
+            };
+            self.feed_whitespace(&mut active, &mut next_active, current, next, node_annos)?;
+            current = next;
+        }
+        Ok(())
+    }
+
+    /// Walk along the text beginning with the token `start` and call
+    /// `on_match` for each token where a text match starting at `start` ends.
+    /// The search is stopped when `on_match` returns `false` or no further
+    /// match is possible.
+    pub fn for_each_match_end<F>(
+        &self,
+        start: NodeID,
+        node_annos: &dyn NodeAnnotationStorage,
+        mut on_match: F,
+    ) -> Result<()>
+    where
+        F: FnMut(NodeID) -> bool,
+    {
+        let mut active = vec![(self.start_state, vec![start])];
+        let mut next_active = Vec::new();
+
+        let mut current = start;
+        while let Some(token_value) = node_annos.get_value_for_item(&current, &TOKEN_KEY)? {
+            self.feed(&mut active, &mut next_active, &token_value);
+            let Some((state, _)) = active.first() else {
+                break;
+            };
+            if self.dfa.is_match_state(self.dfa.next_eoi_state(*state)) && !on_match(current) {
+                break;
+            }
+
+            let next = if let Some(order_gs) = &self.order_gs {
+                order_gs.get_outgoing_edges(current).next().transpose()?
+            } else {
+                None
+            };
+            let Some(next) = next else {
+                break;
+            };
+            self.feed_whitespace(&mut active, &mut next_active, current, next, node_annos)?;
+            current = next;
+        }
+        Ok(())
+    }
+
+    /// Returns all token where a text match starting at `start` ends.
+    pub fn match_ends(
+        &self,
+        start: NodeID,
+        node_annos: &dyn NodeAnnotationStorage,
+    ) -> Result<Vec<NodeID>> {
+        let mut result = Vec::new();
+        self.for_each_match_end(start, node_annos, |end| {
+            result.push(end);
+            true
+        })?;
+        Ok(result)
+    }
+
+    /// Feed the whitespace between the token `current` and the following
+    /// token `next` to the automaton for all partial matches.
+    fn feed_whitespace(
+        &self,
+        active: &mut Vec<(StateID, Vec<NodeID>)>,
+        next_active: &mut Vec<(StateID, Vec<NodeID>)>,
+        current: NodeID,
+        next: NodeID,
+        node_annos: &dyn NodeAnnotationStorage,
+    ) -> Result<()> {
+        if active.is_empty() {
+            return Ok(());
+        }
+        let whitespace_after =
+            node_annos.get_value_for_item(&current, &self.whitespace_after_key)?;
+        let whitespace_before =
+            node_annos.get_value_for_item(&next, &self.whitespace_before_key)?;
+        if whitespace_after.is_none() && whitespace_before.is_none() {
+            self.feed(active, next_active, " ");
+        } else {
+            if let Some(ws) = whitespace_after {
+                self.feed(active, next_active, &ws);
+            }
+            if let Some(ws) = whitespace_before {
+                self.feed(active, next_active, &ws);
+            }
+        }
+        Ok(())
+    }
+
+    /// Feed the bytes of the text to the automaton for all partial matches
+    /// and remove the ones that can't match anymore.
+    fn feed(
+        &self,
+        active: &mut Vec<(StateID, Vec<NodeID>)>,
+        next_active: &mut Vec<(StateID, Vec<NodeID>)>,
+        text: &str,
+    ) {
+        for b in text.bytes() {
+            if active.is_empty() {
+                break;
+            }
+            for (state, starts) in active.drain(..) {
+                let state = self.dfa.next_state(state, b);
+                if !self.dfa.is_dead_state(state) {
+                    add_partial_match(next_active, state, starts);
+                }
+            }
+            std::mem::swap(active, next_active);
+        }
+    }
+}
+
+/// Add the start token of partial matches in the given state, merging them
+/// with the existing ones in the same state.
+fn add_partial_match(
+    active: &mut Vec<(StateID, Vec<NodeID>)>,
+    state: StateID,
+    mut starts: Vec<NodeID>,
+) {
+    if let Some((_, existing)) = active.iter_mut().find(|(s, _)| *s == state) {
+        if existing.len() < starts.len() {
+            std::mem::swap(existing, &mut starts);
+        }
+        existing.append(&mut starts);
+    } else {
+        active.push((state, starts));
+    }
+}
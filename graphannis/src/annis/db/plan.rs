@@ -3,6 +3,7 @@ use crate::annis::db::aql::Config;
 use crate::annis::db::aql::disjunction::Disjunction;
 use crate::annis::db::exec::{EmptyResultSet, ExecutionNode, ExecutionNodeDesc};
 use crate::annis::errors::*;
+use crate::annis::types::AnalyzedExecutionNode;
 use crate::annis::util::TimeoutCheck;
 use graphannis_core::annostorage::match_group_with_symbol_ids;
 use graphannis_core::annostorage::symboltable::SymbolTable;
@@ -35,7 +36,7 @@ impl<'a> ExecutionPlan<'a> {
         let mut descriptions = Vec::new();
         let mut inverse_node_pos = Vec::new();
         for alt in &query.alternatives {
-            let p = alt.make_exec_node(db, config, timeout);
+            let p = alt.make_exec_node(db, config, timeout.clone());
             if let Ok(p) = p {
                 descriptions.push(p.get_desc().cloned());
 
@@ -127,6 +128,16 @@ impl<'a> ExecutionPlan<'a> {
         estimation
     }
 
+    /// Returns the estimated and actual statistics for each alternative of
+    /// the plan. The actual statistics are only available if the plan was
+    /// created with the `analyze` configuration and has been executed.
+    pub fn analysis(&self) -> Vec<Option<AnalyzedExecutionNode>> {
+        self.descriptions
+            .iter()
+            .map(|d| d.as_ref().map(|d| d.analysis()))
+            .collect()
+    }
+
     pub fn is_sorted_by_text(&self) -> bool {
         if self.plans.len() > 1 {
             false
@@ -25,12 +25,22 @@ pub enum GraphAnnisError {
     ImpossibleSearch(String),
     #[error("timeout")]
     Timeout,
+    #[error("query was cancelled")]
+    Cancelled,
+    #[error("query exceeded the memory limit of {0} bytes")]
+    MemoryLimitExceeded(usize),
     #[error("could not load graph {name} from disk")]
     LoadingGraphFailed { name: String },
     #[error("corpus {0} not found")]
     NoSuchCorpus(String),
     #[error("corpus {0} already exists.")]
     CorpusExists(String),
+    #[error("snapshot {0} already exists.")]
+    SnapshotExists(String),
+    #[error("invalid snapshot name \"{0}\"")]
+    InvalidSnapshotName(String),
+    #[error("corpus {0} is a snapshot and can't be changed")]
+    ReadOnlySnapshot(String),
     #[error("could not get internal node ID for node {0}")]
     NoSuchNodeID(String),
     #[error("could not covered token for node {0}")]
@@ -56,6 +66,10 @@ pub enum GraphAnnisError {
     the referenced node and the annotation name or \"tok\" separated by \":\""
     )]
     InvalidFrequencyDefinition,
+    #[error("invalid find cursor: {0}")]
+    InvalidFindCursor(String),
+    #[error("the maximum number of sampled documents must be larger than zero")]
+    EmptyDocumentSample,
     #[error(transparent)]
     CorpusStorage(#[from] CorpusStorageError),
     #[error(transparent)]
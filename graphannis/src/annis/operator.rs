@@ -1,7 +1,47 @@
 use super::db::{aql::model::AnnotationComponentType, exec::CostEstimate};
 use crate::{AnnotationGraph, errors::Result, graph::Match};
-use graphannis_core::{annostorage::EdgeAnnotationStorage, types::Component};
-use std::{collections::HashSet, fmt::Display};
+use graphannis_core::{
+    annostorage::{EdgeAnnotationStorage, parse_numeric_value},
+    types::{Component, NodeID},
+    util::RegexFlags,
+};
+use std::{collections::HashSet, fmt::Display, ops::Bound};
+
+/// Comparison operators for annotation values that are numbers, e.g. `year>=1850`.
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, Hash, PartialEq, Eq)]
+pub enum NumericComparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl NumericComparison {
+    /// Get the range of numeric values that fulfill this comparison with the given `reference` value.
+    ///
+    /// Returns `None` if the reference value is not a number.
+    pub fn value_range(&self, reference: &str) -> Option<(Bound<f64>, Bound<f64>)> {
+        let reference = parse_numeric_value(reference)?;
+        let range = match self {
+            NumericComparison::Less => (Bound::Unbounded, Bound::Excluded(reference)),
+            NumericComparison::LessEqual => (Bound::Unbounded, Bound::Included(reference)),
+            NumericComparison::Greater => (Bound::Excluded(reference), Bound::Unbounded),
+            NumericComparison::GreaterEqual => (Bound::Included(reference), Bound::Unbounded),
+        };
+        Some(range)
+    }
+}
+
+impl std::fmt::Display for NumericComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NumericComparison::Less => write!(f, "<"),
+            NumericComparison::LessEqual => write!(f, "<="),
+            NumericComparison::Greater => write!(f, ">"),
+            NumericComparison::GreaterEqual => write!(f, ">="),
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialOrd, Ord, Hash, PartialEq, Eq)]
 #[allow(clippy::enum_variant_names)]
@@ -20,11 +60,19 @@ pub enum EdgeAnnoSearchSpec {
         ns: Option<String>,
         name: String,
         val: String,
+        flags: RegexFlags,
     },
     NotRegexValue {
         ns: Option<String>,
         name: String,
         val: String,
+        flags: RegexFlags,
+    },
+    NumericValue {
+        ns: Option<String>,
+        name: String,
+        cmp: NumericComparison,
+        val: String,
     },
 }
 
@@ -53,23 +101,42 @@ impl std::fmt::Display for EdgeAnnoSearchSpec {
 
                 write!(f, "{}!=\"{}\"", qname, val)
             }
-            EdgeAnnoSearchSpec::RegexValue { ns, name, val } => {
+            EdgeAnnoSearchSpec::RegexValue {
+                ns,
+                name,
+                val,
+                flags,
+            } => {
+                let qname = if let Some(ns) = ns {
+                    format!("{}:{}", ns, name)
+                } else {
+                    name.clone()
+                };
+
+                write!(f, "{}=/{}/{}", qname, val, flags)
+            }
+            EdgeAnnoSearchSpec::NotRegexValue {
+                ns,
+                name,
+                val,
+                flags,
+            } => {
                 let qname = if let Some(ns) = ns {
                     format!("{}:{}", ns, name)
                 } else {
                     name.clone()
                 };
 
-                write!(f, "{}=/{}/", qname, val)
+                write!(f, "{}!=/{}/{}", qname, val, flags)
             }
-            EdgeAnnoSearchSpec::NotRegexValue { ns, name, val } => {
+            EdgeAnnoSearchSpec::NumericValue { ns, name, cmp, val } => {
                 let qname = if let Some(ns) = ns {
                     format!("{}:{}", ns, name)
                 } else {
                     name.clone()
                 };
 
-                write!(f, "{}!=/{}/", qname, val)
+                write!(f, "{}{}{}", qname, cmp, val)
             }
         }
     }
@@ -99,10 +166,23 @@ impl EdgeAnnoSearchSpec {
                     )?;
                 Ok(result)
             }
-            EdgeAnnoSearchSpec::RegexValue { ns, name, val } => Ok(
-                anno_storage.guess_max_count_regex(ns.as_ref().map(String::as_str), name, val)?
-            ),
-            EdgeAnnoSearchSpec::NotRegexValue { ns, name, val } => {
+            EdgeAnnoSearchSpec::RegexValue {
+                ns,
+                name,
+                val,
+                flags,
+            } => Ok(anno_storage.guess_max_count_regex(
+                ns.as_ref().map(String::as_str),
+                name,
+                val,
+                *flags,
+            )?),
+            EdgeAnnoSearchSpec::NotRegexValue {
+                ns,
+                name,
+                val,
+                flags,
+            } => {
                 let total = anno_storage
                     .number_of_annotations_by_name(ns.as_ref().map(String::as_str), name)?;
                 let result = total
@@ -110,9 +190,22 @@ impl EdgeAnnoSearchSpec {
                         ns.as_ref().map(String::as_str),
                         name,
                         val,
+                        *flags,
                     )?;
                 Ok(result)
             }
+            EdgeAnnoSearchSpec::NumericValue { ns, name, cmp, val } => {
+                if let Some((lower, upper)) = cmp.value_range(val) {
+                    Ok(anno_storage.guess_max_count_numeric(
+                        ns.as_ref().map(String::as_str),
+                        name,
+                        lower,
+                        upper,
+                    )?)
+                } else {
+                    Ok(0)
+                }
+            }
         }
     }
 }
@@ -127,6 +220,23 @@ pub enum EstimationType {
     Min,
 }
 
+/// Describes which property of the operands must be equal for an operator
+/// that can be executed as an equi-join.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EquiJoinKind {
+    /// Both operands must be the same node.
+    SameNode,
+    /// Both operands must have the same annotation value.
+    SameValue,
+}
+
+/// The key of a match that is compared in an equi-join.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EquiJoinKey {
+    Node(NodeID),
+    Value(String),
+}
+
 pub trait BinaryOperatorBase: std::fmt::Display + Send + Sync {
     fn filter_match(&self, lhs: &Match, rhs: &Match) -> Result<bool>;
 
@@ -148,6 +258,20 @@ pub trait BinaryOperatorBase: std::fmt::Display + Send + Sync {
     fn edge_anno_selectivity(&self) -> Result<Option<f64>> {
         Ok(None)
     }
+
+    /// Returns the kind of equi-join if this operator is fulfilled exactly
+    /// when the [keys](BinaryOperatorBase::equi_join_key) of both operands
+    /// are equal. Such operators can be executed as hash or merge join.
+    fn equi_join_kind(&self) -> Option<EquiJoinKind> {
+        None
+    }
+
+    /// Get the key of a match that is compared in an equi-join. The `lhs`
+    /// parameter determines if the match is the left-hand side operand.
+    /// Matches without a key can't fulfill the operator.
+    fn equi_join_key(&self, _m: &Match, _lhs: bool) -> Result<Option<EquiJoinKey>> {
+        Ok(None)
+    }
 }
 
 /// Holds an instance of one of the possibly binary operator types.
@@ -209,6 +333,20 @@ impl BinaryOperatorBase for BinaryOperator<'_> {
             BinaryOperator::Index(op) => op.edge_anno_selectivity(),
         }
     }
+
+    fn equi_join_kind(&self) -> Option<EquiJoinKind> {
+        match self {
+            BinaryOperator::Base(op) => op.equi_join_kind(),
+            BinaryOperator::Index(op) => op.equi_join_kind(),
+        }
+    }
+
+    fn equi_join_key(&self, m: &Match, lhs: bool) -> Result<Option<EquiJoinKey>> {
+        match self {
+            BinaryOperator::Base(op) => op.equi_join_key(m, lhs),
+            BinaryOperator::Index(op) => op.equi_join_key(m, lhs),
+        }
+    }
 }
 
 /// A binary operator that can be used in an [`IndexJoin`](crate::annis::db::exec::indexjoin::IndexJoin).
@@ -230,14 +368,29 @@ pub trait BinaryOperatorSpec: std::fmt::Debug + Send + Sync {
         cost_estimate: Option<(&CostEstimate, &CostEstimate)>,
     ) -> Result<BinaryOperator<'a>>;
 
-    fn get_edge_anno_spec(&self) -> Option<EdgeAnnoSearchSpec> {
-        None
+    /// Describes the operator in AQL syntax. Specifications with the same
+    /// description must create the same operator, because the description is
+    /// used to recognize equal queries.
+    fn canonical_description(&self) -> String;
+
+    /// Returns the edge annotation conditions that all need to be fulfilled
+    /// by the edges of the necessary components.
+    fn get_edge_anno_specs(&self) -> Vec<EdgeAnnoSearchSpec> {
+        Vec::default()
     }
 
     fn is_binding(&self) -> bool {
         true
     }
 
+    /// Returns `true` if all left-hand side matches of this operator are
+    /// source nodes of the [necessary
+    /// components](BinaryOperatorSpec::necessary_components). The query planner
+    /// can then restrict the search for the left-hand side to these nodes.
+    fn lhs_is_component_source(&self) -> bool {
+        true
+    }
+
     #[cfg(test)]
     fn into_any(self: std::sync::Arc<Self>) -> std::sync::Arc<dyn std::any::Any>;
 
@@ -255,6 +408,10 @@ pub trait UnaryOperatorSpec: std::fmt::Debug {
         &'a self,
         db: &'a AnnotationGraph,
     ) -> Result<Box<dyn UnaryOperator + 'a>>;
+
+    /// Describes the operator in AQL syntax. Specifications with the same
+    /// description must create the same operator.
+    fn canonical_description(&self) -> String;
 }
 
 pub trait UnaryOperator: std::fmt::Display {
@@ -11,6 +11,137 @@ pub struct CountExtra {
     pub document_count: u64,
 }
 
+/// An estimation of the number of matches, which is extrapolated from the
+/// matches in a random sample of the documents.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApproximateCount {
+    /// Estimated total number of matches.
+    pub match_count: u64,
+    /// Lower bound of the 95% confidence interval of the estimation.
+    pub lower_bound: u64,
+    /// Upper bound of the 95% confidence interval of the estimation.
+    pub upper_bound: u64,
+    /// `false` if all documents have been searched and the count is exact.
+    pub approximate: bool,
+}
+
+/// Statistics about the usage of the query result cache of a corpus storage.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResultCacheStatistics {
+    /// Number of queries that could be answered from the cache.
+    pub hits: u64,
+    /// Number of queries that had to be executed, because their result was not cached.
+    pub misses: u64,
+    /// Number of cached query results.
+    pub entries: usize,
+    /// Estimated memory size of all cached query results in bytes.
+    pub used_size: usize,
+    /// Maximum memory size of the cache in bytes.
+    pub max_size: usize,
+}
+
+/// An execution node of an analyzed query, with the estimated and the actual
+/// number of produced tuples.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzedExecutionNode {
+    /// Number of the query node for node searches, starting with 1.
+    pub node_nr: Option<usize>,
+    /// Name of the implementation, e.g. "indexjoin" or "nestedloop".
+    pub impl_description: String,
+    /// The part of the query that is executed by this node.
+    pub query_fragment: String,
+    /// Number of tuples the planner estimated for this node.
+    pub estimated_output: Option<usize>,
+    /// Number of tuples actually produced by this node. This is `None` if
+    /// the node has never been iterated, e.g. because it is the right-hand
+    /// side of an index join and only used to filter the candidates.
+    pub actual_output: Option<usize>,
+    /// Ratio of the actual to the estimated number of tuples. Values larger
+    /// than 1 mean that the planner underestimated the output.
+    pub estimation_error: Option<f64>,
+    /// Time spent in this node in milliseconds, including all child nodes.
+    pub time_ms: f64,
+    /// The input nodes of this execution node.
+    pub children: Vec<AnalyzedExecutionNode>,
+}
+
+impl AnalyzedExecutionNode {
+    fn write_indented(&self, f: &mut std::fmt::Formatter, indention: &str) -> std::fmt::Result {
+        let estimated = self
+            .estimated_output
+            .map(|o| o.to_string())
+            .unwrap_or_else(|| "?".to_string());
+        let statistics = if let Some(actual_output) = self.actual_output {
+            let error = self
+                .estimation_error
+                .map(|e| format!("{:.2}", e))
+                .unwrap_or_else(|| "?".to_string());
+            format!(
+                "est: {}, actual: {}, error: {}, time: {:.3} ms",
+                estimated, actual_output, error, self.time_ms
+            )
+        } else {
+            format!("est: {}, not iterated", estimated)
+        };
+        if let Some(node_nr) = self.node_nr {
+            writeln!(
+                f,
+                "{}#{} ({}) [{}] {}",
+                indention, node_nr, self.query_fragment, statistics, self.impl_description
+            )?;
+        } else {
+            writeln!(
+                f,
+                "{}+|{} ({}) [{}]",
+                indention, self.impl_description, self.query_fragment, statistics
+            )?;
+        }
+        let new_indention = format!("{}    ", indention);
+        for c in &self.children {
+            c.write_indented(f, &new_indention)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for AnalyzedExecutionNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write_indented(f, "")
+    }
+}
+
+/// The result of executing a query with statistics for every execution node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryAnalysis {
+    /// Name of the analyzed corpus.
+    pub corpus_name: String,
+    /// The execution plan for each alternative of the query or `None` if
+    /// there is no plan for an alternative, e.g. because it can't have any
+    /// results.
+    pub alternatives: Vec<Option<AnalyzedExecutionNode>>,
+    /// Number of matches of the whole query.
+    pub match_count: u64,
+    /// Time needed to execute the whole query in milliseconds.
+    pub time_ms: f64,
+}
+
+impl std::fmt::Display for QueryAnalysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}:", self.corpus_name)?;
+        for (i, alt) in self.alternatives.iter().enumerate() {
+            if i > 0 {
+                writeln!(f, "---[OR]---")?;
+            }
+            if let Some(alt) = alt {
+                write!(f, "{}", alt)?;
+            } else {
+                writeln!(f, "<no description>")?;
+            }
+        }
+        write!(f, "{} matches in {:.3} ms", self.match_count, self.time_ms)
+    }
+}
+
 /// Definition of the result of a `frequency` query.
 pub type FrequencyTable<T> = Vec<FrequencyTableRow<T>>;
 
@@ -23,6 +154,28 @@ pub struct FrequencyTableRow<T> {
     pub count: usize,
 }
 
+/// Definition of the result of a normalized `frequency` query.
+pub type NormalizedFrequencyTable<T> = Vec<NormalizedFrequencyTableRow<T>>;
+
+/// Represents the unique combination of attribute values, how often this
+/// combination occurs and how it is distributed over the documents.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NormalizedFrequencyTableRow<T> {
+    /// Combination of different attribute values.
+    pub values: Vec<T>,
+    /// Number of matches having this combination of attribute values.
+    pub count: usize,
+    /// Number of matches per million token of all queried corpora.
+    pub per_million: f64,
+    /// Number of documents having at least one match with this combination.
+    pub range: usize,
+    /// Juilland's D as measure how evenly the matches are distributed over
+    /// the documents, ranging from 0 (only in one document) to 1 (same
+    /// relative frequency in all documents). `None` if there are less than
+    /// two documents.
+    pub juilland_d: Option<f64>,
+}
+
 /// Description of an attribute of a query.
 #[derive(Serialize)]
 pub struct QueryAttributeDescription {
@@ -1,6 +1,7 @@
 pub mod quicksort;
 pub mod sortablecontainer;
 
+use graphannis_core::annostorage::{Match, MatchGroup};
 use graphannis_core::serializer::KeyVec;
 use regex_syntax::hir::literal::Extractor;
 
@@ -8,6 +9,10 @@ use crate::errors::{GraphAnnisError, Result};
 
 use std::{
     path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
     time::{Duration, Instant},
 };
 
@@ -108,22 +113,116 @@ pub fn node_names_from_match(match_line: &str) -> Vec<String> {
     result
 }
 
-#[derive(Clone, Copy)]
+/// Allows to cancel a running query from another thread.
+///
+/// All clones of a token share the same state, so a clone can be given to
+/// the query while the original is used to cancel it.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    parent: Option<Arc<CancellationToken>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Request the cancellation of all queries that use this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_cancelled())
+    }
+
+    /// Create a new token that is also cancelled when this token is
+    /// cancelled. Cancelling the new token does not cancel this one.
+    pub fn child_token(&self) -> CancellationToken {
+        CancellationToken {
+            cancelled: Arc::default(),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+}
+
+/// The memory that is used by the intermediate results of a single query.
+struct MemoryBudget {
+    limit: usize,
+    used: AtomicUsize,
+}
+
+/// Estimated number of bytes that are needed to hold a match group in memory.
+pub(crate) fn estimated_match_group_size(m: &MatchGroup) -> usize {
+    std::mem::size_of::<MatchGroup>() + (m.len() * std::mem::size_of::<Match>())
+}
+
+/// Checks the limits of a single query execution, which are the timeout, the
+/// cancellation and the memory budget.
+#[derive(Clone)]
 pub struct TimeoutCheck {
     start_time: Instant,
     timeout: Option<Duration>,
+    cancellation: Option<CancellationToken>,
+    memory: Option<Arc<MemoryBudget>>,
 }
 
 impl TimeoutCheck {
     pub fn new(timeout: Option<Duration>) -> TimeoutCheck {
+        TimeoutCheck::with_cancellation(timeout, None)
+    }
+
+    pub fn with_cancellation(
+        timeout: Option<Duration>,
+        cancellation: Option<CancellationToken>,
+    ) -> TimeoutCheck {
         TimeoutCheck {
             start_time: Instant::now(),
             timeout,
+            cancellation,
+            memory: None,
         }
     }
 
-    /// Check if too much time was used and return an error if this is the case.
+    /// Limit the memory (in bytes) that the intermediate results of the query
+    /// are allowed to use.
+    pub fn with_memory_limit(mut self, limit: Option<usize>) -> TimeoutCheck {
+        self.memory = limit.map(|limit| {
+            Arc::new(MemoryBudget {
+                limit,
+                used: AtomicUsize::new(0),
+            })
+        });
+        self
+    }
+
+    pub fn has_memory_limit(&self) -> bool {
+        self.memory.is_some()
+    }
+
+    /// Create an empty reservation for memory that is used by the
+    /// intermediate results of the query.
+    pub fn memory_reservation(&self) -> MemoryReservation {
+        MemoryReservation {
+            memory: self.memory.clone(),
+            bytes: 0,
+        }
+    }
+
+    /// Check if too much time was used or the query was cancelled and return
+    /// an error if this is the case.
     pub fn check(&self) -> Result<()> {
+        if let Some(cancellation) = &self.cancellation
+            && cancellation.is_cancelled()
+        {
+            debug!("Query was cancelled");
+            return Err(GraphAnnisError::Cancelled);
+        }
         if let Some(timeout) = self.timeout {
             let elapsed = self.start_time.elapsed();
             if elapsed > timeout {
@@ -139,6 +238,48 @@ impl TimeoutCheck {
     }
 }
 
+/// Memory that is reserved for intermediate results of a query. The reserved
+/// bytes are returned to the memory budget of the query when the reservation
+/// is dropped, so it should live as long as the results it accounts for.
+pub struct MemoryReservation {
+    memory: Option<Arc<MemoryBudget>>,
+    bytes: usize,
+}
+
+impl MemoryReservation {
+    /// Reserve the given number of additional bytes and return an error if
+    /// this exceeds the memory limit of the query.
+    pub fn grow(&mut self, bytes: usize) -> Result<()> {
+        if let Some(memory) = &self.memory {
+            let used = memory.used.fetch_add(bytes, Ordering::Relaxed) + bytes;
+            if used > memory.limit {
+                memory.used.fetch_sub(bytes, Ordering::Relaxed);
+                debug!(
+                    "Memory limit of {} bytes reached ({} bytes requested)",
+                    memory.limit, used
+                );
+                return Err(GraphAnnisError::MemoryLimitExceeded(memory.limit));
+            }
+            self.bytes += bytes;
+        }
+        Ok(())
+    }
+
+    /// Return all reserved bytes to the memory budget of the query.
+    pub fn clear(&mut self) {
+        if let Some(memory) = &self.memory {
+            memory.used.fetch_sub(self.bytes, Ordering::Relaxed);
+        }
+        self.bytes = 0;
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,4 +317,35 @@ mod tests {
         assert_eq!(Some("A/b".to_string()), exact_value_for_regex("A\x2Fb"));
         assert_eq!(Some("Test".to_string()), exact_value_for_regex("Test"));
     }
+
+    #[test]
+    fn memory_reservation_released_on_drop() {
+        let limits = TimeoutCheck::new(None).with_memory_limit(Some(100));
+
+        let mut first = limits.memory_reservation();
+        first.grow(80).unwrap();
+        let mut second = limits.memory_reservation();
+        assert!(second.grow(40).is_err());
+
+        drop(first);
+        second.grow(40).unwrap();
+        second.grow(60).unwrap();
+        assert!(second.grow(1).is_err());
+
+        second.clear();
+        second.grow(100).unwrap();
+    }
+
+    #[test]
+    fn cancel_child_token() {
+        let parent = CancellationToken::new();
+        let child = parent.child_token();
+        child.cancel();
+        assert!(child.is_cancelled());
+        assert!(!parent.is_cancelled());
+
+        let child = parent.child_token();
+        parent.cancel();
+        assert!(child.is_cancelled());
+    }
 }
@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 
+use super::{MemoryReservation, TimeoutCheck};
 use crate::annis::errors::{GraphAnnisError, Result};
 use serde::{Serialize, de::DeserializeOwned};
+use transient_btree_index::{BtreeConfig, BtreeIndex};
 
 pub trait SortableContainer<T: Clone>: Send {
     /// Swaps two elements in the container.
@@ -44,7 +46,7 @@ where
     }
 }
 
-impl<T> SortableContainer<T> for transient_btree_index::BtreeIndex<usize, T>
+impl<T> SortableContainer<T> for BtreeIndex<usize, T>
 where
     T: Serialize + DeserializeOwned + Clone + Sync + Send + 'static,
 {
@@ -66,3 +68,118 @@ where
         Ok(Cow::Owned(result))
     }
 }
+
+/// A container that keeps its items in main memory as long as the memory
+/// limit of the query allows it and moves all items to a temporary file on
+/// disk otherwise. Without a memory limit, the items are always stored on
+/// disk.
+pub struct SpillingContainer<T>
+where
+    T: Serialize + DeserializeOwned + Clone + Sync + Send + 'static,
+{
+    memory: Vec<T>,
+    disk: Option<BtreeIndex<usize, T>>,
+    btree_config: BtreeConfig,
+    reserved: MemoryReservation,
+}
+
+impl<T> SpillingContainer<T>
+where
+    T: Serialize + DeserializeOwned + Clone + Sync + Send + 'static,
+{
+    pub fn new(
+        btree_config: BtreeConfig,
+        estimated_size: usize,
+        limits: TimeoutCheck,
+    ) -> Result<SpillingContainer<T>> {
+        let disk = if limits.has_memory_limit() {
+            None
+        } else {
+            Some(BtreeIndex::with_capacity(
+                btree_config.clone(),
+                estimated_size,
+            )?)
+        };
+        Ok(SpillingContainer {
+            memory: Vec::new(),
+            disk,
+            btree_config,
+            reserved: limits.memory_reservation(),
+        })
+    }
+
+    /// Add an item, which needs approximately `size` bytes in main memory, to
+    /// the end of the container.
+    pub fn push(&mut self, item: T, size: usize) -> Result<()> {
+        if self.disk.is_none() {
+            match self.reserved.grow(size) {
+                Ok(()) => {
+                    self.memory.push(item);
+                    return Ok(());
+                }
+                Err(GraphAnnisError::MemoryLimitExceeded(_)) => self.spill()?,
+                Err(e) => return Err(e),
+            }
+        }
+        if let Some(disk) = &mut self.disk {
+            disk.insert(disk.len(), item)?;
+        }
+        Ok(())
+    }
+
+    /// Move all items from main memory to disk.
+    fn spill(&mut self) -> Result<()> {
+        debug!("Moving {} sorted items to disk", self.memory.len());
+        let mut disk = BtreeIndex::with_capacity(self.btree_config.clone(), self.memory.len())?;
+        for (idx, item) in self.memory.drain(..).enumerate() {
+            disk.insert(idx, item)?;
+        }
+        self.memory.shrink_to_fit();
+        self.reserved.clear();
+        self.disk = Some(disk);
+        Ok(())
+    }
+
+    pub fn into_iter(mut self) -> Result<Box<dyn Iterator<Item = Result<T>>>> {
+        if let Some(disk) = self.disk.take() {
+            let it = disk.into_iter()?.map(|item| Ok(item?.1));
+            Ok(Box::new(it))
+        } else {
+            // The memory is still used by the items until the iterator is dropped
+            let reserved = self.reserved;
+            Ok(Box::new(self.memory.into_iter().map(move |item| {
+                let _reserved = &reserved;
+                Ok(item)
+            })))
+        }
+    }
+}
+
+impl<T> SortableContainer<T> for SpillingContainer<T>
+where
+    T: Serialize + DeserializeOwned + Clone + Sync + Send + 'static,
+{
+    fn try_swap(&mut self, a: usize, b: usize) -> Result<()> {
+        if let Some(disk) = &mut self.disk {
+            disk.try_swap(a, b)
+        } else {
+            self.memory.try_swap(a, b)
+        }
+    }
+
+    fn try_len(&self) -> Result<usize> {
+        if let Some(disk) = &self.disk {
+            disk.try_len()
+        } else {
+            self.memory.try_len()
+        }
+    }
+
+    fn try_get(&self, index: usize) -> Result<Cow<'_, T>> {
+        if let Some(disk) = &self.disk {
+            disk.try_get(index)
+        } else {
+            self.memory.try_get(index)
+        }
+    }
+}
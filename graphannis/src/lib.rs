@@ -30,12 +30,17 @@ pub use crate::annis::db::corpusstorage::CorpusStorage;
 pub mod corpusstorage {
     pub use crate::annis::db::corpusstorage::SearchQuery;
     pub use crate::annis::db::corpusstorage::{
-        CacheStrategy, CorpusInfo, ExportFormat, FrequencyDefEntry, GraphStorageInfo, ImportFormat,
-        LoadStatus, QueryLanguage, ResultOrder,
+        CacheStrategy, CorpusInfo, ExportFormat, FindCursor, FrequencyDefEntry, GraphStorageInfo,
+        GroupByEntry, ImportFormat, LoadStatus, QueryLanguage, ResultOrder, SNAPSHOT_SEPARATOR,
     };
     pub use crate::annis::types::{
-        CountExtra, FrequencyTable, FrequencyTableRow, QueryAttributeDescription,
+        AnalyzedExecutionNode, ApproximateCount, CountExtra, FrequencyTable, FrequencyTableRow,
+        NormalizedFrequencyTable, NormalizedFrequencyTableRow, QueryAnalysis,
+        QueryAttributeDescription, ResultCacheStatistics,
     };
+    pub use crate::annis::util::CancellationToken;
+    pub use graphannis_core::annostorage::ondisk::CompressionOptions;
+    pub use graphannis_core::graph::integrity::{IntegrityProblem, IntegrityReport};
 }
 
 pub use graphannis_core::graph::update;
@@ -31,6 +31,7 @@ fn non_reflexivity_nodes() {
                 query: "node",
                 query_language: QueryLanguage::AQL,
                 timeout: None,
+                cancellation: None,
             };
             cs.count(query).unwrap_or(0)
         };
@@ -47,6 +48,7 @@ fn non_reflexivity_nodes() {
                     query: "node {} node",
                     query_language: QueryLanguage::AQL,
                     timeout: None,
+                    cancellation: None,
                 };
                 cs.count(query).unwrap_or(0)
             };
@@ -69,6 +71,7 @@ fn non_reflexivity_tokens() {
                 query: "tok",
                 query_language: QueryLanguage::AQL,
                 timeout: None,
+                cancellation: None,
             };
             cs.count(query).unwrap_or(0)
         };
@@ -84,6 +87,7 @@ fn non_reflexivity_tokens() {
                     query: &format!("tok {} tok", o),
                     query_language: QueryLanguage::AQL,
                     timeout: None,
+                    cancellation: None,
                 };
                 cs.count(query).unwrap_or(0)
             };
@@ -106,6 +110,7 @@ fn reorder_and_negation() {
         query: "pos=/V.*/ _=_ tok !->dep tok? & #1 _o_ s",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs.count(q);
     assert_eq!(true, result.is_ok());
@@ -122,6 +127,7 @@ fn find_order() {
         query: "pos",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs
         .find(
@@ -174,6 +180,7 @@ fn find_order_presorted() {
         query: "tok",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs
         .find(
@@ -226,6 +233,7 @@ fn meta_node_output_quirks() {
         query: "\"researching\" & meta::type=\"interview\"",
         query_language: QueryLanguage::AQLQuirksV3,
         timeout: None,
+        cancellation: None,
     };
     let result = cs
         .find(
@@ -250,6 +258,7 @@ fn meta_node_output_standard() {
         query: "\"researching\" @* type=\"interview\"",
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs
         .find(
@@ -277,6 +286,7 @@ fn exclude_optional_node_in_between() {
         query: r#"entity="person" !_o_ q? & infstat="giv" & #1 _r_ #3"#,
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs
         .find(
@@ -306,6 +316,7 @@ fn exclude_optional_node_at_end() {
         query: r#"entity="person" _r_ infstat="giv" &  q? & #1 !_o_ #3"#,
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     let result = cs
         .find(
@@ -341,6 +352,7 @@ fn token_search_loads_components_for_leaf_filter() {
                 query,
                 query_language: QueryLanguage::AQL,
                 timeout: None,
+                cancellation: None,
             };
 
             // Unload corpus to test that query loads components necessary to filter for leaves
@@ -366,6 +378,7 @@ fn negative_token_search_applies_leaf_filter() {
                 query,
                 query_language: QueryLanguage::AQL,
                 timeout: None,
+                cancellation: None,
             };
 
             let count = cs.count(query).unwrap();
@@ -385,6 +398,7 @@ fn no_error_on_large_token_distance() {
         query: r#"tok .100 tok"#,
         query_language: QueryLanguage::AQL,
         timeout: None,
+        cancellation: None,
     };
     // There should be an empty result, but no error
     let result = cs
@@ -410,6 +424,7 @@ fn legacy_meta_query_with_multiple_alternatives() {
         query: "\"buy\" | \"favorite\" & meta::type=\"interview\"",
         query_language: QueryLanguage::AQLQuirksV3,
         timeout: None,
+        cancellation: None,
     };
 
     let count = cs.count(query).unwrap();
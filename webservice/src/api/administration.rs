@@ -264,6 +264,16 @@ pub async fn export_corpus(
     }))
 }
 
+pub async fn result_cache(
+    cs: web::Data<CorpusStorage>,
+    claims: ClaimsFromAuth,
+) -> Result<HttpResponse, ServiceError> {
+    check_is_admin(&claims.0)?;
+
+    let statistics = cs.result_cache_statistics()?;
+    Ok(HttpResponse::Ok().json(statistics))
+}
+
 pub async fn jobs(
     uuid: web::Path<String>,
     background_jobs: web::Data<BackgroundJobs>,
@@ -34,6 +34,12 @@ async fn needs_bearer_token() {
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), StatusCode::FORBIDDEN);
 
+    let req = test::TestRequest::get()
+        .uri("/v1/result-cache")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
     let req = test::TestRequest::get().uri("/v1/groups").to_request();
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), StatusCode::FORBIDDEN);
@@ -112,3 +118,22 @@ async fn test_user_groups() {
     let response_body: Vec<Group> = test::read_body_json(resp).await;
     assert_eq!(response_body.len(), 0);
 }
+
+#[actix_web::test]
+async fn test_result_cache_statistics() {
+    let db_dir = tempfile::TempDir::new().unwrap();
+    let cs = graphannis::CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap();
+    let app = test::init_service(create_test_app(web::Data::new(cs), Settings::default())).await;
+
+    let req = test::TestRequest::get()
+        .insert_header(create_auth_header())
+        .uri("/v1/result-cache")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let response_body: graphannis::corpusstorage::ResultCacheStatistics =
+        test::read_body_json(resp).await;
+    assert_eq!(response_body.hits, 0);
+    assert_eq!(response_body.misses, 0);
+    assert_eq!(response_body.entries, 0);
+}
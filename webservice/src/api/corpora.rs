@@ -1,4 +1,4 @@
-use super::{check_corpora_authorized_read, check_is_admin};
+use super::{CancelOnDrop, check_corpora_authorized_read, check_is_admin};
 use crate::{
     DbPool, actions, errors::ServiceError, extractors::ClaimsFromAuth, settings::Settings,
 };
@@ -110,12 +110,19 @@ pub async fn subgraph_for_query(
 ) -> Result<HttpResponse, ServiceError> {
     check_corpora_authorized_read(vec![corpus.clone()], claims.0, &settings, &db_pool).await?;
 
-    let graph = cs.subgraph_for_query(
-        &corpus,
-        params.query.as_str(),
-        params.query_language,
-        params.component_type_filter.clone(),
-    )?;
+    let cancel_on_drop = CancelOnDrop::default();
+    let cancellation = cancel_on_drop.token();
+    let cs = cs.into_inner();
+    let graph = web::block(move || {
+        cs.subgraph_for_query(
+            &corpus,
+            params.query.as_str(),
+            params.query_language,
+            params.component_type_filter.clone(),
+            cancellation,
+        )
+    })
+    .await??;
     // Export subgraph to GraphML
     let mut output = Vec::new();
     graphannis_core::graph::serialization::graphml::export(&graph, None, &mut output, |_| {})?;
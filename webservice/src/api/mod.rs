@@ -1,5 +1,6 @@
 use crate::{DbPool, actions, auth::Claims, errors::ServiceError, settings::Settings};
 use actix_web::web;
+use graphannis::corpusstorage::CancellationToken;
 
 pub mod administration;
 pub mod corpora;
@@ -13,6 +14,24 @@ fn check_is_admin(claims: &Claims) -> Result<(), ServiceError> {
     }
 }
 
+/// Cancels the query of a request when dropped. Actix drops the future of a
+/// handler and the body of a streamed response when the client disconnects,
+/// so queries that are executed in a background thread are stopped early.
+#[derive(Default)]
+struct CancelOnDrop(CancellationToken);
+
+impl CancelOnDrop {
+    fn token(&self) -> Option<CancellationToken> {
+        Some(self.0.clone())
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
 /// Check that all `requested_corpora` are authorized for the user.
 /// If any of them is not, a `ServiceError::NonAuthorizedCorpus` error is returned.
 async fn check_corpora_authorized_read(
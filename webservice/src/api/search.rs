@@ -1,15 +1,18 @@
 use std::time::Duration;
 
-use super::check_corpora_authorized_read;
+use super::{CancelOnDrop, check_corpora_authorized_read};
 use crate::{DbPool, errors::ServiceError, extractors::ClaimsFromAuth, settings::Settings};
 use actix_web::{
     HttpResponse,
     web::{self, Bytes},
 };
-use futures::stream::iter;
+use futures::{SinkExt, StreamExt, channel::mpsc, executor::block_on, stream::iter};
 use graphannis::{
     CorpusStorage,
-    corpusstorage::{FrequencyDefEntry, QueryLanguage, ResultOrder, SearchQuery},
+    corpusstorage::{
+        FindCursor, FrequencyDefEntry, GroupByEntry, QueryLanguage, ResultOrder, SearchQuery,
+    },
+    errors::GraphAnnisError,
 };
 use serde::Deserialize;
 
@@ -31,14 +34,72 @@ pub async fn count(
     let corpora =
         check_corpora_authorized_read(params.corpora.clone(), claims.0, &settings, &db_pool)
             .await?;
-    let query = SearchQuery {
-        corpus_names: &corpora,
-        query: &params.query,
-        query_language: params.query_language,
-        timeout: settings.database.query_timeout.map(Duration::from_secs),
-    };
-
-    let count = cs.count_extra(query)?;
+    let timeout = settings.database.query_timeout.map(Duration::from_secs);
+    let cancel_on_drop = CancelOnDrop::default();
+    let cancellation = cancel_on_drop.token();
+    let cs = cs.into_inner();
+
+    let count = web::block(move || {
+        let query = SearchQuery {
+            corpus_names: &corpora,
+            query: &params.query,
+            query_language: params.query_language,
+            timeout,
+            cancellation,
+        };
+        cs.count_extra(query)
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(count))
+}
+
+/// Number of documents per corpus that are searched for an approximate count
+/// if the query does not define it.
+fn default_max_documents() -> usize {
+    100
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CountApproximateQuery {
+    query: String,
+    #[serde(default)]
+    query_language: QueryLanguage,
+    corpora: Vec<String>,
+    #[serde(default = "default_max_documents")]
+    max_documents: usize,
+}
+
+pub async fn count_approximate(
+    params: web::Json<CountApproximateQuery>,
+    cs: web::Data<CorpusStorage>,
+    db_pool: web::Data<DbPool>,
+    settings: web::Data<Settings>,
+    claims: ClaimsFromAuth,
+) -> Result<HttpResponse, ServiceError> {
+    if params.max_documents == 0 {
+        return Err(ServiceError::InvalidParameter(
+            "max_documents must be larger than zero".into(),
+        ));
+    }
+    let corpora =
+        check_corpora_authorized_read(params.corpora.clone(), claims.0, &settings, &db_pool)
+            .await?;
+    let timeout = settings.database.query_timeout.map(Duration::from_secs);
+    let cancel_on_drop = CancelOnDrop::default();
+    let cancellation = cancel_on_drop.token();
+    let cs = cs.into_inner();
+
+    let count = web::block(move || {
+        let query = SearchQuery {
+            corpus_names: &corpora,
+            query: &params.query,
+            query_language: params.query_language,
+            timeout,
+            cancellation,
+        };
+        cs.count_approximate(query, params.max_documents)
+    })
+    .await??;
     Ok(HttpResponse::Ok().json(count))
 }
 
@@ -57,7 +118,7 @@ pub async fn node_descriptions(
     Ok(HttpResponse::Ok().json(desc))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct FindQuery {
     query: String,
     #[serde(default)]
@@ -69,8 +130,17 @@ pub struct FindQuery {
     offset: usize,
     #[serde(default)]
     order: ResultOrder,
+    #[serde(default)]
+    cursor: Option<String>,
 }
 
+/// Response header with the cursor for the next page of a `find` query.
+pub const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+
+/// Number of matches that are buffered before the query execution waits for
+/// the client to receive them.
+const FIND_CHANNEL_SIZE: usize = 1024;
+
 pub async fn find(
     params: web::Json<FindQuery>,
     cs: web::Data<CorpusStorage>,
@@ -81,25 +151,154 @@ pub async fn find(
     let corpora =
         check_corpora_authorized_read(params.corpora.clone(), claims.0, &settings, &db_pool)
             .await?;
-    let query = SearchQuery {
-        corpus_names: &corpora,
-        query: &params.query,
-        query_language: params.query_language,
-        timeout: settings.database.query_timeout.map(Duration::from_secs),
-    };
-    let matches = cs.find(query, params.offset, params.limit, params.order)?;
-
-    let body = iter(
-        matches
+    let params = params.into_inner();
+    let timeout = settings.database.query_timeout.map(Duration::from_secs);
+    let cancel_on_drop = CancelOnDrop::default();
+    let cancellation = cancel_on_drop.token();
+    let cs = cs.into_inner();
+
+    if let Some(limit) = params.limit
+        && params.offset == 0
+        && (params.order == ResultOrder::Normal || params.order == ResultOrder::Inverted)
+    {
+        // A page is small enough to be collected before sending the response,
+        // which allows to return the cursor for the next page as header.
+        let cursor: Option<FindCursor> = params.cursor.as_deref().map(str::parse).transpose()?;
+        let (matches, next_cursor) = web::block(move || {
+            let query = SearchQuery {
+                corpus_names: &corpora,
+                query: &params.query,
+                query_language: params.query_language,
+                timeout,
+                cancellation,
+            };
+            cs.find_with_cursor(query, cursor.as_ref(), limit, params.order)
+        })
+        .await??;
+
+        let mut response = HttpResponse::Ok();
+        response.content_type("text/plain");
+        if let Some(next_cursor) = next_cursor {
+            response.insert_header((NEXT_CURSOR_HEADER, next_cursor.to_string()));
+        }
+        let body: String = matches
             .into_iter()
-            .map(|mut line| -> Result<_, ServiceError> {
+            .map(|mut line| {
+                line.push('\n');
+                line
+            })
+            .collect();
+        return Ok(response.body(body));
+    } else if params.cursor.is_some() {
+        return Err(GraphAnnisError::InvalidFindCursor(
+            "a cursor needs a limit and can't be combined with an offset or a random or unsorted order"
+                .into(),
+        )
+        .into());
+    }
+
+    // Execute the query on the blocking thread pool and send each match to the
+    // response as soon as it is found. The task does not need to be awaited,
+    // it ends when all matches have been sent or the query has been cancelled.
+    let (mut sender, mut receiver) = mpsc::channel(FIND_CHANNEL_SIZE);
+    let _producer = web::block(move || {
+        let query = SearchQuery {
+            corpus_names: &corpora,
+            query: &params.query,
+            query_language: params.query_language,
+            timeout,
+            cancellation,
+        };
+        let result = cs.find_iter(
+            query,
+            params.offset,
+            params.limit,
+            params.order,
+            |mut line| {
                 line.push('\n');
-                Ok(Bytes::from(line))
-            }),
-    );
-    Ok(HttpResponse::Ok()
-        .content_type("text/plain")
-        .streaming(body))
+                // Stop the query execution when the client is gone
+                block_on(sender.send(Ok(Bytes::from(line)))).is_ok()
+            },
+        );
+        if let Err(e) = result {
+            block_on(sender.send(Err(ServiceError::from(e)))).ok();
+        }
+    });
+
+    // Wait for the first match, so that errors in the query (e.g. syntax
+    // errors) can be reported with the proper status code.
+    match receiver.next().await {
+        Some(Err(e)) => Err(e),
+        Some(Ok(first)) => {
+            // The query is cancelled when the response body is dropped
+            let receiver = receiver.map(move |m| {
+                let _guard = &cancel_on_drop;
+                m
+            });
+            Ok(HttpResponse::Ok()
+                .content_type("text/plain")
+                .streaming(iter(vec![Ok(first)]).chain(receiver)))
+        }
+        None => Ok(HttpResponse::Ok().content_type("text/plain").finish()),
+    }
+}
+
+/// Maximum size of a sample, which is held completely in memory while the
+/// query is executed.
+const MAX_SAMPLE_SIZE: usize = 10_000;
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SampleQuery {
+    query: String,
+    #[serde(default)]
+    query_language: QueryLanguage,
+    corpora: Vec<String>,
+    n: usize,
+    seed: u64,
+}
+
+pub async fn sample(
+    params: web::Json<SampleQuery>,
+    cs: web::Data<CorpusStorage>,
+    db_pool: web::Data<DbPool>,
+    settings: web::Data<Settings>,
+    claims: ClaimsFromAuth,
+) -> Result<HttpResponse, ServiceError> {
+    if params.n > MAX_SAMPLE_SIZE {
+        return Err(ServiceError::InvalidParameter(format!(
+            "sample size {} is larger than the maximum of {MAX_SAMPLE_SIZE}",
+            params.n
+        )));
+    }
+    let corpora =
+        check_corpora_authorized_read(params.corpora.clone(), claims.0, &settings, &db_pool)
+            .await?;
+    let params = params.into_inner();
+    let timeout = settings.database.query_timeout.map(Duration::from_secs);
+    let cancel_on_drop = CancelOnDrop::default();
+    let cancellation = cancel_on_drop.token();
+    let cs = cs.into_inner();
+
+    let matches = web::block(move || {
+        let query = SearchQuery {
+            corpus_names: &corpora,
+            query: &params.query,
+            query_language: params.query_language,
+            timeout,
+            cancellation,
+        };
+        cs.sample(query, params.n, params.seed)
+    })
+    .await??;
+
+    let body: String = matches
+        .into_iter()
+        .map(|mut line| {
+            line.push('\n');
+            line
+        })
+        .collect();
+    Ok(HttpResponse::Ok().content_type("text/plain").body(body))
 }
 
 #[derive(Deserialize)]
@@ -121,13 +320,61 @@ pub async fn frequency(
     let corpora =
         check_corpora_authorized_read(params.corpora.clone(), claims.0, &settings, &db_pool)
             .await?;
-    let query = SearchQuery {
-        corpus_names: &corpora,
-        query: &params.query,
-        query_language: params.query_language,
-        timeout: settings.database.query_timeout.map(Duration::from_secs),
-    };
-    let result = cs.frequency(query, params.definition.clone())?;
+    let timeout = settings.database.query_timeout.map(Duration::from_secs);
+    let cancel_on_drop = CancelOnDrop::default();
+    let cancellation = cancel_on_drop.token();
+    let cs = cs.into_inner();
+
+    let result = web::block(move || {
+        let query = SearchQuery {
+            corpus_names: &corpora,
+            query: &params.query,
+            query_language: params.query_language,
+            timeout,
+            cancellation,
+        };
+        cs.frequency(query, params.definition.clone())
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CountGroupedQuery {
+    query: String,
+    #[serde(default)]
+    query_language: QueryLanguage,
+    corpora: Vec<String>,
+    group_by: Vec<GroupByEntry>,
+}
+
+pub async fn count_grouped(
+    params: web::Json<CountGroupedQuery>,
+    cs: web::Data<CorpusStorage>,
+    db_pool: web::Data<DbPool>,
+    settings: web::Data<Settings>,
+    claims: ClaimsFromAuth,
+) -> Result<HttpResponse, ServiceError> {
+    let corpora =
+        check_corpora_authorized_read(params.corpora.clone(), claims.0, &settings, &db_pool)
+            .await?;
+    let timeout = settings.database.query_timeout.map(Duration::from_secs);
+    let cancel_on_drop = CancelOnDrop::default();
+    let cancellation = cancel_on_drop.token();
+    let cs = cs.into_inner();
+
+    let result = web::block(move || {
+        let query = SearchQuery {
+            corpus_names: &corpora,
+            query: &params.query,
+            query_language: params.query_language,
+            timeout,
+            cancellation,
+        };
+        cs.count_grouped(query, params.group_by.clone())
+    })
+    .await??;
 
     Ok(HttpResponse::Ok().json(result))
 }
@@ -1,5 +1,5 @@
 use actix_web::http::StatusCode;
-use graphannis::corpusstorage::CountExtra;
+use graphannis::corpusstorage::{ApproximateCount, CountExtra, FrequencyTable};
 
 use crate::tests::{create_auth_header, create_test_app, import_test_corpora};
 
@@ -32,3 +32,252 @@ async fn test_count() {
     assert_eq!(response_body.document_count, 4);
     assert_eq!(response_body.match_count, 44);
 }
+
+#[actix_web::test]
+async fn test_count_approximate() {
+    let db_dir = tempfile::TempDir::new().unwrap();
+    let cs = graphannis::CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap(); // Import three corpora A,B and C
+    import_test_corpora(&cs);
+
+    let app =
+        actix_web::test::init_service(create_test_app(web::Data::new(cs), Settings::default()))
+            .await;
+
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/search/count-approximate")
+        .set_json(CountApproximateQuery {
+            query: "tok".into(),
+            query_language: QueryLanguage::AQL,
+            corpora: vec!["A".into()],
+            max_documents: 2,
+        })
+        .insert_header(create_auth_header())
+        .to_request();
+    let resp = actix_web::test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let response_body: ApproximateCount = actix_web::test::read_body_json(resp).await;
+
+    // Each of the 4 documents has 11 token
+    assert!(response_body.approximate);
+    assert_eq!(response_body.match_count, 44);
+    assert_eq!(response_body.lower_bound, 44);
+    assert_eq!(response_body.upper_bound, 44);
+
+    // At least one document must be searched
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/search/count-approximate")
+        .set_json(CountApproximateQuery {
+            query: "tok".into(),
+            query_language: QueryLanguage::AQL,
+            corpora: vec!["A".into()],
+            max_documents: 0,
+        })
+        .insert_header(create_auth_header())
+        .to_request();
+    let resp = actix_web::test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_find() {
+    let db_dir = tempfile::TempDir::new().unwrap();
+    let cs = graphannis::CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap(); // Import three corpora A,B and C
+    import_test_corpora(&cs);
+
+    let app =
+        actix_web::test::init_service(create_test_app(web::Data::new(cs), Settings::default()))
+            .await;
+
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/search/find")
+        .set_json(FindQuery {
+            query: "pos=\"VB\"".into(),
+            query_language: QueryLanguage::AQL,
+            corpora: vec!["A".into(), "B".into()],
+            limit: None,
+            offset: 2,
+            order: ResultOrder::Normal,
+            cursor: None,
+        })
+        .insert_header(create_auth_header())
+        .to_request();
+    let resp = actix_web::test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let response_body = actix_web::test::read_body(resp).await;
+    let response_body = std::str::from_utf8(&response_body).unwrap();
+    let matches: Vec<_> = response_body.lines().collect();
+    assert_eq!(6, matches.len());
+    assert!(
+        matches
+            .iter()
+            .all(|m| m.starts_with("salt::pos::rootCorpus/"))
+    );
+
+    // Errors in the query must be reported before the result is streamed
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/search/find")
+        .set_json(FindQuery {
+            query: "pos=".into(),
+            query_language: QueryLanguage::AQL,
+            corpora: vec!["A".into()],
+            limit: None,
+            offset: 0,
+            order: ResultOrder::Normal,
+            cursor: None,
+        })
+        .insert_header(create_auth_header())
+        .to_request();
+    let resp = actix_web::test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_find_with_cursor() {
+    let db_dir = tempfile::TempDir::new().unwrap();
+    let cs = graphannis::CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap(); // Import three corpora A,B and C
+    import_test_corpora(&cs);
+
+    let app =
+        actix_web::test::init_service(create_test_app(web::Data::new(cs), Settings::default()))
+            .await;
+
+    // Get all 12 matches in pages of 5 matches
+    let mut cursor = None;
+    let mut pages = Vec::new();
+    loop {
+        let req = actix_web::test::TestRequest::post()
+            .uri("/v1/search/find")
+            .set_json(FindQuery {
+                query: "pos=\"VB\"".into(),
+                query_language: QueryLanguage::AQL,
+                corpora: vec!["A".into(), "B".into(), "C".into()],
+                limit: Some(5),
+                offset: 0,
+                order: ResultOrder::Normal,
+                cursor: cursor.clone(),
+            })
+            .insert_header(create_auth_header())
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        cursor = resp
+            .headers()
+            .get(NEXT_CURSOR_HEADER)
+            .map(|c| c.to_str().unwrap().to_string());
+        let response_body = actix_web::test::read_body(resp).await;
+        pages.push(std::str::from_utf8(&response_body).unwrap().lines().count());
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(vec![5, 5, 2], pages);
+
+    // Cursors can't be combined with an offset
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/search/find")
+        .set_json(FindQuery {
+            query: "pos=\"VB\"".into(),
+            query_language: QueryLanguage::AQL,
+            corpora: vec!["A".into()],
+            limit: Some(5),
+            offset: 1,
+            order: ResultOrder::Normal,
+            cursor: Some("A.n".into()),
+        })
+        .insert_header(create_auth_header())
+        .to_request();
+    let resp = actix_web::test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_sample() {
+    let db_dir = tempfile::TempDir::new().unwrap();
+    let cs = graphannis::CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap(); // Import three corpora A,B and C
+    import_test_corpora(&cs);
+
+    let app =
+        actix_web::test::init_service(create_test_app(web::Data::new(cs), Settings::default()))
+            .await;
+
+    let mut samples = Vec::new();
+    for n in [3, 3, 100] {
+        let req = actix_web::test::TestRequest::post()
+            .uri("/v1/search/sample")
+            .set_json(SampleQuery {
+                query: "pos=\"VB\"".into(),
+                query_language: QueryLanguage::AQL,
+                corpora: vec!["A".into(), "B".into()],
+                n,
+                seed: 42,
+            })
+            .insert_header(create_auth_header())
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let response_body = actix_web::test::read_body(resp).await;
+        let response_body = std::str::from_utf8(&response_body).unwrap();
+        let matches: Vec<String> = response_body.lines().map(String::from).collect();
+        samples.push(matches);
+    }
+    // The same seed must result in the same sample
+    assert_eq!(3, samples[0].len());
+    assert_eq!(samples[0], samples[1]);
+    // Sampling more than the number of matches returns all of them
+    assert_eq!(8, samples[2].len());
+
+    // The sample size is limited
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/search/sample")
+        .set_json(SampleQuery {
+            query: "pos=\"VB\"".into(),
+            query_language: QueryLanguage::AQL,
+            corpora: vec!["A".into(), "B".into()],
+            n: usize::MAX,
+            seed: 42,
+        })
+        .insert_header(create_auth_header())
+        .to_request();
+    let resp = actix_web::test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_count_grouped() {
+    let db_dir = tempfile::TempDir::new().unwrap();
+    let cs = graphannis::CorpusStorage::with_auto_cache_size(db_dir.path(), false).unwrap(); // Import three corpora A,B and C
+    import_test_corpora(&cs);
+
+    let app =
+        actix_web::test::init_service(create_test_app(web::Data::new(cs), Settings::default()))
+            .await;
+
+    let req = actix_web::test::TestRequest::post()
+        .uri("/v1/search/count-grouped")
+        .set_json(CountGroupedQuery {
+            query: "pos=\"VB\"".into(),
+            query_language: QueryLanguage::AQL,
+            corpora: vec!["A".into()],
+            group_by: vec![GroupByEntry::Document],
+        })
+        .insert_header(create_auth_header())
+        .to_request();
+    let resp = actix_web::test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let response_body: FrequencyTable<String> = actix_web::test::read_body_json(resp).await;
+
+    let mut documents: Vec<_> = response_body
+        .into_iter()
+        .map(|row| (row.values, row.count))
+        .collect();
+    documents.sort();
+    assert_eq!(
+        vec![
+            (vec!["rootCorpus/subCorpus1/doc1".to_string()], 1),
+            (vec!["rootCorpus/subCorpus1/doc2".to_string()], 1),
+            (vec!["rootCorpus/subCorpus2/doc3".to_string()], 1),
+            (vec!["rootCorpus/subCorpus2/doc4".to_string()], 1),
+        ],
+        documents
+    );
+}
@@ -28,6 +28,8 @@ pub enum ServiceError {
     Uuid(#[from] uuid::Error),
     #[error("{0}")]
     IllegalNodePath(String),
+    #[error("{0}")]
+    InvalidParameter(String),
     #[error("Lock poisoning ({0})")]
     LockPoisoning(String),
 }
@@ -43,8 +45,10 @@ enum BadRequestError {
     AQLSyntaxError(AQLError),
     AQLSemanticError(AQLError),
     ImpossibleSearch(String),
+    InvalidFindCursor(String),
     Uuid(String),
     IllegalNodePath(String),
+    InvalidParameter(String),
 }
 
 impl ResponseError for ServiceError {
@@ -65,14 +69,23 @@ impl ResponseError for ServiceError {
             ServiceError::IllegalNodePath(err) => {
                 HttpResponse::BadRequest().json(BadRequestError::IllegalNodePath(err.to_string()))
             }
+            ServiceError::InvalidParameter(err) => {
+                HttpResponse::BadRequest().json(BadRequestError::InvalidParameter(err.to_string()))
+            }
             ServiceError::GraphAnnisError(err) => match err {
                 GraphAnnisError::Timeout => HttpResponse::GatewayTimeout().finish(),
+                GraphAnnisError::MemoryLimitExceeded(_) => {
+                    HttpResponse::InsufficientStorage().json(err.to_string())
+                }
                 GraphAnnisError::AQLSemanticError(aql_error) => HttpResponse::BadRequest()
                     .json(BadRequestError::AQLSemanticError(aql_error.clone())),
                 GraphAnnisError::AQLSyntaxError(aql_error) => HttpResponse::BadRequest()
                     .json(BadRequestError::AQLSyntaxError(aql_error.clone())),
                 GraphAnnisError::ImpossibleSearch(aql_error) => HttpResponse::BadRequest()
                     .json(BadRequestError::ImpossibleSearch(aql_error.clone())),
+                GraphAnnisError::InvalidFindCursor(msg) => {
+                    HttpResponse::BadRequest().json(BadRequestError::InvalidFindCursor(msg.clone()))
+                }
                 _ => HttpResponse::InternalServerError().json(err.to_string()),
             },
             ServiceError::NotFound => HttpResponse::NotFound().finish(),
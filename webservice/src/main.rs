@@ -101,6 +101,12 @@ fn init_app_state() -> anyhow::Result<(graphannis::CorpusStorage, settings::Sett
         settings.database.cache.clone(),
         true,
     )?;
+    if let Some(result_cache_size) = settings.database.result_cache_size {
+        cs.set_result_cache_size(result_cache_size * 1024 * 1024)?;
+    }
+    if let Some(query_memory_limit) = settings.database.query_memory_limit {
+        cs.set_query_memory_limit(Some(query_memory_limit * 1024 * 1024))?;
+    }
 
     // Add a connection pool to the SQLite database
     let manager = ConnectionManager::<SqliteConnection>::new(&settings.database.sqlite);
@@ -187,7 +193,8 @@ fn create_app(
         .wrap(
             Cors::default()
                 .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-                .allowed_header(http::header::CONTENT_TYPE),
+                .allowed_header(http::header::CONTENT_TYPE)
+                .expose_headers(vec![api::search::NEXT_CURSOR_HEADER]),
         )
         .app_data(cs)
         .app_data(settings)
@@ -207,10 +214,20 @@ fn create_app(
                     web::post().to(api::administration::export_corpus),
                 )
                 .route("/jobs/{uuid}", web::get().to(api::administration::jobs))
+                .route(
+                    "/result-cache",
+                    web::get().to(api::administration::result_cache),
+                )
                 .service(
                     web::scope("/search")
                         .route("/count", web::post().to(api::search::count))
+                        .route("/count-grouped", web::post().to(api::search::count_grouped))
+                        .route(
+                            "/count-approximate",
+                            web::post().to(api::search::count_approximate),
+                        )
                         .route("/find", web::post().to(api::search::find))
+                        .route("/sample", web::post().to(api::search::sample))
                         .route("/frequency", web::post().to(api::search::frequency))
                         .route(
                             "/node-descriptions",
@@ -27,6 +27,14 @@ pub struct Database {
     pub cache: CacheStrategy,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub query_timeout: Option<u64>,
+    /// Maximum size of the cache for query results in megabytes. Query
+    /// results are not cached if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_cache_size: Option<usize>,
+    /// Maximum memory a single query can use in megabytes. Queries are not
+    /// limited if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_memory_limit: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]